@@ -4,10 +4,10 @@ use anyhow::{Context, Result};
 use std::cell::RefCell;
 use std::ffi::{c_char, c_void, CStr, CString};
 use tract_api::{
-    AsFact, DatumType, InferenceModelInterface, ModelInterface, NnefInterface, OnnxInterface,
-    RunnableInterface, StateInterface, ValueInterface,
+    AsFact, CancellationInterface, DatumType, InferenceModelInterface, ModelInterface,
+    NnefInterface, OnnxInterface, RunnableInterface, StateInterface, ValueInterface,
 };
-use tract_rs::{State, Value};
+use tract_rs::{Cancellation, State, Value};
 
 /// Used as a return type of functions that can encounter errors.
 /// If the function encountered an error, you can retrieve it using the `tract_get_last_error`
@@ -20,6 +20,8 @@ pub enum TRACT_RESULT {
     TRACT_RESULT_OK = 0,
     /// The function returned an error
     TRACT_RESULT_KO = 1,
+    /// The function was stopped by a cancellation token
+    TRACT_RESULT_CANCELLED = 2,
 }
 
 thread_local! {
@@ -40,13 +42,18 @@ fn wrap<F: FnOnce() -> anyhow::Result<()>>(func: F) -> TRACT_RESULT {
                         .unwrap()
                 }))
             });
-            TRACT_RESULT::TRACT_RESULT_KO
+            if Cancellation::is_cancellation(&e) {
+                TRACT_RESULT::TRACT_RESULT_CANCELLED
+            } else {
+                TRACT_RESULT::TRACT_RESULT_KO
+            }
         }
     }
 }
 
 /// Retrieve the last error that happened in this thread. A function encountered an error if
-/// its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO`.
+/// its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO` or
+/// `TRACT_RESULT_CANCELLED`.
 ///
 /// # Return value
 ///  It returns a pointer to a null-terminated UTF-8 string that will contain the error description.
@@ -158,6 +165,27 @@ pub unsafe extern "C" fn tract_nnef_enable_extended_identifier_syntax(
     })
 }
 
+/// Write a manifest.sha256 member with the digest of every other member when dumping models.
+#[no_mangle]
+pub unsafe extern "C" fn tract_nnef_enable_manifest(nnef: *mut TractNnef) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(nnef);
+        (*nnef).0.enable_manifest()
+    })
+}
+
+/// Check models against their manifest.sha256 when loading them. Loading fails if the manifest
+/// is missing, or if a member is missing, unexpected or corrupted.
+#[no_mangle]
+pub unsafe extern "C" fn tract_nnef_enable_manifest_verification(
+    nnef: *mut TractNnef,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(nnef);
+        (*nnef).0.enable_manifest_verification()
+    })
+}
+
 /// Destroy the NNEF parser. It is safe to detroy the NNEF parser once the model had been loaded.
 #[no_mangle]
 pub unsafe extern "C" fn tract_nnef_destroy(nnef: *mut *mut TractNnef) -> TRACT_RESULT {
@@ -978,6 +1006,86 @@ pub unsafe extern "C" fn tract_state_run(
     })
 }
 
+/// Bind a caller buffer to an input of a state: `tract_state_run_bound` reads the input from it,
+/// without copying it. Datum type and shape are checked against the input fact.
+///
+/// rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`
+/// must be aligned for the datum type, and stay valid until the bindings are cleared or the
+/// state destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn tract_state_bind_input(
+    state: *mut TractState,
+    input: usize,
+    datum_type: DatumType,
+    rank: usize,
+    shape: *const usize,
+    data: *const c_void,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(state, shape, data);
+        let shape = std::slice::from_raw_parts(shape, rank);
+        (*state).0.bind_input(input, datum_type, shape, data as *const u8)
+    })
+}
+
+/// Bind a caller buffer to an output of a state: `tract_state_run_bound` writes the output in it,
+/// without allocating it when the operator computing it supports it. Datum type and shape are
+/// checked against the output fact.
+///
+/// rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`
+/// must be aligned for the datum type, and stay valid until the bindings are cleared or the
+/// state destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn tract_state_bind_output(
+    state: *mut TractState,
+    output: usize,
+    datum_type: DatumType,
+    rank: usize,
+    shape: *const usize,
+    data: *mut c_void,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(state, shape, data);
+        let shape = std::slice::from_raw_parts(shape, rank);
+        (*state).0.bind_output(output, datum_type, shape, data as *mut u8)
+    })
+}
+
+/// Forget the buffers bound to the inputs and outputs of a state.
+#[no_mangle]
+pub unsafe extern "C" fn tract_state_clear_bindings(state: *mut TractState) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(state);
+        (*state).0.clear_bindings()
+    })
+}
+
+/// Run a turn on the buffers bound to a state. All inputs must be bound. Outputs without a bound
+/// buffer are dropped.
+#[no_mangle]
+pub unsafe extern "C" fn tract_state_run_bound(state: *mut TractState) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(state);
+        (*state).0.run_bound()
+    })
+}
+
+/// Make the next runs of a state stop with `TRACT_RESULT_CANCELLED` once `cancellation` is
+/// cancelled or past its deadline. Runs check it between operators, scan iterations and matrix
+/// product tiles. A null `cancellation` runs without cancellation.
+///
+/// The state keeps its own reference to the token: `cancellation` can be destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn tract_state_set_cancellation(
+    state: *mut TractState,
+    cancellation: *const TractCancellation,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(state);
+        (*state).0.set_cancellation(cancellation.as_ref().map(|c| &c.0))
+    })
+}
+
 /// Query a State input counts.
 #[no_mangle]
 pub unsafe extern "C" fn tract_state_input_count(
@@ -1011,6 +1119,48 @@ pub unsafe extern "C" fn tract_state_destroy(state: *mut *mut TractState) -> TRA
     release!(state)
 }
 
+// CANCELLATION
+pub struct TractCancellation(tract_rs::Cancellation);
+
+/// Create a cancellation token, with a deadline `timeout_us` microseconds from now, or without
+/// deadline if `timeout_us` is 0.
+///
+/// The token must be destroyed with `tract_cancellation_destroy`.
+#[no_mangle]
+pub unsafe extern "C" fn tract_cancellation_create(
+    timeout_us: u64,
+    cancellation: *mut *mut TractCancellation,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(cancellation);
+        *cancellation = std::ptr::null_mut();
+        let timeout = (timeout_us > 0).then(|| std::time::Duration::from_micros(timeout_us));
+        let token = Cancellation::new(timeout)?;
+        *cancellation = Box::into_raw(Box::new(TractCancellation(token)));
+        Ok(())
+    })
+}
+
+/// Cancel a token: the runs of the states it is set to stop with `TRACT_RESULT_CANCELLED`.
+///
+/// This function can be called from any thread, including while a state runs.
+#[no_mangle]
+pub unsafe extern "C" fn tract_cancellation_cancel(
+    cancellation: *const TractCancellation,
+) -> TRACT_RESULT {
+    wrap(|| unsafe {
+        check_not_null!(cancellation);
+        (*cancellation).0.cancel()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tract_cancellation_destroy(
+    cancellation: *mut *mut TractCancellation,
+) -> TRACT_RESULT {
+    release!(cancellation)
+}
+
 // FACT
 pub struct TractFact(tract_rs::Fact);
 
@@ -11,16 +11,34 @@ use ndarray::*;
 macro_rules! check {
     ($expr:expr) => {
         unsafe {
-            if $expr == sys::TRACT_RESULT_TRACT_RESULT_KO {
-                let buf = CStr::from_ptr(sys::tract_get_last_error());
-                Err(anyhow::anyhow!(buf.to_string_lossy().to_string()))
-            } else {
+            let result = $expr;
+            if result == sys::TRACT_RESULT_TRACT_RESULT_OK {
                 Ok(())
+            } else {
+                let buf = CStr::from_ptr(sys::tract_get_last_error());
+                let message = buf.to_string_lossy().to_string();
+                if result == sys::TRACT_RESULT_TRACT_RESULT_CANCELLED {
+                    Err(anyhow::Error::new(Cancelled(message)))
+                } else {
+                    Err(anyhow::anyhow!(message))
+                }
             }
         }
     };
 }
 
+/// Error of a run stopped by a cancellation token, with the message of libtract.
+#[derive(Debug)]
+pub struct Cancelled(pub String);
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
 macro_rules! wrapper {
     ($new_type:ident, $c_type:ident, $dest:ident $(, $typ:ty )*) => {
         #[derive(Debug, Clone)]
@@ -85,6 +103,14 @@ impl NnefInterface for Nnef {
         check!(sys::tract_nnef_enable_extended_identifier_syntax(self.0))
     }
 
+    fn enable_manifest(&mut self) -> Result<()> {
+        check!(sys::tract_nnef_enable_manifest(self.0))
+    }
+
+    fn enable_manifest_verification(&mut self) -> Result<()> {
+        check!(sys::tract_nnef_enable_manifest_verification(self.0))
+    }
+
     fn write_model_to_dir(&self, path: impl AsRef<Path>, model: &Model) -> Result<()> {
         let path = path.as_ref();
         let path = CString::new(
@@ -448,6 +474,7 @@ wrapper!(State, TractState, tract_state_destroy);
 
 impl StateInterface for State {
     type Value = Value;
+    type Cancellation = Cancellation;
     fn run<I, V, E>(&mut self, inputs: I) -> Result<Vec<Value>>
     where
         I: IntoIterator<Item = V>,
@@ -465,6 +492,53 @@ impl StateInterface for State {
         Ok(outputs)
     }
 
+    unsafe fn bind_input(
+        &mut self,
+        input: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *const u8,
+    ) -> Result<()> {
+        check!(sys::tract_state_bind_input(
+            self.0,
+            input,
+            dt as _,
+            shape.len(),
+            shape.as_ptr(),
+            data as _
+        ))
+    }
+
+    unsafe fn bind_output(
+        &mut self,
+        output: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> Result<()> {
+        check!(sys::tract_state_bind_output(
+            self.0,
+            output,
+            dt as _,
+            shape.len(),
+            shape.as_ptr(),
+            data as _
+        ))
+    }
+
+    fn clear_bindings(&mut self) -> Result<()> {
+        check!(sys::tract_state_clear_bindings(self.0))
+    }
+
+    fn run_bound(&mut self) -> Result<()> {
+        check!(sys::tract_state_run_bound(self.0))
+    }
+
+    fn set_cancellation(&mut self, cancellation: Option<&Cancellation>) -> Result<()> {
+        let cancellation = cancellation.map(|c| c.0 .0 as *const _).unwrap_or(null());
+        check!(sys::tract_state_set_cancellation(self.0, cancellation))
+    }
+
     fn input_count(&self) -> Result<usize> {
         let mut count = 0;
         check!(sys::tract_state_input_count(self.0, &mut count))?;
@@ -478,6 +552,42 @@ impl StateInterface for State {
     }
 }
 
+// CANCELLATION
+#[derive(Debug)]
+struct CancellationPtr(*mut sys::TractCancellation);
+
+// libtract tokens can be cancelled from any thread
+unsafe impl Send for CancellationPtr {}
+unsafe impl Sync for CancellationPtr {}
+
+impl Drop for CancellationPtr {
+    fn drop(&mut self) {
+        unsafe {
+            sys::tract_cancellation_destroy(&mut self.0);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cancellation(std::sync::Arc<CancellationPtr>);
+
+impl CancellationInterface for Cancellation {
+    fn new(timeout: Option<std::time::Duration>) -> Result<Self> {
+        let timeout_us = timeout.map(|t| (t.as_micros() as u64).max(1)).unwrap_or(0);
+        let mut cancellation = null_mut();
+        check!(sys::tract_cancellation_create(timeout_us, &mut cancellation))?;
+        Ok(Cancellation(std::sync::Arc::new(CancellationPtr(cancellation))))
+    }
+
+    fn cancel(&self) -> Result<()> {
+        check!(sys::tract_cancellation_cancel(self.0 .0))
+    }
+
+    fn is_cancellation(error: &anyhow::Error) -> bool {
+        error.downcast_ref::<Cancelled>().is_some()
+    }
+}
+
 // VALUE
 wrapper!(Value, TractValue, tract_value_destroy);
 
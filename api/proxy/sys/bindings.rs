@@ -1913,10 +1913,17 @@ pub type DatumType = ::std::os::raw::c_uint;
 pub const TRACT_RESULT_TRACT_RESULT_OK: TRACT_RESULT = 0;
 #[doc = " The function returned an error"]
 pub const TRACT_RESULT_TRACT_RESULT_KO: TRACT_RESULT = 1;
+#[doc = " The function was stopped by a cancellation token"]
+pub const TRACT_RESULT_TRACT_RESULT_CANCELLED: TRACT_RESULT = 2;
 #[doc = " Used as a return type of functions that can encounter errors.\n If the function encountered an error, you can retrieve it using the `tract_get_last_error`\n function"]
 pub type TRACT_RESULT = ::std::os::raw::c_uint;
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct TractCancellation {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct TractFact {
     _unused: [u8; 0],
 }
@@ -1961,7 +1968,7 @@ pub struct TractValue {
     _unused: [u8; 0],
 }
 extern "C" {
-    #[doc = " Retrieve the last error that happened in this thread. A function encountered an error if\n its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO`.\n\n # Return value\n  It returns a pointer to a null-terminated UTF-8 string that will contain the error description.\n  Rust side keeps ownership of the buffer. It will be valid as long as no other tract calls is\n  performed by the thread.\n  If no error occured, null is returned."]
+    #[doc = " Retrieve the last error that happened in this thread. A function encountered an error if\n its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO` or\n `TRACT_RESULT_CANCELLED`.\n\n # Return value\n  It returns a pointer to a null-terminated UTF-8 string that will contain the error description.\n  Rust side keeps ownership of the buffer. It will be valid as long as no other tract calls is\n  performed by the thread.\n  If no error occured, null is returned."]
     pub fn tract_get_last_error() -> *const ::std::os::raw::c_char;
 }
 extern "C" {
@@ -1991,6 +1998,14 @@ extern "C" {
 extern "C" {
     pub fn tract_nnef_enable_extended_identifier_syntax(nnef: *mut TractNnef) -> TRACT_RESULT;
 }
+extern "C" {
+    #[doc = " Write a manifest.sha256 member with the digest of every other member when dumping models."]
+    pub fn tract_nnef_enable_manifest(nnef: *mut TractNnef) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Check models against their manifest.sha256 when loading them. Loading fails if the manifest\n is missing, or if a member is missing, unexpected or corrupted."]
+    pub fn tract_nnef_enable_manifest_verification(nnef: *mut TractNnef) -> TRACT_RESULT;
+}
 extern "C" {
     #[doc = " Destroy the NNEF parser. It is safe to detroy the NNEF parser once the model had been loaded."]
     pub fn tract_nnef_destroy(nnef: *mut *mut TractNnef) -> TRACT_RESULT;
@@ -2313,6 +2328,43 @@ extern "C" {
         outputs: *mut *mut TractValue,
     ) -> TRACT_RESULT;
 }
+extern "C" {
+    #[doc = " Bind a caller buffer to an input of a state: `tract_state_run_bound` reads the input from it,\n without copying it. Datum type and shape are checked against the input fact.\n\n rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`\n must be aligned for the datum type, and stay valid until the bindings are cleared or the\n state destroyed."]
+    pub fn tract_state_bind_input(
+        state: *mut TractState,
+        input: usize,
+        datum_type: DatumType,
+        rank: usize,
+        shape: *const usize,
+        data: *const ::std::os::raw::c_void,
+    ) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Bind a caller buffer to an output of a state: `tract_state_run_bound` writes the output in it,\n without allocating it when the operator computing it supports it. Datum type and shape are\n checked against the output fact.\n\n rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`\n must be aligned for the datum type, and stay valid until the bindings are cleared or the\n state destroyed."]
+    pub fn tract_state_bind_output(
+        state: *mut TractState,
+        output: usize,
+        datum_type: DatumType,
+        rank: usize,
+        shape: *const usize,
+        data: *mut ::std::os::raw::c_void,
+    ) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Forget the buffers bound to the inputs and outputs of a state."]
+    pub fn tract_state_clear_bindings(state: *mut TractState) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Run a turn on the buffers bound to a state. All inputs must be bound. Outputs without a bound\n buffer are dropped."]
+    pub fn tract_state_run_bound(state: *mut TractState) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Make the next runs of a state stop with `TRACT_RESULT_CANCELLED` once `cancellation` is\n cancelled or past its deadline. Runs check it between operators, scan iterations and matrix\n product tiles. A null `cancellation` runs without cancellation.\n\n The state keeps its own reference to the token: `cancellation` can be destroyed."]
+    pub fn tract_state_set_cancellation(
+        state: *mut TractState,
+        cancellation: *const TractCancellation,
+    ) -> TRACT_RESULT;
+}
 extern "C" {
     #[doc = " Query a State input counts."]
     pub fn tract_state_input_count(state: *const TractState, inputs: *mut usize) -> TRACT_RESULT;
@@ -2324,6 +2376,20 @@ extern "C" {
 extern "C" {
     pub fn tract_state_destroy(state: *mut *mut TractState) -> TRACT_RESULT;
 }
+extern "C" {
+    #[doc = " Create a cancellation token, with a deadline `timeout_us` microseconds from now, or without\n deadline if `timeout_us` is 0.\n\n The token must be destroyed with `tract_cancellation_destroy`."]
+    pub fn tract_cancellation_create(
+        timeout_us: u64,
+        cancellation: *mut *mut TractCancellation,
+    ) -> TRACT_RESULT;
+}
+extern "C" {
+    #[doc = " Cancel a token: the runs of the states it is set to stop with `TRACT_RESULT_CANCELLED`.\n\n This function can be called from any thread, including while a state runs."]
+    pub fn tract_cancellation_cancel(cancellation: *const TractCancellation) -> TRACT_RESULT;
+}
+extern "C" {
+    pub fn tract_cancellation_destroy(cancellation: *mut *mut TractCancellation) -> TRACT_RESULT;
+}
 extern "C" {
     #[doc = " Parse a fact specification string into an Fact.\n\n The returned fact must be free with `tract_fact_destroy`."]
     pub fn tract_fact_parse(
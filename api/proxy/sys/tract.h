@@ -38,8 +38,14 @@ typedef enum TRACT_RESULT {
    * The function returned an error
    */
   TRACT_RESULT_KO = 1,
+  /**
+   * The function was stopped by a cancellation token
+   */
+  TRACT_RESULT_CANCELLED = 2,
 } TRACT_RESULT;
 
+typedef struct TractCancellation TractCancellation;
+
 typedef struct TractFact TractFact;
 
 typedef struct TractInferenceFact TractInferenceFact;
@@ -60,7 +66,8 @@ typedef struct TractValue TractValue;
 
 /**
  * Retrieve the last error that happened in this thread. A function encountered an error if
- * its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO`.
+ * its return type is of type `TRACT_RESULT` and it returned `TRACT_RESULT_KO` or
+ * `TRACT_RESULT_CANCELLED`.
  *
  * # Return value
  *  It returns a pointer to a null-terminated UTF-8 string that will contain the error description.
@@ -100,6 +107,17 @@ enum TRACT_RESULT tract_nnef_enable_pulse(struct TractNnef *nnef);
 
 enum TRACT_RESULT tract_nnef_enable_extended_identifier_syntax(struct TractNnef *nnef);
 
+/**
+ * Write a manifest.sha256 member with the digest of every other member when dumping models.
+ */
+enum TRACT_RESULT tract_nnef_enable_manifest(struct TractNnef *nnef);
+
+/**
+ * Check models against their manifest.sha256 when loading them. Loading fails if the manifest
+ * is missing, or if a member is missing, unexpected or corrupted.
+ */
+enum TRACT_RESULT tract_nnef_enable_manifest_verification(struct TractNnef *nnef);
+
 /**
  * Destroy the NNEF parser. It is safe to detroy the NNEF parser once the model had been loaded.
  */
@@ -492,6 +510,58 @@ enum TRACT_RESULT tract_state_run(struct TractState *state,
                                   struct TractValue **inputs,
                                   struct TractValue **outputs);
 
+/**
+ * Bind a caller buffer to an input of a state: `tract_state_run_bound` reads the input from it,
+ * without copying it. Datum type and shape are checked against the input fact.
+ *
+ * rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`
+ * must be aligned for the datum type, and stay valid until the bindings are cleared or the
+ * state destroyed.
+ */
+enum TRACT_RESULT tract_state_bind_input(struct TractState *state,
+                                         uintptr_t input,
+                                         DatumType datum_type,
+                                         uintptr_t rank,
+                                         const uintptr_t *shape,
+                                         const void *data);
+
+/**
+ * Bind a caller buffer to an output of a state: `tract_state_run_bound` writes the output in it,
+ * without allocating it when the operator computing it supports it. Datum type and shape are
+ * checked against the output fact.
+ *
+ * rank is the number of dimensions of the tensor (i.e. the length of the shape vector). `data`
+ * must be aligned for the datum type, and stay valid until the bindings are cleared or the
+ * state destroyed.
+ */
+enum TRACT_RESULT tract_state_bind_output(struct TractState *state,
+                                          uintptr_t output,
+                                          DatumType datum_type,
+                                          uintptr_t rank,
+                                          const uintptr_t *shape,
+                                          void *data);
+
+/**
+ * Forget the buffers bound to the inputs and outputs of a state.
+ */
+enum TRACT_RESULT tract_state_clear_bindings(struct TractState *state);
+
+/**
+ * Run a turn on the buffers bound to a state. All inputs must be bound. Outputs without a bound
+ * buffer are dropped.
+ */
+enum TRACT_RESULT tract_state_run_bound(struct TractState *state);
+
+/**
+ * Make the next runs of a state stop with `TRACT_RESULT_CANCELLED` once `cancellation` is
+ * cancelled or past its deadline. Runs check it between operators, scan iterations and matrix
+ * product tiles. A null `cancellation` runs without cancellation.
+ *
+ * The state keeps its own reference to the token: `cancellation` can be destroyed.
+ */
+enum TRACT_RESULT tract_state_set_cancellation(struct TractState *state,
+                                               const struct TractCancellation *cancellation);
+
 /**
  * Query a State input counts.
  */
@@ -504,6 +574,24 @@ enum TRACT_RESULT tract_state_output_count(const struct TractState *state, uintp
 
 enum TRACT_RESULT tract_state_destroy(struct TractState **state);
 
+/**
+ * Create a cancellation token, with a deadline `timeout_us` microseconds from now, or without
+ * deadline if `timeout_us` is 0.
+ *
+ * The token must be destroyed with `tract_cancellation_destroy`.
+ */
+enum TRACT_RESULT tract_cancellation_create(uint64_t timeout_us,
+                                            struct TractCancellation **cancellation);
+
+/**
+ * Cancel a token: the runs of the states it is set to stop with `TRACT_RESULT_CANCELLED`.
+ *
+ * This function can be called from any thread, including while a state runs.
+ */
+enum TRACT_RESULT tract_cancellation_cancel(const struct TractCancellation *cancellation);
+
+enum TRACT_RESULT tract_cancellation_destroy(struct TractCancellation **cancellation);
+
 /**
  * Parse a fact specification string into an Fact.
  *
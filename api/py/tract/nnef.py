@@ -88,6 +88,22 @@ class Nnef:
         check(lib.tract_nnef_enable_extended_identifier_syntax(self.ptr, True))
         return self
 
+    def with_manifest(self) -> "Nnef":
+        """
+        Write a manifest with the SHA-256 digest of every member alongside the dumped models
+        """
+        self._valid()
+        check(lib.tract_nnef_enable_manifest(self.ptr))
+        return self
+
+    def with_manifest_verification(self) -> "Nnef":
+        """
+        Check loaded models against their manifest, failing on a missing, unexpected or corrupted member
+        """
+        self._valid()
+        check(lib.tract_nnef_enable_manifest_verification(self.ptr))
+        return self
+
     def write_model_to_dir(self, model: Model, path: Union[str, Path]) -> None:
         """
         Save `model` as a NNEF directory model in `path`.
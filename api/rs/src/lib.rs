@@ -9,7 +9,7 @@ use tract_libcli::annotations::Annotations;
 use tract_libcli::profile::BenchLimits;
 use tract_nnef::internal::parse_tdim;
 use tract_nnef::prelude::{
-    Framework, IntoTValue, SymbolValues, TValue, TVec, Tensor, TractResult, TypedFact, TypedModel,
+    CancellationToken, Cancelled, Framework, IntoTValue, SymbolValues, TValue, TVec, Tensor, TractResult, TypedFact, TypedModel,
     TypedRunnableModel, TypedSimplePlan, TypedSimpleState,
 };
 use tract_onnx::prelude::InferenceModelExt;
@@ -67,6 +67,16 @@ impl NnefInterface for Nnef {
         Ok(())
     }
 
+    fn enable_manifest(&mut self) -> Result<()> {
+        self.0.manifest(true);
+        Ok(())
+    }
+
+    fn enable_manifest_verification(&mut self) -> Result<()> {
+        self.0.verify_manifest(true);
+        Ok(())
+    }
+
     fn write_model_to_dir(&self, path: impl AsRef<Path>, model: &Model) -> Result<()> {
         self.0.write_to_dir(&model.0, path)
     }
@@ -333,6 +343,7 @@ pub struct State(TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>)
 
 impl StateInterface for State {
     type Value = Value;
+    type Cancellation = Cancellation;
 
     fn input_count(&self) -> Result<usize> {
         Ok(self.0.model().inputs.len())
@@ -355,6 +366,63 @@ impl StateInterface for State {
         let outputs = self.0.run(inputs)?;
         Ok(outputs.into_iter().map(Value).collect())
     }
+
+    unsafe fn bind_input(
+        &mut self,
+        input: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *const u8,
+    ) -> Result<()> {
+        self.0.bind_input(input, to_internal_dt(dt), shape, data)
+    }
+
+    unsafe fn bind_output(
+        &mut self,
+        output: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> Result<()> {
+        self.0.bind_output(output, to_internal_dt(dt), shape, data)
+    }
+
+    fn clear_bindings(&mut self) -> Result<()> {
+        self.0.clear_bindings();
+        Ok(())
+    }
+
+    fn run_bound(&mut self) -> Result<()> {
+        self.0.run_bound()
+    }
+
+    fn set_cancellation(&mut self, cancellation: Option<&Cancellation>) -> Result<()> {
+        self.0.set_cancellation(cancellation.map(|c| c.0.clone()));
+        Ok(())
+    }
+}
+
+// CANCELLATION
+#[derive(Clone, Debug)]
+pub struct Cancellation(CancellationToken);
+
+impl CancellationInterface for Cancellation {
+    fn new(timeout: Option<std::time::Duration>) -> Result<Self> {
+        let mut token = CancellationToken::new();
+        if let Some(timeout) = timeout {
+            token = token.with_timeout(timeout);
+        }
+        Ok(Cancellation(token))
+    }
+
+    fn cancel(&self) -> Result<()> {
+        self.0.cancel();
+        Ok(())
+    }
+
+    fn is_cancellation(error: &anyhow::Error) -> bool {
+        error.downcast_ref::<Cancelled>().is_some()
+    }
 }
 
 // VALUE
@@ -35,6 +35,14 @@ pub trait NnefInterface: Sized {
     /// the node names in serialized form.
     fn enable_extended_identifier_syntax(&mut self) -> Result<()>;
 
+    /// Write a manifest with the SHA-256 digest of every member (graph, tensors, ...) alongside
+    /// the models dumped by this framework.
+    fn enable_manifest(&mut self) -> Result<()>;
+
+    /// Refuse to load models without a manifest, or with a member that does not match it. The
+    /// error names the offending member.
+    fn enable_manifest_verification(&mut self) -> Result<()>;
+
     /// Convenience function, similar with enable_tract_core but allowing method chaining.
     fn with_tract_core(mut self) -> Result<Self> {
         self.enable_tract_core()?;
@@ -65,6 +73,18 @@ pub trait NnefInterface: Sized {
         Ok(self)
     }
 
+    /// Convenience function, similar with enable_manifest but allowing method chaining.
+    fn with_manifest(mut self) -> Result<Self> {
+        self.enable_manifest()?;
+        Ok(self)
+    }
+
+    /// Convenience function, similar with enable_manifest_verification but allowing method chaining.
+    fn with_manifest_verification(mut self) -> Result<Self> {
+        self.enable_manifest_verification()?;
+        Ok(self)
+    }
+
     /// Dump a TypedModel as a NNEF directory.
     ///
     /// `path` is the directory name to dump to
@@ -192,6 +212,7 @@ pub trait RunnableInterface {
 
 pub trait StateInterface {
     type Value: ValueInterface;
+    type Cancellation: CancellationInterface;
 
     fn input_count(&self) -> Result<usize>;
     fn output_count(&self) -> Result<usize>;
@@ -201,6 +222,61 @@ pub trait StateInterface {
         I: IntoIterator<Item = V>,
         V: TryInto<Self::Value, Error = E>,
         E: Into<anyhow::Error>;
+
+    /// Bind a caller buffer to an input: `run_bound` reads the input from it, without copying
+    /// it. Datum type and shape are checked against the input fact.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to the elements of a `dt` tensor of `shape`, be aligned for `dt`, and
+    /// stay valid until the bindings are cleared or the state dropped.
+    unsafe fn bind_input(
+        &mut self,
+        input: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *const u8,
+    ) -> Result<()>;
+
+    /// Bind a caller buffer to an output: `run_bound` writes the output in it, without
+    /// allocating it when the operator computing it supports it. Datum type and shape are
+    /// checked against the output fact.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to the elements of a `dt` tensor of `shape`, be aligned for `dt`, and
+    /// stay valid and writable until the bindings are cleared or the state dropped.
+    unsafe fn bind_output(
+        &mut self,
+        output: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> Result<()>;
+
+    /// Forget the buffers bound to inputs and outputs.
+    fn clear_bindings(&mut self) -> Result<()>;
+
+    /// Run a turn on the bound buffers. All inputs must be bound. Outputs without a bound buffer
+    /// are dropped.
+    fn run_bound(&mut self) -> Result<()>;
+
+    /// Make the next runs fail with a cancellation error once `cancellation` is cancelled or past
+    /// its deadline. Runs check it between operators, scan iterations and matrix product tiles.
+    /// `None` runs without cancellation.
+    fn set_cancellation(&mut self, cancellation: Option<&Self::Cancellation>) -> Result<()>;
+}
+
+/// A cancellation token, with an optional deadline. Clones share the token, and can cancel it
+/// from any thread.
+pub trait CancellationInterface: Sized + Clone + Send + Sync {
+    /// Create a token, with a deadline `timeout` from now if any.
+    fn new(timeout: Option<std::time::Duration>) -> Result<Self>;
+
+    fn cancel(&self) -> Result<()>;
+
+    /// True if `error` comes from a run stopped by a cancellation token.
+    fn is_cancellation(error: &anyhow::Error) -> bool;
 }
 
 pub trait ValueInterface: Sized + Clone {
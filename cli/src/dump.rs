@@ -12,6 +12,7 @@ use tract_libcli::model::Model;
 use tract_libcli::profile::BenchLimits;
 use tract_libcli::tensor::retrieve_or_make_inputs;
 use tract_libcli::terminal;
+use tract_nnef::compression::Compression;
 
 #[allow(unused_variables)]
 pub fn annotate_with_graph_def(
@@ -192,16 +193,48 @@ pub fn handle(
         }
     }
 
+    if sub_matches.is_present("nnef-check") {
+        let nnef = super::nnef(matches);
+        if let Some(typed) = model.downcast_ref::<TypedModel>() {
+            let unsupported = nnef.unsupported_nodes(typed)?;
+            for node in &unsupported {
+                println!("{} ({}): {}", node.name, node.op, node.reason);
+            }
+            if !unsupported.is_empty() {
+                bail!("{} node(s) can not be serialized to NNEF", unsupported.len())
+            }
+        } else {
+            bail!("Only typed model can be dumped")
+        }
+    }
+
     let compress_submodels = sub_matches.is_present("compress-submodels");
     let deterministic = sub_matches.is_present("nnef-deterministic");
-    if let Some(path) = sub_matches.value_of("nnef") {
-        let nnef = super::nnef(matches);
+    let compression_level =
+        sub_matches.value_of("nnef-compression-level").map(|l| l.parse::<i32>()).transpose()?;
+    for (flag, mut compression) in
+        [("nnef", Compression::gzip()), ("nnef-tar-zst", Compression::zstd())]
+    {
+        let Some(path) = sub_matches.value_of(flag) else { continue };
+        match (&mut compression, compression_level) {
+            (Compression::Gzip { level }, Some(l)) => *level = l as u32,
+            (Compression::Zstd { level }, Some(l)) => *level = l,
+            _ => (),
+        }
+        let mut nnef = super::nnef(matches);
+        if compress_submodels {
+            // nested models use the codec and level of the archive
+            nnef.member_compression.submodels = Some(compression);
+        }
         if let Some(mut typed) = model.downcast_ref::<TypedModel>().cloned() {
             rename_outputs(&mut typed, sub_matches)?;
             let file = fs::File::create(path)?;
-            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
-            nnef.write_to_tar_with_config(&typed, encoder, compress_submodels, deterministic)
-                .context("Writing model to tgz")?;
+            compression
+                .compress(file, |w| {
+                    nnef.write_to_tar_with_config(&typed, w, compress_submodels, deterministic)?;
+                    Ok(())
+                })
+                .with_context(|| format!("Writing model to {path:?}"))?;
         } else {
             bail!("Only typed model can be dumped")
         }
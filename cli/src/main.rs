@@ -152,6 +152,7 @@ fn main() -> TractResult<()> {
         .arg(arg!(--"nnef-tract-pulse" "Allow usage of tract-pulse extension in NNEF dump and load"))
         .arg(arg!(--"nnef-tract-extra" "Allow usage of tract-extra extension in NNEF dump and load"))
         .arg(arg!(--"nnef-extended-identifier" "Allow usage of the i\"...\" syntax to escape identifier names"))
+        .arg(arg!(--"nnef-mmap" "Memory-map NNEF tensor files from directories and uncompressed tars instead of reading them"))
 
         .arg(arg!(--"threads" [THREADS] "Setup a thread pool for computing. 0 will guess the number of physical cores"))
 
@@ -374,6 +375,23 @@ fn dump_subcommand<'a>() -> clap::Command<'a> {
             .long("nnef")
             .help("Dump the network in NNEF format (as a tar.gz file)"),
             )
+        .arg(
+            Arg::new("nnef-tar-zst")
+            .takes_value(true)
+            .long("nnef-tar-zst")
+            .help("Dump the network in NNEF format (as a tar.zst file)"),
+            )
+        .arg(
+            Arg::new("nnef-compression-level")
+            .takes_value(true)
+            .long("nnef-compression-level")
+            .help("Compression level for --nnef (0 to 9) and --nnef-tar-zst (1 to 22)"),
+            )
+        .arg(
+            Arg::new("nnef-check")
+            .long("nnef-check")
+            .help("List the nodes that can not be serialized to NNEF, and fail if there is any"),
+            )
         .arg(
             Arg::new("tflite")
             .takes_value(true)
@@ -383,7 +401,7 @@ fn dump_subcommand<'a>() -> clap::Command<'a> {
         .arg(
             Arg::new("compress-submodels")
             .long("compress-submodels")
-            .help("Compress submodels if any (as a .tgz file)"),
+            .help("Compress submodels if any (like the archive, as a .tgz file for --nnef-tar)"),
         )
         .arg(
             Arg::new("nnef-deterministic")
@@ -507,6 +525,14 @@ fn run_options(command: clap::Command) -> clap::Command {
             .long("skip-order-opt-ram")
             .help("Plan node evaluation order without RAM optimisation"),
             )
+        .arg(Arg::new("parallel-plan")
+            .long("parallel-plan")
+            .help("Evaluate independent nodes concurrently"),
+            )
+        .arg(Arg::new("memory-arena")
+            .long("memory-arena")
+            .help("Allocate operator outputs in a statically planned memory arena"),
+            )
         .arg(
             Arg::new("allow-random-input")
             .short('R')
@@ -767,5 +793,8 @@ fn nnef(matches: &clap::ArgMatches) -> tract_nnef::internal::Nnef {
     if matches.is_present("nnef-extended-identifier") {
         fw.allow_extended_identifier_syntax(true);
     }
+    if matches.is_present("nnef-mmap") {
+        fw.mmap_weights(true);
+    }
     fw
 }
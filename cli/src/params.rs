@@ -194,6 +194,7 @@ impl Parameters {
             } else if location.is_dir()
                 || location.path().to_string_lossy().ends_with(".tar")
                 || location.path().to_string_lossy().ends_with(".tar.gz")
+                || location.path().to_string_lossy().ends_with(".tar.zst")
                 || location.path().extension().map(|s| s == "tgz").unwrap_or(false)
             {
                 "nnef"
@@ -210,6 +211,8 @@ impl Parameters {
                     } else {
                         unreachable!();
                     }
+                } else if let (true, Location::Fs(path)) = (nnef.mmap_weights, &location) {
+                    nnef.proto_model_for_path(path)?
                 } else if location
                     .path()
                     .extension()
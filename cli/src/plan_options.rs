@@ -5,5 +5,13 @@ pub fn plan_options_from_subcommand(sub_matches: &clap::ArgMatches) -> TractResu
     if skip_order_opt_ram {
         log::info!("Plan options: skip_order_opt_ram -> {:?}", skip_order_opt_ram);
     }
-    Ok(PlanOptions { skip_order_opt_ram, ..PlanOptions::default() })
-}
\ No newline at end of file
+    let parallel: bool = sub_matches.is_present("parallel-plan");
+    if parallel {
+        log::info!("Plan options: parallel -> {:?}", parallel);
+    }
+    let memory_arena: bool = sub_matches.is_present("memory-arena");
+    if memory_arena {
+        log::info!("Plan options: memory_arena -> {:?}", memory_arena);
+    }
+    Ok(PlanOptions { skip_order_opt_ram, parallel, memory_arena, ..PlanOptions::default() })
+}
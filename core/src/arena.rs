@@ -0,0 +1,323 @@
+//! Static memory planning for the outputs of CPU operators.
+//!
+//! The plan computes the lifetime of each node output from its evaluation order and flush lists,
+//! and packs them in partitions of nodes with disjoint lifetimes. Each run, the partition sizes
+//! are resolved with the session symbols and the outputs of the operators supporting it are
+//! allocated at their partition offset in a single arena, kept across runs.
+//!
+//! Operators get these outputs from [uninitialized_output]. A partition is only reused when no
+//! tensor allocated in it is alive anymore: outputs still referenced past their planned lifetime
+//! (because an operator forwarded them, for instance) make the next node of the partition fall
+//! back to a regular allocation.
+use std::cell::Cell;
+use std::fmt::{Debug, Display};
+
+use crate::internal::*;
+use crate::io_binding::IoBindings;
+use crate::model::order::build_flush_list;
+use crate::model::Fact;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lifetime {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Lifetime {
+    pub fn is_disjoint(&self, other: &Lifetime) -> bool {
+        self.start >= other.end || other.start >= self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Memory requirement for the output of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeMemReq {
+    pub node: usize,
+    pub lifetime: Lifetime,
+    pub mem_size: TDim,
+}
+
+/// Nodes with disjoint lifetimes, sharing the same memory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Partition {
+    pub nodes: Vec<NodeMemReq>,
+}
+
+impl Partition {
+    pub fn eval_size(&self, symbols: &SymbolValues) -> TractResult<usize> {
+        let mut size = 0;
+        for node in &self.nodes {
+            size = size.max(node.mem_size.eval_to_i64(symbols)? as usize);
+        }
+        Ok(size.next_multiple_of(vector_size()))
+    }
+
+    pub fn has_no_conflict_with_lifetime(&self, lifetime: &Lifetime) -> bool {
+        self.nodes.iter().all(|n| n.lifetime.is_disjoint(lifetime))
+    }
+}
+
+/// Memory schema of a plan: outputs of the single-output nodes using the arena, except the plan
+/// outputs, which escape to the caller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemSchema {
+    pub model_num_nodes: usize,
+    pub by_partition: Vec<Partition>,
+}
+
+impl MemSchema {
+    pub fn build<F, O>(
+        model: &Graph<F, O>,
+        order: &[usize],
+        outputs: &[OutletId],
+    ) -> TractResult<MemSchema>
+    where
+        F: Fact + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+    {
+        let flush_lists = build_flush_list(model, order, outputs, |n| {
+            n.op().uses_memory_arena()
+                && n.outputs.len() == 1
+                && !outputs.iter().any(|o| o.node == n.id)
+        });
+        let mut flushed_at = vec![None; model.nodes().len()];
+        for (step, flush_list) in flush_lists.iter().enumerate() {
+            for &node in flush_list {
+                flushed_at[node] = Some(step);
+            }
+        }
+        let mut nodes_mem_req = vec![];
+        for (step, &n) in order.iter().enumerate() {
+            let Some(end) = flushed_at[n] else { continue };
+            let Ok(fact) = model.node(n).outputs[0].fact.to_typed_fact() else { continue };
+            if !fact.datum_type.is_copy() {
+                continue;
+            }
+            let mem_size = fact.shape.volume() * fact.datum_type.size_of();
+            // the output is flushed when its last consumer gets its inputs, before it runs
+            let lifetime = Lifetime { start: step, end: (end + 1).min(order.len()) };
+            nodes_mem_req.push(NodeMemReq { node: n, lifetime, mem_size });
+        }
+        nodes_mem_req.sort_by(|lhs, rhs| {
+            lhs.lifetime
+                .end
+                .cmp(&rhs.lifetime.end)
+                .reverse()
+                .then(lhs.lifetime.len().cmp(&rhs.lifetime.len()).reverse())
+        });
+        let mut by_partition: Vec<Partition> = vec![];
+        for node_mem in nodes_mem_req {
+            match by_partition
+                .iter_mut()
+                .find(|p| p.has_no_conflict_with_lifetime(&node_mem.lifetime))
+            {
+                Some(partition) => partition.nodes.push(node_mem),
+                None => by_partition.push(Partition { nodes: vec![node_mem] }),
+            }
+        }
+        Ok(MemSchema { model_num_nodes: model.nodes().len(), by_partition })
+    }
+
+    /// Compute partition offsets and sizes for given symbol values.
+    pub fn resolve(&self, symbols: &SymbolValues) -> TractResult<ResolvedMemSchema> {
+        let mut resolved = ResolvedMemSchema {
+            partition_by_node: vec![None; self.model_num_nodes],
+            offsets_by_partition: vec![],
+            sizes_by_partition: vec![],
+            memory_size: 0,
+        };
+        for (ix, partition) in self.by_partition.iter().enumerate() {
+            for node in &partition.nodes {
+                resolved.partition_by_node[node.node] = Some(ix);
+            }
+            let size = partition.eval_size(symbols)?;
+            resolved.offsets_by_partition.push(resolved.memory_size);
+            resolved.sizes_by_partition.push(size);
+            resolved.memory_size += size;
+        }
+        Ok(resolved)
+    }
+}
+
+/// Memory schema with concrete offsets and sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedMemSchema {
+    pub partition_by_node: Vec<Option<usize>>,
+    pub offsets_by_partition: Vec<usize>,
+    pub sizes_by_partition: Vec<usize>,
+    pub memory_size: usize,
+}
+
+/// Keeps the arena storage alive, and tracks the tensors allocated in a partition.
+#[derive(Debug)]
+struct PartitionToken {
+    _storage: Arc<Tensor>,
+}
+
+/// Arena holding the outputs planned by a resolved memory schema, stored in the session scratch
+/// extensions by the plan.
+#[derive(Debug)]
+pub struct MemoryArena {
+    schema: ResolvedMemSchema,
+    storage: Arc<Tensor>,
+    tokens: Vec<Arc<PartitionToken>>,
+    current_node: Cell<Option<usize>>,
+}
+
+impl MemoryArena {
+    pub fn new(schema: ResolvedMemSchema) -> TractResult<MemoryArena> {
+        let storage = Arc::new(unsafe {
+            Tensor::uninitialized_aligned_dt(DatumType::U8, &[schema.memory_size], vector_size())?
+        });
+        let tokens = (0..schema.sizes_by_partition.len())
+            .map(|_| Arc::new(PartitionToken { _storage: storage.clone() }))
+            .collect();
+        Ok(MemoryArena { schema, storage, tokens, current_node: Cell::new(None) })
+    }
+
+    pub fn schema(&self) -> &ResolvedMemSchema {
+        &self.schema
+    }
+
+    /// Set the node being evaluated, the only one allowed to get a tensor from the arena.
+    pub fn set_current_node(&self, node: Option<usize>) {
+        self.current_node.set(node)
+    }
+
+    /// Tensor in the partition of the current node, if it is free and large enough. The arena
+    /// serves a node once.
+    pub fn tensor_for_current_node(
+        &self,
+        dt: DatumType,
+        shape: &[usize],
+    ) -> TractResult<Option<Tensor>> {
+        let Some(node) = self.current_node.take() else { return Ok(None) };
+        let Some(partition) = self.schema.partition_by_node.get(node).copied().flatten() else {
+            return Ok(None);
+        };
+        let bytes = shape.iter().product::<usize>() * dt.size_of();
+        let token = &self.tokens[partition];
+        if !dt.is_copy()
+            || bytes == 0
+            || bytes > self.schema.sizes_by_partition[partition]
+            || Arc::strong_count(token) > 1
+        {
+            return Ok(None);
+        }
+        let offset = self.schema.offsets_by_partition[partition];
+        // the partition is only reachable through this pointer while the token is alive
+        unsafe {
+            let data = self.storage.as_raw_storage_ptr().add(offset);
+            Tensor::from_external_ptr(dt, shape, data, token.clone()).map(Some)
+        }
+    }
+}
+
+/// Allocate the output of the node being evaluated, in the caller buffer bound to it if any, in
+/// the session memory arena if the plan has one, on the heap otherwise. The content is
+/// uninitialized.
+pub unsafe fn uninitialized_output(
+    session: &SessionState,
+    dt: DatumType,
+    shape: &[usize],
+) -> TractResult<Tensor> {
+    if let Some(bindings) = session.scratch_extensions.get::<IoBindings>() {
+        if let Some(tensor) = bindings.tensor_for_current_node(dt, shape)? {
+            return Ok(tensor);
+        }
+    }
+    if let Some(arena) = session.scratch_extensions.get::<MemoryArena>() {
+        if let Some(tensor) = arena.tensor_for_current_node(dt, shape)? {
+            return Ok(tensor);
+        }
+    }
+    Tensor::uninitialized_dt(dt, shape)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::einsum::EinSum;
+
+    fn arena() -> TractResult<MemoryArena> {
+        MemoryArena::new(ResolvedMemSchema {
+            partition_by_node: vec![Some(0), Some(0), Some(1)],
+            offsets_by_partition: vec![0, 64],
+            sizes_by_partition: vec![64, 64],
+            memory_size: 128,
+        })
+    }
+
+    #[test]
+    fn partition_reused_once_free() -> TractResult<()> {
+        let arena = arena()?;
+        arena.set_current_node(Some(0));
+        let t0 = arena.tensor_for_current_node(f32::datum_type(), &[16])?.unwrap();
+        assert!(t0.has_external_storage());
+        // served once per node
+        assert!(arena.tensor_for_current_node(f32::datum_type(), &[16])?.is_none());
+        // node 0 output still alive
+        arena.set_current_node(Some(1));
+        assert!(arena.tensor_for_current_node(f32::datum_type(), &[16])?.is_none());
+        arena.set_current_node(Some(2));
+        assert!(arena.tensor_for_current_node(f32::datum_type(), &[16])?.is_some());
+        drop(t0);
+        arena.set_current_node(Some(1));
+        assert!(arena.tensor_for_current_node(f32::datum_type(), &[16])?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_output_not_in_arena() -> TractResult<()> {
+        let arena = arena()?;
+        arena.set_current_node(Some(0));
+        assert!(arena.tensor_for_current_node(f32::datum_type(), &[17])?.is_none());
+        Ok(())
+    }
+
+    // a chain of matmuls, the intermediate ones are planned in the arena, all in the same
+    // partition as each one is consumed by the packing of the next one
+    fn chain() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let s = model.symbols.sym("S");
+        let mut wire = model.add_source("x", f32::fact(&[s.to_dim(), 8.to_dim()]))?;
+        for ix in 0..4 {
+            let w = (0..64).map(|i| ((i + ix) % 7) as f32 / 10.).collect::<Vec<_>>();
+            let w = model.add_const(format!("w.{ix}"), tensor1(&w).into_shape(&[8, 8])?)?;
+            let op = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+            wire = model.wire_node(format!("mm.{ix}"), op, &[wire, w])?[0];
+        }
+        model.set_output_outlets(&[wire])?;
+        model.into_optimized()
+    }
+
+    #[test]
+    fn plan_with_arena() -> TractResult<()> {
+        let model = chain()?;
+        let reference = model.clone().into_runnable()?;
+        let options = PlanOptions { memory_arena: true, ..PlanOptions::default() };
+        let plan = model.into_runnable_with_options(&options)?;
+        let schema = plan.mem_schema().unwrap();
+        assert_eq!(schema.by_partition.len(), 1);
+        assert_eq!(schema.by_partition[0].nodes.len(), 3);
+        let mut state = SimpleState::new(&plan)?;
+        for rows in [3, 3, 5, 1] {
+            let x = tensor1(&(0..rows * 8).map(|i| i as f32).collect::<Vec<_>>())
+                .into_shape(&[rows, 8])?;
+            let expected = reference.run(tvec!(x.clone().into_tvalue()))?;
+            let found = state.run(tvec!(x.into_tvalue()))?;
+            assert_eq!(expected, found);
+            let arena = state.session_state.scratch_extensions.get::<MemoryArena>().unwrap();
+            assert!(arena.schema().memory_size >= rows * 8 * 4);
+        }
+        Ok(())
+    }
+}
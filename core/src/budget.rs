@@ -0,0 +1,270 @@
+//! Peak memory budget of plans.
+//!
+//! The memory usage at a step of the evaluation order is the size of the values alive during the
+//! evaluation of its node, as estimated by [crate::model::memory::eval_tmp_memory_usage]. When it
+//! depends on symbols, it is evaluated at their upper bounds, as given by the assertions of the
+//! model symbol scope, in every scenario. If some symbols are not bounded, the budget is checked
+//! by each run instead, once the symbols of the model inputs are resolved.
+use std::fmt::{Debug, Display};
+
+use crate::internal::*;
+use crate::model::memory::eval_tmp_memory_usage_for_outputs;
+use crate::model::Fact;
+use crate::ops::konst::Const;
+use tract_data::internal::Assertion;
+
+/// Error of a plan, or a run, whose memory usage exceeds the budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryBudgetExceeded {
+    pub budget: usize,
+    pub peak: usize,
+    /// Scenario in which the budget is exceeded, if the model has scenarios.
+    pub scenario: Option<String>,
+    /// Steps exceeding the budget, as node ids and names, and their memory usage in bytes, by
+    /// decreasing usage.
+    pub offending: Vec<(usize, String, usize)>,
+}
+
+impl Display for MemoryBudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Peak memory usage of {} bytes exceeds budget of {} bytes",
+            self.peak, self.budget
+        )?;
+        if let Some(scenario) = &self.scenario {
+            write!(f, " in scenario {scenario}")?;
+        }
+        write!(f, ". Offending nodes:")?;
+        for (id, name, usage) in self.offending.iter().take(10) {
+            write!(f, " {name} (#{id}): {usage} bytes,")?;
+        }
+        if self.offending.len() > 10 {
+            write!(f, " and {} more.", self.offending.len() - 10)
+        } else {
+            write!(f, " done.")
+        }
+    }
+}
+
+impl std::error::Error for MemoryBudgetExceeded {}
+
+/// Memory usage of an evaluation order, by step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub by_step: TVec<(usize, TDim)>,
+}
+
+impl MemoryUsage {
+    pub fn eval<F, O>(
+        model: &Graph<F, O>,
+        order: &[usize],
+        outputs: &[OutletId],
+    ) -> TractResult<MemoryUsage>
+    where
+        F: Fact + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+    {
+        let by_step =
+            eval_tmp_memory_usage_for_outputs(model, order, outputs, |n| !n.op_is::<Const>())?;
+        Ok(MemoryUsage { by_step })
+    }
+
+    /// Check the usage against the budget with the given symbol values. Returns false if some
+    /// step usage is not known with these values.
+    pub fn check<F, O>(
+        &self,
+        model: &Graph<F, O>,
+        budget: usize,
+        symbols: &SymbolValues,
+        scenario: Option<&str>,
+    ) -> TractResult<bool>
+    where
+        F: Fact + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+    {
+        let mut offending = vec![];
+        let mut peak = 0;
+        for (node, usage) in &self.by_step {
+            let Ok(usage) = usage.eval(symbols).to_i64() else { return Ok(false) };
+            let usage = usage as usize;
+            peak = peak.max(usage);
+            if usage > budget {
+                offending.push((*node, model.node(*node).name.clone(), usage));
+            }
+        }
+        if !offending.is_empty() {
+            offending.sort_by_key(|(node, _, usage)| (std::cmp::Reverse(*usage), *node));
+            let scenario = scenario.map(|s| s.to_string());
+            return Err(MemoryBudgetExceeded { budget, peak, scenario, offending }.into());
+        }
+        Ok(true)
+    }
+}
+
+/// Upper bounds of the symbols, from assertions comparing them to integers.
+fn upper_bounds<'a>(assertions: impl IntoIterator<Item = &'a Assertion>) -> SymbolValues {
+    let mut bounds = SymbolValues::default();
+    let mut bound = |sym: &TDim, value: &TDim, margin: i64| {
+        if let (TDim::Sym(sym), Ok(value)) = (sym, value.to_i64()) {
+            let value = value - margin;
+            if !bounds.get(sym).is_some_and(|b| b <= value) {
+                bounds.set(sym, value);
+            }
+        }
+    };
+    for assertion in assertions {
+        match assertion {
+            Assertion::LTE(sym, value) | Assertion::GTE(value, sym) => bound(sym, value, 0),
+            Assertion::LT(sym, value) | Assertion::GT(value, sym) => bound(sym, value, 1),
+            Assertion::Eq(left, right) => {
+                bound(left, right, 0);
+                bound(right, left, 0);
+            }
+        }
+    }
+    bounds
+}
+
+/// Pick the first evaluation order meeting the budget at the symbols upper bounds, in every
+/// scenario. Returns the order, and the usage the runs must check if it depends on unbounded
+/// symbols. Fails with the report of the least consuming order if no order meets the budget.
+pub(crate) fn select_order<F, O>(
+    model: &Graph<F, O>,
+    outputs: &[OutletId],
+    budget: usize,
+    candidates: Vec<Vec<usize>>,
+) -> TractResult<(Vec<usize>, Option<MemoryUsage>)>
+where
+    F: Fact + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+{
+    let global = model.symbols.all_assertions();
+    let mut scenarios: Vec<(Option<String>, SymbolValues)> = model
+        .symbols
+        .all_scenarios()
+        .into_iter()
+        .map(|(name, assertions)| (Some(name), upper_bounds(global.iter().chain(&assertions))))
+        .collect();
+    if scenarios.is_empty() {
+        scenarios.push((None, upper_bounds(&global)));
+    }
+    let mut best: Option<MemoryBudgetExceeded> = None;
+    let mut first_unknown = None;
+    'candidates: for order in candidates {
+        let usage = MemoryUsage::eval(model, &order, outputs)?;
+        let mut known = true;
+        for (scenario, bounds) in &scenarios {
+            match usage.check(model, budget, bounds, scenario.as_deref()) {
+                Ok(checked) => known &= checked,
+                Err(e) => {
+                    let exceeded = e.downcast::<MemoryBudgetExceeded>()?;
+                    if !best.as_ref().is_some_and(|best| best.peak <= exceeded.peak) {
+                        best = Some(exceeded);
+                    }
+                    continue 'candidates;
+                }
+            }
+        }
+        if known {
+            return Ok((order, None));
+        }
+        first_unknown.get_or_insert((order, usage));
+    }
+    if let Some((order, usage)) = first_unknown {
+        return Ok((order, Some(usage)));
+    }
+    Err(best.context("No evaluation order to check")?.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    // two branches from x, each computing a [n, n] temporary reduced to [1, n]. The memory
+    // friendly order computes one branch after the other, the simple order computes both
+    // temporaries before reducing them.
+    fn model(n: TDim) -> TractResult<TypedModel> {
+        use crate::ops::array::MultiBroadcastTo;
+        use crate::ops::nn::{Reduce, Reducer};
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact(&[1.to_dim(), n.clone()]))?;
+        let mut branches = tvec!();
+        for b in 0..2 {
+            let wide = model.wire_node(
+                format!("wide.{b}"),
+                MultiBroadcastTo::new(tvec!(n.clone(), n.clone()).into()),
+                &[x],
+            )?;
+            let reduced = model.wire_node(
+                format!("reduced.{b}"),
+                Reduce::new(tvec!(0), Reducer::Sum),
+                &wide,
+            )?;
+            branches.push(reduced[0]);
+        }
+        let sum = model.wire_node("sum", math::add(), &branches)?;
+        model.set_output_outlets(&sum)?;
+        Ok(model)
+    }
+
+    fn options(budget: usize, skip_order_opt_ram: bool) -> PlanOptions {
+        PlanOptions { memory_budget: Some(budget), skip_order_opt_ram, ..PlanOptions::default() }
+    }
+
+    #[test]
+    fn budget_picks_order() -> TractResult<()> {
+        let model = model(100.to_dim())?;
+        let id = |name: &str| model.node_id_by_name(name).unwrap();
+        let order = |names: &[&str]| names.iter().map(|n| id(n)).collect::<Vec<_>>();
+        // both [100, 100] temporaries alive at once, then one after the other
+        let wide = order(&["x", "wide.0", "wide.1", "reduced.0", "reduced.1", "sum"]);
+        let narrow = order(&["x", "wide.0", "reduced.0", "wide.1", "reduced.1", "sum"]);
+        let outputs = model.output_outlets()?;
+        let candidates = vec![wide.clone(), narrow.clone()];
+        assert_eq!(select_order(&model, outputs, 100_000, candidates.clone())?, (wide, None));
+        assert_eq!(select_order(&model, outputs, 60_000, candidates)?, (narrow, None));
+        let plan = SimplePlan::new_with_options(&model, &options(60_000, true))?;
+        plan.run(tvec!(Tensor::zero::<f32>(&[1, 100])?.into()))?;
+        Ok(())
+    }
+
+    #[test]
+    fn budget_not_met() -> TractResult<()> {
+        let model = model(100.to_dim())?;
+        let err = SimplePlan::new_with_options(&model, &options(30_000, false)).unwrap_err();
+        let exceeded = err.downcast_ref::<MemoryBudgetExceeded>().unwrap();
+        assert_eq!(exceeded.budget, 30_000);
+        assert!(exceeded.peak > 40_000);
+        assert!(exceeded.offending.iter().any(|(_, name, _)| name.starts_with("reduced")));
+        Ok(())
+    }
+
+    #[test]
+    fn budget_with_scenario_bounds() -> TractResult<()> {
+        let symbols = SymbolScope::default()
+            .with_scenario_assertion("small", "N <= 10")?
+            .with_scenario_assertion("large", "N <= 1000")?;
+        let n = symbols.sym("N");
+        let mut model = model(n.to_dim())?;
+        model.symbols = symbols;
+        let err = SimplePlan::new_with_options(&model, &options(1_000_000, false)).unwrap_err();
+        let exceeded = err.downcast_ref::<MemoryBudgetExceeded>().unwrap();
+        assert_eq!(exceeded.scenario.as_deref(), Some("large"));
+        Ok(())
+    }
+
+    #[test]
+    fn budget_checked_by_runs() -> TractResult<()> {
+        let symbols = SymbolScope::default();
+        let n = symbols.sym("N");
+        let mut model = model(n.to_dim())?;
+        model.symbols = symbols;
+        let plan = SimplePlan::new_with_options(&model, &options(60_000, false))?;
+        plan.run(tvec!(Tensor::zero::<f32>(&[1, 100])?.into()))?;
+        let err = plan.run(tvec!(Tensor::zero::<f32>(&[1, 1000])?.into())).unwrap_err();
+        assert!(err.downcast_ref::<MemoryBudgetExceeded>().is_some(), "{err:?}");
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+//! Byte format of frozen states.
+//!
+//! [crate::plan::FrozenSimpleState::save] writes a state to bytes, and
+//! [crate::plan::SimpleState::restore] reads them back into a state for the same model, possibly
+//! in another process: this allows migrating a live streaming session.
+//!
+//! The format starts with a magic and a version, followed by the session (resolved symbols, by
+//! name, scenario, inputs and tensors stored by memory ops), then the states of the stateful
+//! nodes, each preceded by the node id and name to check the model is the same. Op states are
+//! written by [FrozenOpState::save] and read by [OpState::restore], which states fail to do by
+//! default. Integers are little endian, and so is tensor data, whatever the endianness of the
+//! host: tensors of strings, booleans, integers, floats and quantized integers are supported,
+//! their datum type written as a tag defined by the format version. Lengths read from the bytes
+//! are not trusted: memory is only allocated as the bytes they announce are actually read, and
+//! booleans other than 0 or 1 are rejected.
+use std::io::{Read, Write};
+
+use crate::internal::*;
+
+pub const FROZEN_STATE_MAGIC: &[u8; 4] = b"TRFS";
+pub const FROZEN_STATE_VERSION: u32 = 2;
+
+pub fn write_u64(w: &mut dyn Write, v: u64) -> TractResult<()> {
+    w.write_all(&v.to_le_bytes())?;
+    Ok(())
+}
+
+pub fn read_u64(r: &mut dyn Read) -> TractResult<u64> {
+    let mut bytes = [0u8; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+pub fn write_usize(w: &mut dyn Write, v: usize) -> TractResult<()> {
+    write_u64(w, v as u64)
+}
+
+pub fn read_usize(r: &mut dyn Read) -> TractResult<usize> {
+    Ok(read_u64(r)?.try_into()?)
+}
+
+pub fn write_string(w: &mut dyn Write, s: &str) -> TractResult<()> {
+    write_usize(w, s.len())?;
+    w.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+pub fn read_string(r: &mut dyn Read) -> TractResult<String> {
+    let len = read_usize(r)?;
+    Ok(String::from_utf8(read_bytes(r, len)?)?)
+}
+
+// exactly `len` bytes, allocated as they are read
+fn read_bytes(r: &mut dyn Read, len: usize) -> TractResult<Vec<u8>> {
+    let mut bytes = vec![];
+    (&mut *r).take(len as u64).read_to_end(&mut bytes)?;
+    ensure!(bytes.len() == len, "Truncated frozen state: expected {len} bytes");
+    Ok(bytes)
+}
+
+fn write_datum_type(w: &mut dyn Write, dt: DatumType) -> TractResult<()> {
+    use DatumType::*;
+    let tag: u8 = match dt {
+        Bool => 1,
+        U8 => 2,
+        U16 => 3,
+        U32 => 4,
+        U64 => 5,
+        I8 => 6,
+        I16 => 7,
+        I32 => 8,
+        I64 => 9,
+        F16 => 10,
+        F32 => 11,
+        F64 => 12,
+        String => 13,
+        QI8(_) => 14,
+        QU8(_) => 15,
+        QI32(_) => 16,
+        _ => bail!("Can not serialize a tensor of {dt:?}"),
+    };
+    w.write_all(&[tag])?;
+    match dt.qparams() {
+        Some(QParams::MinMax { min, max }) => {
+            w.write_all(&[0])?;
+            w.write_all(&min.to_le_bytes())?;
+            w.write_all(&max.to_le_bytes())?;
+        }
+        Some(QParams::ZpScale { zero_point, scale }) => {
+            w.write_all(&[1])?;
+            w.write_all(&zero_point.to_le_bytes())?;
+            w.write_all(&scale.to_le_bytes())?;
+        }
+        None => (),
+    }
+    Ok(())
+}
+
+fn read_datum_type(r: &mut dyn Read) -> TractResult<DatumType> {
+    use DatumType::*;
+    let mut tag = [0u8];
+    r.read_exact(&mut tag)?;
+    let qparams = |r: &mut dyn Read| -> TractResult<QParams> {
+        let mut bytes = [0u8; 9];
+        r.read_exact(&mut bytes)?;
+        let a: [u8; 4] = bytes[1..5].try_into().unwrap();
+        let b: [u8; 4] = bytes[5..9].try_into().unwrap();
+        Ok(match bytes[0] {
+            0 => QParams::MinMax { min: f32::from_le_bytes(a), max: f32::from_le_bytes(b) },
+            1 => {
+                QParams::ZpScale { zero_point: i32::from_le_bytes(a), scale: f32::from_le_bytes(b) }
+            }
+            kind => bail!("Invalid quantization parameters kind {kind} in frozen state"),
+        })
+    };
+    Ok(match tag[0] {
+        1 => Bool,
+        2 => U8,
+        3 => U16,
+        4 => U32,
+        5 => U64,
+        6 => I8,
+        7 => I16,
+        8 => I32,
+        9 => I64,
+        10 => F16,
+        11 => F32,
+        12 => F64,
+        13 => String,
+        14 => QI8(qparams(r)?),
+        15 => QU8(qparams(r)?),
+        16 => QI32(qparams(r)?),
+        tag => bail!("Invalid datum type tag {tag} in frozen state"),
+    })
+}
+
+pub fn write_tensor(w: &mut dyn Write, t: &Tensor) -> TractResult<()> {
+    let dt = t.datum_type();
+    write_datum_type(w, dt)?;
+    write_usize(w, t.rank())?;
+    for d in t.shape() {
+        write_usize(w, *d)?;
+    }
+    if dt == String::datum_type() {
+        for s in t.as_slice::<String>()? {
+            write_string(w, s)?;
+        }
+    } else if cfg!(target_endian = "little") || dt.size_of() == 1 {
+        w.write_all(t.as_bytes())?;
+    } else {
+        for item in t.as_bytes().chunks(dt.size_of()) {
+            let mut item = item.to_vec();
+            item.reverse();
+            w.write_all(&item)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_tensor(r: &mut dyn Read) -> TractResult<Tensor> {
+    let dt = read_datum_type(r)?;
+    let rank = read_usize(r)?;
+    let shape = (0..rank).map(|_| read_usize(r)).collect::<TractResult<TVec<_>>>()?;
+    let volume = shape
+        .iter()
+        .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+        .context("Invalid tensor shape in frozen state")?;
+    if dt == String::datum_type() {
+        let mut strings = vec![];
+        for _ in 0..volume {
+            strings.push(read_string(r)?);
+        }
+        Ok(tract_ndarray::ArrayD::from_shape_vec(&*shape, strings)?.into())
+    } else {
+        let len =
+            volume.checked_mul(dt.size_of()).context("Invalid tensor size in frozen state")?;
+        let mut bytes = read_bytes(r, len)?;
+        if dt == bool::datum_type() {
+            ensure!(bytes.iter().all(|b| *b <= 1), "Invalid boolean in frozen state");
+        }
+        if cfg!(target_endian = "big") {
+            bytes.chunks_mut(dt.size_of()).for_each(|item| item.reverse());
+        }
+        unsafe { Tensor::from_raw_dt(dt, &shape, &bytes) }
+    }
+}
+
+pub fn write_opt_tensor(w: &mut dyn Write, t: Option<&Tensor>) -> TractResult<()> {
+    w.write_all(&[t.is_some() as u8])?;
+    if let Some(t) = t {
+        write_tensor(w, t)?;
+    }
+    Ok(())
+}
+
+pub fn read_opt_tensor(r: &mut dyn Read) -> TractResult<Option<Tensor>> {
+    let mut tag = [0u8];
+    r.read_exact(&mut tag)?;
+    Ok(if tag[0] != 0 { Some(read_tensor(r)?) } else { None })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn roundtrip(t: Tensor) -> TractResult<()> {
+        let mut bytes = vec![];
+        write_tensor(&mut bytes, &t)?;
+        let restored = read_tensor(&mut &*bytes)?;
+        assert_eq!(restored.datum_type(), t.datum_type());
+        assert_eq!(restored, t);
+        Ok(())
+    }
+
+    #[test]
+    fn tensors() -> TractResult<()> {
+        roundtrip(tensor2(&[[1f32, -2.], [3., 4.5]]))?;
+        roundtrip(tensor1(&[f16::from_f32(1.5), f16::from_f32(-2.)]))?;
+        roundtrip(tensor1(&[i64::MIN, 0, i64::MAX]))?;
+        roundtrip(tensor1(&[true, false]))?;
+        roundtrip(tensor0(12u16))?;
+        roundtrip(tensor1(&["foo".to_string(), String::new()]))?;
+        roundtrip(Tensor::zero::<f32>(&[0, 3])?)?;
+        let qi8 = i8::datum_type().quantize(QParams::ZpScale { zero_point: -3, scale: 0.5 });
+        roundtrip(tensor1(&[1i8, -7]).cast_to_dt(qi8)?.into_owned())?;
+        let qu8 = u8::datum_type().quantize(QParams::MinMax { min: -1., max: 2. });
+        roundtrip(tensor1(&[1u8, 255]).cast_to_dt(qu8)?.into_owned())?;
+        Ok(())
+    }
+
+    #[test]
+    fn little_endian_data() -> TractResult<()> {
+        let mut bytes = vec![];
+        write_tensor(&mut bytes, &tensor1(&[0x0102_0304u32]))?;
+        // tag, rank, dim, data
+        assert_eq!(bytes.len(), 1 + 8 + 8 + 4);
+        assert_eq!(&bytes[17..], &[4, 3, 2, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn untrusted_lengths() -> TractResult<()> {
+        // a string announcing more bytes than there are
+        let mut bytes = vec![];
+        write_usize(&mut bytes, usize::MAX / 2)?;
+        bytes.extend_from_slice(b"abc");
+        assert!(read_string(&mut &*bytes).is_err());
+        // a tensor announcing a huge shape
+        let mut bytes = vec![11u8];
+        write_usize(&mut bytes, 2)?;
+        write_usize(&mut bytes, 1 << 40)?;
+        write_usize(&mut bytes, 1 << 40)?;
+        bytes.extend_from_slice(&[0; 16]);
+        assert!(read_tensor(&mut &*bytes).is_err());
+        let mut bytes = vec![11u8];
+        write_usize(&mut bytes, 1)?;
+        write_usize(&mut bytes, 1 << 40)?;
+        assert!(read_tensor(&mut &*bytes).is_err());
+        // booleans that are neither 0 nor 1
+        let mut bytes = vec![1u8];
+        write_usize(&mut bytes, 1)?;
+        write_usize(&mut bytes, 2)?;
+        bytes.extend_from_slice(&[1, 2]);
+        assert!(read_tensor(&mut &*bytes).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_datum_types() {
+        assert!(write_tensor(&mut vec![], &tensor1(&[TDim::from(1)])).is_err());
+        assert!(read_tensor(&mut &[42u8][..]).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+//! Caller buffers bound to the inputs and outputs of a state.
+//!
+//! Input buffers are wrapped once, at bind time, in tensors sharing their storage, so runs do not
+//! copy them. Output buffers are written in place by the operators allocating their output with
+//! [crate::arena::uninitialized_output], and receive a copy of the output otherwise.
+use std::cell::Cell;
+
+use crate::internal::*;
+
+/// Keeps alive the tensors sharing the storage of a bound buffer.
+#[derive(Debug)]
+struct BindingToken;
+
+/// Check a buffer against the fact of the outlet it is bound to. Symbolic dimensions are checked
+/// at run time.
+pub(crate) fn check_fact(fact: &TypedFact, dt: DatumType, shape: &[usize]) -> TractResult<()> {
+    ensure!(dt.is_copy(), "Can not bind a buffer of {dt:?}");
+    ensure!(
+        fact.datum_type == dt
+            && fact.rank() == shape.len()
+            && fact
+                .shape
+                .iter()
+                .zip(shape)
+                .all(|(d, s)| d.to_i64().map_or(true, |d| d == *s as i64)),
+        "Buffer of {dt:?} {shape:?} does not match fact {fact:?}"
+    );
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) struct OutputBinding {
+    outlet: OutletId,
+    dt: DatumType,
+    shape: TVec<usize>,
+    data: *mut u8,
+    token: Arc<BindingToken>,
+}
+
+impl OutputBinding {
+    fn write(&self, value: &Tensor) -> TractResult<()> {
+        ensure!(
+            value.datum_type() == self.dt && value.shape() == &*self.shape,
+            "Got {:?} {:?}, bound buffer is {:?} {:?}",
+            value.datum_type(),
+            value.shape(),
+            self.dt,
+            self.shape
+        );
+        let bytes = value.as_bytes();
+        if !std::ptr::eq(bytes.as_ptr(), self.data) {
+            unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.data, bytes.len()) }
+        }
+        Ok(())
+    }
+}
+
+/// Buffers bound to a state, stored in its session scratch extensions.
+#[derive(Debug, Default)]
+pub(crate) struct IoBindings {
+    inputs: TVec<Option<TValue>>,
+    outputs: TVec<Option<OutputBinding>>,
+    /// Set while running on the bound buffers: plain runs do not write in them.
+    pub active: bool,
+    current_node: Cell<Option<usize>>,
+}
+
+impl IoBindings {
+    pub unsafe fn bind_input(
+        &mut self,
+        input: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *const u8,
+    ) -> TractResult<()> {
+        // the tensor is shared by the bindings, so it is never written in
+        let tensor =
+            Tensor::from_external_ptr(dt, shape, data as *mut u8, Arc::new(BindingToken))?;
+        if self.inputs.len() <= input {
+            self.inputs.resize(input + 1, None);
+        }
+        self.inputs[input] = Some(tensor.into_tvalue());
+        Ok(())
+    }
+
+    pub unsafe fn bind_output(
+        &mut self,
+        output: usize,
+        outlet: OutletId,
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> TractResult<()> {
+        ensure!(shape.iter().product::<usize>() > 0, "Can not bind an empty buffer");
+        ensure!(data as usize % dt.alignment() == 0, "Misaligned buffer for {dt:?}");
+        if self.outputs.len() <= output {
+            self.outputs.resize_with(output + 1, || None);
+        }
+        let binding =
+            OutputBinding { outlet, dt, shape: shape.into(), data, token: Arc::new(BindingToken) };
+        self.outputs[output] = Some(binding);
+        Ok(())
+    }
+
+    pub fn input(&self, input: usize) -> TractResult<TValue> {
+        self.inputs
+            .get(input)
+            .cloned()
+            .flatten()
+            .with_context(|| format!("No buffer bound to input {input}"))
+    }
+
+    /// True if operators may compute outputs in their bound buffers.
+    pub fn writes_in_place(&self) -> bool {
+        self.active && self.outputs.iter().any(|o| o.is_some())
+    }
+
+    /// Set the node being evaluated, the only one allowed to get its bound output buffer.
+    pub fn set_current_node(&self, node: Option<usize>) {
+        self.current_node.set(node)
+    }
+
+    /// Tensor sharing the buffer bound to the output of the current node, if it matches and is
+    /// not referenced by another tensor anymore.
+    pub fn tensor_for_current_node(
+        &self,
+        dt: DatumType,
+        shape: &[usize],
+    ) -> TractResult<Option<Tensor>> {
+        let Some(node) = self.current_node.take() else { return Ok(None) };
+        let Some(binding) = self.outputs.iter().flatten().find(|b| b.outlet == (node, 0).into())
+        else {
+            return Ok(None);
+        };
+        if binding.dt != dt || &*binding.shape != shape || Arc::strong_count(&binding.token) > 1 {
+            return Ok(None);
+        }
+        unsafe {
+            Tensor::from_external_ptr(dt, shape, binding.data, binding.token.clone()).map(Some)
+        }
+    }
+
+    /// Write the outputs in their bound buffers, unless they have been computed in place.
+    pub fn write_outputs<'a>(
+        &self,
+        mut output: impl FnMut(usize) -> TractResult<&'a Tensor>,
+    ) -> TractResult<()> {
+        for (ix, binding) in self.outputs.iter().enumerate() {
+            if let Some(binding) = binding {
+                binding.write(output(ix)?).with_context(|| format!("Writing output {ix}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::einsum::EinSum;
+    use crate::ops::math::add;
+
+    // y = x.w, computed in its bound buffer once optimized, and z = y + w[0]
+    fn model() -> TractResult<TypedSimplePlan<TypedModel>> {
+        let mut model = TypedModel::default();
+        let s = model.symbols.sym("S");
+        let x = model.add_source("x", f32::fact(&[s.to_dim(), 4.to_dim()]))?;
+        let w = (0..16).map(|i| i as f32).collect::<Vec<_>>();
+        let w = model.add_const("w", tensor1(&w).into_shape(&[4, 4])?)?;
+        let op = EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let y = model.wire_node("y", op, &[x, w])?[0];
+        let one = model.add_const("one", tensor2(&[[1f32]]))?;
+        let z = model.wire_node("z", add(), &[y, one])?[0];
+        model.set_output_outlets(&[y, z])?;
+        model.into_optimized()?.into_runnable()
+    }
+
+    #[test]
+    fn run_bound() -> TractResult<()> {
+        let plan = model()?;
+        let mut state = SimpleState::new(&plan)?;
+        let mut x = [0f32; 8];
+        let mut y = [0f32; 8];
+        let mut z = [0f32; 8];
+        unsafe {
+            state.bind_input(0, f32::datum_type(), &[2, 4], x.as_ptr() as _)?;
+            state.bind_output(0, f32::datum_type(), &[2, 4], y.as_mut_ptr() as _)?;
+            state.bind_output(1, f32::datum_type(), &[2, 4], z.as_mut_ptr() as _)?;
+        }
+        for turn in 0..3 {
+            for (ix, x) in x.iter_mut().enumerate() {
+                *x = (ix + turn) as f32;
+            }
+            state.run_bound()?;
+            let expected = plan.run(tvec!(tensor1(&x).into_shape(&[2, 4])?.into_tvalue()))?;
+            assert_eq!(&y, expected[0].as_slice::<f32>()?);
+            assert_eq!(&z, expected[1].as_slice::<f32>()?);
+        }
+        // plain runs do not write in the bound buffers
+        state.run(tvec!(Tensor::zero::<f32>(&[2, 4])?.into_tvalue()))?;
+        assert_ne!(y, [0f32; 8]);
+        Ok(())
+    }
+
+    #[test]
+    fn binding_checks_facts() -> TractResult<()> {
+        let plan = model()?;
+        let mut state = SimpleState::new(&plan)?;
+        let mut buffer = [0f32; 8];
+        let ptr = buffer.as_mut_ptr() as *mut u8;
+        unsafe {
+            assert!(state.bind_input(0, i32::datum_type(), &[2, 4], ptr).is_err());
+            assert!(state.bind_input(0, f32::datum_type(), &[4, 2], ptr).is_err());
+            assert!(state.bind_input(1, f32::datum_type(), &[2, 4], ptr).is_err());
+            assert!(state.bind_output(0, f32::datum_type(), &[8], ptr).is_err());
+            state.bind_output(0, f32::datum_type(), &[2, 4], ptr)?;
+        }
+        let err = state.run_bound().unwrap_err();
+        assert!(err.to_string().contains("No buffer bound to input 0"), "{err}");
+        state.clear_bindings();
+        assert!(state.run_bound().is_err());
+        Ok(())
+    }
+}
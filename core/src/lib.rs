@@ -79,19 +79,25 @@ pub mod macros;
 #[macro_use]
 pub mod ops;
 
+pub mod arena;
 pub mod axes;
 pub mod broadcast;
+pub mod budget;
 pub mod framework;
 pub mod floats;
+pub mod frozen;
 pub mod model;
+pub mod observer;
 pub mod optim;
 pub mod plan;
 pub mod runtime;
 pub mod transform;
 pub mod value;
+pub mod weights;
 
 pub use dyn_clone;
 
+mod io_binding;
 mod late_bind;
 
 /// This prelude is meant for code using tract.
@@ -101,6 +107,7 @@ pub mod prelude {
     pub use crate::plan::{SimplePlan, SimpleState, PlanOptions};
     pub use crate::value::{IntoTValue, TValue};
     pub use std::sync::Arc;
+    pub use tract_linalg::cancel::{CancellationToken, Cancelled};
     pub use tract_data::prelude::*;
 
     pub use ndarray as tract_ndarray;
@@ -113,11 +120,13 @@ pub mod prelude {
 /// This prelude is meant for code extending tract (like implementing new ops).
 pub mod internal {
     pub use crate::axes::{AxesMapping, Axis};
+    pub use crate::budget::MemoryBudgetExceeded;
     pub use crate::late_bind::*;
     pub use crate::model::*;
     pub use crate::ops::change_axes::*;
     pub use crate::ops::element_wise::ElementWiseMiniOp;
     pub use crate::ops::{Cost, EvalOp, FrozenOpState, Op, OpState, Validation};
+    pub use crate::observer::{NodeObserver, ObservedNode};
     pub use crate::plan::{ SessionState, SessionStateHandler };
     pub use crate::prelude::*;
     pub use dims;
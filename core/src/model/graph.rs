@@ -715,9 +715,16 @@ where
     }
 
     pub fn compact(&mut self) -> TractResult<()> {
+        self.compact_with_mapping()?;
+        Ok(())
+    }
+
+    /// Compact the graph, returning the new id of each previous node (`usize::MAX` for the
+    /// removed ones).
+    pub fn compact_with_mapping(&mut self) -> TractResult<Vec<usize>> {
         let mut order = self.eval_order()?;
         if order.len() == self.nodes.len() && order.iter().enumerate().all(|(a, b)| a == *b) {
-            return Ok(());
+            return Ok(order);
         }
         for i in &self.inputs {
             if !order.contains(&i.node) {
@@ -776,7 +783,7 @@ where
         {
             self.check_compact().context("after graph compaction")?;
         }
-        Ok(())
+        Ok(old_to_new)
     }
 
     pub fn into_compact(mut self) -> TractResult<Self> {
@@ -17,8 +17,23 @@ where
     Flushable: Fn(&Node<F, O>) -> bool,
 {
     let outputs = model.output_outlets()?.to_vec();
+    eval_tmp_memory_usage_for_outputs(model, order, &outputs, flushable)
+}
 
-    let flush_lists = super::order::build_flush_list(model, order, &outputs, &flushable);
+/// Evaluate temporary memory usage with its related node at each step of the given order, when
+/// computing the given outputs.
+pub fn eval_tmp_memory_usage_for_outputs<F, O, Flushable>(
+    model: &Graph<F, O>,
+    order: &[usize],
+    outputs: &[OutletId],
+    flushable: Flushable,
+) -> TractResult<TVec<(usize, TDim)>>
+where
+    F: Fact + Clone + 'static,
+    O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+    Flushable: Fn(&Node<F, O>) -> bool,
+{
+    let flush_lists = super::order::build_flush_list(model, order, outputs, &flushable);
     let mut values: TVec<bool> = tvec![false; model.nodes.len()];
 
     let mut mem_by_steps: TVec<_> = tvec![(0, 0.into()); order.len()];
@@ -162,6 +162,19 @@ impl TypedModel {
         self.optimize()?;
         Ok(self)
     }
+
+    /// Optimize the model, keeping the constants named in `weights`, by node name or outlet
+    /// label, updatable. See [crate::weights].
+    pub fn into_optimized_with_updatable_weights(
+        mut self,
+        weights: &[impl AsRef<str>],
+    ) -> TractResult<(TypedModel, crate::weights::UpdatableWeights)> {
+        crate::weights::UpdatableWeights::mark(&mut self, weights)?;
+        self.declutter()?;
+        self.optimize()?;
+        let weights = crate::weights::UpdatableWeights::fold(&mut self)?;
+        Ok((self, weights))
+    }
     #[cfg(not(all(debug_assertions, feature = "paranoid_assertions")))]
     #[inline]
     pub fn check_consistency(&self) -> TractResult<()> {
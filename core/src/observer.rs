@@ -0,0 +1,243 @@
+//! Observation of the nodes evaluated by a plan.
+//!
+//! An observer attached to a plan or to a state is called around the evaluation of each node,
+//! including the nodes of the bodies of Scan and submodel operators: their states are nested in
+//! the state evaluating the operator, and observed in its scope.
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use crate::internal::*;
+
+/// A node being evaluated.
+#[derive(Clone, Copy, Debug)]
+pub struct ObservedNode<'a> {
+    /// Ids of the nodes whose bodies contain the node, outermost first. Empty for the nodes of the
+    /// top model.
+    pub scope: &'a [usize],
+    pub id: usize,
+    pub name: &'a str,
+    pub op: &'a dyn Op,
+}
+
+/// Callbacks around the evaluation of each node. An error aborts the run.
+///
+/// Parallel plans call the observer from the threads of their pool.
+pub trait NodeObserver: Send + Sync + Debug {
+    /// Called with the inputs of the node. The operator may consume its inputs in place, so they
+    /// are not available anymore after its evaluation.
+    #[allow(unused_variables)]
+    fn before_node(&self, node: &ObservedNode, inputs: &[TValue]) -> TractResult<()> {
+        Ok(())
+    }
+
+    /// Called with the outputs of the node, and the time its evaluation took.
+    #[allow(unused_variables)]
+    fn after_node(
+        &self,
+        node: &ObservedNode,
+        outputs: &[TValue],
+        elapsed: Duration,
+    ) -> TractResult<()> {
+        Ok(())
+    }
+}
+
+impl<N: NodeObserver> NodeObserver for Arc<N> {
+    fn before_node(&self, node: &ObservedNode, inputs: &[TValue]) -> TractResult<()> {
+        (**self).before_node(node, inputs)
+    }
+
+    fn after_node(
+        &self,
+        node: &ObservedNode,
+        outputs: &[TValue],
+        elapsed: Duration,
+    ) -> TractResult<()> {
+        (**self).after_node(node, outputs, elapsed)
+    }
+}
+
+/// The observer of a state, stored in its session scratch extensions.
+#[derive(Clone, Debug)]
+pub(crate) struct Observation {
+    pub observer: Arc<dyn NodeObserver>,
+    pub scope: TVec<usize>,
+    /// The node being evaluated, scope of the nested states.
+    pub current_node: Option<usize>,
+}
+
+impl Observation {
+    pub fn new(observer: Arc<dyn NodeObserver>) -> Observation {
+        Observation { observer, scope: tvec!(), current_node: None }
+    }
+
+    pub fn observe(
+        &self,
+        id: usize,
+        name: &str,
+        op: &dyn Op,
+        inputs: TVec<TValue>,
+        eval: impl FnOnce(TVec<TValue>) -> TractResult<TVec<TValue>>,
+    ) -> TractResult<TVec<TValue>> {
+        let node = ObservedNode { scope: &self.scope, id, name, op };
+        self.observer.before_node(&node, &inputs)?;
+        let start = Instant::now();
+        let outputs = eval(inputs)?;
+        self.observer.after_node(&node, &outputs, start.elapsed())?;
+        Ok(outputs)
+    }
+}
+
+/// Observe a state nested in the evaluation of a node of the `outer` session, if it is observed.
+pub(crate) fn nest(outer: &SessionState, inner: &mut SessionState) {
+    match outer.scratch_extensions.get::<Observation>() {
+        Some(observation) => {
+            let scope = observation.scope.iter().copied().chain(observation.current_node);
+            inner.scratch_extensions.insert(Observation {
+                observer: observation.observer.clone(),
+                scope: scope.collect(),
+                current_node: None,
+            });
+        }
+        None => {
+            inner.scratch_extensions.remove::<Observation>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::ops::math;
+    use crate::ops::scan::{InputMapping, OutputMapping, Scan, ScanInfo};
+
+    // scope, name, after evaluation, first value of the first input or output
+    type Record = (TVec<usize>, String, bool, f32);
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<Record>>);
+
+    impl NodeObserver for Recorder {
+        fn before_node(&self, node: &ObservedNode, inputs: &[TValue]) -> TractResult<()> {
+            let first = inputs.first().map(|i| i.as_slice::<f32>().unwrap()[0]).unwrap_or(0.0);
+            self.0.lock().unwrap().push((node.scope.into(), node.name.to_string(), false, first));
+            Ok(())
+        }
+
+        fn after_node(
+            &self,
+            node: &ObservedNode,
+            outputs: &[TValue],
+            _: Duration,
+        ) -> TractResult<()> {
+            let first = outputs[0].as_slice::<f32>()?[0];
+            ensure!(first.is_finite(), "{} computed a non finite value", node.name);
+            self.0.lock().unwrap().push((node.scope.into(), node.name.to_string(), true, first));
+            Ok(())
+        }
+    }
+
+    // y = abs(x) scanning over the three rows of x, then z = log(y)
+    fn model() -> TractResult<TypedModel> {
+        let mut body = TypedModel::default();
+        let row = body.add_source("row", f32::fact([1, 2]))?;
+        let abs = body.wire_node("abs", math::abs(), &[row])?;
+        body.set_output_outlets(&abs)?;
+        let info = ScanInfo { axis: 0, chunk: 1 };
+        let output = OutputMapping {
+            scan: Some((0, info)),
+            full_dim_hint: None,
+            last_value_slot: None,
+            state: false,
+        };
+        let scan = Scan::new(body, vec![InputMapping::Scan(info)], vec![output], 0)?;
+
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([3, 2]))?;
+        let y = model.wire_node("y", scan, &[x])?;
+        let z = model.wire_node("z", math::ln(), &y)?;
+        model.set_output_outlets(&z)?;
+        Ok(model)
+    }
+
+    fn input(first: f32) -> TVec<TValue> {
+        tvec!(tensor2(&[[first, 1.], [-2., 2.], [-3., 3.]]).into())
+    }
+
+    #[test]
+    fn observe_plan_and_scan_body() -> TractResult<()> {
+        let y = model()?.node_id_by_name("y")?;
+        let mut expected = vec![
+            (tvec!(), "x".to_string(), false, 0.),
+            (tvec!(), "x".to_string(), true, -1.),
+            (tvec!(), "y".to_string(), false, -1.),
+        ];
+        for row in 1..=3 {
+            let row = row as f32;
+            expected.push((tvec!(y), "row".to_string(), false, 0.));
+            expected.push((tvec!(y), "row".to_string(), true, -row));
+            expected.push((tvec!(y), "abs".to_string(), false, -row));
+            expected.push((tvec!(y), "abs".to_string(), true, row));
+        }
+        expected.push((tvec!(), "y".to_string(), true, 1.));
+        expected.push((tvec!(), "z".to_string(), false, 1.));
+        expected.push((tvec!(), "z".to_string(), true, 0.));
+        for parallel in [false, true] {
+            let options = PlanOptions { parallel, ..PlanOptions::default() };
+            let recorder = Arc::new(Recorder::default());
+            let plan =
+                SimplePlan::new_with_options(model()?, &options)?.with_observer(recorder.clone());
+            plan.run(input(-1.))?;
+            assert_eq!(*recorder.0.lock().unwrap(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn observer_error_aborts_run() -> TractResult<()> {
+        let plan = model()?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        state.set_observer(Recorder::default());
+        let err = state.run(input(0.)).unwrap_err();
+        assert!(format!("{err:?}").contains("z computed a non finite value"), "{err:?}");
+        state.clear_observer();
+        state.run(input(0.))?;
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct CancelAfterFirstRow(CancellationToken, Recorder);
+
+    impl NodeObserver for CancelAfterFirstRow {
+        fn after_node(
+            &self,
+            node: &ObservedNode,
+            outputs: &[TValue],
+            d: Duration,
+        ) -> TractResult<()> {
+            self.1.after_node(node, outputs, d)?;
+            if node.name == "abs" {
+                self.0.cancel();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cancellation_between_scan_iterations() -> TractResult<()> {
+        let plan = model()?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        let token = CancellationToken::new();
+        let observer = Arc::new(CancelAfterFirstRow(token.clone(), Recorder::default()));
+        state.set_observer(observer.clone());
+        state.set_cancellation(Some(token));
+        let err = state.run(input(-1.)).unwrap_err();
+        assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Requested), "{err:?}");
+        let records = observer.1 .0.lock().unwrap();
+        let names = records.iter().map(|r| &*r.1).collect::<Vec<_>>();
+        assert_eq!(names, vec!("x", "row", "abs"));
+        Ok(())
+    }
+}
@@ -42,10 +42,21 @@ pub trait BinMiniOp: fmt::Debug + dyn_clone::DynClone + Send + Sync + 'static +
             Ok(tensor)
         } else {
             let c_shape = crate::broadcast::multi_broadcast(&[a.shape(), b.shape()])?;
-            if &*c_shape == a.shape() && c_dt == a.datum_type() {
+            let a_fits = &*c_shape == a.shape() && c_dt == a.datum_type();
+            // only swap operands of the same plain type, eval_in_a looks at a for quantization
+            let b_fits = self.is_commutative()
+                && &*c_shape == b.shape()
+                && c_dt == b.datum_type()
+                && a.datum_type() == b.datum_type()
+                && !c_dt.is_quantized();
+            if a_fits && (a.is_exclusive() || !b_fits || !b.is_exclusive()) {
                 let mut a = a.into_tensor();
                 self.eval_in_a(&mut a, &b)?;
                 Ok(a)
+            } else if b_fits && b.is_exclusive() {
+                let mut b = b.into_tensor();
+                self.eval_in_a(&mut b, &a)?;
+                Ok(b)
             } else {
                 let mut c = unsafe { Tensor::uninitialized_dt(c_dt, &c_shape)? };
                 self.eval_out_of_place(&mut c, &a, &b)?;
@@ -123,6 +134,14 @@ impl EvalOp for TypedBinOp {
         true
     }
 
+    fn in_place_inputs(&self) -> TVec<usize> {
+        if self.0.is_commutative() {
+            tvec!(0, 1)
+        } else {
+            tvec!(0)
+        }
+    }
+
     fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let (a, b) = args_2!(inputs);
         ensure!(a.rank() == b.rank());
@@ -274,34 +293,22 @@ impl TypedOp for TypedBinOp {
 
             let dt = model.node_input_facts(node.id)?[0].datum_type().unwrap();
             if by_scalar_should_be_efficient & can_eval_in_a & !op_is_quant {
-                let Some(func) = tract_linalg::bin_by_scalar(dt, linalg_bin_op) else {
+                let Some(op) = OptBinByScalar::new(op, dt) else {
                     return Ok(None);
                 };
-                let eval_fn = Arc::from(func);
                 return Ok(Some(
-                    TypedModelPatch::replace_single_op(
-                        model,
-                        node,
-                        &inputs,
-                        OptBinByScalar { binop: op, eval_fn },
-                    )?
-                    .with_context("ByScalar"),
+                    TypedModelPatch::replace_single_op(model, node, &inputs, op)?
+                        .with_context("ByScalar"),
                 ));
             }
 
             if unicast_should_be_efficient & can_eval_in_a & !op_is_quant {
-                let Some(func) = tract_linalg::bin_unicast(dt, linalg_bin_op) else {
+                let Some(op) = OptBinUnicast::new(op, dt) else {
                     return Ok(None);
                 };
-                let eval_fn = Arc::from(func);
                 return Ok(Some(
-                    TypedModelPatch::replace_single_op(
-                        model,
-                        node,
-                        &inputs,
-                        OptBinUnicast { binop: op, eval_fn },
-                    )?
-                    .with_context("Unicast"),
+                    TypedModelPatch::replace_single_op(model, node, &inputs, op)?
+                        .with_context("Unicast"),
                 ));
             }
         }
@@ -441,6 +448,12 @@ impl Debug for OptBinByScalar {
 }
 
 impl OptBinByScalar {
+    /// Evaluate `binop` with the linalg kernel for `dt`, if there is one.
+    pub fn new(binop: Box<dyn BinMiniOp>, dt: DatumType) -> Option<OptBinByScalar> {
+        let eval_fn = Arc::from(tract_linalg::bin_by_scalar(dt, binop.as_linalg_binop()?)?);
+        Some(OptBinByScalar { binop, eval_fn })
+    }
+
     fn check_input_shapes(a_shape: &[TDim], b_shape: &[TDim]) -> bool {
         if a_shape.len() != b_shape.len() {
             return false;
@@ -472,6 +485,10 @@ impl EvalOp for OptBinByScalar {
         true
     }
 
+    fn in_place_inputs(&self) -> TVec<usize> {
+        tvec!(0)
+    }
+
     fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let (a, b) = args_2!(inputs);
         // Not a requirement as TensorView doesn't require a owned tensor but in reality
@@ -540,6 +557,12 @@ impl Debug for OptBinUnicast {
 }
 
 impl OptBinUnicast {
+    /// Evaluate `binop` with the linalg kernel for `dt`, if there is one.
+    pub fn new(binop: Box<dyn BinMiniOp>, dt: DatumType) -> Option<OptBinUnicast> {
+        let eval_fn = Arc::from(tract_linalg::bin_unicast(dt, binop.as_linalg_binop()?)?);
+        Some(OptBinUnicast { binop, eval_fn })
+    }
+
     fn check_b_alignement(a_shape: &[TDim], b_shape: &[TDim]) -> bool {
         let num_iterations: TDim = a_shape
             .iter()
@@ -599,6 +622,10 @@ impl EvalOp for OptBinUnicast {
         true
     }
 
+    fn in_place_inputs(&self) -> TVec<usize> {
+        tvec!(0)
+    }
+
     fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let (a, b) = args_2!(inputs);
         // Not a requirement as TensorView doesn't require a owned tensor but in reality
@@ -99,6 +99,10 @@ impl EvalOp for ElementWiseOp {
         true
     }
 
+    fn in_place_inputs(&self) -> TVec<usize> {
+        tvec!(0)
+    }
+
     fn eval(&self, mut inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         if let Some(_dt) = self.0.output_type(inputs[0].datum_type()) {
             Ok(tvec!(self.0.eval_out_of_place(&inputs[0], self.1)?.into_tvalue()))
@@ -89,6 +89,10 @@ impl OpState for PinConst {
         ) -> TractResult<TVec<TValue>> {
         Ok(inputs)
     }
+
+    fn restore(&mut self, _op: &dyn Op, _r: &mut dyn std::io::Read) -> TractResult<()> {
+        Ok(())
+    }
 }
 
 impl TypedOp for PinConst {
@@ -99,4 +103,4 @@ impl TypedOp for PinConst {
     as_op!();
 }
 
-trivial_op_state_freeeze!(PinConst);
+trivial_op_state_freeeze!(PinConst, from_op);
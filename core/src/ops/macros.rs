@@ -232,5 +232,22 @@ macro_rules! trivial_op_state_freeeze {
             }
         }
     };
+    // states rebuilt from their op, with nothing to serialize
+    ($state:ty, from_op) => {
+        impl $crate::ops::FrozenOpState for $state {
+            fn unfreeze(&self) -> Box<dyn OpState> {
+                Box::new(self.clone())
+            }
+
+            fn save(&self, _w: &mut dyn std::io::Write) -> TractResult<()> {
+                Ok(())
+            }
+        }
+        impl $crate::ops::OpStateFreeze for $state {
+            fn freeze(&self) -> Box<dyn $crate::ops::FrozenOpState> {
+                Box::new(self.clone())
+            }
+        }
+    };
 }
 
@@ -230,6 +230,7 @@ bin_to_super_type!(rem, Rem,
                                                   Rem.generic_eval(a,b, c_dt)
                                               }
                                       },
+                                      is_commutative: false,
                                       out_of_place: |c:&mut Tensor, a:&Tensor, b: &Tensor| -> TractResult<bool> {
                                           if c.datum_type() == TDim::datum_type() &&
                                               a.datum_type() == TDim::datum_type() && b.datum_type() == TDim::datum_type() {
@@ -731,7 +732,7 @@ q: [i8, u8, i32] => f32::signum);
 
 #[cfg(test)]
 mod tests {
-    use crate::ops::binary::TypedBinOp;
+    use crate::ops::binary::{BinMiniOp, TypedBinOp};
 
     use super::*;
     use ndarray::arr2;
@@ -793,4 +794,19 @@ mod tests {
         assert!(op.0.downcast_ref::<ShiftRight>().is_some());
         Ok(())
     }
+
+    #[test]
+    fn add_in_b() -> TractResult<()> {
+        let a: TValue = tensor2(&[[1f32, 2.]]).into();
+        let b: TValue = tensor2(&[[1f32, 1.], [2., 2.]]).into();
+        let ptr = b.as_ptr::<f32>()?;
+        let c = Add.eval(a.clone(), b, f32::datum_type())?;
+        assert_eq!(c, tensor2(&[[2f32, 3.], [3., 4.]]));
+        assert_eq!(c.as_ptr::<f32>()?, ptr);
+        // not commutative
+        let b: TValue = tensor2(&[[3f32, 3.], [4., 4.]]).into();
+        let c = Rem.eval(a, b, f32::datum_type())?;
+        assert_eq!(c, tensor2(&[[1f32, 2.], [1., 2.]]));
+        Ok(())
+    }
 }
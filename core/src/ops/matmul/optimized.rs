@@ -329,6 +329,10 @@ impl EvalOp for OptMatMul {
         true
     }
 
+    fn uses_memory_arena(&self) -> bool {
+        true
+    }
+
     fn eval_with_session(
         &self,
         session: &SessionState,
@@ -336,7 +340,8 @@ impl EvalOp for OptMatMul {
     ) -> TractResult<TVec<TValue>> {
         unsafe {
             let c_shape = self.c_fact.shape.eval_to_usize(&session.resolved_symbols)?;
-            let mut c = Tensor::uninitialized_dt(self.c_fact.datum_type, &c_shape)?;
+            let mut c =
+                crate::arena::uninitialized_output(session, self.c_fact.datum_type, &c_shape)?;
             let mode = self.mode_picker.pick(c_shape[self.c_n_axis])?;
             let mmm = &*self.mmm[mode];
             let mut cell = session.cached_mmm_scratch_space.borrow_mut();
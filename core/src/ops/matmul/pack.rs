@@ -78,6 +78,35 @@ impl TypedOp for OptMatMulPack {
 }
 
 impl OptMatMulPack {
+    /// Pack the input along `k_axis` and `mn_axis` in one of the `packers` formats, chosen by the
+    /// mode picker from the mn dimension.
+    pub fn new(
+        packers: Vec<PackedFormat>,
+        mode_picker: ModePicker,
+        k_axis: usize,
+        mn_axis: usize,
+    ) -> TractResult<OptMatMulPack> {
+        ensure!(!packers.is_empty(), "OptMatMulPack needs at least one packing format");
+        ensure!(k_axis != mn_axis, "OptMatMulPack k and mn axes must differ");
+        Ok(OptMatMulPack { packers, mode_picker, k_axis, mn_axis })
+    }
+
+    pub fn packers(&self) -> &[PackedFormat] {
+        &self.packers
+    }
+
+    pub fn mode_picker(&self) -> &ModePicker {
+        &self.mode_picker
+    }
+
+    pub fn k_axis(&self) -> usize {
+        self.k_axis
+    }
+
+    pub fn mn_axis(&self) -> usize {
+        self.mn_axis
+    }
+
     fn do_eval(&self, _session: &SessionState, input: TValue) -> TractResult<TVec<TValue>> {
         unsafe {
             let mode = self.mode_picker.pick(input.shape()[self.mn_axis])?;
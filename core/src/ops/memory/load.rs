@@ -86,6 +86,10 @@ impl OpState for Load {
 
         Ok(tensor)
     }
+
+    fn restore(&mut self, _op: &dyn Op, _r: &mut dyn std::io::Read) -> TractResult<()> {
+        Ok(())
+    }
 }
 
-trivial_op_state_freeeze!(Load);
+trivial_op_state_freeeze!(Load, from_op);
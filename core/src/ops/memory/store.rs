@@ -61,6 +61,10 @@ impl OpState for Store {
         session.tensors.insert(self.id.clone(), state.into_tensor());
         Ok(tvec![input])
     }
+
+    fn restore(&mut self, _op: &dyn Op, _r: &mut dyn std::io::Read) -> TractResult<()> {
+        Ok(())
+    }
 }
 
-trivial_op_state_freeeze!(Store);
+trivial_op_state_freeeze!(Store, from_op);
@@ -70,6 +70,12 @@ impl Cost {
 
 pub trait FrozenOpState: fmt::Debug + dyn_clone::DynClone + Send + 'static {
     fn unfreeze(&self) -> Box<dyn OpState>;
+
+    /// Write the state in the format read by [OpState::restore], see [crate::frozen].
+    #[allow(unused_variables)]
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        bail!("Serialization of {self:?} is not supported")
+    }
 }
 
 pub trait OpStateFreeze {
@@ -85,6 +91,13 @@ pub trait OpState: fmt::Debug + dyn_clone::DynClone + OpStateFreeze + Downcast {
         op: &dyn Op,
         inputs: TVec<TValue>,
     ) -> TractResult<TVec<TValue>>;
+
+    /// Restore a state written by [FrozenOpState::save] into this fresh state, built by the op.
+    /// The bytes may come from anywhere: implementations check them against the op.
+    #[allow(unused_variables)]
+    fn restore(&mut self, op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        bail!("Deserialization of {self:?} is not supported")
+    }
 }
 dyn_clone::clone_trait_object!(OpState);
 impl_downcast!(OpState);
@@ -114,6 +127,19 @@ pub trait EvalOp {
     }
 
     fn is_stateless(&self) -> bool;
+
+    /// True if the operator allocates its output with [crate::arena::uninitialized_output], so
+    /// plans with a memory arena reserve room for it.
+    fn uses_memory_arena(&self) -> bool {
+        false
+    }
+
+    /// Inputs the operator can compute its output in, when it receives the only reference to
+    /// them. The plan moves such inputs to the operator once they are dead, so even the model
+    /// inputs can be consumed.
+    fn in_place_inputs(&self) -> TVec<usize> {
+        tvec!()
+    }
 }
 
 /// A base operation
@@ -183,6 +183,12 @@ impl Scan {
         node: &TypedNode,
     ) -> TractResult<Option<TypedModelPatch>> {
         for (slot, input) in self.body.input_outlets()?.iter().enumerate() {
+            // the last scanning input drives the iteration count, even if the body ignores it
+            if self.input_mapping[slot].is_scan()
+                && self.input_mapping.iter().filter(|m| m.is_scan()).count() == 1
+            {
+                continue;
+            }
             let source_node = self.body.node(input.node);
             if source_node.outputs[0].successors.len() == 0
                 && !self.body.output_outlets()?.contains(input)
@@ -886,3 +892,46 @@ impl TypedOp for Scan {
         )?))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::math;
+
+    // x is doubled once per element of the scanned input, which the body ignores
+    fn model() -> TractResult<TypedModel> {
+        let mut body = TypedModel::default();
+        let x = body.add_source("x", f32::fact([2]))?;
+        body.add_source("iteration", i64::fact([1]))?;
+        let two = body.add_const("two", tensor1(&[2f32, 2.]))?;
+        let y = body.wire_node("y", math::mul(), &[x, two])?;
+        body.set_output_outlets(&y)?;
+        let output = OutputMapping {
+            scan: None,
+            full_dim_hint: None,
+            last_value_slot: Some(0),
+            state: true,
+        };
+        let input_mapping =
+            vec![InputMapping::State, InputMapping::Scan(ScanInfo { axis: 0, chunk: 1 })];
+        let scan = Scan::new(body, input_mapping, vec![output], 0)?;
+
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([2]))?;
+        let iterations = model.add_const("iterations", tensor1(&[0i64, 1, 2]))?;
+        let y = model.wire_node("scan", scan, &[x, iterations])?;
+        model.set_output_outlets(&y)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn keep_unused_single_scan_input() -> TractResult<()> {
+        let mut model = model()?;
+        model.declutter()?;
+        let scan = model.node_by_name("scan")?.op_as::<Scan>().unwrap();
+        assert_eq!(scan.input_mapping.iter().filter(|m| m.is_scan()).count(), 1);
+        let y = model.into_runnable()?.run(tvec!(tensor1(&[1f32, -1.]).into()))?;
+        assert_eq!(*y[0], tensor1(&[8f32, -8.]));
+        Ok(())
+    }
+}
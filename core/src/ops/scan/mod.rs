@@ -3,9 +3,11 @@ use std::fmt;
 
 mod decluttered;
 mod optimized;
+mod while_loop;
 
 pub use optimized::{OptScan, State};
 pub use decluttered::Scan;
+pub use while_loop::{while_trip_count, wire_while, OptWhile, While, MAX_WHILE_ITERATIONS};
 
 #[derive(Clone, new, Hash, Eq, PartialEq, Copy, Debug)]
 pub struct ScanInfo {
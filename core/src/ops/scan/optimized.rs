@@ -110,6 +110,16 @@ impl FrozenOpState for FrozenState {
             model_state: self.model_state.unfreeze(),
         })
     }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        use crate::frozen::*;
+        write_usize(w, self.position)?;
+        write_usize(w, self.hidden_state.len())?;
+        for t in &self.hidden_state {
+            write_tensor(w, t)?;
+        }
+        self.model_state.save(w)
+    }
 }
 
 impl State {
@@ -230,8 +240,10 @@ impl OpState for State {
         }
         outputs.sort_by_key(|a| a.0);
         let mut outputs: TVec<Tensor> = outputs.into_iter().map(|(_slot, v)| v).collect();
+        session.nest(&mut model_state.session_state);
 
         for i in 0..iters {
+            session.check_cancellation()?;
             *position += 1;
             if *position <= op.skip {
                 continue;
@@ -280,6 +292,16 @@ impl OpState for State {
 
         Ok(outputs.into_iter().map(|t| t.into_tvalue()).collect())
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        use crate::frozen::*;
+        self.position = read_usize(r)?;
+        let hidden_state = read_usize(r)?;
+        self.hidden_state = (0..hidden_state)
+            .map(|_| Ok(read_tensor(r)?.into_tvalue()))
+            .collect::<TractResult<_>>()?;
+        self.model_state.restore(r)
+    }
 }
 
 impl TypedOp for OptScan {
@@ -0,0 +1,405 @@
+//! While loops: loop variables are updated by a body while a condition holds.
+//!
+//! [wire_while] translates a loop whose trip count can be computed at load time, like a counter
+//! based loop, to a [Scan] over an empty constant tensor, which is then decluttered and optimized
+//! with the rest of the model. Other loops are translated to a [While] op, evaluating the
+//! condition at runtime: its condition and body are decluttered and optimized with the model, and
+//! planned once.
+use std::collections::HashSet;
+
+use crate::internal::*;
+use crate::ops::OpStateFreeze;
+
+use super::{InputMapping, OutputMapping, Scan, ScanInfo};
+
+/// Upper bound for the load-time evaluation of trip counts: longer loops run as a [While].
+pub const MAX_WHILE_ITERATIONS: usize = 1 << 12;
+
+/// Loop evaluated at runtime.
+///
+/// Both models take the loop variables as inputs. The condition computes a single boolean, the
+/// body computes the next values of the loop variables, which must keep their types and shapes.
+#[derive(Debug, Clone, Default)]
+pub struct While {
+    pub cond: TypedModel,
+    pub body: TypedModel,
+    pub decluttered: bool,
+}
+
+impl While {
+    pub fn new(cond: TypedModel, body: TypedModel) -> While {
+        While { cond, body, decluttered: false }
+    }
+
+    pub fn to_codegen_op(&self, optimize_inner: bool) -> TractResult<OptWhile> {
+        let (mut cond, mut body) = (self.cond.clone(), self.body.clone());
+        if optimize_inner {
+            cond = cond.into_optimized()?;
+            body = body.into_optimized()?;
+        }
+        Ok(OptWhile {
+            cond: Arc::new(SimplePlan::new(cond)?),
+            body: Arc::new(SimplePlan::new(body)?),
+        })
+    }
+}
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl TypedOp for While {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(self.cond.inputs.len() == inputs.len());
+        ensure!(self.body.inputs.len() == inputs.len());
+        ensure!(self.body.outputs.len() == inputs.len());
+        let cond = self.cond.output_fact(0)?;
+        ensure!(cond.datum_type == bool::datum_type() && cond.shape.volume() == 1.to_dim());
+        for (ix, input) in inputs.iter().enumerate() {
+            let output = self.body.output_fact(ix)?;
+            ensure!(
+                output.datum_type == input.datum_type && output.shape == input.shape,
+                "Loop variable {ix} is a {input:?} but the body computes a {output:?}"
+            );
+        }
+        Ok(inputs.iter().map(|f| f.without_value()).collect())
+    }
+
+    fn declutter(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        if self.decluttered {
+            return Ok(None);
+        }
+        let mut new = self.clone();
+        new.cond.declutter()?;
+        new.body.declutter()?;
+        new.decluttered = true;
+        Ok(Some(TypedModelPatch::replace_single_op(model, node, &node.inputs, new)?))
+    }
+
+    fn codegen(
+        &self,
+        model: &TypedModel,
+        node: &TypedNode,
+    ) -> TractResult<Option<TypedModelPatch>> {
+        let op = self.to_codegen_op(true)?;
+        Ok(Some(TypedModelPatch::replace_single_op(model, node, &node.inputs, op)?))
+    }
+
+    as_op!();
+}
+
+impl EvalOp for While {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        session: &mut SessionState,
+        node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        self.to_codegen_op(false)?.state(session, node_id)
+    }
+}
+
+/// While loop with planned condition and body.
+#[derive(Debug, Clone)]
+pub struct OptWhile {
+    pub cond: Arc<TypedSimplePlan<TypedModel>>,
+    pub body: Arc<TypedSimplePlan<TypedModel>>,
+}
+
+impl Op for OptWhile {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl TypedOp for OptWhile {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(inputs.iter().map(|f| f.without_value()).collect())
+    }
+
+    as_op!();
+}
+
+impl EvalOp for OptWhile {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(WhileState {
+            cond: SimpleState::new(self.cond.clone())?,
+            body: SimpleState::new(self.body.clone())?,
+        })))
+    }
+}
+
+type WhileModelState = TypedSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>;
+
+#[derive(Debug, Clone)]
+pub struct WhileState {
+    cond: WhileModelState,
+    body: WhileModelState,
+}
+
+impl OpState for WhileState {
+    fn eval(
+        &mut self,
+        session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        session.nest(&mut self.cond.session_state);
+        session.nest(&mut self.body.session_state);
+        let mut values = inputs;
+        loop {
+            session.check_cancellation()?;
+            let cond = self.cond.run(values.clone()).context("Evaluating loop condition")?;
+            if !cond[0].cast_to_scalar::<bool>()? {
+                return Ok(values);
+            }
+            values = self.body.run(values).context("Evaluating loop body")?;
+        }
+    }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.cond.restore(r)?;
+        self.body.restore(r)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FrozenWhileState {
+    cond: TypedFrozenSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+    body: TypedFrozenSimpleState<TypedModel, Arc<TypedSimplePlan<TypedModel>>>,
+}
+
+impl OpStateFreeze for WhileState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(FrozenWhileState { cond: self.cond.freeze(), body: self.body.freeze() })
+    }
+}
+
+impl FrozenOpState for FrozenWhileState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(WhileState { cond: self.cond.unfreeze(), body: self.body.unfreeze() })
+    }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        self.cond.save(w)?;
+        self.body.save(w)
+    }
+}
+
+/// Number of iterations of a while loop, computed at load time.
+///
+/// `inputs` are the values of the loop variables entering the loop, when they are constant. The
+/// trip count is known if the variables the condition depends on, directly or through the body,
+/// are constant. Returns None otherwise, or if the loop runs for more than
+/// [MAX_WHILE_ITERATIONS].
+pub fn while_trip_count(
+    cond: &TypedModel,
+    body: &TypedModel,
+    inputs: &[Option<Arc<Tensor>>],
+) -> TractResult<Option<usize>> {
+    let mut control = sources_feeding(cond, &cond.outputs);
+    loop {
+        let outlets: Vec<OutletId> = control.iter().map(|&ix| body.outputs[ix]).collect();
+        let mut next = sources_feeding(body, &outlets);
+        next.extend(control.iter().copied());
+        if next == control {
+            break;
+        }
+        control = next;
+    }
+    let mut control: Vec<usize> = control.into_iter().collect();
+    control.sort();
+    let Some(mut values) = control
+        .iter()
+        .map(|&ix| inputs[ix].clone().map(|k| k.into_tvalue()))
+        .collect::<Option<TVec<TValue>>>()
+    else {
+        return Ok(None);
+    };
+
+    let mut cond = cond.clone();
+    cond.set_input_outlets(&control.iter().map(|&ix| cond.inputs[ix]).collect::<Vec<_>>())?;
+    let cond = cond.into_runnable()?;
+    let mut body = body.clone();
+    body.set_input_outlets(&control.iter().map(|&ix| body.inputs[ix]).collect::<Vec<_>>())?;
+    body.set_output_outlets(&control.iter().map(|&ix| body.outputs[ix]).collect::<Vec<_>>())?;
+    let body = body.into_runnable()?;
+
+    let mut iters = 0;
+    while cond.run(values.clone())?[0].cast_to_scalar::<bool>()? {
+        if iters == MAX_WHILE_ITERATIONS {
+            return Ok(None);
+        }
+        values = body.run(values)?;
+        iters += 1;
+    }
+    Ok(Some(iters))
+}
+
+/// Indices of the model inputs the given outlets depend on.
+fn sources_feeding(model: &TypedModel, outlets: &[OutletId]) -> HashSet<usize> {
+    let mut visited = HashSet::new();
+    let mut todo: Vec<usize> = outlets.iter().map(|o| o.node).collect();
+    while let Some(node) = todo.pop() {
+        if visited.insert(node) {
+            todo.extend(model.node(node).inputs.iter().map(|i| i.node));
+        }
+    }
+    model
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| visited.contains(&input.node))
+        .map(|(ix, _)| ix)
+        .collect()
+}
+
+/// Wire a while loop over the `inputs` loop variables, as a [Scan] if its trip count is known at
+/// load time, as a [While] otherwise.
+pub fn wire_while(
+    target: &mut TypedModel,
+    name: &str,
+    cond: TypedModel,
+    mut body: TypedModel,
+    inputs: &[OutletId],
+) -> TractResult<TVec<OutletId>> {
+    let konsts = inputs
+        .iter()
+        .map(|i| Ok(target.outlet_fact(*i)?.konst.clone()))
+        .collect::<TractResult<TVec<_>>>()?;
+    let Some(iters) = while_trip_count(&cond, &body, &konsts)
+        .with_context(|| format!("Computing trip count for while loop {name}"))?
+    else {
+        return target.wire_node(name, While::new(cond, body), inputs);
+    };
+    if iters == 0 {
+        return Ok(inputs.into());
+    }
+    // the scanned input only drives the iteration count, the body ignores it: its items are
+    // empty, so it takes no memory
+    body.add_source(format!("{name}.iteration"), i64::fact([1, 0]))?;
+    let mut input_mapping = vec![InputMapping::State; inputs.len()];
+    input_mapping.push(InputMapping::Scan(ScanInfo { axis: 0, chunk: 1 }));
+    let output_mapping = (0..inputs.len())
+        .map(|ix| OutputMapping {
+            state: true,
+            last_value_slot: Some(ix),
+            scan: None,
+            full_dim_hint: None,
+        })
+        .collect();
+    let mut scan = Scan::new(body, input_mapping, output_mapping, 0)?;
+    scan.reset_every_turn = true;
+    let iterations = Tensor::zero::<i64>(&[iters, 0])?;
+    let mut inputs: TVec<OutletId> = inputs.into();
+    inputs.push(target.add_const(format!("{name}.iterations"), iterations)?);
+    target.wire_node(name, scan, &inputs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::logic::Comp;
+    use crate::ops::math;
+
+    // while i < n { i += 1; x *= 2 }
+    fn cond() -> TractResult<TypedModel> {
+        let mut cond = TypedModel::default();
+        let i = cond.add_source("i", i64::scalar_fact())?;
+        cond.add_source("x", f32::fact([2]))?;
+        let n = cond.add_source("n", i64::scalar_fact())?;
+        let lt = cond.wire_node("lt", Comp::LT, &[i, n])?;
+        cond.set_output_outlets(&lt)?;
+        Ok(cond)
+    }
+
+    fn body() -> TractResult<TypedModel> {
+        let mut body = TypedModel::default();
+        let i = body.add_source("i", i64::scalar_fact())?;
+        let x = body.add_source("x", f32::fact([2]))?;
+        let n = body.add_source("n", i64::scalar_fact())?;
+        let one = body.add_const("one", tensor0(1i64))?;
+        let two = body.add_const("two", tensor1(&[2f32, 2.]))?;
+        let i = body.wire_node("i+1", math::add(), &[i, one])?[0];
+        let x = body.wire_node("x*2", math::mul(), &[x, two])?[0];
+        body.set_output_outlets(&[i, x, n])?;
+        Ok(body)
+    }
+
+    fn model(n: Option<i64>) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([2]))?;
+        let n = if let Some(n) = n {
+            model.add_const("n", tensor0(n))?
+        } else {
+            model.add_source("n", i64::scalar_fact())?
+        };
+        let zero = model.add_const("zero", tensor0(0i64))?;
+        let outputs = wire_while(&mut model, "loop", cond()?, body()?, &[zero, x, n])?;
+        model.set_output_outlets(&outputs[1..2])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn trip_count_known_at_load_time() -> TractResult<()> {
+        let model = model(Some(3))?;
+        assert!(model.node_by_name("loop")?.op_is::<Scan>());
+        let y = model.into_runnable()?.run(tvec!(tensor1(&[1f32, -1.]).into()))?;
+        assert_eq!(*y[0], tensor1(&[8f32, -8.]));
+        Ok(())
+    }
+
+    #[test]
+    fn trip_count_known_at_runtime() -> TractResult<()> {
+        let model = model(None)?.into_optimized()?;
+        assert!(model.node_by_name("loop")?.op_is::<OptWhile>());
+        let plan = model.into_runnable()?;
+        for n in [0, 3] {
+            let y = plan.run(tvec!(tensor1(&[1f32, -1.]).into(), tensor0(n as i64).into()))?;
+            let scale = (1 << n) as f32;
+            assert_eq!(*y[0], tensor1(&[scale, -scale]));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn loop_is_cancellable() -> TractResult<()> {
+        let plan = model(None)?.into_optimized()?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        let token = CancellationToken::new().with_timeout(std::time::Duration::from_millis(20));
+        state.set_cancellation(Some(token));
+        let err = state.run(tvec!(tensor1(&[1f32, -1.]).into(), tensor0(i64::MAX).into()));
+        let err = err.unwrap_err();
+        assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::DeadlineExceeded));
+        Ok(())
+    }
+
+    #[test]
+    fn zero_iterations() -> TractResult<()> {
+        let model = model(Some(0))?;
+        let y = model.into_runnable()?.run(tvec!(tensor1(&[1f32, -1.]).into()))?;
+        assert_eq!(*y[0], tensor1(&[1f32, -1.]));
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use crate::internal::*;
 
 #[derive(Debug, Clone, new)]
 pub struct SourceState(pub usize);
-trivial_op_state_freeeze!(SourceState);
+trivial_op_state_freeeze!(SourceState, from_op);
 
 impl OpState for SourceState {
     fn eval(
@@ -17,6 +17,10 @@ impl OpState for SourceState {
             .with_context(|| format!("Input for node {} is missing", self.0))?
             .clone()))
     }
+
+    fn restore(&mut self, _op: &dyn Op, _r: &mut dyn std::io::Read) -> TractResult<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, new, Hash)]
@@ -159,13 +159,18 @@ pub type TypedModelOpState = TypedSimpleState<TypedModel, Arc<TypedSimplePlan<Ty
 impl OpState for TypedModelOpState {
     fn eval(
         &mut self,
-        _session: &mut SessionState,
+        session: &mut SessionState,
         _op: &dyn Op,
         inputs: TVec<TValue>,
     ) -> TractResult<TVec<TValue>> {
+        session.nest(&mut self.session_state);
         let inference_out = self.run(inputs)?;
         Ok(inference_out)
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.restore(r)
+    }
 }
 
 pub type FrozenSubmodelOpState =
@@ -175,6 +180,10 @@ impl FrozenOpState for FrozenSubmodelOpState {
     fn unfreeze(&self) -> Box<dyn OpState> {
         Box::new(self.unfreeze())
     }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        self.save(w)
+    }
 }
 
 impl OpStateFreeze for TypedModelOpState {
@@ -4,13 +4,19 @@ use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
 use multithread::Executor;
+use tract_itertools::Itertools;
+use tract_linalg::cancel::cancellable_scope;
 
+use crate::arena::{MemSchema, MemoryArena};
+use crate::budget::MemoryUsage;
 use crate::internal::*;
+use crate::io_binding::IoBindings;
 use crate::model::{Fact, Graph, OutletId};
+use crate::observer::{NodeObserver, Observation};
 use crate::ops::konst::Const;
 use crate::ops::FrozenOpState;
 
-use self::order::{eval_order_for_nodes, eval_order_opt_ram_for_nodes, build_flush_list};
+use self::order::{build_flush_list, eval_order_for_nodes, eval_order_opt_ram_for_nodes};
 
 #[derive(Clone, Debug, Default)]
 pub struct PlanOptions {
@@ -19,6 +25,20 @@ pub struct PlanOptions {
 
     /// Override default global executor
     pub executor: Option<Executor>,
+
+    /// Evaluate independent nodes concurrently, on the executor thread pool if it is multithreaded,
+    /// on the global rayon pool otherwise
+    pub parallel: bool,
+
+    /// Allocate the outputs of the operators supporting it in a memory arena planned with the
+    /// evaluation order, and reused across runs (sequential evaluation only)
+    pub memory_arena: bool,
+
+    /// Peak memory budget, in bytes, of the values alive during the evaluation. The plan picks the
+    /// first evaluation order meeting it at the symbols upper bounds, and fails with a
+    /// [crate::budget::MemoryBudgetExceeded] error if none does. If the usage depends on unbounded
+    /// symbols, runs check it instead. Parallel plans may keep more values alive than estimated
+    pub memory_budget: Option<usize>,
 }
 
 pub struct SessionState {
@@ -28,10 +48,12 @@ pub struct SessionState {
     pub tensors: HashMap<String, Tensor>,
     pub cached_mmm_scratch_space: RefCell<Option<Box<dyn tract_linalg::mmm::ScratchSpace>>>,
     pub scratch_extensions: anymap3::Map,
+    /// Checked between nodes, scan iterations and matrix product tiles: runs fail with a
+    /// [Cancelled] error once it is cancelled or past its deadline.
+    pub cancellation: Option<CancellationToken>,
 }
 
 impl Default for SessionState {
-
     fn default() -> Self {
         SessionState {
             inputs: HashMap::default(),
@@ -40,6 +62,7 @@ impl Default for SessionState {
             scenario: None,
             cached_mmm_scratch_space: None.into(),
             scratch_extensions: anymap3::Map::new(),
+            cancellation: None,
         }
     }
 }
@@ -53,10 +76,24 @@ impl Clone for SessionState {
             scenario: self.scenario,
             cached_mmm_scratch_space: None.into(),
             scratch_extensions: anymap3::Map::new(),
+            cancellation: self.cancellation.clone(),
         }
     }
 }
 
+impl SessionState {
+    /// Share the observer and the cancellation token of the session with the session of a state
+    /// nested in the evaluation of one of its nodes.
+    pub fn nest(&self, inner: &mut SessionState) {
+        crate::observer::nest(self, inner);
+        inner.cancellation = self.cancellation.clone();
+    }
+
+    /// Fail if the cancellation token is cancelled or past its deadline.
+    pub fn check_cancellation(&self) -> TractResult<()> {
+        self.cancellation.as_ref().map_or(Ok(()), |c| c.check())
+    }
+}
 
 pub trait SessionStateHandler: Send + Sync + Debug {
     fn before_plan_eval(&self, session_state: &mut SessionState) -> TractResult<()>;
@@ -82,7 +119,11 @@ where
     flush_lists: Vec<TVec<usize>>,
     has_unresolved_symbols: bool,
     executor: Option<Executor>,
+    dependencies: Option<Dependencies>,
+    mem_schema: Option<MemSchema>,
     session_handler: Option<Arc<dyn SessionStateHandler + 'static>>,
+    observer: Option<Arc<dyn NodeObserver>>,
+    memory_budget: Option<(usize, MemoryUsage)>,
     _casper: PhantomData<(F, O)>,
 }
 
@@ -116,11 +157,20 @@ where
         Self::build(model, outputs, &[], &PlanOptions::default())
     }
 
-    pub fn with_session_handler<H: SessionStateHandler + 'static>(mut self, session_handler: H) -> Self {
+    pub fn with_session_handler<H: SessionStateHandler + 'static>(
+        mut self,
+        session_handler: H,
+    ) -> Self {
         self.session_handler = Some(Arc::new(session_handler));
         self
     }
 
+    /// Observe the nodes evaluated by the states of the plan, unless they have their own observer.
+    pub fn with_observer<N: NodeObserver + 'static>(mut self, observer: N) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
     #[deprecated]
     pub fn new_for_outputs_and_deps(
         model: M,
@@ -138,13 +188,32 @@ where
     ) -> TractResult<SimplePlan<F, O, M>> {
         let inputs = model.borrow().input_outlets()?.iter().map(|n| n.node).collect::<Vec<usize>>();
         let outputs_nodes = outputs.iter().map(|n| n.node).collect::<Vec<usize>>();
-        let mut order = if options.skip_order_opt_ram {
-            eval_order_for_nodes(model.borrow().nodes(), &inputs, &outputs_nodes, deps)?
+        let simple_order =
+            || eval_order_for_nodes(model.borrow().nodes(), &inputs, &outputs_nodes, deps);
+        let opt_ram_order =
+            || eval_order_opt_ram_for_nodes(model.borrow().nodes(), &inputs, &outputs_nodes, deps);
+        let mut order = if options.skip_order_opt_ram { simple_order()? } else { opt_ram_order()? };
+        order.retain(|node| !model.borrow().node(*node).op_is::<Const>());
+        let mut memory_budget = None;
+        if let Some(budget) = options.memory_budget {
+            let mut other =
+                if options.skip_order_opt_ram { opt_ram_order()? } else { simple_order()? };
+            other.retain(|node| !model.borrow().node(*node).op_is::<Const>());
+            let (selected, usage) =
+                crate::budget::select_order(model.borrow(), outputs, budget, vec![order, other])?;
+            order = selected;
+            memory_budget = usage.map(|usage| (budget, usage));
+        }
+        let flush_lists =
+            build_flush_list(model.borrow(), &order, outputs, |n| !n.op_is::<Const>());
+        let dependencies =
+            options.parallel.then(|| Dependencies::new(model.borrow(), &order, outputs, deps));
+        let mem_schema = if options.memory_arena {
+            Some(MemSchema::build(model.borrow(), &order, outputs)?)
+                .filter(|schema| !schema.by_partition.is_empty())
         } else {
-            eval_order_opt_ram_for_nodes(model.borrow().nodes(), &inputs, &outputs_nodes, deps)?
+            None
         };
-        order.retain(|node| !model.borrow().node(*node).op_is::<Const>());
-        let flush_lists = build_flush_list(model.borrow(), &order, outputs, |n| !n.op_is::<Const>());
 
         #[allow(clippy::mutable_key_type)]
         let mut symbols: std::collections::HashSet<Symbol> = Default::default();
@@ -163,7 +232,11 @@ where
             has_unresolved_symbols: !symbols.is_empty(),
             _casper: PhantomData,
             executor: options.executor.clone(),
+            dependencies,
+            mem_schema,
             session_handler: None,
+            observer: None,
+            memory_budget,
         })
     }
 
@@ -171,6 +244,10 @@ where
         &self.order
     }
 
+    pub fn mem_schema(&self) -> Option<&MemSchema> {
+        self.mem_schema.as_ref()
+    }
+
     pub fn run(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
         let mut state = SimpleState::new(self)?;
         state.run(inputs)
@@ -181,6 +258,58 @@ where
     }
 }
 
+/// Dependencies between the nodes of a plan, to evaluate them as soon as their inputs are ready.
+#[derive(Clone, Debug)]
+struct Dependencies {
+    /// Number of plan nodes each node waits for, by node id.
+    predecessors: Vec<usize>,
+    /// Plan nodes waiting for each node, by node id.
+    successors: Vec<TVec<usize>>,
+    /// Number of plan nodes consuming the outputs of each node, by node id.
+    consumers: Vec<usize>,
+    /// Nodes whose outputs are plan outputs, and must not be flushed.
+    outputs: Vec<bool>,
+}
+
+impl Dependencies {
+    fn new<F, O>(
+        model: &Graph<F, O>,
+        order: &[usize],
+        outputs: &[OutletId],
+        deps: &[(usize, usize)],
+    ) -> Dependencies
+    where
+        F: Fact + Clone + 'static,
+        O: Debug + Display + AsRef<dyn Op> + AsMut<dyn Op> + Clone + 'static,
+    {
+        let mut in_plan = vec![false; model.nodes().len()];
+        order.iter().for_each(|&n| in_plan[n] = true);
+        let mut predecessors = vec![0; model.nodes().len()];
+        let mut successors = vec![tvec!(); model.nodes().len()];
+        let mut consumers = vec![0; model.nodes().len()];
+        let mut outputs_nodes = vec![false; model.nodes().len()];
+        outputs.iter().for_each(|o| outputs_nodes[o.node] = true);
+        // stateful nodes are evaluated in plan order, as they may have side effects on the session
+        let mut previous_stateful = None;
+        for &node in order {
+            let mut precs: TVec<usize> = model.node(node).inputs.iter().map(|i| i.node).collect();
+            precs.sort();
+            precs.dedup();
+            precs.retain(|p| in_plan[*p]);
+            precs.iter().for_each(|&p| consumers[p] += 1);
+            precs.extend(deps.iter().filter(|d| d.0 == node && in_plan[d.1]).map(|d| d.1));
+            if !model.node(node).op().is_stateless() {
+                precs.extend(previous_stateful.replace(node));
+            }
+            precs.sort();
+            precs.dedup();
+            predecessors[node] = precs.len();
+            precs.iter().for_each(|&p| successors[p].push(node));
+        }
+        Dependencies { predecessors, successors, consumers, outputs: outputs_nodes }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SimpleState<F, O, M, P>
 where
@@ -221,7 +350,7 @@ where
 
         Ok(state)
     }
-    
+
     fn populate_consts(&mut self) {
         for node in &self.plan.borrow().model().nodes {
             if let Some(k) = node.op_as::<Const>() {
@@ -250,11 +379,181 @@ where
     }
 
     pub fn run(&mut self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
-        self.run_plan_with_eval(inputs, self::eval)
+        if self.plan().dependencies.is_none() {
+            return self.run_plan_with_eval(inputs, self::eval);
+        }
+        self.set_inputs(inputs)?;
+        self.exec()?;
+        let outputs = self.outputs()?;
+        self.reset_turn()?;
+        Ok(outputs)
     }
 
+    /// Run the plan on the inputs set in the session.
     pub fn exec(&mut self) -> TractResult<()> {
-        self.exec_plan_with_eval(self::eval)
+        if self.plan().dependencies.is_none() {
+            return self.exec_plan_with_eval(self::eval);
+        }
+        let cancellation = self.session_state.cancellation.clone();
+        cancellable_scope(cancellation, || match self.plan().executor.clone() {
+            Some(Executor::MultiThread(pool)) => {
+                pool.in_place_scope(|scope| self.do_exec_plan_in_parallel(scope))
+            }
+            _ => rayon::in_place_scope(|scope| self.do_exec_plan_in_parallel(scope)),
+        })
+    }
+
+    /// Evaluate the plan nodes as soon as their inputs are ready: stateless nodes are spawned on
+    /// the pool, stateful ones are evaluated on the current thread with the session state.
+    fn do_exec_plan_in_parallel<'s>(&'s mut self, scope: &rayon::Scope<'s>) -> TractResult<()> {
+        let SimpleState { ref plan, ref mut session_state, ref mut states, ref mut values, .. } =
+            self;
+        let plan = plan.borrow();
+        let model = plan.model();
+        let deps = plan.dependencies.as_ref().context("Plan is not parallel")?;
+        plan.session_handler.as_ref().map(|it| it.before_plan_eval(session_state)).transpose()?;
+        Self::check_memory_budget(plan, session_state)?;
+        let observation = Self::prepare_observation(plan, session_state);
+
+        let mut waiting = deps.predecessors.clone();
+        let mut consumers = deps.consumers.clone();
+        let mut ready: std::collections::VecDeque<usize> =
+            plan.order.iter().filter(|n| waiting[**n] == 0).copied().collect();
+        let mut remaining = plan.order.len();
+        let mut running = 0;
+        let (sender, receiver) = std::sync::mpsc::channel();
+        while remaining > 0 {
+            while let Some(n) = ready.pop_front() {
+                session_state.check_cancellation()?;
+                let node = model.node(n);
+                trace!("Running node {}", node);
+                let mut inputs: TVec<TValue> = tvec![];
+                for i in &node.inputs {
+                    let prec = values[i.node].as_ref().ok_or_else(|| {
+                        format_err!("Computing {}, precursor {} not done", node, model.node(i.node))
+                    })?;
+                    inputs.push(prec[i.slot].clone())
+                }
+                for prec in node.inputs.iter().map(|i| i.node).sorted().dedup() {
+                    if consumers[prec] > 0 {
+                        consumers[prec] -= 1;
+                        if consumers[prec] == 0 && !deps.outputs[prec] {
+                            trace!("  Ran {} can now flush {}", node, model.node(prec));
+                            values[prec] = None;
+                        }
+                    }
+                }
+                if !node.op().is_stateless() {
+                    let state = states[n].as_deref_mut();
+                    let vs = Self::eval_observed(
+                        observation.as_ref(),
+                        session_state,
+                        node,
+                        inputs,
+                        |session, inputs| eval(session, state, node, inputs),
+                    )?;
+                    let vs = vs.into_iter().map(|v| v.into_arc_tensor().into_tvalue()).collect();
+                    ready.extend(Self::node_done(
+                        plan,
+                        session_state,
+                        values,
+                        &mut waiting,
+                        n,
+                        vs,
+                    )?);
+                    remaining -= 1;
+                    continue;
+                }
+                let inputs: TVec<Arc<Tensor>> =
+                    inputs.into_iter().map(|v| v.into_arc_tensor()).collect();
+                let op = node.op();
+                let symbols = session_state.resolved_symbols.clone();
+                let scenario = session_state.scenario;
+                let executor = plan.executor.clone();
+                let observed = observation.clone().map(|o| (o, n, &*node.name));
+                let cancellation = session_state.cancellation.clone();
+                let sender = sender.clone();
+                running += 1;
+                scope.spawn(move |_| {
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        eval_detached(
+                            op,
+                            inputs,
+                            symbols,
+                            scenario,
+                            executor,
+                            observed,
+                            cancellation,
+                        )
+                    }))
+                    .unwrap_or_else(|_| Err(format_err!("Evaluation panicked")));
+                    let _ = sender.send((n, result));
+                });
+            }
+            if remaining == 0 {
+                break;
+            }
+            ensure!(running > 0, "No node ready to evaluate, {remaining} nodes remaining");
+            let (n, result) = receive(&receiver);
+            running -= 1;
+            remaining -= 1;
+            let vs = result.with_context(|| format!("Evaluating {}", model.node(n)))?;
+            let vs = vs.into_iter().map(TValue::Const).collect();
+            ready.extend(Self::node_done(plan, session_state, values, &mut waiting, n, vs)?);
+        }
+        plan.session_handler.as_ref().map(|it| it.after_plan_eval(session_state)).transpose()?;
+        Ok(())
+    }
+
+    /// Check the memory budget the plan could not check at build time, if the symbols of the
+    /// inputs make the usage concrete.
+    fn check_memory_budget(
+        plan: &SimplePlan<F, O, M>,
+        session_state: &SessionState,
+    ) -> TractResult<()> {
+        if let Some((budget, usage)) = &plan.memory_budget {
+            usage.check(plan.model(), *budget, &session_state.resolved_symbols, None)?;
+        }
+        Ok(())
+    }
+
+    /// Store the outputs of an evaluated node, and return the successors it made ready.
+    fn node_done(
+        plan: &SimplePlan<F, O, M>,
+        session_state: &mut SessionState,
+        values: &mut [Option<TVec<TValue>>],
+        waiting: &mut [usize],
+        n: usize,
+        vs: TVec<TValue>,
+    ) -> TractResult<TVec<usize>> {
+        if plan.has_unresolved_symbols {
+            Self::resolve_output_symbols(session_state, plan.model().node(n), &vs)?;
+        }
+        values[n] = Some(vs);
+        let deps = plan.dependencies.as_ref().unwrap();
+        let mut ready = tvec!();
+        for &succ in &deps.successors[n] {
+            waiting[succ] -= 1;
+            if waiting[succ] == 0 {
+                ready.push(succ);
+            }
+        }
+        Ok(ready)
+    }
+
+    fn resolve_output_symbols(
+        session_state: &mut SessionState,
+        node: &Node<F, O>,
+        vs: &[TValue],
+    ) -> TractResult<()> {
+        for (o, v) in node.outputs.iter().zip(vs.iter()) {
+            if let Ok(f) = o.fact.to_typed_fact() {
+                for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
+                    Self::resolve(session_state, dim_abstract, *dim_concrete as i64)?;
+                }
+            }
+        }
+        Ok(())
     }
 
     pub fn run_plan_with_eval<Eval, E>(
@@ -272,7 +571,7 @@ where
         E: Into<anyhow::Error> + Send + Sync + 'static,
     {
         self.set_inputs(inputs)?;
-        self.exec_plan_with_eval(eval)?;
+        self.exec_plan(eval, true)?;
         let outputs = self.outputs()?;
         self.reset_turn()?;
         Ok(outputs)
@@ -288,16 +587,38 @@ where
         ) -> Result<TVec<TValue>, E>,
         E: Into<anyhow::Error> + Send + Sync + 'static,
     {
-        if let Some(executor) = self.plan().executor.as_ref() {
-            tract_linalg::multithread::multithread_tract_scope(executor.clone(), || {
-                self.do_exec_plan_with_eval(eval)
-            })
-        } else {
-            self.do_exec_plan_with_eval(eval)
-        }
+        self.exec_plan(eval, false)
+    }
+
+    /// Run the plan. If `consume_inputs` is set, the caller will not run the plan again on the
+    /// same inputs: the session stops referencing the dead inputs operators can consume in place.
+    fn exec_plan<Eval, E>(&mut self, eval: Eval, consume_inputs: bool) -> TractResult<()>
+    where
+        Eval: for<'a, 'b, 'c> FnMut(
+            &'a mut SessionState,
+            Option<&'b mut (dyn OpState + 'static)>,
+            &'c Node<F, O>,
+            TVec<TValue>,
+        ) -> Result<TVec<TValue>, E>,
+        E: Into<anyhow::Error> + Send + Sync + 'static,
+    {
+        let cancellation = self.session_state.cancellation.clone();
+        cancellable_scope(cancellation, || {
+            if let Some(executor) = self.plan().executor.as_ref() {
+                tract_linalg::multithread::multithread_tract_scope(executor.clone(), || {
+                    self.do_exec_plan_with_eval(eval, consume_inputs)
+                })
+            } else {
+                self.do_exec_plan_with_eval(eval, consume_inputs)
+            }
+        })
     }
 
-    fn do_exec_plan_with_eval<Eval, E>(&mut self, mut eval: Eval) -> TractResult<()>
+    fn do_exec_plan_with_eval<Eval, E>(
+        &mut self,
+        mut eval: Eval,
+        consume_inputs: bool,
+    ) -> TractResult<()>
     where
         Eval: for<'a, 'b, 'c> FnMut(
             &'a mut SessionState,
@@ -314,13 +635,36 @@ where
                 .as_ref()
                 .map(|it| it.before_plan_eval(&mut self.session_state))
                 .transpose()?;
+            let arena = if let Some(schema) = &plan.mem_schema {
+                Self::prepare_memory_arena(&mut self.session_state, schema)?
+            } else {
+                false
+            };
+            Self::check_memory_budget(plan, &self.session_state)?;
+            let observation = Self::prepare_observation(plan, &mut self.session_state);
+            let track_nodes = arena
+                || self
+                    .session_state
+                    .scratch_extensions
+                    .get::<IoBindings>()
+                    .is_some_and(|b| b.writes_in_place());
 
             for (step, n) in plan.order.iter().enumerate() {
+                self.session_state.check_cancellation()?;
                 let node = model.node(*n);
                 trace!("Running step {}, node {}", step, node);
                 let mut inputs: TVec<TValue> = tvec![];
-                for i in &node.inputs {
+                let in_place = node.op().in_place_inputs();
+                for (ix, i) in node.inputs.iter().enumerate() {
                     trace!("  use input {:?}", i);
+                    if consume_inputs
+                        && in_place.contains(&ix)
+                        && plan.flush_lists[step].contains(&i.node)
+                        && node.inputs.iter().filter(|j| *j == i).count() == 1
+                    {
+                        // a dead model input can be consumed: the session stops referencing it
+                        self.session_state.inputs.remove(&i.node);
+                    }
                     let prec_node = model.node(i.node);
                     let prec = self.values[i.node].as_ref().ok_or_else(|| {
                         format_err!("Computing {}, precursor {} not done:", node, prec_node)
@@ -356,26 +700,24 @@ where
                     }
                 }
 
-                let vs = eval(
+                if track_nodes {
+                    set_current_node(&self.session_state, Some(node.id));
+                }
+                let state = self.states[node.id].as_deref_mut();
+                let vs = Self::eval_observed(
+                    observation.as_ref(),
                     &mut self.session_state,
-                    self.states[node.id].as_deref_mut(),
                     node,
                     inputs,
-                )
-                .map_err(|e| e.into())?;
+                    |session, inputs| eval(session, state, node, inputs).map_err(|e| e.into()),
+                );
+                if track_nodes {
+                    set_current_node(&self.session_state, None);
+                }
+                let vs = vs?;
 
                 if plan.has_unresolved_symbols {
-                    for (o, v) in node.outputs.iter().zip(vs.iter()) {
-                        if let Ok(f) = o.fact.to_typed_fact() {
-                            for (dim_abstract, dim_concrete) in f.shape.iter().zip(v.shape()) {
-                                Self::resolve(
-                                    &mut self.session_state,
-                                    dim_abstract,
-                                    *dim_concrete as i64,
-                                )?;
-                            }
-                        }
-                    }
+                    Self::resolve_output_symbols(&mut self.session_state, node, &vs)?;
                 }
                 if cfg!(debug_assertions) {
                     let facts = model.node_output_facts(node.id)?;
@@ -413,6 +755,57 @@ where
         Ok(())
     }
 
+    /// The observation of the state, falling back to the observer of the plan.
+    fn prepare_observation(
+        plan: &SimplePlan<F, O, M>,
+        session_state: &mut SessionState,
+    ) -> Option<Observation> {
+        if !session_state.scratch_extensions.contains::<Observation>() {
+            if let Some(observer) = &plan.observer {
+                session_state.scratch_extensions.insert(Observation::new(observer.clone()));
+            }
+        }
+        session_state.scratch_extensions.get::<Observation>().cloned()
+    }
+
+    /// Evaluate a node, between the callbacks of the observer if any. The node is made current
+    /// in the session observation, so the states nested in its evaluation are observed in its
+    /// scope.
+    fn eval_observed(
+        observation: Option<&Observation>,
+        session_state: &mut SessionState,
+        node: &Node<F, O>,
+        inputs: TVec<TValue>,
+        eval: impl FnOnce(&mut SessionState, TVec<TValue>) -> TractResult<TVec<TValue>>,
+    ) -> TractResult<TVec<TValue>> {
+        let Some(observation) = observation else { return eval(session_state, inputs) };
+        if let Some(current) = session_state.scratch_extensions.get_mut::<Observation>() {
+            current.current_node = Some(node.id);
+        }
+        observation
+            .observe(node.id, &node.name, node.op(), inputs, |inputs| eval(session_state, inputs))
+    }
+
+    /// Make the session arena match the memory schema resolved with the current symbols. Returns
+    /// false if the symbols are not all known yet: the run goes without arena.
+    fn prepare_memory_arena(
+        session_state: &mut SessionState,
+        schema: &MemSchema,
+    ) -> TractResult<bool> {
+        let Ok(resolved) = schema.resolve(&session_state.resolved_symbols) else {
+            session_state.scratch_extensions.remove::<MemoryArena>();
+            return Ok(false);
+        };
+        if !session_state
+            .scratch_extensions
+            .get::<MemoryArena>()
+            .is_some_and(|arena| *arena.schema() == resolved)
+        {
+            session_state.scratch_extensions.insert(MemoryArena::new(resolved)?);
+        }
+        Ok(true)
+    }
+
     pub fn set_inputs(&mut self, inputs: TVec<TValue>) -> TractResult<()> {
         ensure!(
             inputs.len() == self.model().inputs.len(),
@@ -467,6 +860,111 @@ where
         Ok(())
     }
 
+    /// Bind a caller buffer to an input: [Self::run_bound] reads the input from it, without
+    /// copying it.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to the elements of a `dt` tensor of `shape`, be aligned for `dt`, and
+    /// stay valid until the binding is replaced or cleared, or the state dropped.
+    pub unsafe fn bind_input(
+        &mut self,
+        input: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *const u8,
+    ) -> TractResult<()> {
+        let outlet = *self
+            .model()
+            .input_outlets()?
+            .get(input)
+            .with_context(|| format!("Invalid input id for model ({input})."))?;
+        let fact = self.model().outlet_fact(outlet)?.to_typed_fact()?;
+        crate::io_binding::check_fact(&fact, dt, shape)
+            .with_context(|| format!("Binding input {input}"))?;
+        self.session_state
+            .scratch_extensions
+            .entry::<IoBindings>()
+            .or_default()
+            .bind_input(input, dt, shape, data)
+    }
+
+    /// Bind a caller buffer to an output: [Self::run_bound] writes the output in it, directly
+    /// from the operator computing it when the operator supports it.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to the elements of a `dt` tensor of `shape`, be aligned for `dt`, and
+    /// stay valid and writable until the binding is replaced or cleared, or the state dropped.
+    /// The caller must not access it during runs.
+    pub unsafe fn bind_output(
+        &mut self,
+        output: usize,
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+    ) -> TractResult<()> {
+        let outlet = *self
+            .plan()
+            .outputs
+            .get(output)
+            .with_context(|| format!("Invalid output id for model ({output})."))?;
+        let fact = self.model().outlet_fact(outlet)?.to_typed_fact()?;
+        crate::io_binding::check_fact(&fact, dt, shape)
+            .with_context(|| format!("Binding output {output}"))?;
+        self.session_state
+            .scratch_extensions
+            .entry::<IoBindings>()
+            .or_default()
+            .bind_output(output, outlet, dt, shape, data)
+    }
+
+    /// Observe the nodes evaluated by the state, instead of the observer of the plan.
+    pub fn set_observer<N: NodeObserver + 'static>(&mut self, observer: N) {
+        self.session_state.scratch_extensions.insert(Observation::new(Arc::new(observer)));
+    }
+
+    /// Detach the observer of the state. The observer of the plan, if any, still applies.
+    pub fn clear_observer(&mut self) {
+        self.session_state.scratch_extensions.remove::<Observation>();
+    }
+
+    /// Make the next runs fail with a [Cancelled] error once `token` is cancelled or past its
+    /// deadline, or run without cancellation if `token` is None.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.session_state.cancellation = token;
+    }
+
+    /// Forget the buffers bound to the inputs and outputs.
+    pub fn clear_bindings(&mut self) {
+        self.session_state.scratch_extensions.remove::<IoBindings>();
+    }
+
+    /// Run a turn on the bound buffers. All inputs must be bound. The outputs bound to a buffer
+    /// are written in it, the others are dropped.
+    pub fn run_bound(&mut self) -> TractResult<()> {
+        let bindings = self
+            .session_state
+            .scratch_extensions
+            .get_mut::<IoBindings>()
+            .context("No buffer bound to the state")?;
+        let inputs = (0..self.plan.borrow().model().inputs.len())
+            .map(|ix| bindings.input(ix))
+            .collect::<TractResult<TVec<_>>>()?;
+        bindings.active = true;
+        let result = self.set_inputs(inputs).and_then(|_| self.exec());
+        let SimpleState { ref plan, ref values, ref mut session_state, .. } = self;
+        let bindings = session_state.scratch_extensions.get_mut::<IoBindings>().unwrap();
+        bindings.active = false;
+        result?;
+        bindings.write_outputs(|ix| {
+            let o = plan.borrow().outputs[ix];
+            let vs = values[o.node].as_ref().context("Output is not computed")?;
+            Ok(&*vs[o.slot])
+        })?;
+        self.reset_turn()
+    }
+
     pub fn output(&self, id: usize) -> TractResult<&TValue> {
         let outlet = self.model().output_outlets()?.get(id).with_context(|| {
             format!(
@@ -625,6 +1123,66 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Restore a state written by [FrozenSimpleState::save] for the same model, replacing the
+    /// session and the op states. See [crate::frozen].
+    pub fn restore(&mut self, r: &mut dyn std::io::Read) -> TractResult<()> {
+        use crate::frozen::*;
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        ensure!(&magic == FROZEN_STATE_MAGIC, "Not a frozen state");
+        let mut version = [0u8; 4];
+        r.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        ensure!(version == FROZEN_STATE_VERSION, "Unsupported frozen state version {version}");
+        self.reset_op_states()?;
+        self.values.iter_mut().for_each(|v| *v = None);
+        self.populate_consts();
+        let &mut SimpleState { ref plan, ref mut states, ref mut session_state, .. } = self;
+        let model = plan.borrow().model();
+        let node_count = read_usize(r)?;
+        ensure!(
+            node_count == model.nodes().len(),
+            "Frozen state has {node_count} nodes, model has {}",
+            model.nodes().len()
+        );
+
+        session_state.resolved_symbols = SymbolValues::default();
+        for _ in 0..read_usize(r)? {
+            let symbol = model.symbols.sym(&read_string(r)?);
+            session_state.resolved_symbols.set(&symbol, read_u64(r)? as i64);
+        }
+        session_state.scenario = match read_u64(r)? {
+            u64::MAX => None,
+            scenario => Some(scenario as usize),
+        };
+        session_state.inputs.clear();
+        for _ in 0..read_usize(r)? {
+            let input = read_usize(r)?;
+            session_state.inputs.insert(input, read_tensor(r)?.into_tvalue());
+        }
+        session_state.tensors.clear();
+        for _ in 0..read_usize(r)? {
+            let name = read_string(r)?;
+            session_state.tensors.insert(name, read_tensor(r)?);
+        }
+
+        let state_count = read_usize(r)?;
+        ensure!(
+            state_count == states.iter().flatten().count(),
+            "Frozen state has {state_count} op states, model has {}",
+            states.iter().flatten().count()
+        );
+        for _ in 0..state_count {
+            let id = read_usize(r)?;
+            let name = read_string(r)?;
+            let node = model.nodes().get(id).with_context(|| format!("No node #{id}"))?;
+            ensure!(node.name == name, "Node #{id} is {}, frozen state has {name}", node.name);
+            let state = states[id].as_mut().with_context(|| format!("{node} has no state"))?;
+            state.restore(node.op(), r).with_context(|| format!("Restoring state of {node}"))?;
+        }
+        Ok(())
+    }
 }
 
 pub fn eval<F, O>(
@@ -647,6 +1205,71 @@ where
     r
 }
 
+fn set_current_node(session_state: &SessionState, node: Option<usize>) {
+    if let Some(arena) = session_state.scratch_extensions.get::<MemoryArena>() {
+        arena.set_current_node(node)
+    }
+    if let Some(bindings) = session_state.scratch_extensions.get::<IoBindings>() {
+        bindings.set_current_node(node)
+    }
+}
+
+/// Evaluate a stateless operator out of the plan thread, with a session only holding the resolved
+/// symbols.
+fn eval_detached(
+    op: &dyn Op,
+    inputs: TVec<Arc<Tensor>>,
+    resolved_symbols: SymbolValues,
+    scenario: Option<usize>,
+    executor: Option<Executor>,
+    observed: Option<(Observation, usize, &str)>,
+    cancellation: Option<CancellationToken>,
+) -> TractResult<TVec<Arc<Tensor>>> {
+    let session_state =
+        SessionState { resolved_symbols, scenario, cancellation, ..SessionState::default() };
+    session_state.check_cancellation()?;
+    // inputs not shared with the plan anymore can be consumed by the operator
+    let inputs = inputs
+        .into_iter()
+        .map(|t| Arc::try_unwrap(t).map(|t| t.into_tvalue()).unwrap_or_else(TValue::Const))
+        .collect();
+    let cancellation = session_state.cancellation.clone();
+    let eval = || {
+        cancellable_scope(cancellation, || match observed {
+            Some((observation, id, name)) => observation.observe(id, name, op, inputs, |inputs| {
+                op.eval_with_session(&session_state, inputs)
+            }),
+            None => op.eval_with_session(&session_state, inputs),
+        })
+    };
+    let outputs = if let Some(executor) = executor {
+        tract_linalg::multithread::multithread_tract_scope(executor, eval)
+    } else {
+        eval()
+    }?;
+    Ok(outputs.into_iter().map(|v| v.into_arc_tensor()).collect())
+}
+
+/// Wait for a node evaluation, running pending jobs of the pool meanwhile if the current thread
+/// is one of its workers.
+fn receive<T>(receiver: &std::sync::mpsc::Receiver<T>) -> T {
+    loop {
+        if let Ok(message) = receiver.try_recv() {
+            return message;
+        }
+        match rayon::yield_now() {
+            Some(rayon::Yield::Executed) => (),
+            Some(rayon::Yield::Idle) => {
+                if let Ok(message) = receiver.recv_timeout(std::time::Duration::from_micros(100)) {
+                    return message;
+                }
+            }
+            // the plan keeps a sender, so the channel can not be disconnected
+            None => return receiver.recv().unwrap(),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FrozenSimpleState<F, O, M, P>
 where
@@ -682,6 +1305,7 @@ where
                 tensors: self.tensors.clone(),
                 cached_mmm_scratch_space: None.into(),
                 scratch_extensions: anymap3::Map::new(),
+                cancellation: None,
             },
             states: self.states.iter().map(|s| s.as_ref().map(|s| s.unfreeze())).collect(),
             values: self
@@ -694,11 +1318,62 @@ where
         state.populate_consts();
         state
     }
+
+    /// Write the state to bytes, to be restored by [SimpleState::restore]. See [crate::frozen].
+    pub fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        use crate::frozen::*;
+        let model = self.plan.borrow().model();
+        w.write_all(FROZEN_STATE_MAGIC)?;
+        w.write_all(&FROZEN_STATE_VERSION.to_le_bytes())?;
+        write_usize(w, model.nodes().len())?;
+
+        let symbols = self
+            .resolved_symbols
+            .iter()
+            .map(|(symbol, value)| (symbol.to_string(), value))
+            .sorted()
+            .collect_vec();
+        write_usize(w, symbols.len())?;
+        for (symbol, value) in symbols {
+            write_string(w, &symbol)?;
+            write_u64(w, value as u64)?;
+        }
+        write_u64(w, self.scenario.map_or(u64::MAX, |s| s as u64))?;
+        write_usize(w, self.inputs.len())?;
+        for (input, tensor) in self.inputs.iter().sorted_by_key(|(input, _)| **input) {
+            write_usize(w, *input)?;
+            write_tensor(w, tensor)?;
+        }
+        write_usize(w, self.tensors.len())?;
+        for (name, tensor) in self.tensors.iter().sorted_by_key(|(name, _)| *name) {
+            write_string(w, name)?;
+            write_tensor(w, tensor)?;
+        }
+
+        write_usize(w, self.states.iter().flatten().count())?;
+        for (id, state) in self.states.iter().enumerate() {
+            if let Some(state) = state {
+                let node = &model.nodes()[id];
+                write_usize(w, id)?;
+                write_string(w, &node.name)?;
+                state.save(w).with_context(|| format!("Saving state of {node}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a state written by [FrozenSimpleState::save] for the model of `plan`.
+    pub fn load(plan: P, r: &mut dyn std::io::Read) -> TractResult<FrozenSimpleState<F, O, M, P>> {
+        let mut state = SimpleState::new(plan)?;
+        state.restore(r)?;
+        Ok(state.freeze())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::ops::math;
     fn is_send<T: Send>() {}
     fn is_sync<T: Sync>() {}
 
@@ -726,4 +1401,232 @@ mod test {
     fn frozen_type_state_is_send() {
         is_send::<TypedFrozenSimpleState<TypedModel, TypedSimplePlan<TypedModel>>>();
     }
+
+    // three independent branches of different lengths, joined at the end
+    fn branches() -> TractResult<TypedModel> {
+        use crate::ops::math::{add, exp, mul, tanh};
+        let mut model = TypedModel::default();
+        let s = model.symbols.sym("S");
+        let x = model.add_source("x", f32::fact(&[s.to_dim(), 8.to_dim()]))?;
+        let a = model.wire_node("a", exp(), &[x])?;
+        let b = model.wire_node("b.0", tanh(), &[x])?;
+        let b = model.wire_node("b.1", mul(), &[b[0], x])?;
+        let c = model.wire_node("c.0", add(), &[x, x])?;
+        let c = model.wire_node("c.1", tanh(), &c)?;
+        let c = model.wire_node("c.2", exp(), &c)?;
+        let ab = model.wire_node("ab", add(), &[a[0], b[0]])?;
+        let abc = model.wire_node("abc", mul(), &[ab[0], c[0]])?;
+        model.set_output_outlets(&[abc[0], b[0]])?;
+        Ok(model)
+    }
+
+    fn check_parallel(options: &PlanOptions) -> TractResult<()> {
+        let model = branches()?;
+        let sequential = model.clone().into_runnable()?;
+        let parallel = model.into_runnable_with_options(options)?;
+        for rows in [1, 5] {
+            let x = Tensor::from_shape(&[rows, 8], &vec![0.1f32; rows * 8])?;
+            let expected = sequential.run(tvec!(x.clone().into_tvalue()))?;
+            let mut state = SimpleState::new(&parallel)?;
+            for _ in 0..2 {
+                let found = state.run(tvec!(x.clone().into_tvalue()))?;
+                assert_eq!(expected, found);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_plan_on_global_pool() -> TractResult<()> {
+        check_parallel(&PlanOptions { parallel: true, ..PlanOptions::default() })
+    }
+
+    #[test]
+    fn parallel_plan_on_executor_pool() -> TractResult<()> {
+        let executor = Some(Executor::multithread(2));
+        check_parallel(&PlanOptions { parallel: true, executor, ..PlanOptions::default() })
+    }
+
+    // an element-wise chain on the model input
+    fn chain() -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let mut wire = model.add_source("x", f32::fact([3, 4]))?;
+        for (ix, op) in [math::add(), math::mul(), math::sub()].into_iter().enumerate() {
+            let k = model.add_const(format!("k.{ix}"), tensor2(&[[2f32]]))?;
+            wire = model.wire_node(format!("op.{ix}"), op, &[wire, k])?[0];
+        }
+        wire = model.wire_node("abs", math::abs(), &[wire])?[0];
+        model.set_output_outlets(&[wire])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn element_wise_chain_in_place() -> TractResult<()> {
+        for model in [chain()?, chain()?.into_optimized()?] {
+            let plan = model.into_runnable()?;
+            let x = Tensor::from_shape(&[3, 4], &(0..12).map(|i| i as f32).collect::<Vec<_>>())?;
+            let expected = x.to_array_view::<f32>()?.map(|x| ((x + 2.) * 2. - 2.).abs());
+            let ptr = x.as_ptr::<f32>()?;
+            let outputs = plan.run(tvec!(x.into_tvalue()))?;
+            assert_eq!(outputs[0].to_array_view::<f32>()?, expected);
+            assert_eq!(outputs[0].as_ptr::<f32>()?, ptr);
+            // the caller keeps a reference: its input is left untouched
+            let x: TValue = Tensor::zero::<f32>(&[3, 4])?.into();
+            let outputs = plan.run(tvec!(x.clone()))?;
+            assert_ne!(outputs[0].as_ptr::<f32>()?, x.as_ptr::<f32>()?);
+            assert_eq!(*x, Tensor::zero::<f32>(&[3, 4])?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn exec_twice_on_set_inputs() -> TractResult<()> {
+        for model in [chain()?, chain()?.into_optimized()?] {
+            let plan = model.into_runnable()?;
+            let mut state = SimpleState::new(&plan)?;
+            let x = Tensor::from_shape(&[3, 4], &(0..12).map(|i| i as f32).collect::<Vec<_>>())?;
+            let expected = x.to_array_view::<f32>()?.map(|x| ((x + 2.) * 2. - 2.).abs());
+            state.set_inputs(tvec!(x.into_tvalue()))?;
+            for _ in 0..2 {
+                state.exec()?;
+                assert_eq!(state.outputs()?[0].to_array_view::<f32>()?, expected);
+            }
+        }
+        Ok(())
+    }
+
+    // cancels its token once the node is evaluated, and records the evaluated nodes
+    #[derive(Debug)]
+    struct CancelAfter(&'static str, CancellationToken, std::sync::Mutex<Vec<String>>);
+
+    impl NodeObserver for CancelAfter {
+        fn after_node(
+            &self,
+            node: &crate::observer::ObservedNode,
+            _outputs: &[TValue],
+            _elapsed: std::time::Duration,
+        ) -> TractResult<()> {
+            self.2.lock().unwrap().push(node.name.to_string());
+            if node.name == self.0 {
+                self.1.cancel();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cancellation_between_nodes() -> TractResult<()> {
+        for parallel in [false, true] {
+            let options = PlanOptions { parallel, ..PlanOptions::default() };
+            let plan = chain()?.into_runnable_with_options(&options)?;
+            let mut state = SimpleState::new(&plan)?;
+            let token = CancellationToken::new();
+            let observer = Arc::new(CancelAfter("op.1", token.clone(), Default::default()));
+            state.set_observer(observer.clone());
+            state.set_cancellation(Some(token));
+            let err = state.run(tvec!(Tensor::zero::<f32>(&[3, 4])?.into())).unwrap_err();
+            assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Requested), "{err:?}");
+            assert_eq!(*observer.2.lock().unwrap(), vec!("x", "op.0", "op.1"));
+            state.set_cancellation(None);
+            state.reset_turn()?;
+            state.run(tvec!(Tensor::zero::<f32>(&[3, 4])?.into()))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn deadline_exceeded() -> TractResult<()> {
+        let plan = chain()?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        let token = CancellationToken::new().with_timeout(std::time::Duration::ZERO);
+        state.set_cancellation(Some(token));
+        let err = state.run(tvec!(Tensor::zero::<f32>(&[3, 4])?.into())).unwrap_err();
+        assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::DeadlineExceeded));
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct CancelBeforeMatMul(CancellationToken);
+
+    impl NodeObserver for CancelBeforeMatMul {
+        fn before_node(
+            &self,
+            node: &crate::observer::ObservedNode,
+            _inputs: &[TValue],
+        ) -> TractResult<()> {
+            if node.op.is::<crate::ops::matmul::optimized::OptMatMul>() {
+                self.0.cancel();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cancellation_in_matmul_tiles() -> TractResult<()> {
+        let mut model = TypedModel::default();
+        let a = model.add_source("a", f32::fact([64, 64]))?;
+        let b = model.add_const("b", Tensor::zero::<f32>(&[64, 64])?)?;
+        let op = crate::ops::einsum::EinSum::new("mk,kn->mn".parse()?, f32::datum_type());
+        let c = model.wire_node("c", op, &[a, b])?;
+        model.set_output_outlets(&c)?;
+        let plan = model.into_optimized()?.into_runnable()?;
+        // the matrix product is the last node: no check between nodes follows it
+        let last = plan.model().node(*plan.order_without_consts().last().unwrap());
+        assert!(last.op_is::<crate::ops::matmul::optimized::OptMatMul>());
+        let mut state = SimpleState::new(&plan)?;
+        let token = CancellationToken::new();
+        state.set_observer(CancelBeforeMatMul(token.clone()));
+        state.set_cancellation(Some(token));
+        let err = state.run(tvec!(Tensor::zero::<f32>(&[64, 64])?.into())).unwrap_err();
+        assert_eq!(err.downcast_ref::<Cancelled>(), Some(&Cancelled::Requested), "{err:?}");
+        Ok(())
+    }
+
+    // sums its inputs over runs in a memory op
+    fn accumulator() -> TractResult<TypedModel> {
+        use crate::ops::memory::{load::Load, store::Store};
+        let mut model = TypedModel::default();
+        let s = model.symbols.sym("S");
+        let x = model.add_source("x", f32::fact(dims!(s)))?;
+        let acc = model.wire_node("load", Load::new("acc"), &[x])?;
+        let sum = model.wire_node("sum", math::add(), &[acc[0], x])?;
+        let store = model.wire_node("store", Store::new("acc"), &[sum[0], sum[0]])?;
+        model.set_output_outlets(&store)?;
+        Ok(model)
+    }
+
+    #[test]
+    fn save_and_restore() -> TractResult<()> {
+        let plan = accumulator()?.into_runnable()?;
+        let x = || tvec!(tensor1(&[1f32, 2.0]).into());
+        let s = plan.model().symbols.sym("S");
+        let mut state = SimpleState::new(&plan)?;
+        state.run(x())?;
+        // runs reset the symbols at the end of the turn, callers may set some between them
+        state.session_state.resolved_symbols.set(&s, 2);
+        let mut bytes = vec![];
+        state.freeze().save(&mut bytes)?;
+        let expected = state.run(x())?;
+        assert_eq!(*expected[0], tensor1(&[3f32, 6.0]));
+
+        let mut restored = SimpleState::new(&plan)?;
+        restored.restore(&mut &*bytes)?;
+        assert_eq!(restored.session_state.resolved_symbols.get(&s), Some(2));
+        assert_eq!(restored.run(x())?, expected);
+        let frozen = FrozenSimpleState::load(&plan, &mut &*bytes)?;
+        assert_eq!(frozen.unfreeze().run(x())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn restore_into_other_model() -> TractResult<()> {
+        let plan = accumulator()?.into_runnable()?;
+        let mut state = SimpleState::new(&plan)?;
+        state.run(tvec!(tensor1(&[1f32, 2.0]).into()))?;
+        let mut bytes = vec![];
+        state.freeze().save(&mut bytes)?;
+        let other = branches()?.into_runnable()?;
+        assert!(SimpleState::new(&other)?.restore(&mut &*bytes).is_err());
+        Ok(())
+    }
 }
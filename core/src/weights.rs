@@ -0,0 +1,438 @@
+//! Constant weights that can be replaced in an optimized model.
+//!
+//! Optimizing a model folds its constants into the tensors its optimized operators consume:
+//! transposed, casted, or packed for a matrix multiplication kernel. Replacing a weight would
+//! then require to optimize the model again.
+//!
+//! [UpdatableWeights::mark] isolates the named constants from the rest of the model behind an
+//! [UpdatableWeight] marker, that the optimizer can not fold. Once the model is optimized,
+//! [UpdatableWeights::fold] folds the nodes computed from the weights only, keeping for each
+//! folded tensor the recipe computing it from the weights. [UpdatableWeights::update] replaces
+//! some weights, re-computing only the folded tensors depending on them.
+//! [TypedModel::into_optimized_with_updatable_weights] chains the three steps.
+//!
+//! The weights are optimized as if they were model inputs: the kernels selected for constant
+//! operands, if any, are not used.
+use std::collections::HashSet;
+
+use crate::internal::*;
+use crate::ops::dummy::Dummy;
+use crate::ops::konst::Const;
+use crate::ops::source::TypedSource;
+
+/// Marker of an updatable weight, preventing its propagation as a constant.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct UpdatableWeight {
+    /// Name, or outlet label, of the weight constant.
+    pub name: String,
+}
+
+impl Op for UpdatableWeight {
+    fn name(&self) -> Cow<str> {
+        "UpdatableWeight".into()
+    }
+
+    fn info(&self) -> TractResult<Vec<String>> {
+        Ok(vec![format!("weight: {}", self.name)])
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for UpdatableWeight {
+    fn is_stateless(&self) -> bool {
+        false
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        Ok(inputs)
+    }
+
+    fn state(
+        &self,
+        _session: &mut SessionState,
+        _node_id: usize,
+    ) -> TractResult<Option<Box<dyn OpState>>> {
+        Ok(Some(Box::new(self.clone())))
+    }
+}
+
+impl OpState for UpdatableWeight {
+    fn eval(
+        &mut self,
+        _session: &mut SessionState,
+        _op: &dyn Op,
+        inputs: TVec<TValue>,
+    ) -> TractResult<TVec<TValue>> {
+        Ok(inputs)
+    }
+
+    fn restore(&mut self, _op: &dyn Op, _r: &mut dyn std::io::Read) -> TractResult<()> {
+        Ok(())
+    }
+}
+
+impl TypedOp for UpdatableWeight {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].without_value()))
+    }
+
+    as_op!();
+}
+
+trivial_op_state_freeeze!(UpdatableWeight, from_op);
+
+#[derive(Clone, Debug)]
+struct Weight {
+    fact: TypedFact,
+    value: Arc<Tensor>,
+}
+
+/// Computation of a folded tensor from the weights.
+#[derive(Clone, Debug)]
+struct Recipe {
+    /// Id of the constant node holding the tensor in the optimized model.
+    node: usize,
+    /// Weights feeding the plan inputs.
+    weights: Vec<String>,
+    plan: Arc<TypedSimplePlan<TypedModel>>,
+}
+
+impl Recipe {
+    fn eval(&self, weights: &HashMap<String, Weight>) -> TractResult<Arc<Tensor>> {
+        let inputs = self.weights.iter().map(|w| weights[w].value.clone().into_tvalue()).collect();
+        let outputs = self.plan.run(inputs)?;
+        Ok(outputs[0].clone().into_arc_tensor())
+    }
+}
+
+/// Weights of an optimized model, and recipes to compute the tensors folded from them.
+#[derive(Clone, Debug, Default)]
+pub struct UpdatableWeights {
+    weights: HashMap<String, Weight>,
+    recipes: Vec<Recipe>,
+}
+
+impl UpdatableWeights {
+    /// Mark constants, identified by node name or outlet label, as updatable weights.
+    pub fn mark(model: &mut TypedModel, weights: &[impl AsRef<str>]) -> TractResult<()> {
+        for name in weights {
+            let name = name.as_ref();
+            let outlet = if let Ok(id) = model.node_id_by_name(name) {
+                OutletId::new(id, 0)
+            } else {
+                model
+                    .find_outlet_label(name)
+                    .with_context(|| format!("No node or outlet named {name}"))?
+            };
+            let fact = model.outlet_fact(outlet)?.clone();
+            ensure!(
+                model.node(outlet.node).op_is::<Const>() && fact.konst.is_some(),
+                "{name} is not a constant"
+            );
+            let op = UpdatableWeight { name: name.to_string() };
+            let marker = format!("{}.updatable", model.node(outlet.node).name);
+            TypedModelPatch::intercept(model, outlet, marker, op, fact.without_value())?
+                .apply(model)?;
+        }
+        Ok(())
+    }
+
+    /// Fold the nodes computed from the marked weights and constants only.
+    pub fn fold(model: &mut TypedModel) -> TractResult<UpdatableWeights> {
+        let order = model.eval_order()?;
+        let mut weights = HashMap::new();
+        let mut derived = HashSet::new();
+        for &id in &order {
+            let node = model.node(id);
+            if let Some(op) = node.op_as::<UpdatableWeight>() {
+                let fact = model.outlet_fact(node.inputs[0])?;
+                let value = fact.konst.clone().context("Updatable weight is not a constant")?;
+                weights.insert(op.name.clone(), Weight { fact: fact.without_value(), value });
+                derived.insert(id);
+            } else if !node.op_is::<Const>()
+                && !node.op_is::<Dummy>()
+                && !node.op_is::<TypedSource>()
+                && node.op.is_stateless()
+                && node.inputs.iter().any(|i| derived.contains(&i.node))
+                && node.inputs.iter().all(|i| {
+                    derived.contains(&i.node)
+                        || model.outlet_fact(*i).is_ok_and(|f| f.konst.is_some())
+                })
+                && node.outputs.iter().all(|o| o.fact.shape.is_concrete())
+            {
+                derived.insert(id);
+            }
+        }
+        let mut boundaries = vec![];
+        for &id in &order {
+            if !derived.contains(&id) {
+                continue;
+            }
+            for slot in 0..model.node(id).outputs.len() {
+                let outlet = OutletId::new(id, slot);
+                if model.outputs.contains(&outlet)
+                    || model
+                        .outlet_successors(outlet)
+                        .iter()
+                        .any(|succ| !derived.contains(&succ.node))
+                {
+                    boundaries.push(outlet);
+                }
+            }
+        }
+        let mut updatable = UpdatableWeights { weights, recipes: vec![] };
+        let mut patch = TypedModelPatch::new("fold updatable weights");
+        for boundary in boundaries {
+            let mut recipe = Self::recipe(model, &order, &derived, boundary)?;
+            let node = &model.node(boundary.node);
+            let name = if node.outputs.len() > 1 {
+                format!("{}.{}", node.name, boundary.slot)
+            } else {
+                node.name.clone()
+            };
+            // the boundary node is discarded, but other nodes may already use the name
+            let name = if model.nodes().iter().any(|n| n.id != boundary.node && n.name == name) {
+                model.unique_name(name).into_owned()
+            } else {
+                name
+            };
+            let value = recipe.eval(&updatable.weights)?;
+            let opaque_fact = model.outlet_fact(boundary)?.opaque_fact.clone();
+            // added to the model and tapped, so that the patch does not renumber it. The name
+            // is still held by the boundary node until compaction.
+            let konst = Const(value, opaque_fact);
+            let fact = konst.output_facts(&[])?.remove(0);
+            recipe.node = model.add_node(name, konst, tvec!(fact))?;
+            let tap = patch.tap_model(model, recipe.node.into())?;
+            patch.shunt_outside(model, boundary, tap)?;
+            updatable.recipes.push(recipe);
+        }
+        patch.apply(model)?;
+        let old_to_new = model.compact_with_mapping()?;
+        for recipe in &mut updatable.recipes {
+            recipe.node = old_to_new[recipe.node];
+            ensure!(recipe.node < model.nodes().len(), "Folded weight constant is not used");
+            let name = &model.node(recipe.node).name;
+            ensure!(
+                model.nodes().iter().filter(|n| &n.name == name).count() == 1,
+                "Duplicate name {name} for a folded weight constant"
+            );
+        }
+        Ok(updatable)
+    }
+
+    /// Extract the computation of a derived outlet as a model taking the weights as inputs.
+    fn recipe(
+        model: &TypedModel,
+        order: &[usize],
+        derived: &HashSet<usize>,
+        boundary: OutletId,
+    ) -> TractResult<Recipe> {
+        let mut ancestors = HashSet::new();
+        let mut todo = vec![boundary.node];
+        while let Some(id) = todo.pop() {
+            if ancestors.insert(id) {
+                todo.extend(
+                    model.node(id).inputs.iter().map(|i| i.node).filter(|n| derived.contains(n)),
+                );
+            }
+        }
+        let mut recipe = TypedModel::default();
+        let mut weights = vec![];
+        let mut mapping = HashMap::<OutletId, OutletId>::new();
+        for &id in order.iter().filter(|id| ancestors.contains(id)) {
+            let node = model.node(id);
+            if let Some(op) = node.op_as::<UpdatableWeight>() {
+                let name = &model.node(node.inputs[0].node).name;
+                let source =
+                    recipe.add_source(name, model.outlet_fact(node.inputs[0])?.without_value())?;
+                mapping.insert(id.into(), source);
+                weights.push(op.name.clone());
+                continue;
+            }
+            let mut inputs = tvec!();
+            for input in &node.inputs {
+                if !mapping.contains_key(input) {
+                    let fact = model.outlet_fact(*input)?;
+                    let konst = Const(fact.konst.clone().unwrap(), fact.opaque_fact.clone());
+                    let name = &model.node(input.node).name;
+                    let name = if input.slot > 0 {
+                        format!("{name}.{}", input.slot)
+                    } else {
+                        name.clone()
+                    };
+                    mapping.insert(*input, recipe.wire_node(name, konst, &[])?[0]);
+                }
+                inputs.push(mapping[input]);
+            }
+            let outputs = recipe.wire_node(&node.name, node.op.clone(), &inputs)?;
+            for (slot, output) in outputs.into_iter().enumerate() {
+                mapping.insert(OutletId::new(id, slot), output);
+            }
+        }
+        recipe.set_output_outlets(&[mapping[&boundary]])?;
+        Ok(Recipe { node: usize::MAX, weights, plan: Arc::new(SimplePlan::new(recipe)?) })
+    }
+
+    /// Names, or outlet labels, of the weights.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.weights.keys().map(|s| s.as_str())
+    }
+
+    /// Current value of a weight.
+    pub fn weight(&self, name: &str) -> Option<&Arc<Tensor>> {
+        self.weights.get(name).map(|w| &w.value)
+    }
+
+    /// Replace some weights in the optimized model, re-computing the folded tensors depending on
+    /// them. Plans built before the update keep using the previous weights.
+    pub fn update<S: AsRef<str>>(
+        &mut self,
+        model: &mut TypedModel,
+        values: impl IntoIterator<Item = (S, Tensor)>,
+    ) -> TractResult<()> {
+        let mut weights = self.weights.clone();
+        let mut updated = HashSet::<String>::new();
+        for (name, value) in values {
+            let name = name.as_ref();
+            let weight =
+                weights.get_mut(name).with_context(|| format!("No weight named {name}"))?;
+            ensure!(
+                weight.fact.datum_type == value.datum_type()
+                    && weight.fact.shape.as_concrete() == Some(value.shape()),
+                "Weight {name} is a {:?}, got a {:?}",
+                weight.fact,
+                value
+            );
+            weight.value = value.into_arc_tensor();
+            updated.insert(name.to_string());
+        }
+        let mut folded = vec![];
+        for recipe in &self.recipes {
+            if recipe.weights.iter().any(|w| updated.contains(w)) {
+                let id = recipe.node;
+                let node = model.nodes().get(id).context("Folded weight node not found")?;
+                let Some(Const(_, opaque_fact)) = node.op_as::<Const>() else {
+                    bail!("{} is not a constant", node.name)
+                };
+                let konst = Const(recipe.eval(&weights)?, opaque_fact.clone());
+                let fact = konst.output_facts(&[])?.remove(0);
+                let previous = &model.node(id).outputs[0].fact;
+                ensure!(
+                    fact.datum_type == previous.datum_type && fact.shape == previous.shape,
+                    "Updated {} is a {:?}, expected a {:?}",
+                    model.node(id).name,
+                    fact,
+                    previous
+                );
+                folded.push((id, konst, fact));
+            }
+        }
+        for (id, konst, fact) in folded {
+            let node = model.node_mut(id);
+            node.op = Box::new(konst);
+            node.outputs[0].fact = fact;
+        }
+        self.weights = weights;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ops::einsum::EinSum;
+    use crate::ops::math;
+
+    fn values(shape: &[usize], seed: usize) -> TractResult<Tensor> {
+        let len = shape.iter().product::<usize>();
+        let data = (0..len).map(|i| ((i * 7 + seed) % 13) as f32 * 0.1 - 0.6).collect::<Vec<_>>();
+        tensor1(&data).into_shape(shape)
+    }
+
+    // (x.w)^T + b
+    fn model(w: Tensor, b: Tensor) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([4, 16]))?;
+        let w = model.wire_node("w", Const::new(w.into_arc_tensor()), &[])?[0];
+        let b = model.wire_node("b", Const::new(b.into_arc_tensor()), &[])?[0];
+        let y =
+            model.wire_node("mm", EinSum::new("mk,nk->nm".parse()?, f32::datum_type()), &[x, w])?;
+        let y = model.wire_node("add", math::add(), &[y[0], b])?;
+        model.set_output_outlets(&y)?;
+        Ok(model)
+    }
+
+    fn run(model: &TypedModel) -> TractResult<Arc<Tensor>> {
+        let outputs = SimplePlan::new(model)?.run(tvec!(values(&[4, 16], 3)?.into()))?;
+        Ok(outputs[0].clone().into_arc_tensor())
+    }
+
+    #[test]
+    fn update_optimized_weights() -> TractResult<()> {
+        let (mut optimized, mut weights) = model(values(&[8, 16], 0)?, values(&[8, 1], 1)?)?
+            .into_optimized_with_updatable_weights(&["w", "b"])?;
+        assert!(optimized.nodes().iter().all(|n| !n.op_is::<UpdatableWeight>()));
+        assert!(optimized.nodes().iter().any(|n| n.op().name() == "OptMatMul"));
+        // packed weights
+        assert!(optimized
+            .nodes()
+            .iter()
+            .any(|n| n.op_is::<Const>() && n.outputs[0].fact.opaque_fact.is_some()));
+        let expected = run(&model(values(&[8, 16], 0)?, values(&[8, 1], 1)?)?)?;
+        run(&optimized)?.close_enough(&expected, Approximation::Approximate)?;
+
+        weights.update(&mut optimized, [("w", values(&[8, 16], 5)?)])?;
+        let expected = run(&model(values(&[8, 16], 5)?, values(&[8, 1], 1)?)?)?;
+        run(&optimized)?.close_enough(&expected, Approximation::Approximate)?;
+
+        weights.update(&mut optimized, [("b", values(&[8, 1], 2)?)])?;
+        let expected = run(&model(values(&[8, 16], 5)?, values(&[8, 1], 2)?)?)?;
+        run(&optimized)?.close_enough(&expected, Approximation::Approximate)?;
+        Ok(())
+    }
+
+    // (x.w)^T and z + w, sharing w
+    fn shared(w: Tensor) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let x = model.add_source("x", f32::fact([4, 16]))?;
+        let z = model.add_source("z", f32::fact([8, 16]))?;
+        let w = model.wire_node("w", Const::new(w.into_arc_tensor()), &[])?[0];
+        let mm = EinSum::new("mk,nk->nm".parse()?, f32::datum_type());
+        let y0 = model.wire_node("mm", mm, &[x, w])?[0];
+        let y1 = model.wire_node("add", math::add(), &[z, w])?[0];
+        model.set_output_outlets(&[y0, y1])?;
+        Ok(model)
+    }
+
+    #[test]
+    fn update_shared_weight() -> TractResult<()> {
+        let (mut optimized, mut weights) =
+            shared(values(&[8, 16], 0)?)?.into_optimized_with_updatable_weights(&["w"])?;
+        assert!(weights.recipes.len() > 1);
+        for recipe in &weights.recipes {
+            let name = &optimized.node(recipe.node).name;
+            assert!(optimized.node(recipe.node).op_is::<Const>());
+            assert_eq!(optimized.node_id_by_name(name)?, recipe.node);
+        }
+        weights.update(&mut optimized, [("w", values(&[8, 16], 5)?)])?;
+        let input = tvec!(values(&[4, 16], 3)?.into(), values(&[8, 16], 4)?.into());
+        let outputs = SimplePlan::new(&optimized)?.run(input.clone())?;
+        let expected = SimplePlan::new(shared(values(&[8, 16], 5)?)?)?.run(input)?;
+        for (output, expected) in outputs.iter().zip(expected.iter()) {
+            output.close_enough(expected, Approximation::Approximate)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn update_checks_weights() -> TractResult<()> {
+        let (mut optimized, mut weights) = model(values(&[8, 16], 0)?, values(&[8, 1], 1)?)?
+            .into_optimized_with_updatable_weights(&["w"])?;
+        assert!(weights.update(&mut optimized, [("b", values(&[8, 1], 2)?)]).is_err());
+        assert!(weights.update(&mut optimized, [("w", values(&[16, 8], 2)?)]).is_err());
+        assert_eq!(**weights.weight("w").unwrap(), values(&[8, 16], 0)?);
+        Ok(())
+    }
+}
@@ -2,14 +2,17 @@ use num_traits::Zero;
 
 use crate::{TractError, TractResult};
 use std::alloc::*;
+use std::any::Any;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::ptr::null_mut;
+use std::sync::Arc;
 
-#[derive(Eq)]
 pub struct Blob {
     layout: std::alloc::Layout,
     data: *mut u8,
+    // owner of the storage when it has not been allocated by the blob (a memory map for instance)
+    external: Option<Arc<dyn Any + Send + Sync>>,
 }
 
 impl Default for Blob {
@@ -29,7 +32,7 @@ impl Clone for Blob {
 impl Drop for Blob {
     #[inline]
     fn drop(&mut self) {
-        if !self.data.is_null() {
+        if !self.data.is_null() && self.external.is_none() {
             unsafe { dealloc(self.data, self.layout) }
         }
     }
@@ -42,6 +45,8 @@ impl PartialEq for Blob {
     }
 }
 
+impl Eq for Blob {}
+
 impl Hash for Blob {
     #[inline]
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
@@ -59,9 +64,10 @@ impl Blob {
     #[inline]
     pub unsafe fn ensure_size_and_align(&mut self, size: usize, align: usize) {
         if size > self.layout.size() || align > self.layout.align() {
-            if !self.data.is_null() {
+            if !self.data.is_null() && self.external.is_none() {
                 std::alloc::dealloc(self.data as _, self.layout);
             }
+            self.external = None;
             self.layout = Layout::from_size_align_unchecked(size, align);
             self.data = std::alloc::alloc(self.layout);
             assert!(!self.data.is_null());
@@ -75,7 +81,35 @@ impl Blob {
             data = unsafe { alloc(layout) };
             assert!(!data.is_null(), "failed to allocate {layout:?}");
         }
-        Blob { layout, data }
+        Blob { layout, data, external: None }
+    }
+
+    /// Wrap storage owned by `owner` without copying it.
+    ///
+    /// # Safety
+    ///
+    /// `data` must be valid for `layout`, and stay valid (and writable, if the blob is to be
+    /// mutated) as long as `owner` is alive.
+    #[inline]
+    pub unsafe fn from_external(
+        data: *mut u8,
+        layout: Layout,
+        owner: Arc<dyn Any + Send + Sync>,
+    ) -> Blob {
+        Blob { layout, data, external: Some(owner) }
+    }
+
+    /// True if the blob storage is owned by something else, typically a memory map.
+    #[inline]
+    pub fn is_external(&self) -> bool {
+        self.external.is_some()
+    }
+
+    /// Pointer to the storage. It is not derived from a reference to the content, so it can be
+    /// written through while the blob is shared, provided no reference to the content is alive.
+    #[inline]
+    pub fn as_raw_ptr(&self) -> *mut u8 {
+        self.data
     }
 
     #[inline]
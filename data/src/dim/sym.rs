@@ -330,6 +330,10 @@ impl SymbolValues {
     pub fn get(&self, s: &Symbol) -> Option<i64> {
         self.values.get(s).copied()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Symbol, i64)> {
+        self.values.iter().map(|(s, v)| (s, *v))
+    }
 }
 
 #[cfg(test)]
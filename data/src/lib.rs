@@ -39,7 +39,7 @@ pub mod prelude {
 
 pub mod internal {
     pub use crate::datum::ClampCast;
-    pub use crate::dim::{parse_tdim, solve_for, DimLike};
+    pub use crate::dim::{parse_tdim, solve_for, Assertion, DimLike};
     pub use crate::opaque::{ OpaquePayload, OpaqueFact };
     pub use crate::prelude::*;
     pub use crate::tensor::view::TensorView;
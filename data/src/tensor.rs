@@ -434,6 +434,65 @@ impl Tensor {
         Self::from_raw_dt_align(T::datum_type(), &[content.len()], bytes, align)
     }
 
+    /// Create a tensor sharing its storage with `content`, kept alive by `owner`.
+    ///
+    /// Nothing is copied: this is meant for tensors backed by a memory map. Fails if the datum
+    /// type is not copy, if content size does not match the shape, or if content is empty or not
+    /// aligned for the datum type.
+    ///
+    /// # Safety
+    ///
+    /// `content` must stay valid as long as `owner` is alive, and be writable (a private memory
+    /// map for instance) in case the tensor gets mutated.
+    pub unsafe fn from_external_bytes(
+        dt: DatumType,
+        shape: &[usize],
+        content: &[u8],
+        owner: Arc<dyn std::any::Any + Send + Sync>,
+    ) -> TractResult<Tensor> {
+        let bytes = shape.iter().product::<usize>() * dt.size_of();
+        ensure!(
+            bytes == content.len(),
+            "Expected {} bytes for {:?} {:?}, got {}",
+            bytes,
+            dt,
+            shape,
+            content.len()
+        );
+        Self::from_external_ptr(dt, shape, content.as_ptr() as *mut u8, owner)
+    }
+
+    /// Create a tensor sharing its storage with the buffer at `data`, kept alive by `owner`.
+    ///
+    /// Same as [Tensor::from_external_bytes], for buffers the tensor is meant to be written in:
+    /// the pointer must not be derived from a shared reference.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to enough bytes for `dt` and `shape`, valid as long as `owner` is alive,
+    /// and writable.
+    pub unsafe fn from_external_ptr(
+        dt: DatumType,
+        shape: &[usize],
+        data: *mut u8,
+        owner: Arc<dyn std::any::Any + Send + Sync>,
+    ) -> TractResult<Tensor> {
+        ensure!(dt.is_copy(), "Can not share storage of {dt:?} tensor");
+        let bytes = shape.iter().product::<usize>() * dt.size_of();
+        ensure!(bytes > 0, "Can not share storage of empty tensor");
+        ensure!(data as usize % dt.alignment() == 0, "Misaligned storage for {dt:?} tensor");
+        let layout = std::alloc::Layout::from_size_align(bytes, dt.alignment())?;
+        let data = Blob::from_external(data, layout, owner);
+        let mut tensor = Tensor { strides: tvec!(), dt, shape: shape.into(), data, len: 0 };
+        tensor.update_strides_and_len();
+        Ok(tensor)
+    }
+
+    /// True if the tensor storage is borrowed from something else, typically a memory map.
+    pub fn has_external_storage(&self) -> bool {
+        self.data.is_external()
+    }
+
     /// Get the number of dimensions (or axes) of the tensor.
     #[inline]
     pub fn rank(&self) -> usize {
@@ -939,6 +998,11 @@ impl Tensor {
         self.data.as_mut_ptr() as *mut D
     }
 
+    /// Pointer to the storage, for writing in a shared tensor (see [Blob::as_raw_ptr]).
+    pub unsafe fn as_raw_storage_ptr(&self) -> *mut u8 {
+        self.data.as_raw_ptr()
+    }
+
     /// Access the data as a mutable pointer.
     pub fn as_ptr_mut<D: Datum>(&mut self) -> TractResult<*mut D> {
         self.as_ptr::<D>().map(|p| p as *mut D)
@@ -1873,4 +1937,46 @@ mod tests {
         let t = tensor0(TDim::from(a));
         let _ = t.clone();
     }
+
+    #[test]
+    fn external_storage() -> TractResult<()> {
+        let storage: Arc<Vec<f32>> = Arc::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let bytes =
+            unsafe { std::slice::from_raw_parts(storage.as_ptr() as *const u8, storage.len() * 4) };
+        let t = unsafe {
+            Tensor::from_external_bytes(f32::datum_type(), &[2, 3], bytes, storage.clone())?
+        };
+        assert!(t.has_external_storage());
+        assert_eq!(t.as_ptr::<f32>()?, storage.as_ptr());
+        assert_eq!(t, crate::internal::tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]));
+        let cloned = t.clone();
+        assert!(!cloned.has_external_storage());
+        assert_eq!(cloned, t);
+        drop(t);
+        drop(cloned);
+        assert_eq!(Arc::strong_count(&storage), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn external_storage_written_in() -> TractResult<()> {
+        let storage = Arc::new(Tensor::zero::<f32>(&[4])?);
+        let mut t = unsafe {
+            let data = storage.as_raw_storage_ptr().add(2 * 4);
+            Tensor::from_external_ptr(f32::datum_type(), &[2], data, storage.clone())?
+        };
+        t.as_slice_mut::<f32>()?.copy_from_slice(&[1., 2.]);
+        drop(t);
+        assert_eq!(storage.as_slice::<f32>()?, &[0f32, 0., 1., 2.]);
+        Ok(())
+    }
+
+    #[test]
+    fn external_storage_misaligned() {
+        let storage: Arc<Vec<u8>> = Arc::new(vec![0; 12]);
+        assert!(unsafe {
+            Tensor::from_external_bytes(f32::datum_type(), &[2], &storage[1..9], storage.clone())
+        }
+        .is_err());
+    }
 }
@@ -28,7 +28,7 @@ fn cpu_part() -> Option<usize> {
     })
 }
 
-fn has_neon() -> bool {
+pub(crate) fn has_neon() -> bool {
     if let Ok(v) = env::var("TRACT_CPU_ARM32_NEON") {
         return v == "true" || v == "1";
     }
@@ -0,0 +1,127 @@
+//! Cooperative cancellation of computations.
+//!
+//! Long computations check the cancellation token of their thread, set with
+//! [cancellable_scope], and fail with a [Cancelled] error once it has been cancelled or its
+//! deadline has passed.
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tract_data::TractResult;
+
+/// Error of a computation stopped by its cancellation token. It can be told apart from other
+/// errors with `error.downcast_ref::<Cancelled>()`, context added on the way included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cancelled {
+    /// The token was cancelled.
+    Requested,
+    /// The deadline of the token has passed.
+    DeadlineExceeded,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cancelled::Requested => write!(f, "Computation cancelled"),
+            Cancelled::DeadlineExceeded => write!(f, "Computation deadline exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// A flag cancelling the computations checking it when raised from any thread, and an optional
+/// deadline. Clones share the flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn with_deadline(self, deadline: Instant) -> CancellationToken {
+        CancellationToken { deadline: Some(deadline), ..self }
+    }
+
+    /// Set the deadline `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> CancellationToken {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail with a [Cancelled] error if the token is cancelled or past its deadline.
+    pub fn check(&self) -> TractResult<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled::Requested.into());
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(Cancelled::DeadlineExceeded.into());
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static TLS_CANCELLATION: RefCell<Option<CancellationToken>> = Default::default();
+}
+
+/// The cancellation token of the current thread.
+pub fn current_cancellation() -> Option<CancellationToken> {
+    TLS_CANCELLATION.with_borrow(|tls| tls.clone())
+}
+
+// restores the previous token of the thread, even if the scope unwinds
+struct RestoreCancellation(Option<CancellationToken>);
+
+impl Drop for RestoreCancellation {
+    fn drop(&mut self) {
+        TLS_CANCELLATION.set(self.0.take());
+    }
+}
+
+/// Run `f` with `token` as the cancellation token of the current thread.
+pub fn cancellable_scope<R, F: FnOnce() -> R>(token: Option<CancellationToken>, f: F) -> R {
+    let _restore = RestoreCancellation(TLS_CANCELLATION.replace(token));
+    f()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scopes_nest() {
+        let outer = CancellationToken::new().with_timeout(Duration::from_secs(1));
+        cancellable_scope(Some(outer.clone()), || {
+            cancellable_scope(None, || assert!(current_cancellation().is_none()));
+            assert_eq!(current_cancellation().and_then(|t| t.deadline()), outer.deadline());
+        });
+        assert!(current_cancellation().is_none());
+    }
+
+    #[test]
+    fn token_restored_after_panic() {
+        let result = std::panic::catch_unwind(|| {
+            cancellable_scope(Some(CancellationToken::new()), || panic!("boom"))
+        });
+        assert!(result.is_err());
+        assert!(current_cancellation().is_none());
+    }
+}
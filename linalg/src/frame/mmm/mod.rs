@@ -17,6 +17,7 @@ mod storage;
 #[macro_use]
 pub mod tests;
 
+use crate::cancel::CancellationToken;
 use crate::multithread::Executor;
 use rayon::prelude::*;
 use std::fmt::Debug;
@@ -163,23 +164,30 @@ impl<K: MatMatMulKer> MatMatMul for K {
     }
 }
 
+fn check_cancellation(cancellation: &Option<CancellationToken>) -> TractResult<()> {
+    cancellation.as_ref().map_or(Ok(()), |c| c.check())
+}
+
 unsafe fn run_with_scratch_space_vec<K: MatMatMulKer>(
     ker: &K,
     m: usize,
     scratch: &mut ScratchSpaceImpl<K::Acc>,
     non_linear: &[FusedSpec],
 ) -> TractResult<()> {
+    let cancellation = crate::cancel::current_cancellation();
     match crate::multithread::current_tract_executor() {
         Executor::SingleThread => {
             for ia in 0..m.divceil(ker.mr()) {
+                check_cancellation(&cancellation)?;
                 scratch.run(ker, non_linear, ia, 0)?;
             }
             Ok(())
         }
         Executor::MultiThread(pool) => pool.install(|| {
-            (0..m.div_ceil(ker.mr()))
-                .into_par_iter()
-                .try_for_each(|ia| scratch.run(ker, non_linear, ia, 0))
+            (0..m.div_ceil(ker.mr())).into_par_iter().try_for_each(|ia| {
+                check_cancellation(&cancellation)?;
+                scratch.run(ker, non_linear, ia, 0)
+            })
         }),
     }
 }
@@ -191,9 +199,11 @@ unsafe fn run_with_scratch_space_col_outer<K: MatMatMulKer>(
     scratch: &mut ScratchSpaceImpl<K::Acc>,
     non_linear: &[FusedSpec],
 ) -> TractResult<()> {
+    let cancellation = crate::cancel::current_cancellation();
     match crate::multithread::current_tract_executor() {
         Executor::SingleThread => {
             for ib in 0..n.divceil(ker.nr()) {
+                check_cancellation(&cancellation)?;
                 for ia in 0..m.divceil(ker.mr()) {
                     scratch.run(ker, non_linear, ia, ib)?;
                 }
@@ -202,6 +212,7 @@ unsafe fn run_with_scratch_space_col_outer<K: MatMatMulKer>(
         }
         Executor::MultiThread(pool) => pool.install(|| {
             (0..n.div_ceil(ker.nr())).into_par_iter().try_for_each(|ib| {
+                check_cancellation(&cancellation)?;
                 for ia in 0..m.divceil(ker.mr()) {
                     scratch.run(ker, non_linear, ia, ib)?;
                 }
@@ -218,9 +229,11 @@ unsafe fn run_with_scratch_space_row_outer<K: MatMatMulKer>(
     scratch: &mut ScratchSpaceImpl<K::Acc>,
     non_linear: &[FusedSpec],
 ) -> TractResult<()> {
+    let cancellation = crate::cancel::current_cancellation();
     match crate::multithread::current_tract_executor() {
         Executor::SingleThread => {
             for ia in 0..m.divceil(ker.mr()) {
+                check_cancellation(&cancellation)?;
                 for ib in 0..n.divceil(ker.nr()) {
                     scratch.run(ker, non_linear, ia, ib)?;
                 }
@@ -230,6 +243,7 @@ unsafe fn run_with_scratch_space_row_outer<K: MatMatMulKer>(
         Executor::MultiThread(pool) => pool.install(|| {
             pool.install(|| {
                 (0..m.div_ceil(ker.mr())).into_par_iter().try_for_each(|ia| {
+                    check_cancellation(&cancellation)?;
                     for ib in 0..n.divceil(ker.nr()) {
                         scratch.run(ker, non_linear, ia, ib)?;
                     }
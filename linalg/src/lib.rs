@@ -19,6 +19,7 @@ include!(concat!(env!("OUT_DIR"), "/extern_kernel_macro.rs"));
 
 #[macro_use]
 pub mod frame;
+pub mod cancel;
 pub mod generic;
 pub mod multithread;
 use frame::by_scalar::ByScalarKer;
@@ -130,6 +131,15 @@ impl Ops {
         &self.panel_extractors
     }
 
+    /// Look up a matrix multiplication kernel by name, among the implementations and the kits.
+    pub fn mmm_by_name(&self, name: &str) -> Option<Box<dyn mmm::MatMatMul>> {
+        self.mmm_impls
+            .iter()
+            .chain(self.mmm_kits.iter().flat_map(|kit| kit.items.iter().map(|item| &item.mmm)))
+            .find(|mmm| mmm.name() == name)
+            .cloned()
+    }
+
     pub fn mmm(
         &self,
         accumulator: DatumType,
@@ -291,6 +301,37 @@ pub fn ops() -> &'static Ops {
     &OPS
 }
 
+/// The CPU architecture, followed by the detected features the kernel selection depends on.
+pub fn cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = vec![std::env::consts::ARCH];
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("fma") {
+            features.push("fma");
+        }
+        if is_x86_feature_detected!("f16c") {
+            features.push("f16c");
+        }
+        if is_x86_feature_detected!("avx2") {
+            features.push("avx2");
+        }
+        if is_x86_feature_detected!("avx512f") {
+            features.push("avx512f");
+        }
+    }
+    #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
+    if arm32::has_neon() {
+        features.push("neon");
+    }
+    if has_fp16() {
+        features.push("fp16");
+    }
+    #[cfg(all(target_family = "wasm", target_feature = "simd128"))]
+    features.push("simd128");
+    features
+}
+
 use num_traits::*;
 use std::collections::HashMap;
 use std::fmt::Debug;
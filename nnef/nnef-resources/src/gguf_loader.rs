@@ -0,0 +1,305 @@
+//! GGUF support: https://github.com/ggerganov/ggml/blob/master/docs/gguf.md
+//!
+//! A header with metadata key-values and tensor descriptions, then the aligned tensor data.
+use std::path::Path;
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::tract_linalg::frame::block_quant::{
+    BlockQuantFact, BlockQuantValue, Q4_0,
+};
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+const GGUF_DEFAULT_ALIGNMENT: usize = 32;
+
+// metadata value types
+const GGUF_TYPE_UINT8: u32 = 0;
+const GGUF_TYPE_INT8: u32 = 1;
+const GGUF_TYPE_UINT16: u32 = 2;
+const GGUF_TYPE_INT16: u32 = 3;
+const GGUF_TYPE_UINT32: u32 = 4;
+const GGUF_TYPE_INT32: u32 = 5;
+const GGUF_TYPE_FLOAT32: u32 = 6;
+const GGUF_TYPE_BOOL: u32 = 7;
+const GGUF_TYPE_STRING: u32 = 8;
+const GGUF_TYPE_ARRAY: u32 = 9;
+const GGUF_TYPE_UINT64: u32 = 10;
+const GGUF_TYPE_INT64: u32 = 11;
+const GGUF_TYPE_FLOAT64: u32 = 12;
+
+// tensor types
+const GGML_TYPE_F32: u32 = 0;
+const GGML_TYPE_F16: u32 = 1;
+const GGML_TYPE_Q4_0: u32 = 2;
+const GGML_TYPE_Q8_0: u32 = 8;
+const GGML_TYPE_I8: u32 = 24;
+const GGML_TYPE_I16: u32 = 25;
+const GGML_TYPE_I32: u32 = 26;
+const GGML_TYPE_I64: u32 = 27;
+const GGML_TYPE_F64: u32 = 28;
+const GGML_TYPE_BF16: u32 = 30;
+
+/// Loader for GGUF files (llama.cpp weights) inside a NNEF archive.
+///
+/// F16, F32 and integer tensors are loaded as tract tensors, Q4_0 tensors as tract Q4_0
+/// `BlockQuantValue`, BF16 tensors are converted to F32. Q8_0 tensors are dequantized to F32 as
+/// tract has no Q8_0 block format: they take about four times their file size in memory. Each
+/// tensor is exposed to the graph as a variable labelled by its GGUF name, and the GGUF metadata
+/// as model properties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GgufLoader;
+
+impl ResourceLoader for GgufLoader {
+    fn name(&self) -> Cow<str> {
+        "GgufLoader".into()
+    }
+
+    fn try_load(
+        &self,
+        path: &Path,
+        reader: &mut dyn std::io::Read,
+        _framework: &tract_nnef::framework::Nnef,
+    ) -> TractResult<Option<(String, Arc<dyn Resource>)>> {
+        if path.extension().map(|e| e == "gguf").unwrap_or(false) {
+            let mut content = vec![];
+            reader.read_to_end(&mut content)?;
+            let gguf = GgufResource::from_bytes(&content)
+                .with_context(|| anyhow!("Error while reading GGUF file {:?}", path))?;
+            Ok(Some((tract_nnef::resource::resource_path_to_id(path)?, Arc::new(gguf))))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// GGUF resource: tensors and metadata. Both can also be queried while loading a NNEF graph.
+#[derive(Debug, Clone, Default)]
+pub struct GgufResource {
+    pub metadata: HashMap<String, Arc<Tensor>>,
+    pub tensors: HashMap<String, Arc<Tensor>>,
+}
+
+impl Resource for GgufResource {
+    fn get(&self, key: &str) -> TractResult<Value> {
+        let value = self
+            .metadata
+            .get(key)
+            .or_else(|| self.tensors.get(key))
+            .with_context(|| anyhow!("No metadata or tensor named {:?}", key))?;
+        Ok(Value::Tensor(value.clone()))
+    }
+
+    fn variables(&self) -> Vec<(String, Arc<Tensor>)> {
+        self.tensors.iter().map(|(name, tensor)| (name.clone(), tensor.clone())).collect()
+    }
+
+    fn properties(&self) -> Vec<(String, Arc<Tensor>)> {
+        self.metadata.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+}
+
+impl GgufResource {
+    pub fn from_bytes(content: &[u8]) -> TractResult<GgufResource> {
+        let mut r = GgufReader { content, pos: 0 };
+        ensure!(r.bytes(4)? == GGUF_MAGIC, "Not a GGUF file");
+        let version = r.u32()?;
+        ensure!(version == 2 || version == 3, "Unsupported GGUF version {}", version);
+        let tensor_count = r.u64()?;
+        let metadata_count = r.u64()?;
+
+        let mut metadata = HashMap::default();
+        for _ in 0..metadata_count {
+            let key = r.string()?;
+            let ty = r.u32()?;
+            let value = if ty == GGUF_TYPE_ARRAY {
+                let item_ty = r.u32()?;
+                let len = r.u64()? as usize;
+                r.array(item_ty, len)?
+            } else {
+                Some(r.values(ty, 1)?.into_shape(&[])?)
+            };
+            match value {
+                Some(value) => {
+                    metadata.insert(key, value.into_arc_tensor());
+                }
+                None => warn!("Ignoring GGUF metadata {:?}: nested arrays are not supported", key),
+            }
+        }
+
+        let mut infos = vec![];
+        for _ in 0..tensor_count {
+            let name = r.string()?;
+            let rank = r.u32()? as usize;
+            // ggml lists dimensions from the innermost one
+            let mut shape =
+                (0..rank).map(|_| Ok(r.u64()? as usize)).collect::<TractResult<TVec<_>>>()?;
+            shape.reverse();
+            let ty = r.u32()?;
+            let offset = r.u64()? as usize;
+            infos.push((name, shape, ty, offset));
+        }
+
+        let alignment = match metadata.get("general.alignment") {
+            Some(alignment) => alignment.cast_to_scalar::<i64>()? as usize,
+            None => GGUF_DEFAULT_ALIGNMENT,
+        };
+        ensure!(alignment > 0, "Invalid GGUF alignment");
+        let data =
+            content.get(r.pos.next_multiple_of(alignment)..).context("Truncated GGUF file")?;
+
+        let mut tensors = HashMap::default();
+        for (name, shape, ty, offset) in infos {
+            let tensor = read_tensor(data, &shape, ty, offset)
+                .with_context(|| anyhow!("Reading GGUF tensor {:?}", name))?;
+            tensors.insert(name, tensor.into_arc_tensor());
+        }
+        Ok(GgufResource { metadata, tensors })
+    }
+}
+
+fn read_tensor(data: &[u8], shape: &[usize], ty: u32, offset: usize) -> TractResult<Tensor> {
+    let volume = shape
+        .iter()
+        .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+        .context("Invalid tensor shape")?;
+    let len = match ty {
+        GGML_TYPE_F32 | GGML_TYPE_I32 => volume.checked_mul(4),
+        GGML_TYPE_F16 | GGML_TYPE_I16 | GGML_TYPE_BF16 => volume.checked_mul(2),
+        GGML_TYPE_I8 => Some(volume),
+        GGML_TYPE_I64 | GGML_TYPE_F64 => volume.checked_mul(8),
+        GGML_TYPE_Q4_0 | GGML_TYPE_Q8_0 => {
+            ensure!(
+                shape.last().is_some_and(|k| k % 32 == 0),
+                "Expected innermost dimension to be a multiple of block size, got {:?}",
+                shape
+            );
+            (volume / 32).checked_mul(if ty == GGML_TYPE_Q4_0 { 18 } else { 34 })
+        }
+        _ => bail!("Unsupported ggml tensor type {}", ty),
+    }
+    .context("Invalid tensor size")?;
+    let bytes = offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .context("Tensor data out of bounds")?;
+    let dt = match ty {
+        GGML_TYPE_F32 => f32::datum_type(),
+        GGML_TYPE_F16 => f16::datum_type(),
+        GGML_TYPE_I8 => i8::datum_type(),
+        GGML_TYPE_I16 => i16::datum_type(),
+        GGML_TYPE_I32 => i32::datum_type(),
+        GGML_TYPE_I64 => i64::datum_type(),
+        GGML_TYPE_F64 => f64::datum_type(),
+        GGML_TYPE_BF16 => {
+            let floats = bytes
+                .chunks_exact(2)
+                .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+                .collect::<Vec<f32>>();
+            return Ok(tract_ndarray::ArrayD::from_shape_vec(shape, floats)?.into_tensor());
+        }
+        GGML_TYPE_Q4_0 => return read_q4_0(bytes, shape),
+        GGML_TYPE_Q8_0 => return read_q8_0(bytes, shape),
+        _ => unreachable!(),
+    };
+    unsafe { Tensor::from_raw_dt(dt, shape, bytes) }
+}
+
+fn read_q4_0(bytes: &[u8], shape: &[usize]) -> TractResult<Tensor> {
+    let mut blob = unsafe { Blob::new_for_size_and_align(bytes.len(), 128) };
+    for (ggml, tract) in bytes.chunks_exact(18).zip(blob.chunks_exact_mut(18)) {
+        tract[0..2].copy_from_slice(&ggml[0..2]);
+        // ggml stores the i-th and (i+16)-th weights of the block in the i-th byte, tract
+        // stores them in sequence, two per byte.
+        let nibble = |ix: usize| (ggml[2 + ix % 16] >> (4 * (ix / 16))) & 0x0F;
+        for (ix, byte) in tract[2..].iter_mut().enumerate() {
+            *byte = nibble(2 * ix) | (nibble(2 * ix + 1) << 4);
+        }
+    }
+    let fact = BlockQuantFact { format: Box::new(Q4_0), shape: shape.into() };
+    Ok(tensor0(Opaque(Arc::new(BlockQuantValue { value: blob, fact }))))
+}
+
+fn read_q8_0(bytes: &[u8], shape: &[usize]) -> TractResult<Tensor> {
+    let mut floats = Vec::with_capacity(bytes.len() / 34 * 32);
+    for block in bytes.chunks_exact(34) {
+        let d = f16::from_bits(u16::from_le_bytes([block[0], block[1]])).to_f32();
+        floats.extend(block[2..].iter().map(|q| *q as i8 as f32 * d));
+    }
+    Ok(tract_ndarray::ArrayD::from_shape_vec(shape, floats)?.into_tensor())
+}
+
+struct GgufReader<'a> {
+    content: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> GgufReader<'a> {
+    fn bytes(&mut self, len: usize) -> TractResult<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.content.get(self.pos..end))
+            .context("Truncated GGUF header")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array_of<const N: usize>(&mut self) -> TractResult<[u8; N]> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> TractResult<u32> {
+        Ok(u32::from_le_bytes(self.array_of()?))
+    }
+
+    fn u64(&mut self) -> TractResult<u64> {
+        Ok(u64::from_le_bytes(self.array_of()?))
+    }
+
+    fn string(&mut self) -> TractResult<String> {
+        let len = self.u64()? as usize;
+        let bytes = self.bytes(len)?;
+        Ok(std::str::from_utf8(bytes).context("Invalid utf-8 string")?.to_string())
+    }
+
+    /// Read an array of metadata values, or skip it and return None if it is an array of arrays.
+    fn array(&mut self, ty: u32, len: usize) -> TractResult<Option<Tensor>> {
+        if ty != GGUF_TYPE_ARRAY {
+            return self.values(ty, len).map(Some);
+        }
+        for _ in 0..len {
+            let item_ty = self.u32()?;
+            let item_len = self.u64()? as usize;
+            self.array(item_ty, item_len)?;
+        }
+        Ok(None)
+    }
+
+    /// Read `len` metadata values of the scalar type `ty`.
+    fn values(&mut self, ty: u32, len: usize) -> TractResult<Tensor> {
+        macro_rules! read {
+            ($t: ty) => {
+                (0..len)
+                    .map(|_| Ok(<$t>::from_le_bytes(self.array_of()?)))
+                    .collect::<TractResult<Vec<$t>>>()
+                    .map(|v| tensor1(&v))
+            };
+        }
+        match ty {
+            GGUF_TYPE_UINT8 => read!(u8),
+            GGUF_TYPE_INT8 => read!(i8),
+            GGUF_TYPE_UINT16 => read!(u16),
+            GGUF_TYPE_INT16 => read!(i16),
+            GGUF_TYPE_UINT32 => read!(u32),
+            GGUF_TYPE_INT32 => read!(i32),
+            GGUF_TYPE_FLOAT32 => read!(f32),
+            GGUF_TYPE_UINT64 => read!(u64),
+            GGUF_TYPE_INT64 => read!(i64),
+            GGUF_TYPE_FLOAT64 => read!(f64),
+            GGUF_TYPE_BOOL => read!(u8).and_then(|t| Ok(t.cast_to::<bool>()?.into_owned())),
+            GGUF_TYPE_STRING => (0..len)
+                .map(|_| self.string())
+                .collect::<TractResult<Vec<String>>>()
+                .map(|v| tensor1(&v)),
+            _ => bail!("Unsupported GGUF metadata type {}", ty),
+        }
+    }
+}
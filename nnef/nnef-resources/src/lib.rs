@@ -1,5 +1,10 @@
+#[macro_use]
+extern crate log;
+
+pub mod gguf_loader;
 pub mod json_loader;
 
 pub mod internal {
+    pub use crate::gguf_loader::{GgufLoader, GgufResource};
     pub use crate::json_loader::{JsonLoader, JsonResource};
 }
@@ -0,0 +1,142 @@
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::tract_linalg::frame::block_quant::{BlockQuant, BlockQuantValue, Q4_0};
+use tract_nnef_resources::internal::{GgufLoader, GgufResource};
+
+fn string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend((s.len() as u64).to_le_bytes());
+    buf.extend(s.as_bytes());
+}
+
+fn tensor_info(buf: &mut Vec<u8>, name: &str, dims: &[u64], ty: u32, offset: u64) {
+    string(buf, name);
+    buf.extend((dims.len() as u32).to_le_bytes());
+    for d in dims {
+        buf.extend(d.to_le_bytes());
+    }
+    buf.extend(ty.to_le_bytes());
+    buf.extend(offset.to_le_bytes());
+}
+
+fn weights() -> Vec<f32> {
+    (0..16).map(|i| i as f32).collect()
+}
+
+fn gguf() -> Vec<u8> {
+    let mut buf = b"GGUF".to_vec();
+    buf.extend(3u32.to_le_bytes());
+    buf.extend(4u64.to_le_bytes()); // tensors
+    buf.extend(4u64.to_le_bytes()); // metadata
+
+    string(&mut buf, "general.architecture");
+    buf.extend(8u32.to_le_bytes());
+    string(&mut buf, "llama");
+
+    string(&mut buf, "llama.context_length");
+    buf.extend(4u32.to_le_bytes());
+    buf.extend(2048u32.to_le_bytes());
+
+    string(&mut buf, "tokenizer.ggml.tokens");
+    buf.extend(9u32.to_le_bytes());
+    buf.extend(8u32.to_le_bytes());
+    buf.extend(2u64.to_le_bytes());
+    string(&mut buf, "a");
+    string(&mut buf, "b");
+
+    string(&mut buf, "nested");
+    buf.extend(9u32.to_le_bytes());
+    buf.extend(9u32.to_le_bytes());
+    buf.extend(1u64.to_le_bytes());
+    buf.extend(0u32.to_le_bytes());
+    buf.extend(1u64.to_le_bytes());
+    buf.push(12);
+
+    // f32 [16], f16 [2, 16], q4_0 [2, 32], q8_0 [1, 32]. ggml dims are innermost first.
+    tensor_info(&mut buf, "w", &[16], 0, 0);
+    tensor_info(&mut buf, "h", &[16, 2], 1, 64);
+    tensor_info(&mut buf, "q4", &[32, 2], 2, 128);
+    tensor_info(&mut buf, "q8", &[32, 1], 8, 192);
+
+    buf.resize(buf.len().next_multiple_of(32), 0);
+    let data_start = buf.len();
+    for w in weights() {
+        buf.extend(w.to_le_bytes());
+    }
+    for i in 0..32 {
+        buf.extend(f16::from_f32(i as f32).to_bits().to_le_bytes());
+    }
+    // two q4_0 blocks with scale 0.5, quantized weights are (i % 16) for i in 0..32
+    buf.resize(data_start + 128, 0);
+    for _ in 0..2 {
+        buf.extend(f16::from_f32(0.5).to_bits().to_le_bytes());
+        buf.extend((0..16u8).map(|j| j | (j << 4)));
+    }
+    // one q8_0 block with scale 0.25, quantized weights are i - 16 for i in 0..32
+    buf.resize(data_start + 192, 0);
+    buf.extend(f16::from_f32(0.25).to_bits().to_le_bytes());
+    buf.extend((0..32i8).map(|i| (i - 16) as u8));
+    buf
+}
+
+#[test]
+fn read_gguf() -> TractResult<()> {
+    let gguf = GgufResource::from_bytes(&gguf())?;
+    assert_eq!(*gguf.metadata["general.architecture"], tensor0("llama".to_string()));
+    assert_eq!(*gguf.metadata["llama.context_length"], tensor0(2048u32));
+    assert_eq!(
+        *gguf.metadata["tokenizer.ggml.tokens"],
+        tensor1(&["a".to_string(), "b".to_string()])
+    );
+    assert!(!gguf.metadata.contains_key("nested"));
+
+    assert_eq!(*gguf.tensors["w"], tensor1(&weights()));
+    let h = (0..32).map(|i| f16::from_f32(i as f32)).collect::<Vec<_>>();
+    assert_eq!(*gguf.tensors["h"], tensor1(&h).into_shape(&[2, 16])?);
+
+    let q4 = gguf.tensors["q4"].to_scalar::<Opaque>()?;
+    let q4 = q4.downcast_ref::<BlockQuantValue>().unwrap();
+    assert_eq!(&*q4.fact.shape, &[2, 32]);
+    let expected = (0..64).map(|i| ((i % 16) as f32 - 8.) * 0.5).collect::<Vec<_>>();
+    assert_eq!(Q4_0.dequant_f32(&q4.value)?, tensor1(&expected));
+
+    let expected = (0..32).map(|i| (i as f32 - 16.) * 0.25).collect::<Vec<_>>();
+    assert_eq!(*gguf.tensors["q8"], tensor1(&expected).into_shape(&[1, 32])?);
+    Ok(())
+}
+
+#[test]
+fn load_model_with_gguf_resource() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    std::fs::write(
+        dir.path().join("graph.nnef"),
+        "version 1.0;
+
+graph net(x) -> (y)
+{
+  x = external<scalar>(shape = [16]);
+  w = variable<scalar>(label = \"w\", shape = [16]);
+  y = add(x, w);
+}
+",
+    )?;
+    std::fs::write(dir.path().join("weights.gguf"), gguf())?;
+    let model = tract_nnef::nnef().with_resource_loader(GgufLoader).model_for_path(dir.path())?;
+    assert_eq!(*model.properties["general.architecture"], tensor0("llama".to_string()));
+    let outputs = model.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = weights().iter().map(|w| w + 1.).collect::<Vec<_>>();
+    assert_eq!(*outputs[0], tensor1(&expected));
+    Ok(())
+}
+
+#[test]
+fn reject_overflowing_shape() -> TractResult<()> {
+    for dims in [&[1 << 32, 1 << 32][..], &[1 << 62]] {
+        let mut buf = b"GGUF".to_vec();
+        buf.extend(3u32.to_le_bytes());
+        buf.extend(1u64.to_le_bytes()); // tensors
+        buf.extend(0u64.to_le_bytes()); // metadata
+        tensor_info(&mut buf, "w", dims, 0, 0);
+        buf.resize(buf.len().next_multiple_of(32), 0);
+        assert!(GgufResource::from_bytes(&buf).is_err());
+    }
+    Ok(())
+}
@@ -20,9 +20,23 @@ impl ProtoModel {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum QuantFormat {
-    Linear { params: QParams, bits: i8, signed: bool },
+    Linear {
+        params: QParams,
+        bits: i8,
+        signed: bool,
+    },
+    /// One zero point and scale per slice along `axis` (per output channel of a conv kernel
+    /// for instance). The tensor keeps its raw integer type, the parameters are wired explicitly
+    /// to the operators supporting them: only conv kernels for now, with a uniform zero point.
+    PerAxis {
+        axis: usize,
+        zero_points: Vec<i32>,
+        scales: Vec<f32>,
+        bits: i8,
+        signed: bool,
+    },
 }
 
 impl QuantFormat {
@@ -39,16 +53,23 @@ impl QuantFormat {
         }
     }
 
-    pub fn datum_type(&self) -> DatumType {
-        match self {
+    pub fn datum_type(&self) -> TractResult<DatumType> {
+        Ok(match self {
             QuantFormat::Linear { params, bits, signed } => match (bits, signed) {
                 (8, true) => DatumType::QI8(*params),
                 (8, false) => DatumType::QU8(*params),
                 (32, true) => DatumType::QI32(*params),
                 (32, false) => DatumType::U32,
-                _ => todo!(),
+                _ => bail!("Unsupported quantization: {bits} bits, signed: {signed}"),
             },
-        }
+            QuantFormat::PerAxis { bits, signed, .. } => match (bits, signed) {
+                (8, true) => DatumType::I8,
+                (8, false) => DatumType::U8,
+                (32, true) => DatumType::I32,
+                (32, false) => DatumType::U32,
+                _ => bail!("Unsupported quantization: {bits} bits, signed: {signed}"),
+            },
+        })
     }
 }
 
@@ -37,9 +37,23 @@ fn integer_numeric<T: FromStr>(i: &str) -> IResult<&str, T> {
 
 // <qparam> ::= "<identifier>": <qparam>
 fn qparam(i: &str) -> IResult<&str, QuantFormat> {
-    let (i, id) =
-        nom::branch::alt((stag("linear_quantize"), stag("zero_point_linear_quantize")))(i)?;
+    let (i, id) = nom::branch::alt((
+        stag("linear_quantize"),
+        stag("zero_point_linear_quantize_per_axis"),
+        stag("zero_point_linear_quantize"),
+    ))(i)?;
     let (i, _) = stag("(")(i)?;
+    if id == "zero_point_linear_quantize_per_axis" {
+        let (i, (axis, zero_points, scales, bits, signed)) = permutation((
+            arg("axis", integer_numeric),
+            arg("zero_point", integer_list),
+            arg("scale", float_list),
+            arg("bits", integer_numeric),
+            arg("signed", logical_literal),
+        ))(i)?;
+        let (i, _) = stag(")")(i)?;
+        return Ok((i, QuantFormat::PerAxis { axis, zero_points, scales, bits, signed }));
+    }
     let (i, params, bits, signed) = match id {
         "linear_quantize" => {
             let (i, (bits, max, min)) =
@@ -65,6 +79,15 @@ fn qparam(i: &str) -> IResult<&str, QuantFormat> {
     let (i, _) = stag(")")(i)?;
     Ok((i, QuantFormat::Linear { params, bits, signed }))
 }
+
+fn integer_list(i: &str) -> IResult<&str, Vec<i32>> {
+    delimited(stag("["), separated_list0(stag(","), integer_numeric), stag("]"))(i)
+}
+
+fn float_list(i: &str) -> IResult<&str, Vec<f32>> {
+    delimited(stag("["), separated_list0(stag(","), float), stag("]"))(i)
+}
+
 // <arg>(<id>, <f>) ::= <id> "=" <f> ","
 fn arg<'s, T, F>(name: &'static str, f: F) -> impl Fn(&'s str) -> IResult<&'s str, T>
 where
@@ -93,6 +116,11 @@ pub(crate) fn write_quant_format(
         QuantFormat::Linear {
             params: QParams::MinMax {min, max}, bits, signed: _
         } => writeln!(w, ": linear_quantize(max = {max:.9}, min = {min:.9}, bits = {bits});")?,
+        QuantFormat::PerAxis { axis, zero_points, scales, bits, signed } => {
+            let zero_points = zero_points.iter().join(", ");
+            let scales = scales.iter().map(|s| format!("{s:.9}")).join(", ");
+            writeln!(w, ": zero_point_linear_quantize_per_axis(axis = {axis}, zero_point = [{zero_points}], scale = [{scales}], bits = {bits}, signed = {signed});")?
+        }
     }
     Ok(())
 }
@@ -185,6 +213,38 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_qparam_per_axis() {
+        assert_eq!(
+            p(
+                qparam,
+                "zero_point_linear_quantize_per_axis(axis = 0, zero_point = [0, -2], scale = [0.5, 0.25], bits = 8, signed = true)"
+            ),
+            QuantFormat::PerAxis {
+                axis: 0,
+                zero_points: vec![0, -2],
+                scales: vec![0.5, 0.25],
+                bits: 8,
+                signed: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_per_axis_round_trip() {
+        let format = QuantFormat::PerAxis {
+            axis: 1,
+            zero_points: vec![3, 4, 5],
+            scales: vec![0.125, 1.5, 3.0],
+            bits: 8,
+            signed: false,
+        };
+        let mut written = vec![];
+        write_quant_format(&mut written, &"w".into(), format.clone(), false).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        assert_eq!(parse_quantization(&written).unwrap(), vec![("w".into(), format)]);
+    }
+
     #[test]
     fn test_quant_file_1() {
         assert_eq!(
@@ -0,0 +1,158 @@
+//! Compression of NNEF tar archives, and of individual members of uncompressed archives.
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use tract_core::internal::*;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression codec, with its level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// gzip, level from 0 (none) to 9 (best).
+    Gzip { level: u32 },
+    /// zstd, level from 1 to 22 (best). Requires the `zstd` feature.
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// gzip with its default level.
+    pub fn gzip() -> Compression {
+        Compression::Gzip { level: 6 }
+    }
+
+    /// zstd with its default level.
+    pub fn zstd() -> Compression {
+        Compression::Zstd { level: 3 }
+    }
+
+    /// Extension of compressed tar members.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip { .. } => "gz",
+            Compression::Zstd { .. } => "zst",
+        }
+    }
+
+    /// Compress the stream written by `f` to `w`.
+    pub fn compress<W: Write>(
+        &self,
+        w: W,
+        f: impl FnOnce(&mut dyn Write) -> TractResult<()>,
+    ) -> TractResult<W> {
+        match *self {
+            Compression::Gzip { level } => {
+                #[cfg(feature = "flate2")]
+                {
+                    let mut encoder =
+                        flate2::write::GzEncoder::new(w, flate2::Compression::new(level));
+                    f(&mut encoder)?;
+                    Ok(encoder.finish()?)
+                }
+                #[cfg(not(feature = "flate2"))]
+                {
+                    let _ = (w, f, level);
+                    bail!("Cannot write gzip file without flate2 enabled.")
+                }
+            }
+            Compression::Zstd { level } => {
+                #[cfg(feature = "zstd")]
+                {
+                    let mut encoder = zstd::Encoder::new(w, level)?;
+                    f(&mut encoder)?;
+                    Ok(encoder.finish()?)
+                }
+                #[cfg(not(feature = "zstd"))]
+                {
+                    let _ = (w, f, level);
+                    bail!("Cannot write zstd file without zstd enabled.")
+                }
+            }
+        }
+    }
+
+    pub(crate) fn compress_bytes(&self, data: &[u8]) -> TractResult<Vec<u8>> {
+        self.compress(vec![], |w| Ok(w.write_all(data)?))
+    }
+}
+
+/// Does the content start like a gzip or zstd stream?
+pub fn is_compressed(header: &[u8]) -> bool {
+    header.starts_with(GZIP_MAGIC) || header.starts_with(ZSTD_MAGIC)
+}
+
+/// Wrap the reader in a decoder if its content is gzip or zstd compressed.
+pub fn decompress<'r>(mut reader: impl Read + 'r) -> TractResult<Box<dyn Read + 'r>> {
+    let mut header = vec![];
+    (&mut reader).take(ZSTD_MAGIC.len() as u64).read_to_end(&mut header)?;
+    let stream = std::io::Cursor::new(header.clone()).chain(reader);
+    if header.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "flate2")]
+        return Ok(Box::new(flate2::read::GzDecoder::new(stream)));
+        #[cfg(not(feature = "flate2"))]
+        bail!("Cannot read gzip file without flate2 enabled.");
+    } else if header.starts_with(ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(zstd::Decoder::new(stream)?));
+        #[cfg(not(feature = "zstd"))]
+        bail!("Cannot read zstd file without zstd enabled.");
+    }
+    Ok(Box::new(stream))
+}
+
+/// Path of the member once decompressed, if it is a tar member compressed by the writer
+/// (graph, quantization, tensors and submodels). Other resources are given to the loaders as is.
+pub(crate) fn compressed_member(path: &Path) -> Option<PathBuf> {
+    let extension = path.extension()?;
+    if extension != "gz" && extension != "zst" {
+        return None;
+    }
+    let decompressed = path.with_extension("");
+    let name = decompressed.file_name()?.to_str()?;
+    let written = name == crate::resource::GRAPH_NNEF_FILENAME
+        || name == crate::resource::GRAPH_QUANT_FILENAME
+        || [".dat", ".safetensors", ".nnef.tar"].iter().any(|ext| name.ends_with(ext));
+    written.then_some(decompressed)
+}
+
+/// Storage policy of the members of NNEF tar archives. Compressed members get a ".gz" or
+/// ".zst" extension, and are decompressed when loading.
+///
+/// This is mostly useful for uncompressed archives: tensors stored as is can be memory-mapped,
+/// while the rest of the archive is still compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MemberCompression {
+    /// Compression of graph.nnef and graph.quant.
+    pub graph: Option<Compression>,
+    /// Compression of tensor files (.dat and .safetensors).
+    pub tensors: Option<Compression>,
+    /// Tensor files bigger than this (in bytes) are stored uncompressed.
+    pub tensors_max_size: Option<usize>,
+    /// Compression of nested models. Gzipped ones are stored as .nnef.tgz, the others as
+    /// .nnef.tar with the codec extension.
+    pub submodels: Option<Compression>,
+}
+
+impl MemberCompression {
+    pub(crate) fn for_tensor(&self, size: usize) -> Option<Compression> {
+        self.tensors.filter(|_| self.tensors_max_size.map(|max| size <= max).unwrap_or(true))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_written_members_are_decompressed() {
+        let member = |p: &str| compressed_member(Path::new(p));
+        assert_eq!(member("graph.nnef.gz"), Some(PathBuf::from("graph.nnef")));
+        assert_eq!(member("sub/graph.quant.zst"), Some(PathBuf::from("sub/graph.quant")));
+        assert_eq!(member("w.dat.gz"), Some(PathBuf::from("w.dat")));
+        assert_eq!(member("sub.nnef.tar.zst"), Some(PathBuf::from("sub.nnef.tar")));
+        assert_eq!(member("w.dat"), None);
+        assert_eq!(member("tokenizer.json.gz"), None);
+        assert_eq!(member("sub.nnef.tgz"), None);
+    }
+}
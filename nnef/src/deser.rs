@@ -125,6 +125,9 @@ impl<'mb> ModelBuilder<'mb> {
     }
 
     fn parse_properties(&mut self) -> TractResult<()> {
+        for resource in self.proto_model.resources.values() {
+            self.model.properties.extend(resource.properties());
+        }
         if let Some(properties) = self
             .proto_model
             .doc
@@ -136,7 +139,7 @@ impl<'mb> ModelBuilder<'mb> {
         {
             let properties: TVec<(String, Arc<Tensor>)> =
                 properties.right.resolve(self, &[])?.to(self)?;
-            self.model.properties = properties.into_iter().collect();
+            self.model.properties.extend(properties);
         }
         Ok(())
     }
@@ -151,9 +154,11 @@ impl<'mb> ModelBuilder<'mb> {
                     self.proto_model
                         .quantization
                         .as_ref()
-                        .and_then(|qm| qm.get(*s).map(|q| q.datum_type()))
+                        .and_then(|qm| qm.get(*s))
+                        .map(|q| q.datum_type())
+                        .transpose()
                 })
-                .collect::<Vec<_>>();
+                .collect::<TractResult<Vec<_>>>()?;
             self.naming_scopes.push(identifiers[0].clone());
             let mut values = if identifiers.len() == 1 {
                 let value: OutletId = assignment
@@ -375,6 +380,16 @@ impl ResolvedInvocation<'_> {
         }
     }
 
+    /// Quantization format of an argument, when it is an identifier described in graph.quant.
+    pub fn named_arg_quant_format(
+        &self,
+        builder: &ModelBuilder,
+        name: &str,
+    ) -> Option<QuantFormat> {
+        let RValue::Identifier(id) = &*self.get_named_arg(name)? else { return None };
+        builder.proto_model.quantization.as_ref()?.get(id).cloned()
+    }
+
     pub fn named_arg(&self, name: &str) -> TractResult<Cow<RValue>> {
         self.get_named_arg(name).ok_or_else(|| format_err!("expected argument {}", name))
     }
@@ -3,13 +3,27 @@ use tract_core::tract_data::itertools::Itertools;
 
 use crate::ast::quant::write_quant_format;
 use crate::ast::{Document, Identifier, ProtoModel, QuantFormat};
+use crate::compression::{Compression, MemberCompression};
+use crate::manifest::{Manifest, Verifier, MANIFEST_FILENAME};
 use crate::{internal::*, nnef};
-use std::io::Read;
 #[cfg(target_family = "unix")]
 use std::os::unix::prelude::OsStrExt;
 use std::path::Path;
 use std::str::FromStr;
 
+/// Storage of the model tensors when writing a model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum WeightsFormat {
+    /// One NNEF .dat file per tensor.
+    #[default]
+    Dat,
+    /// All tensors in a single safetensors file. Tensors of types not supported by safetensors
+    /// are still written as .dat files.
+    Safetensors,
+}
+
+pub const SAFETENSORS_WEIGHTS_FILENAME: &str = "weights.safetensors";
+
 pub fn stdlib() -> Vec<FragmentDef> {
     crate::ast::parse::parse_fragments(include_str!("../stdlib.nnef")).unwrap()
 }
@@ -19,6 +33,16 @@ pub struct Nnef {
     pub registries: Vec<Registry>,
     pub resource_loaders: Vec<Box<dyn ResourceLoader + 'static>>,
     pub allow_extended_identifier_syntax: bool,
+    /// Memory-map tensor files when loading from a path, instead of reading them.
+    pub mmap_weights: bool,
+    /// Storage of the tensors when writing a model.
+    pub weights_format: WeightsFormat,
+    /// Compression of individual members when writing a tar archive.
+    pub member_compression: MemberCompression,
+    /// Write a manifest with the digests of all members when writing a model.
+    pub manifest: bool,
+    /// Check the members against the manifest when loading a model.
+    pub verify_manifest: bool,
 }
 
 impl Default for Nnef {
@@ -30,9 +54,15 @@ impl Default for Nnef {
                 GraphNnefLoader.into_boxed(),
                 DatLoader.into_boxed(),
                 GraphQuantLoader.into_boxed(),
+                SafetensorsLoader.into_boxed(),
                 TypedModelLoader::new(false).into_boxed(),
             ],
             allow_extended_identifier_syntax: false,
+            mmap_weights: false,
+            weights_format: WeightsFormat::Dat,
+            member_compression: MemberCompression::default(),
+            manifest: false,
+            verify_manifest: false,
         }
     }
 }
@@ -66,10 +96,206 @@ impl Nnef {
         self
     }
 
+    pub fn enable_tract_opt(&mut self) {
+        self.registries.push(crate::ops::tract_opt());
+    }
+
+    pub fn with_tract_opt(mut self) -> Self {
+        self.registries.push(crate::ops::tract_opt());
+        self
+    }
+
     pub fn allow_extended_identifier_syntax(&mut self, allow_extended_identifier_syntax: bool) {
         self.allow_extended_identifier_syntax = allow_extended_identifier_syntax;
     }
 
+    /// When loading from a directory or an uncompressed tar file, memory-map tensor (.dat) files
+    /// and share the mapped storage with the model constants instead of copying them to memory.
+    ///
+    /// Tensors fall back to a copy when their type or alignment do not allow sharing. The mapping
+    /// is private: neither a modified tensor nor the loaded model see changes of the file.
+    pub fn mmap_weights(&mut self, mmap_weights: bool) {
+        self.mmap_weights = mmap_weights;
+    }
+
+    pub fn with_mmap_weights(mut self, mmap_weights: bool) -> Self {
+        self.mmap_weights = mmap_weights;
+        self
+    }
+
+    pub fn weights_format(&mut self, weights_format: WeightsFormat) {
+        self.weights_format = weights_format;
+    }
+
+    pub fn with_weights_format(mut self, weights_format: WeightsFormat) -> Self {
+        self.weights_format = weights_format;
+        self
+    }
+
+    pub fn member_compression(&mut self, member_compression: MemberCompression) {
+        self.member_compression = member_compression;
+    }
+
+    pub fn with_member_compression(mut self, member_compression: MemberCompression) -> Self {
+        self.member_compression = member_compression;
+        self
+    }
+
+    /// Write a manifest.sha256 member with the SHA-256 digest of every other member (graph,
+    /// quantization, tensors and submodels).
+    pub fn manifest(&mut self, manifest: bool) {
+        self.manifest = manifest;
+    }
+
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Refuse to load a model without manifest, or with a member missing from the manifest, or
+    /// not matching its digest.
+    pub fn verify_manifest(&mut self, verify_manifest: bool) {
+        self.verify_manifest = verify_manifest;
+    }
+
+    pub fn with_verify_manifest(mut self, verify_manifest: bool) -> Self {
+        self.verify_manifest = verify_manifest;
+        self
+    }
+
+    /// Split tensors in the ones to write in a safetensors blob and the ones to write as .dat,
+    /// both sorted by label.
+    #[allow(clippy::type_complexity)]
+    fn split_tensors<'a>(
+        &self,
+        tensors: &'a HashMap<Identifier, Arc<Tensor>>,
+    ) -> (Vec<(&'a Identifier, &'a Arc<Tensor>)>, Vec<(&'a Identifier, &'a Arc<Tensor>)>) {
+        tensors.iter().sorted_by_key(|(label, _)| *label).partition(|(_, t)| {
+            self.weights_format == WeightsFormat::Safetensors && crate::safetensors::is_storable(t)
+        })
+    }
+
+    /// Load the members of a model directory or tar file accepted by `filter`.
+    ///
+    /// With `complete` unset, members skipped by the filter are not reported missing when
+    /// verifying the manifest.
+    pub(crate) fn resources_for_path(
+        &self,
+        path: &Path,
+        filter: &mut dyn FnMut(&Path) -> bool,
+        complete: bool,
+    ) -> TractResult<HashMap<String, Arc<dyn Resource>>> {
+        if path.is_file() {
+            if self.mmap_weights {
+                let map = map_file(path)?;
+                if !crate::compression::is_compressed(&map) {
+                    return self.resources_for_mapped_tar(&map, filter, complete);
+                }
+            }
+            let mut f = std::fs::File::open(path)?;
+            return self.resources_for_read(&mut f, filter, complete);
+        }
+
+        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
+        let mut verifier = Verifier::new(self.verify_manifest, complete);
+
+        // `walkdir::new` will first yield the given path at depth 0, but we don't want to load this
+        // entry here: only its descendants at depth >= 1.
+        for entry in walkdir::WalkDir::new(path).min_depth(1) {
+            let entry =
+                entry.map_err(|e| format_err!("Can not walk directory {:?}: {:?}", path, e))?;
+            // We don't want to load sub-directories themselves either.
+            if entry.path().is_dir() {
+                continue;
+            }
+            let subpath = entry
+                .path()
+                .components()
+                .skip(path.components().count())
+                .collect::<std::path::PathBuf>();
+            if !is_wanted(&subpath, filter) {
+                continue;
+            }
+            if self.mmap_weights && (is_dat(&subpath) || is_safetensors(&subpath)) {
+                let map = map_file(entry.path())?;
+                verifier.member(&subpath, &mut &map[..], |_| {
+                    read_mapped(&subpath, &map, &map, &mut resources, self)
+                })?;
+                continue;
+            }
+            let mut stream = std::fs::File::open(entry.path())?;
+            verifier.member(&subpath, &mut stream, |mut r| {
+                read_stream(&subpath, &mut r, &mut resources, self)
+            })?;
+        }
+        verifier.verify()?;
+        Ok(resources)
+    }
+
+    fn resources_for_read(
+        &self,
+        reader: &mut dyn std::io::Read,
+        filter: &mut dyn FnMut(&Path) -> bool,
+        complete: bool,
+    ) -> TractResult<HashMap<String, Arc<dyn Resource>>> {
+        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
+
+        let mut verifier = Verifier::new(self.verify_manifest, complete);
+        let mut tar = tar::Archive::new(crate::compression::decompress(reader)?);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let mut path = entry.path()?.to_path_buf();
+            if path.starts_with("./") {
+                path = path.strip_prefix("./")?.to_path_buf();
+            }
+            if !is_wanted(&path, filter) {
+                continue;
+            }
+            verifier.member(&path, &mut entry, |mut r| {
+                read_stream(&path, &mut r, &mut resources, self)
+            })?;
+        }
+        verifier.verify()?;
+        Ok(resources)
+    }
+
+    fn resources_for_mapped_tar(
+        &self,
+        map: &Arc<memmap2::MmapMut>,
+        filter: &mut dyn FnMut(&Path) -> bool,
+        complete: bool,
+    ) -> TractResult<HashMap<String, Arc<dyn Resource>>> {
+        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
+        let mut verifier = Verifier::new(self.verify_manifest, complete);
+        let mut tar = tar::Archive::new(&map[..]);
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let mut path = entry.path()?.to_path_buf();
+            if path.starts_with("./") {
+                path = path.strip_prefix("./")?.to_path_buf();
+            }
+            if !is_wanted(&path, filter) {
+                continue;
+            }
+            let start = entry.raw_file_position() as usize;
+            let content = &map[start..][..entry.size() as usize];
+            verifier.member(&path, &mut &*content, |_| {
+                read_mapped(&path, content, map, &mut resources, self)
+            })?;
+        }
+        verifier.verify()?;
+        Ok(resources)
+    }
+
+    /// First phase of a lazy load: parse the model at `path`, a directory or a tar file, without
+    /// loading the tensors stored in .dat members. See [crate::lazy].
+    pub fn lazy_proto_model_for_path(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> TractResult<crate::lazy::LazyProtoModel> {
+        crate::lazy::LazyProtoModel::for_path(self, path.as_ref())
+    }
+
     pub fn translate(
         &self,
         proto_model: &ProtoModel,
@@ -78,6 +304,34 @@ impl Nnef {
         ModelBuilder::new(self, proto_model, template).into_typed_model()
     }
 
+    /// Nodes of the model that can not be serialized with the registries of this framework.
+    pub fn unsupported_nodes(
+        &self,
+        model: &TypedModel,
+    ) -> TractResult<Vec<crate::ser::UnsupportedNode>> {
+        crate::ser::unsupported_nodes(self, model)
+    }
+
+    /// Load an optimized model artifact, see [crate::optimized]. If it was optimized for another
+    /// target, the model returned by `reoptimize` is used instead.
+    pub fn optimized_model_for_path_or_else(
+        &self,
+        path: impl AsRef<Path>,
+        reoptimize: impl FnOnce() -> TractResult<TypedModel>,
+    ) -> TractResult<TypedModel> {
+        crate::optimized::or_else(self.model_for_path(path), reoptimize)
+    }
+
+    /// Load an optimized model artifact from a tar stream, see
+    /// [Nnef::optimized_model_for_path_or_else].
+    pub fn optimized_model_for_read_or_else(
+        &self,
+        reader: &mut dyn std::io::Read,
+        reoptimize: impl FnOnce() -> TractResult<TypedModel>,
+    ) -> TractResult<TypedModel> {
+        crate::optimized::or_else(self.model_for_read(reader), reoptimize)
+    }
+
     pub fn write(&self, model: &TypedModel, w: impl std::io::Write) -> TractResult<()> {
         self.write_to_tar(model, w)?;
         Ok(())
@@ -91,6 +345,19 @@ impl Nnef {
         ar.into_inner().context("Finalizing tar")
     }
 
+    /// Write the model as a compressed tar archive (.tgz or .tar.zst).
+    pub fn write_to_compressed_tar<W: std::io::Write>(
+        &self,
+        model: &TypedModel,
+        w: W,
+        compression: Compression,
+    ) -> TractResult<W> {
+        compression.compress(w, |w| {
+            self.write_to_tar(model, w)?;
+            Ok(())
+        })
+    }
+
     pub fn write_to_tar_with_config<W: std::io::Write>(
         &self,
         model: &TypedModel,
@@ -125,13 +392,9 @@ impl Nnef {
             .document(&proto_model.doc)
             .context("Serializing graph.nnef")?;
 
-        let mut header = tar::Header::new_gnu();
-        header.set_path("graph.nnef").context("Setting graph.nnef path")?;
-        header.set_size(graph_data.len() as u64);
-        header.set_mode(0o644);
-        header.set_mtime(timestamp.as_secs());
-        header.set_cksum();
-        ar.append(&header, &mut &*graph_data).context("Appending graph.nnef")?;
+        let mut manifest = self.manifest.then(Manifest::default);
+        let graph_compression = self.member_compression.graph;
+        append_member(ar, &mut manifest, "graph.nnef", &graph_data, graph_compression, timestamp)?;
 
         if let Some(mut quantization) = proto_model.quantization {
             let mut quant_data = vec![];
@@ -149,35 +412,44 @@ impl Nnef {
                 .context("Serializing graph.quant")?;
             }
 
-            header.set_path("graph.quant").context("Setting graph.quant path")?;
-            header.set_size(quant_data.len() as u64);
-            header.set_mode(0o644);
-            header.set_mtime(timestamp.as_secs());
-            header.set_cksum();
-            ar.append(&header, &mut &*quant_data).context("Appending graph.quant")?;
+            append_member(
+                ar,
+                &mut manifest,
+                "graph.quant",
+                &quant_data,
+                graph_compression,
+                timestamp,
+            )?;
         }
 
-        let mut labels = proto_model.tensors.keys().collect::<Vec<_>>();
-        labels.sort();
-        for label in labels {
-            let t = proto_model.tensors.get(label).unwrap();
+        let (safetensors, dats) = self.split_tensors(&proto_model.tensors);
+        if safetensors.len() > 0 {
+            let tensors =
+                safetensors.iter().map(|(label, t)| (&*label.0, &***t)).collect::<Vec<_>>();
+            let mut data = vec![];
+            crate::safetensors::write_safetensors(&mut data, &tensors)
+                .context("Serializing safetensors weights")?;
+            let compression = self.member_compression.for_tensor(data.len());
+            append_member(
+                ar,
+                &mut manifest,
+                SAFETENSORS_WEIGHTS_FILENAME,
+                &data,
+                compression,
+                timestamp,
+            )?;
+        }
+
+        for (label, t) in dats {
             let mut label = label.0.to_string() + ".dat";
             if label.starts_with('/') {
                 label.insert(0, '.');
             }
-            let filename = std::path::Path::new(&label);
             let mut data = vec![];
             crate::tensors::write_tensor(&mut data, t)
-                .with_context(|| format!("Serializing tensor {filename:?}: {t:?}"))?;
-
-            let mut header = tar::Header::new_gnu();
-            header.set_size(data.len() as u64);
-            header.set_mode(0o644);
-            header.set_mtime(timestamp.as_secs());
-            header.set_cksum();
-
-            ar.append_data(&mut header, filename, &mut &*data)
-                .with_context(|| format!("Appending tensor {filename:?}"))?;
+                .with_context(|| format!("Serializing tensor {label:?}: {t:?}"))?;
+            let compression = self.member_compression.for_tensor(data.len());
+            append_member(ar, &mut manifest, &label, &data, compression, timestamp)?;
         }
 
         let mut labels = proto_model.resources.keys().collect::<Vec<_>>();
@@ -189,28 +461,38 @@ impl Nnef {
                 let mut filename = std::path::PathBuf::from_str(label)?;
                 let typed_model = &typed_model_resource.0;
 
+                self.write(typed_model, &mut submodel_data)?;
+                let mut compression = self.member_compression.submodels;
                 if compress_nested_models {
+                    compression = compression.or(Some(Compression::gzip()));
+                }
+                // gzipped submodels keep their historical .nnef.tgz name
+                if let Some(gzip @ Compression::Gzip { .. }) = compression {
                     filename.set_extension("nnef.tgz");
-                    let encoder = flate2::write::GzEncoder::new(
-                        &mut submodel_data,
-                        flate2::Compression::default(),
-                    );
-                    self.write(typed_model, encoder)?;
+                    submodel_data = gzip
+                        .compress_bytes(&submodel_data)
+                        .with_context(|| format!("Compressing submodel {label:?}"))?;
+                    compression = None;
                 } else {
                     filename.set_extension("nnef.tar");
-                    self.write(typed_model, &mut submodel_data)?;
                 }
 
-                let mut header = tar::Header::new_gnu();
-                header.set_size(submodel_data.len() as u64);
-                header.set_mode(0o644);
-                header.set_mtime(timestamp.as_secs());
-                header.set_cksum();
-
-                ar.append_data(&mut header, filename, &mut &*submodel_data)
+                let filename = filename.to_str().context("Badly encoded submodel name")?;
+                append_member(ar, &mut manifest, filename, &submodel_data, compression, timestamp)
                     .with_context(|| format!("Appending submodel {label:?}"))?;
             }
         }
+
+        if let Some(manifest) = manifest {
+            append_member(
+                ar,
+                &mut None,
+                MANIFEST_FILENAME,
+                &manifest.to_bytes()?,
+                None,
+                timestamp,
+            )?;
+        }
         Ok(())
     }
 
@@ -225,11 +507,13 @@ impl Nnef {
         }
         let proto_model = crate::ser::to_proto_model(self, model)?;
         std::fs::create_dir_all(path)?;
-        let mut graph_nnef = std::fs::File::create(path.join("graph.nnef"))?;
+        let mut manifest = self.manifest.then(Manifest::default);
+        let mut graph_nnef = vec![];
         crate::ast::dump::Dumper::new(self, &mut graph_nnef).document(&proto_model.doc)?;
+        write_file(path, &mut manifest, "graph.nnef", &graph_nnef)?;
 
         if let Some(quantization) = proto_model.quantization {
-            let mut graph_quant = std::fs::File::create(path.join("graph.quant"))?;
+            let mut graph_quant = vec![];
             for (name, format) in quantization.into_iter().sorted_by_key(|(x, _)| x.clone()) {
                 write_quant_format(
                     &mut graph_quant,
@@ -238,18 +522,30 @@ impl Nnef {
                     self.allow_extended_identifier_syntax,
                 )?;
             }
+            write_file(path, &mut manifest, "graph.quant", &graph_quant)?;
         }
 
-        for (label, t) in &proto_model.tensors {
+        let (safetensors, dats) = self.split_tensors(&proto_model.tensors);
+        if safetensors.len() > 0 {
+            let tensors =
+                safetensors.iter().map(|(label, t)| (&*label.0, &***t)).collect::<Vec<_>>();
+            let mut data = vec![];
+            crate::safetensors::write_safetensors(&mut data, &tensors)?;
+            write_file(path, &mut manifest, SAFETENSORS_WEIGHTS_FILENAME, &data)?;
+        }
+
+        for (label, t) in dats {
             let label = label.0.to_string() + ".dat";
             let label = label.trim_start_matches('/');
             let parent = path.join(label).parent().unwrap().to_owned();
             std::fs::create_dir_all(&parent).with_context(|| format!("Creating dir {parent:?}"))?;
-            let filename = path.join(label).to_owned();
-            let mut file = std::fs::File::create(&filename)
-                .with_context(|| format!("Creating file {filename:?}"))?;
+            let mut data = vec![];
+            crate::tensors::write_tensor(&mut data, t)?;
+            write_file(path, &mut manifest, label, &data)?;
+        }
 
-            crate::tensors::write_tensor(&mut file, t)?;
+        if let Some(manifest) = manifest {
+            write_file(path, &mut None, MANIFEST_FILENAME, &manifest.to_bytes()?)?;
         }
         Ok(())
     }
@@ -262,60 +558,12 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
     }
 
     fn proto_model_for_path(&self, path: impl AsRef<Path>) -> TractResult<ProtoModel> {
-        let path = path.as_ref();
-        if path.is_file() {
-            let mut f = std::fs::File::open(path)?;
-            return self.proto_model_for_read(&mut f);
-        }
-
-        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
-
-        // `walkdir::new` will first yield the given path at depth 0, but we don't want to load this
-        // entry here: only its descendants at depth >= 1.
-        for entry in walkdir::WalkDir::new(path).min_depth(1) {
-            let entry =
-                entry.map_err(|e| format_err!("Can not walk directory {:?}: {:?}", path, e))?;
-            // We don't want to load sub-directories themselves either.
-            if entry.path().is_dir() {
-                continue;
-            }
-            let subpath = entry
-                .path()
-                .components()
-                .skip(path.components().count())
-                .collect::<std::path::PathBuf>();
-            let mut stream = std::fs::File::open(entry.path())?;
-            read_stream(&subpath, &mut stream, &mut resources, self)?;
-        }
+        let resources = self.resources_for_path(path.as_ref(), &mut |_| true, true)?;
         proto_model_from_resources(resources)
     }
 
     fn proto_model_for_read(&self, reader: &mut dyn std::io::Read) -> TractResult<ProtoModel> {
-        let mut resources: HashMap<String, Arc<dyn Resource>> = Default::default();
-
-        let mut buffer = vec![0u8; 2];
-        reader.read_exact(&mut buffer)?;
-        let header = std::io::Cursor::new(buffer.clone());
-        let stream = header.chain(reader);
-        let mut tar = if buffer == [0x1f, 0x8b] {
-            #[cfg(feature = "flate2")]
-            {
-                let f = flate2::read::GzDecoder::new(stream);
-                tar::Archive::new(Box::new(f) as Box<dyn Read>)
-            }
-            #[cfg(not(feature = "flate2"))]
-            bail!("Cannot read gzip file without flate2 enabled.");
-        } else {
-            tar::Archive::new(Box::new(stream) as Box<dyn Read>)
-        };
-        for entry in tar.entries()? {
-            let mut entry = entry?;
-            let mut path = entry.path()?.to_path_buf();
-            if path.starts_with("./") {
-                path = path.strip_prefix("./")?.to_path_buf();
-            }
-            read_stream(&path, &mut entry, &mut resources, self)?;
-        }
+        let resources = self.resources_for_read(reader, &mut |_| true, true)?;
         proto_model_from_resources(resources)
     }
 
@@ -328,7 +576,7 @@ impl tract_core::prelude::Framework<ProtoModel, TypedModel> for Nnef {
     }
 }
 
-fn proto_model_from_resources(
+pub(crate) fn proto_model_from_resources(
     resources: HashMap<String, Arc<dyn Resource>>,
 ) -> TractResult<ProtoModel> {
     // Iter resources IDs to detect submodels. Submodels are IDs with
@@ -381,7 +629,7 @@ fn proto_model_from_resources(
             .map_err(|_| anyhow!("Error while extracting NNEF Document from shared reference. Only one reference to the document is expected"))?;
 
     // Collect all resources that can be downcastable to Arc<Tensor>.
-    let tensors: HashMap<_, _> = new_resources
+    let mut tensors: HashMap<_, _> = new_resources
         .iter()
         .filter_map(|(key, resource)| {
             Arc::clone(resource)
@@ -395,6 +643,14 @@ fn proto_model_from_resources(
         new_resources.remove(&*k.0);
     });
 
+    // Add tensors exposed by resources holding several of them (safetensors files for instance).
+    for resource in new_resources.values() {
+        for (name, tensor) in resource.variables() {
+            let previous = tensors.insert(Identifier::from(&*name), tensor);
+            ensure!(previous.is_none(), "Tensor {:?} has been already loaded", name);
+        }
+    }
+
     // Quantization format resources extraction if present.
     let quantization = if let Some(q_r) =
         new_resources.remove(crate::resource::GRAPH_QUANT_FILENAME)
@@ -415,17 +671,124 @@ fn proto_model_from_resources(
     Ok(proto)
 }
 
+fn map_file(path: &Path) -> TractResult<Arc<memmap2::MmapMut>> {
+    let file = std::fs::File::open(path).with_context(|| format!("Opening {path:?}"))?;
+    // private mapping: tensors can be mutated without touching the file
+    let map = unsafe { memmap2::MmapOptions::new().map_copy(&file) }
+        .with_context(|| format!("Mapping {path:?}"))?;
+    Ok(Arc::new(map))
+}
+
+fn append_member<W: std::io::Write>(
+    ar: &mut Builder<W>,
+    manifest: &mut Option<Manifest>,
+    path: &str,
+    data: &[u8],
+    compression: Option<Compression>,
+    timestamp: std::time::Duration,
+) -> TractResult<()> {
+    let (path, data) = if let Some(compression) = compression {
+        let data =
+            compression.compress_bytes(data).with_context(|| format!("Compressing {path:?}"))?;
+        (format!("{path}.{}", compression.extension()), Cow::Owned(data))
+    } else {
+        (path.to_string(), Cow::Borrowed(data))
+    };
+    if let Some(manifest) = manifest {
+        manifest.add(path.trim_start_matches("./"), &data);
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(timestamp.as_secs());
+    header.set_cksum();
+    ar.append_data(&mut header, &path, &mut &*data)
+        .with_context(|| format!("Appending {path:?}"))?;
+    Ok(())
+}
+
+fn write_file(
+    dir: &Path,
+    manifest: &mut Option<Manifest>,
+    label: &str,
+    data: &[u8],
+) -> TractResult<()> {
+    if let Some(manifest) = manifest {
+        manifest.add(label, data);
+    }
+    let filename = dir.join(label);
+    std::fs::write(&filename, data).with_context(|| format!("Writing file {filename:?}"))
+}
+
+/// The manifest is always loaded, whatever the filter says.
+fn is_wanted(path: &Path, filter: &mut dyn FnMut(&Path) -> bool) -> bool {
+    path.to_str() == Some(MANIFEST_FILENAME) || filter(path)
+}
+
+pub(crate) fn is_dat(path: &Path) -> bool {
+    path.extension().map(|e| e == "dat").unwrap_or(false)
+}
+
+fn is_safetensors(path: &Path) -> bool {
+    path.extension().map(|e| e == crate::safetensors::SAFETENSORS_EXTENSION).unwrap_or(false)
+}
+
+// ignore path with any component starting with "." (because OSX's tar is weird)
+pub(crate) fn is_hidden(path: &Path) -> bool {
+    #[cfg(target_family = "unix")]
+    if path.components().any(|name| name.as_os_str().as_bytes().first() == Some(&b'.')) {
+        return true;
+    }
+    false
+}
+
+/// Tensors share their storage with the map, other resources go through the loaders.
+fn read_mapped(
+    path: &Path,
+    content: &[u8],
+    map: &Arc<memmap2::MmapMut>,
+    resources: &mut HashMap<String, Arc<dyn Resource>>,
+    framework: &Nnef,
+) -> TractResult<()> {
+    if is_hidden(path) {
+        return Ok(());
+    }
+    if is_safetensors(path) {
+        let tensors: TensorsResource =
+            unsafe { crate::safetensors::read_safetensors_shared(content, map.clone()) }
+                .with_context(|| format!("Error while reading safetensors {path:?}"))?
+                .into_iter()
+                .collect();
+        let id = path.to_str().context("Badly encoded filename")?.to_string();
+        ensure!(!resources.contains_key(&id), "Tensors {:?} have been already loaded", id);
+        resources.insert(id, Arc::new(tensors));
+        return Ok(());
+    }
+    if !is_dat(path) {
+        return read_stream(path, &mut &*content, resources, framework);
+    }
+    let tensor = unsafe { crate::tensors::read_tensor_shared(content, map.clone()) }
+        .with_context(|| format!("Error while reading tensor {path:?}"))?;
+    let id = crate::resource::resource_path_to_id(path)?;
+    ensure!(!resources.contains_key(&id), "Tensor {:?} has been already loaded", id);
+    resources.insert(id, Arc::new(tensor));
+    Ok(())
+}
+
 fn read_stream<R: std::io::Read>(
     path: &Path,
     reader: &mut R,
     resources: &mut HashMap<String, Arc<dyn Resource>>,
     framework: &Nnef,
 ) -> TractResult<()> {
-    // ignore path with any component starting with "." (because OSX's tar is weird)
-    #[cfg(target_family = "unix")]
-    if path.components().any(|name| name.as_os_str().as_bytes().first() == Some(&b'.')) {
+    if is_hidden(path) {
         return Ok(());
     }
+    if let Some(decompressed) = crate::compression::compressed_member(path) {
+        let mut reader = crate::compression::decompress(reader)
+            .with_context(|| format!("Decompressing {path:?}"))?;
+        return read_stream(&decompressed, &mut reader, resources, framework);
+    }
     let mut last_loader_name;
     for loader in framework.resource_loaders.iter() {
         last_loader_name = Some(loader.name());
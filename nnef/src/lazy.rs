@@ -0,0 +1,198 @@
+//! Two-phase loading of NNEF models.
+//!
+//! The first phase parses graph.nnef and every other member, but only indexes the tensors stored
+//! in .dat members. The caller can then prune the graph, before the second phase loads the .dat
+//! members the remaining graph still references.
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Identifier, LValue, Literal, ProtoModel, RValue, Subscript};
+use crate::framework::{is_dat, is_hidden, proto_model_from_resources};
+use crate::internal::*;
+
+/// A model whose .dat tensors are not loaded yet.
+#[derive(Clone, Debug)]
+pub struct LazyProtoModel {
+    /// The model, without the tensors stored in .dat members.
+    pub proto: ProtoModel,
+    /// Labels of the tensors not loaded yet, and the members storing them.
+    pub index: BTreeMap<Identifier, PathBuf>,
+    path: PathBuf,
+}
+
+impl LazyProtoModel {
+    pub(crate) fn for_path(framework: &Nnef, path: &Path) -> TractResult<LazyProtoModel> {
+        let mut index = BTreeMap::new();
+        let resources = framework.resources_for_path(
+            path,
+            &mut |member| match tensor_id(member) {
+                Some(id) => {
+                    index.insert(id, member.to_path_buf());
+                    false
+                }
+                None => true,
+            },
+            false,
+        )?;
+        // submodels are built with their tensors, while extracting the resources
+        if let Some(submodel) = resources.keys().find(|id| {
+            id.split('/').count() == 2 && id.ends_with(crate::resource::GRAPH_NNEF_FILENAME)
+        }) {
+            bail!("Lazy loading does not support models with submodels (found {submodel:?})")
+        }
+        let proto = proto_model_from_resources(resources)?;
+        Ok(LazyProtoModel { proto, index, path: path.to_path_buf() })
+    }
+
+    /// Keep only the given graph outputs, and the part of the graph body computing them.
+    /// Graph inputs that are not used anymore are removed.
+    pub fn retain_outputs(&mut self, outputs: &[impl AsRef<str>]) -> TractResult<()> {
+        let graph_def = &mut self.proto.doc.graph_def;
+        let outputs: Vec<Identifier> = outputs.iter().map(|o| o.as_ref().into()).collect();
+        for output in &outputs {
+            ensure!(
+                graph_def.results.contains(output),
+                "{:?} is not an output of the graph (outputs are {:?})",
+                output.0,
+                graph_def.results.iter().map(|r| &r.0).collect::<Vec<_>>()
+            );
+        }
+        let mut needed: HashSet<Identifier> = outputs.iter().cloned().collect();
+        let mut kept = vec![];
+        for assignment in graph_def.body.iter().rev() {
+            let mut assigned = vec![];
+            lvalue_identifiers(&assignment.left, &mut assigned);
+            if assigned.iter().any(|id| needed.contains(id)) {
+                visit(&assignment.right, &mut |rv| {
+                    if let RValue::Identifier(id) = rv {
+                        needed.insert(id.clone());
+                    }
+                });
+                kept.push(assignment.clone());
+            }
+        }
+        kept.reverse();
+        graph_def.body = kept;
+        graph_def.parameters.retain(|p| needed.contains(p));
+        graph_def.results = outputs;
+        Ok(())
+    }
+
+    /// Labels of the indexed tensors referenced by the graph.
+    pub fn required_tensors(&self) -> BTreeSet<Identifier> {
+        let doc = &self.proto.doc;
+        let bodies = std::iter::once(&doc.graph_def.body)
+            .chain(doc.fragments.iter().filter_map(|f| f.body.as_ref()));
+        let mut labels = BTreeSet::new();
+        let mut all = false;
+        for assignment in bodies.flatten() {
+            visit(&assignment.right, &mut |rv| {
+                if let RValue::Invocation(inv) = rv {
+                    if inv.id.0 == "variable" {
+                        match variable_label(inv) {
+                            Some(label) => {
+                                labels.insert(Identifier::from(label.trim_start_matches('/')));
+                            }
+                            // label computed at deserialization time: keep everything
+                            None => all = true,
+                        }
+                    }
+                }
+            });
+        }
+        self.index.keys().filter(|id| all || labels.contains(*id)).cloned().collect()
+    }
+
+    /// Load the tensors the graph references, and get the complete model.
+    pub fn load(self, framework: &Nnef) -> TractResult<ProtoModel> {
+        let required = self.required_tensors();
+        let resources = framework.resources_for_path(
+            &self.path,
+            &mut |member| tensor_id(member).is_some_and(|id| required.contains(&id)),
+            false,
+        )?;
+        let mut proto = self.proto;
+        for (id, resource) in resources {
+            let tensor = resource
+                .downcast_arc::<Tensor>()
+                .map_err(|_| anyhow!("Member {id:?} is not a tensor"))?;
+            proto.tensors.insert(Identifier(id), tensor);
+        }
+        for label in required {
+            ensure!(
+                proto.tensors.contains_key(&label),
+                "Tensor {:?} not found in {:?}",
+                label.0,
+                self.path
+            );
+        }
+        Ok(proto)
+    }
+}
+
+/// Identifier of the tensor stored by a .dat member, maybe compressed.
+fn tensor_id(member: &Path) -> Option<Identifier> {
+    if is_hidden(member) {
+        return None;
+    }
+    let decompressed = crate::compression::compressed_member(member);
+    let member = decompressed.as_deref().unwrap_or(member);
+    if !is_dat(member) {
+        return None;
+    }
+    crate::resource::resource_path_to_id(member).ok().map(Identifier)
+}
+
+fn variable_label(inv: &crate::ast::Invocation) -> Option<&str> {
+    let label = inv
+        .arguments
+        .iter()
+        .find(|arg| arg.id.as_ref().is_some_and(|id| id.0 == "label"))
+        .or_else(|| inv.arguments.get(1).filter(|arg| arg.id.is_none()))?;
+    if let RValue::Literal(Literal::String(label)) = &label.rvalue {
+        Some(label)
+    } else {
+        None
+    }
+}
+
+fn lvalue_identifiers(lv: &LValue, ids: &mut Vec<Identifier>) {
+    match lv {
+        LValue::Identifier(id) => ids.push(id.clone()),
+        LValue::Array(lvs) | LValue::Tuple(lvs) => {
+            lvs.iter().for_each(|lv| lvalue_identifiers(lv, ids))
+        }
+    }
+}
+
+/// Call `f` on `rv` and all the rvalues it contains.
+fn visit(rv: &RValue, f: &mut dyn FnMut(&RValue)) {
+    f(rv);
+    match rv {
+        RValue::Identifier(_) | RValue::Literal(_) => (),
+        RValue::Binary(a, _, b) => {
+            visit(a, f);
+            visit(b, f);
+        }
+        RValue::Unary(_, a) => visit(a, f),
+        RValue::Tuple(rvs) | RValue::Array(rvs) => rvs.iter().for_each(|rv| visit(rv, f)),
+        RValue::Subscript(a, s) => {
+            visit(a, f);
+            match &**s {
+                Subscript::Single(i) => visit(i, f),
+                Subscript::Range(a, b) => a.iter().chain(b.iter()).for_each(|rv| visit(rv, f)),
+            }
+        }
+        RValue::Comprehension(c) => {
+            c.loop_iters.iter().for_each(|(_, rv)| visit(rv, f));
+            c.filter.iter().for_each(|rv| visit(rv, f));
+            visit(&c.yields, f);
+        }
+        RValue::IfThenElse(ite) => {
+            visit(&ite.cond, f);
+            visit(&ite.then, f);
+            visit(&ite.otherwise, f);
+        }
+        RValue::Invocation(inv) => inv.arguments.iter().for_each(|arg| visit(&arg.rvalue, f)),
+    }
+}
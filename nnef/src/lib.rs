@@ -3,11 +3,16 @@
 extern crate log;
 
 pub mod ast;
+pub mod compression;
 pub mod deser;
 pub mod framework;
+pub mod lazy;
+pub mod manifest;
 pub mod ops;
+pub mod optimized;
 pub mod registry;
 pub mod resource;
+pub mod safetensors;
 pub mod ser;
 pub mod tensors;
 
@@ -33,7 +38,8 @@ pub mod internal {
     pub use crate::prelude::*;
     pub use crate::registry::*;
     pub use crate::resource::{
-        DatLoader, GraphNnefLoader, GraphQuantLoader, Resource, ResourceLoader, TypedModelResource, TypedModelLoader,
+        DatLoader, GraphNnefLoader, GraphQuantLoader, Resource, ResourceLoader, SafetensorsLoader,
+        TensorsResource, TypedModelLoader, TypedModelResource,
     };
     pub use crate::ser::{invocation, logical, numeric, string, IntoAst};
     pub use std::any::TypeId;
@@ -0,0 +1,161 @@
+//! Integrity manifest: SHA-256 digests of the members of an NNEF archive or directory.
+//!
+//! The manifest is written in the `sha256sum` format, so a directory can also be checked with
+//! `sha256sum -c manifest.sha256`. Digests are computed on the members as stored, after
+//! member compression.
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use tract_core::internal::*;
+
+pub const MANIFEST_FILENAME: &str = "manifest.sha256";
+
+/// Member paths and their hex-encoded SHA-256 digests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest(pub BTreeMap<String, String>);
+
+impl Manifest {
+    pub fn add(&mut self, path: impl Into<String>, data: &[u8]) {
+        self.0.insert(path.into(), sha256_hex(data));
+    }
+
+    pub fn write(&self, w: &mut impl std::io::Write) -> TractResult<()> {
+        for (path, digest) in &self.0 {
+            writeln!(w, "{digest}  {path}")?;
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> TractResult<Vec<u8>> {
+        let mut data = vec![];
+        self.write(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn parse(s: &str) -> TractResult<Manifest> {
+        let mut manifest = Manifest::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let Some((digest, path)) = line.split_once("  ") else {
+                bail!("Invalid manifest line: {line:?}")
+            };
+            ensure!(
+                digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()),
+                "Invalid digest in manifest line: {line:?}"
+            );
+            manifest.0.insert(path.to_string(), digest.to_ascii_lowercase());
+        }
+        Ok(manifest)
+    }
+
+    /// Check digests of the members found in a model against the manifest.
+    pub fn verify(&self, found: &BTreeMap<String, String>) -> TractResult<()> {
+        for (path, expected) in &self.0 {
+            let Some(digest) = found.get(path) else {
+                bail!("Member {path:?} listed in the manifest is missing")
+            };
+            ensure!(
+                digest == expected,
+                "Member {path:?} is corrupted: sha256 is {digest}, manifest expects {expected}"
+            );
+        }
+        if let Some(path) = found.keys().find(|path| !self.0.contains_key(*path)) {
+            bail!("Member {path:?} is not listed in the manifest")
+        }
+        Ok(())
+    }
+
+    /// Check digests of the members of a partially loaded model: members listed in the manifest
+    /// but not found are not considered missing.
+    pub fn verify_members(&self, found: &BTreeMap<String, String>) -> TractResult<()> {
+        for (path, digest) in found {
+            let Some(expected) = self.0.get(path) else {
+                bail!("Member {path:?} is not listed in the manifest")
+            };
+            ensure!(
+                digest == expected,
+                "Member {path:?} is corrupted: sha256 is {digest}, manifest expects {expected}"
+            );
+        }
+        Ok(())
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Hashes what is read through it.
+struct DigestReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Collects the digests of the members of a model being loaded, and checks them against its
+/// manifest. Does nothing unless enabled.
+///
+/// An incomplete verifier only checks the members it saw, for models loaded partially.
+#[derive(Debug, Default)]
+pub(crate) struct Verifier {
+    enabled: bool,
+    complete: bool,
+    manifest: Option<Manifest>,
+    digests: BTreeMap<String, String>,
+}
+
+impl Verifier {
+    pub fn new(enabled: bool, complete: bool) -> Verifier {
+        Verifier { enabled, complete, ..Verifier::default() }
+    }
+
+    /// Load a member with `load`, hashing all its bytes, including the ones `load` skips.
+    pub fn member(
+        &mut self,
+        path: &Path,
+        reader: &mut dyn Read,
+        load: impl FnOnce(&mut dyn Read) -> TractResult<()>,
+    ) -> TractResult<()> {
+        if !self.enabled || crate::framework::is_hidden(path) {
+            return load(reader);
+        }
+        let name = path.to_str().context("Badly encoded filename")?.to_string();
+        if name == MANIFEST_FILENAME {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            self.manifest = Some(Manifest::parse(&content)?);
+            return Ok(());
+        }
+        let mut reader = DigestReader { inner: reader, hasher: Sha256::new() };
+        load(&mut reader)?;
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        self.digests.insert(name, hex(&reader.hasher.finalize()));
+        Ok(())
+    }
+
+    pub fn verify(self) -> TractResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let manifest = self
+            .manifest
+            .with_context(|| format!("No {MANIFEST_FILENAME} found, can not verify the model"))?;
+        if self.complete {
+            manifest.verify(&self.digests).context("Verifying model integrity")
+        } else {
+            manifest.verify_members(&self.digests).context("Verifying model integrity")
+        }
+    }
+}
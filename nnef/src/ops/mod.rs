@@ -2,6 +2,7 @@ use crate::internal::*;
 
 pub(super) mod core;
 pub mod nnef;
+pub(super) mod opt;
 pub(super) mod resource;
 
 pub use nnef::tract_nnef;
@@ -25,3 +26,13 @@ pub fn tract_resource() -> Registry {
     resource::register(&mut reg);
     reg
 }
+
+pub fn tract_opt() -> Registry {
+    let mut reg = Registry::new("tract_opt")
+        .with_doc("Extension `tract_opt` exposes NNEF fragments for the operators of models")
+        .with_doc("optimized by tract-core, for the CPU kernels selected by tract-linalg.")
+        .with_doc("")
+        .with_doc("Add `extension tract_opt` to `graph.nnef`");
+    opt::register(&mut reg);
+    reg
+}
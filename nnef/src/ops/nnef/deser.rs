@@ -17,6 +17,7 @@ use tract_itertools::Itertools;
 
 use tract_core::ops;
 use tract_linalg::frame::block_quant::BlockQuantValue;
+use tract_linalg::mmm::{EagerPackedInput, MMMInputValue};
 
 use crate::deser::{ModelBuilder, ResolvedInvocation};
 
@@ -112,6 +113,14 @@ pub fn variable(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) ->
     {
         let fact = Box::new(bqv.fact.clone());
         builder.wire(Const::new_with_opaque_fact(tensor, fact), &[])
+    } else if let Some(epi) = tensor
+        .to_scalar::<Opaque>()
+        .ok()
+        .and_then(|o| o.downcast_ref::<Box<dyn MMMInputValue>>())
+        .and_then(|v| v.downcast_ref::<EagerPackedInput>())
+    {
+        let fact = Box::new(epi.fact.clone());
+        builder.wire(Const::new_with_opaque_fact(tensor, fact), &[])
     } else {
         ensure!(
             tensor.shape() == &*shape,
@@ -502,7 +511,31 @@ pub fn conv_or_deconv(
         ))
     } else {
         if let Some(odt) = &output_dt {
-            for dt in &[&input_fact.datum_type, &kernel_fact.datum_type, odt] {
+            let kernel_quant = invocation.named_arg_quant_format(builder, "filter");
+            for (ix, dt) in [&input_fact.datum_type, &kernel_fact.datum_type, odt].iter().enumerate()
+            {
+                if let (1, Some(QuantFormat::PerAxis { axis, zero_points, scales, .. })) =
+                    (ix, &kernel_quant)
+                {
+                    // per output channel kernel quantization
+                    ensure!(
+                        *axis == 0
+                            && zero_points.len() == scales.len()
+                            && kernel_fact.shape[0] == scales.len().to_dim(),
+                        "Unsupported per-axis quantization for kernel {:?}: {:?}",
+                        kernel_fact,
+                        kernel_quant
+                    );
+                    // core conv only compensates a uniform kernel zero point
+                    ensure!(
+                        zero_points.iter().all_equal(),
+                        "Unsupported non-uniform kernel zero points: {:?}",
+                        zero_points
+                    );
+                    inputs.push(builder.add_const(tensor0(zero_points[0]))?);
+                    inputs.push(builder.add_const(tensor1(scales))?);
+                    continue;
+                }
                 let qp = dt.qparams().unwrap_or_default();
                 inputs.push(builder.add_const(tensor0(qp.zp_scale().0))?);
                 inputs.push(builder.add_const(tensor0(qp.zp_scale().1))?);
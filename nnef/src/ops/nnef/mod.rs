@@ -109,7 +109,7 @@ pub fn tract_nnef() -> Registry {
     registry.register_binary("max", &ops::math::Max {});
 
     primitive(&mut registry, "matmul", deser::matmul);
-    //    registry.register_dumper(ser::matmul);
+    registry.register_dumper(ser::basic_matmul);
 
     primitive(&mut registry, "conv", deser::conv);
     registry.register_dumper(ser::conv);
@@ -131,8 +131,6 @@ pub fn tract_nnef() -> Registry {
     primitive(&mut registry, "box", deser::sum_pool);
     registry.register_dumper(ser::sum_pool);
 
-    registry.register_dumper(ser::basic_matmul);
-
     for frag in stdlib {
         if frag.body.is_some() {
             registry.register_fragment(frag);
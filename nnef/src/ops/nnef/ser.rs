@@ -296,6 +296,14 @@ pub fn conv_or_deconv(
     }
     wire = ast.force_variable(format!("{}_input", node.name), &wire);
 
+    let kernel = if let Some(qf) = kernel_per_axis_quant(ast, node, deconv)? {
+        let kernel = ast.force_variable(format!("{}_kernel", node.name), &kernel);
+        let RValue::Identifier(id) = &*kernel else { unreachable!() };
+        ast.quantization.insert(id.clone(), qf);
+        kernel
+    } else {
+        kernel
+    };
     let inputs = tvec![wire, kernel, bias];
     let named_args = make_conv_named_args(node, pool_spec, group, deconv, adjustments)?;
 
@@ -317,6 +325,39 @@ pub fn conv_or_deconv(
     Ok(Some(wire))
 }
 
+// per output channel quantized kernel: the kernel datum type can not carry the parameters, so
+// they go in graph.quant
+fn kernel_per_axis_quant(
+    ast: &IntoAst,
+    node: &TypedNode,
+    deconv: bool,
+) -> TractResult<Option<QuantFormat>> {
+    if deconv || node.inputs.len() != 9 {
+        return Ok(None);
+    }
+    let (Some(k0), Some(k_scale)) = (
+        ast.model.outlet_fact(node.inputs[5])?.konst.as_ref(),
+        ast.model.outlet_fact(node.inputs[6])?.konst.as_ref(),
+    ) else {
+        return Ok(None);
+    };
+    if k0.len() == 1 && k_scale.len() == 1 {
+        return Ok(None);
+    }
+    let kernel_fact = ast.model.outlet_fact(node.inputs[1])?;
+    let channels = kernel_fact.shape[0].to_usize()?;
+    let zero_points = k0.cast_to::<i32>()?.broadcast_to_shape(&[channels])?;
+    let scales = k_scale.cast_to::<f32>()?.broadcast_to_shape(&[channels])?;
+    let dt = kernel_fact.datum_type;
+    Ok(Some(QuantFormat::PerAxis {
+        axis: 0,
+        zero_points: zero_points.as_slice::<i32>()?.to_vec(),
+        scales: scales.as_slice::<f32>()?.to_vec(),
+        bits: 8 * dt.size_of() as i8,
+        signed: dt.is_signed(),
+    }))
+}
+
 pub fn conv(
     ast: &mut IntoAst,
     node: &TypedNode,
@@ -656,7 +697,10 @@ pub fn rewrite_consistent_quantized_conv(
         ensure!(facts[3..9].iter().all(|fact| fact.konst.is_some()));
         for ix in [0, 1] {
             let fact = model.outlet_fact(node.inputs[ix])?;
-            if !fact.datum_type.is_quantized() {
+            // per-axis parameters can not go in the datum type, they are dumped in graph.quant
+            let per_axis =
+                facts[3 + 2 * ix..5 + 2 * ix].iter().any(|f| f.shape.volume() != 1.into());
+            if !fact.datum_type.is_quantized() && !per_axis {
                 let mut patch = TypedModelPatch::default();
                 let mut wire = patch.taps(model, &node.inputs)?;
                 let dt = fact.datum_type.quantize(QParams::ZpScale {
@@ -0,0 +1,23 @@
+use std::ops::ControlFlow;
+
+use crate::internal::*;
+use crate::optimized::{optimized_target, StaleOptimizedModel, OPTIMIZED_TARGET_EXTENSION};
+
+mod binary;
+pub(crate) mod matmul;
+
+pub fn register(registry: &mut Registry) {
+    registry.extensions.push(Box::new(|_builder, id, value| {
+        if id.0 != OPTIMIZED_TARGET_EXTENSION {
+            return Ok(ControlFlow::Continue(()));
+        }
+        let target = optimized_target();
+        if value.trim() != target {
+            let reason = format!("optimized for {:?}, running on {target:?}", value.trim());
+            return Err(StaleOptimizedModel(reason).into());
+        }
+        Ok(ControlFlow::Break(()))
+    }));
+    binary::register(registry);
+    matmul::register(registry);
+}
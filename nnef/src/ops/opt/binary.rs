@@ -0,0 +1,67 @@
+use tract_core::ops::binary::{BinMiniOp, OptBinByScalar, OptBinUnicast};
+use tract_core::ops::math;
+
+use crate::internal::*;
+use crate::optimized::StaleOptimizedModel;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(ser_by_scalar);
+    registry.register_dumper(ser_unicast);
+    for id in ["tract_opt_bin_by_scalar", "tract_opt_bin_unicast"] {
+        registry.register_primitive(
+            id,
+            &[
+                TypeName::Scalar.tensor().named("a"),
+                TypeName::Scalar.tensor().named("b"),
+                TypeName::String.named("op"),
+            ],
+            &[("output", TypeName::Scalar.tensor())],
+            de_bin,
+        );
+    }
+}
+
+fn ser_bin(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    id: &str,
+    binop: &dyn BinMiniOp,
+) -> TractResult<Option<Arc<RValue>>> {
+    let a = ast.mapping[&node.inputs[0]].clone();
+    let b = ast.mapping[&node.inputs[1]].clone();
+    Ok(Some(invocation(id, &[a, b], &[("op", string(binop.name()))])))
+}
+
+fn ser_by_scalar(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    op: &OptBinByScalar,
+) -> TractResult<Option<Arc<RValue>>> {
+    ser_bin(ast, node, "tract_opt_bin_by_scalar", &*op.binop)
+}
+
+fn ser_unicast(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    op: &OptBinUnicast,
+) -> TractResult<Option<Arc<RValue>>> {
+    ser_bin(ast, node, "tract_opt_bin_unicast", &*op.binop)
+}
+
+fn de_bin(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let a = invocation.named_arg_as(builder, "a")?;
+    let b = invocation.named_arg_as(builder, "b")?;
+    let name: String = invocation.named_arg_as(builder, "op")?;
+    let binop = [math::add(), math::sub(), math::subf(), math::mul(), math::min(), math::max()]
+        .into_iter()
+        .map(|op| op.0)
+        .find(|op| op.name() == name)
+        .with_context(|| format!("Unexpected binary operator {name}"))?;
+    let dt = builder.model.outlet_fact(a)?.datum_type;
+    let stale = || StaleOptimizedModel(format!("no {name} kernel for {dt:?}"));
+    if &*invocation.invocation.id.0 == "tract_opt_bin_by_scalar" {
+        builder.wire(OptBinByScalar::new(binop, dt).ok_or_else(stale)?, &[a, b])
+    } else {
+        builder.wire(OptBinUnicast::new(binop, dt).ok_or_else(stale)?, &[a, b])
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use tract_core::ops::matmul::optimized::{
+    AddMatMulGeometry, MapOutputAxisToInput, OptMatMul, ProtoFusedSpec,
+};
+use tract_core::ops::matmul::pack::OptMatMulPack;
+use tract_core::ops::matmul::ModePicker;
+use tract_core::tract_data::internal::parse_tdim;
+use tract_linalg::frame::PackedFormat;
+use tract_linalg::mmm::panel_extract::PanelExtractor;
+use tract_linalg::mmm::{OutputStoreSpec, RoundingPolicy};
+use tract_itertools::Itertools;
+use tract_linalg::{BinOp, Scaler};
+
+use crate::internal::*;
+use crate::optimized::StaleOptimizedModel;
+use crate::ser::*;
+
+pub fn register(registry: &mut Registry) {
+    registry.register_dumper(ser_pack);
+    registry.register_primitive(
+        "tract_opt_matmul_pack",
+        &[
+            TypeName::Scalar.tensor().named("input"),
+            TypeName::String.array().named("packers"),
+            TypeName::String.named("mode_picker"),
+            TypeName::Integer.named("k_axis"),
+            TypeName::Integer.named("mn_axis"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_pack,
+    );
+    registry.register_dumper(ser_matmul);
+    registry.register_primitive(
+        "tract_opt_matmul",
+        &[
+            TypeName::Scalar.tensor().array().named("inputs"),
+            TypeName::Integer.array().named("c_shape"),
+            TypeName::String.named("c_dt"),
+            TypeName::Integer.named("c_m_axis"),
+            TypeName::Integer.named("c_n_axis"),
+            TypeName::String.array().named("kernels"),
+            TypeName::String.named("mode_picker"),
+            TypeName::Logical.named("trivial_packing"),
+            TypeName::String.array().named("micro_ops"),
+        ],
+        &[("output", TypeName::Scalar.tensor())],
+        de_matmul,
+    );
+}
+
+/// Packed formats are written as `dt:r:alignment_bytes:end_padding_record`.
+pub(crate) fn packed_format_to_string(format: &PackedFormat) -> String {
+    format!("{:?}:{}:{}:{}", format.dt, format.r, format.alignment_bytes, format.end_padding_record)
+}
+
+pub(crate) fn parse_packed_format(s: &str) -> TractResult<PackedFormat> {
+    // the datum type may contain colons (quantization parameters), so split from the right
+    let fields: Vec<&str> = s.rsplitn(4, ':').collect();
+    ensure!(fields.len() == 4, "Invalid packed format {s}");
+    Ok(PackedFormat {
+        dt: fields[3].parse()?,
+        r: fields[2].parse()?,
+        alignment_bytes: fields[1].parse()?,
+        end_padding_record: fields[0].parse()?,
+    })
+}
+
+fn mode_picker_to_string(mode_picker: &ModePicker) -> &'static str {
+    match mode_picker {
+        ModePicker::Single => "single",
+        ModePicker::VecVsMat => "vec_vs_mat",
+    }
+}
+
+fn parse_mode_picker(s: &str) -> TractResult<ModePicker> {
+    match s {
+        "single" => Ok(ModePicker::Single),
+        "vec_vs_mat" => Ok(ModePicker::VecVsMat),
+        _ => bail!("Invalid mode picker {s}"),
+    }
+}
+
+fn ser_pack(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    op: &OptMatMulPack,
+) -> TractResult<Option<Arc<RValue>>> {
+    let input = ast.mapping[&node.inputs[0]].clone();
+    let packers: Vec<RValue> =
+        op.packers().iter().map(|p| string(packed_format_to_string(p))).collect();
+    Ok(Some(invocation(
+        "tract_opt_matmul_pack",
+        &[input],
+        &[
+            ("packers", array(packers)),
+            ("mode_picker", string(mode_picker_to_string(op.mode_picker()))),
+            ("k_axis", numeric(op.k_axis())),
+            ("mn_axis", numeric(op.mn_axis())),
+        ],
+    )))
+}
+
+fn de_pack(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let input = invocation.named_arg_as(builder, "input")?;
+    let packers: TVec<String> = invocation.named_arg_as(builder, "packers")?;
+    let packers = packers.iter().map(|p| parse_packed_format(p)).collect::<TractResult<_>>()?;
+    let mode_picker: String = invocation.named_arg_as(builder, "mode_picker")?;
+    let mode_picker = parse_mode_picker(&mode_picker)?;
+    let k_axis = invocation.named_arg_as(builder, "k_axis")?;
+    let mn_axis = invocation.named_arg_as(builder, "mn_axis")?;
+    builder.wire(OptMatMulPack::new(packers, mode_picker, k_axis, mn_axis)?, &[input])
+}
+
+fn ser_matmul(
+    ast: &mut IntoAst,
+    node: &TypedNode,
+    op: &OptMatMul,
+) -> TractResult<Option<Arc<RValue>>> {
+    let inputs = node.inputs.iter().map(|i| (*ast.mapping[i]).clone()).collect();
+    let kernels = op
+        .mmm
+        .iter()
+        .map(|mmm| {
+            ensure!(
+                tract_linalg::ops().mmm_by_name(mmm.name()).is_some(),
+                "Kernel {} can not be looked up by name",
+                mmm.name()
+            );
+            Ok(string(mmm.name()))
+        })
+        .collect::<TractResult<Vec<_>>>()?;
+    let micro_ops: Vec<RValue> =
+        op.micro_ops.iter().map(|op| string(micro_op_to_string(op))).collect();
+    Ok(Some(invocation(
+        "tract_opt_matmul",
+        &[Arc::new(RValue::Array(inputs))],
+        &[
+            ("c_shape", tdims(&op.c_fact.shape)),
+            ("c_dt", string(format!("{:?}", op.c_fact.datum_type))),
+            ("c_m_axis", numeric(op.c_m_axis)),
+            ("c_n_axis", numeric(op.c_n_axis)),
+            ("kernels", array(kernels)),
+            ("mode_picker", string(mode_picker_to_string(&op.mode_picker))),
+            ("trivial_packing", logical(op.trivial_packing)),
+            ("micro_ops", array(micro_ops)),
+        ],
+    )))
+}
+
+fn de_matmul(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractResult<Value> {
+    let inputs: TVec<OutletId> = invocation.named_arg_as(builder, "inputs")?;
+    let c_shape: TVec<TDim> = invocation.named_arg_as(builder, "c_shape")?;
+    let c_dt: String = invocation.named_arg_as(builder, "c_dt")?;
+    let c_dt: DatumType = c_dt.parse()?;
+    let c_m_axis = invocation.named_arg_as(builder, "c_m_axis")?;
+    let c_n_axis = invocation.named_arg_as(builder, "c_n_axis")?;
+    let kernels: TVec<String> = invocation.named_arg_as(builder, "kernels")?;
+    let mmm = kernels
+        .iter()
+        .map(|name| {
+            tract_linalg::ops()
+                .mmm_by_name(name)
+                .ok_or_else(|| StaleOptimizedModel(format!("no kernel {name}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mode_picker: String = invocation.named_arg_as(builder, "mode_picker")?;
+    let mode_picker = parse_mode_picker(&mode_picker)?;
+    let trivial_packing = invocation.named_arg_as(builder, "trivial_packing")?;
+    let micro_ops: TVec<String> = invocation.named_arg_as(builder, "micro_ops")?;
+    let micro_ops = micro_ops
+        .iter()
+        .map(|op| parse_micro_op(&builder.model.symbols, op).with_context(|| format!("In {op}")))
+        .collect::<TractResult<Vec<_>>>()?;
+    for op in &micro_ops {
+        if let ProtoFusedSpec::AddMatMul { packings, .. } = op {
+            ensure!(packings.len() == mmm.len(), "Expected one packing per kernel");
+            for (mmm, (packing, _)) in mmm.iter().zip(packings) {
+                if *packing >= mmm.packings().len() {
+                    bail!(StaleOptimizedModel(format!("no packing {packing} in {}", mmm.name())));
+                }
+            }
+        }
+    }
+    let op = OptMatMul::new(
+        mmm,
+        mode_picker,
+        c_dt.fact(c_shape),
+        c_m_axis,
+        c_n_axis,
+        micro_ops,
+        trivial_packing,
+    )?;
+    builder.wire(op, &inputs)
+}
+
+// Micro ops are written as their kind followed by key=value fields, separated by spaces.
+
+fn map_to_string(map: &MapOutputAxisToInput) -> String {
+    map.0.iter().map(|(c, input)| format!("{c}:{input}")).join(",")
+}
+
+fn parse_map(s: &str) -> TractResult<MapOutputAxisToInput> {
+    let pairs = s
+        .split(',')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (c, input) = pair.split_once(':').with_context(|| format!("Invalid map {s}"))?;
+            Ok((c.parse()?, input.parse()?))
+        })
+        .collect::<TractResult<_>>()?;
+    Ok(MapOutputAxisToInput(pairs))
+}
+
+fn store_to_string(store: &OutputStoreSpec) -> String {
+    match store {
+        OutputStoreSpec::View { m_axis, n_axis, mr, nr } => {
+            format!("view:{m_axis}:{n_axis}:{mr}:{nr}")
+        }
+        OutputStoreSpec::Strides { row_byte_stride, col_byte_stride, mr, nr } => {
+            format!("strides:{row_byte_stride}:{col_byte_stride}:{mr}:{nr}")
+        }
+    }
+}
+
+fn parse_store(s: &str) -> TractResult<OutputStoreSpec> {
+    let fields: Vec<&str> = s.split(':').collect();
+    match &*fields {
+        ["view", m_axis, n_axis, mr, nr] => Ok(OutputStoreSpec::View {
+            m_axis: m_axis.parse()?,
+            n_axis: n_axis.parse()?,
+            mr: mr.parse()?,
+            nr: nr.parse()?,
+        }),
+        ["strides", row_byte_stride, col_byte_stride, mr, nr] => Ok(OutputStoreSpec::Strides {
+            row_byte_stride: row_byte_stride.parse()?,
+            col_byte_stride: col_byte_stride.parse()?,
+            mr: mr.parse()?,
+            nr: nr.parse()?,
+        }),
+        _ => bail!("Invalid output store {s}"),
+    }
+}
+
+fn parse_binop(s: &str) -> TractResult<BinOp> {
+    Ok(match s {
+        "Min" => BinOp::Min,
+        "Max" => BinOp::Max,
+        "Add" => BinOp::Add,
+        "Mul" => BinOp::Mul,
+        "Sub" => BinOp::Sub,
+        "SubF" => BinOp::SubF,
+        _ => bail!("Invalid binary operator {s}"),
+    })
+}
+
+fn parse_policy(s: &str) -> TractResult<RoundingPolicy> {
+    use RoundingPolicy::*;
+    Ok(match s {
+        "Native" => Native,
+        "Zero" => Zero,
+        "Away" => Away,
+        "MinusInf" => MinusInf,
+        "PlusInf" => PlusInf,
+        "Even" => Even,
+        "Odd" => Odd,
+        _ => bail!("Invalid rounding policy {s}"),
+    })
+}
+
+fn micro_op_to_string(op: &ProtoFusedSpec) -> String {
+    use ProtoFusedSpec::*;
+    match op {
+        AddMatMul { geo, a, b, packings } => {
+            let packings = packings
+                .iter()
+                .map(|(packing, extractor)| match extractor {
+                    Some(extractor) => format!("{packing}@{}", extractor.name),
+                    None => packing.to_string(),
+                })
+                .join(",");
+            format!(
+                "add_mat_mul a={a} b={b} k={} c_to_a={} c_to_b={} packings={packings}",
+                geo.k.to_string().replace(' ', ""),
+                map_to_string(&geo.c_to_a_axis_mapping),
+                map_to_string(&geo.c_to_b_axis_mapping),
+            )
+        }
+        BinScalar(v, op) => format!("bin_scalar v={v} op={op:?}"),
+        LeakyRelu(v) => format!("leaky_relu v={v}"),
+        BinPerRow(v, op, map) => format!("bin_per_row v={v} op={op:?} map={}", map_to_string(map)),
+        BinPerCol(v, op, map) => format!("bin_per_col v={v} op={op:?} map={}", map_to_string(map)),
+        AddRowColProducts(row, col) => format!("add_row_col_products row={row} col={col}"),
+        AddUnicast(store, v, map) => {
+            format!("add_unicast v={v} store={} map={}", store_to_string(store), map_to_string(map))
+        }
+        Scaler(scaler) => format!(
+            "scaler scale={:?} mult={} shift={} policy={:?}",
+            scaler.scale,
+            scaler.mult.map(|m| m.to_string()).unwrap_or_else(|| "none".to_string()),
+            scaler.shift,
+            scaler.policy
+        ),
+        Store(stores) => format!("store stores={}", stores.iter().map(store_to_string).join(",")),
+    }
+}
+
+struct Fields<'s>(HashMap<&'s str, &'s str>);
+
+impl<'s> Fields<'s> {
+    fn str(&self, key: &str) -> TractResult<&'s str> {
+        self.0.get(key).copied().with_context(|| format!("Missing field {key}"))
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> TractResult<T>
+    where
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        Ok(self.str(key)?.parse()?)
+    }
+}
+
+fn parse_micro_op(symbols: &SymbolScope, s: &str) -> TractResult<ProtoFusedSpec> {
+    let mut tokens = s.split(' ');
+    let kind = tokens.next().unwrap_or_default();
+    let fields = tokens
+        .map(|token| token.split_once('=').with_context(|| format!("Invalid field {token}")))
+        .collect::<TractResult<HashMap<_, _>>>()?;
+    let fields = Fields(fields);
+    Ok(match kind {
+        "add_mat_mul" => {
+            let extractors = tract_linalg::ops().panel_extractors();
+            let packings = fields
+                .str("packings")?
+                .split(',')
+                .map(|packing| {
+                    let Some((packing, name)) = packing.split_once('@') else {
+                        return Ok((packing.parse()?, None));
+                    };
+                    let extractor =
+                        extractors.iter().find(|ex| ex.name == name).cloned().ok_or_else(|| {
+                            StaleOptimizedModel(format!("no panel extractor {name}"))
+                        })?;
+                    Ok((packing.parse()?, Some(extractor)))
+                })
+                .collect::<TractResult<Vec<(usize, Option<PanelExtractor>)>>>()?;
+            ProtoFusedSpec::AddMatMul {
+                geo: AddMatMulGeometry {
+                    k: parse_tdim(symbols, fields.str("k")?)?,
+                    c_to_a_axis_mapping: parse_map(fields.str("c_to_a")?)?,
+                    c_to_b_axis_mapping: parse_map(fields.str("c_to_b")?)?,
+                },
+                a: fields.get("a")?,
+                b: fields.get("b")?,
+                packings,
+            }
+        }
+        "bin_scalar" => {
+            ProtoFusedSpec::BinScalar(fields.get("v")?, parse_binop(fields.str("op")?)?)
+        }
+        "leaky_relu" => ProtoFusedSpec::LeakyRelu(fields.get("v")?),
+        "bin_per_row" => ProtoFusedSpec::BinPerRow(
+            fields.get("v")?,
+            parse_binop(fields.str("op")?)?,
+            parse_map(fields.str("map")?)?,
+        ),
+        "bin_per_col" => ProtoFusedSpec::BinPerCol(
+            fields.get("v")?,
+            parse_binop(fields.str("op")?)?,
+            parse_map(fields.str("map")?)?,
+        ),
+        "add_row_col_products" => {
+            ProtoFusedSpec::AddRowColProducts(fields.get("row")?, fields.get("col")?)
+        }
+        "add_unicast" => ProtoFusedSpec::AddUnicast(
+            parse_store(fields.str("store")?)?,
+            fields.get("v")?,
+            parse_map(fields.str("map")?)?,
+        ),
+        "scaler" => {
+            let mult = match fields.str("mult")? {
+                "none" => None,
+                mult => Some(mult.parse()?),
+            };
+            ProtoFusedSpec::Scaler(Scaler {
+                scale: fields.get("scale")?,
+                mult,
+                shift: fields.get("shift")?,
+                policy: parse_policy(fields.str("policy")?)?,
+            })
+        }
+        "store" => ProtoFusedSpec::Store(
+            fields.str("stores")?.split(',').map(parse_store).collect::<TractResult<_>>()?,
+        ),
+        _ => bail!("Invalid micro op {s}"),
+    })
+}
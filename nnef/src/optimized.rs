@@ -0,0 +1,59 @@
+//! Optimized model artifacts.
+//!
+//! With the `tract_opt` registry enabled, on top of `tract_core`, the NNEF serializer accepts the
+//! models returned by `into_optimized()`: the matrix products are written with their kernels,
+//! identified by name, and their constant operand as the packed weights. Loading such an artifact
+//! skips decluttering, codegen, kernel selection and weight packing.
+//!
+//! The artifact is keyed by the target it was optimized for: the tract version and the CPU
+//! features the kernel selection depends on. Loading it on another target, or on a CPU lacking
+//! one of its kernels, fails with a [StaleOptimizedModel] error.
+//! [crate::framework::Nnef::optimized_model_for_path_or_else] and
+//! [crate::framework::Nnef::optimized_model_for_read_or_else] re-optimize the source model
+//! instead.
+//!
+//! Not all optimized operators are supported yet: writing a model using others fails.
+use std::fmt;
+
+use crate::internal::*;
+
+/// Version of the encoding of the optimized operators.
+pub const OPTIMIZED_FORMAT_VERSION: usize = 1;
+
+/// Extension of the graph naming the target of an optimized model.
+pub const OPTIMIZED_TARGET_EXTENSION: &str = "tract_opt_target";
+
+/// The target of the models optimized by this build on this CPU.
+pub fn optimized_target() -> String {
+    format!(
+        "v{OPTIMIZED_FORMAT_VERSION} tract-{} {}",
+        env!("CARGO_PKG_VERSION"),
+        tract_linalg::cpu_features().join(" ")
+    )
+}
+
+/// Error of an optimized model artifact built for another target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StaleOptimizedModel(pub String);
+
+impl fmt::Display for StaleOptimizedModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Optimized model does not match the current target: {}", self.0)
+    }
+}
+
+impl std::error::Error for StaleOptimizedModel {}
+
+/// Return the loaded model, or the re-optimized one if the artifact is stale.
+pub(crate) fn or_else(
+    loaded: TractResult<TypedModel>,
+    reoptimize: impl FnOnce() -> TractResult<TypedModel>,
+) -> TractResult<TypedModel> {
+    match loaded {
+        Err(e) if e.downcast_ref::<StaleOptimizedModel>().is_some() => {
+            info!("{e:#}, re-optimizing");
+            reoptimize()
+        }
+        loaded => loaded,
+    }
+}
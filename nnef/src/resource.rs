@@ -2,6 +2,7 @@ use std::path::Path;
 
 use crate::ast::{Document, QuantFormat};
 use crate::internal::*;
+use crate::safetensors::SAFETENSORS_EXTENSION;
 use tract_core::downcast_rs::{impl_downcast, DowncastSync};
 
 pub const GRAPH_NNEF_FILENAME: &str = "graph.nnef";
@@ -20,6 +21,16 @@ pub trait Resource: DowncastSync + std::fmt::Debug + Send + Sync {
     fn get(&self, _key: &str) -> TractResult<Value> {
         bail!("No key access supported by this resource");
     }
+
+    /// Tensors exposed to the graph as variables, by label.
+    fn variables(&self) -> Vec<(String, Arc<Tensor>)> {
+        vec![]
+    }
+
+    /// Properties added to the loaded model.
+    fn properties(&self) -> Vec<(String, Arc<Tensor>)> {
+        vec![]
+    }
 }
 
 impl_downcast!(sync Resource);
@@ -97,6 +108,55 @@ impl ResourceLoader for DatLoader {
     }
 }
 
+/// Named tensors loaded together, each of them exposed to the graph as a variable.
+#[derive(Debug, Clone, Default)]
+pub struct TensorsResource(pub HashMap<String, Arc<Tensor>>);
+
+impl Resource for TensorsResource {
+    fn get(&self, key: &str) -> TractResult<Value> {
+        let tensor = self.0.get(key).with_context(|| format!("No tensor named {key:?}"))?;
+        Ok(Value::Tensor(tensor.clone()))
+    }
+
+    fn variables(&self) -> Vec<(String, Arc<Tensor>)> {
+        self.0.iter().map(|(name, tensor)| (name.clone(), tensor.clone())).collect()
+    }
+}
+
+impl FromIterator<(String, Tensor)> for TensorsResource {
+    fn from_iter<I: IntoIterator<Item = (String, Tensor)>>(iter: I) -> Self {
+        TensorsResource(iter.into_iter().map(|(name, t)| (name, t.into_arc_tensor())).collect())
+    }
+}
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub struct SafetensorsLoader;
+
+impl ResourceLoader for SafetensorsLoader {
+    fn name(&self) -> Cow<str> {
+        "SafetensorsLoader".into()
+    }
+
+    fn try_load(
+        &self,
+        path: &Path,
+        reader: &mut dyn std::io::Read,
+        _framework: &Nnef,
+    ) -> TractResult<Option<(String, Arc<dyn Resource>)>> {
+        if path.extension().map(|e| e == SAFETENSORS_EXTENSION).unwrap_or(false) {
+            let mut content = vec![];
+            reader.read_to_end(&mut content)?;
+            let tensors: TensorsResource = crate::safetensors::read_safetensors(&content)
+                .with_context(|| format!("Error while reading safetensors {path:?}"))?
+                .into_iter()
+                .collect();
+            Ok(Some((path.to_str().unwrap().to_string(), Arc::new(tensors))))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
 impl Resource for HashMap<String, QuantFormat> {}
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
@@ -158,13 +218,11 @@ impl ResourceLoader for TypedModelLoader {
             };
 
             let label = if path_str.ends_with(NNEF_TGZ) {
-                path
-                    .to_str()
+                path.to_str()
                     .ok_or_else(|| anyhow!("invalid model resource path"))?
                     .trim_end_matches(NNEF_TGZ)
             } else {
-                path
-                    .to_str()
+                path.to_str()
                     .ok_or_else(|| anyhow!("invalid model resource path"))?
                     .trim_end_matches(NNEF_TAR)
             };
@@ -0,0 +1,184 @@
+//! Safetensors support: https://github.com/huggingface/safetensors
+//!
+//! A little-endian u64 header size, a JSON header mapping tensor names to their datum type, shape
+//! and offsets in the data buffer, then the data buffer.
+use std::io::Write;
+
+use tract_core::internal::*;
+
+pub const SAFETENSORS_EXTENSION: &str = "safetensors";
+
+fn datum_type_name(dt: DatumType) -> Option<&'static str> {
+    use DatumType::*;
+    // quantization parameters are stored in graph.quant, as for .dat files
+    Some(match dt.unquantized() {
+        Bool => "BOOL",
+        U8 => "U8",
+        U16 => "U16",
+        U32 => "U32",
+        U64 => "U64",
+        I8 => "I8",
+        I16 => "I16",
+        I32 => "I32",
+        I64 => "I64",
+        F16 => "F16",
+        F32 => "F32",
+        F64 => "F64",
+        _ => return None,
+    })
+}
+
+fn datum_type(name: &str) -> TractResult<DatumType> {
+    use DatumType::*;
+    Ok(match name {
+        "BOOL" => Bool,
+        "U8" => U8,
+        "U16" => U16,
+        "U32" => U32,
+        "U64" => U64,
+        "I8" => I8,
+        "I16" => I16,
+        "I32" => I32,
+        "I64" => I64,
+        "F16" => F16,
+        "F32" => F32,
+        "F64" => F64,
+        // converted to F32 when loading
+        "BF16" => F32,
+        _ => bail!("Unsupported safetensors dtype {}", name),
+    })
+}
+
+/// Can the tensor be stored in a safetensors file?
+pub fn is_storable(tensor: &Tensor) -> bool {
+    datum_type_name(tensor.datum_type()).is_some()
+}
+
+/// Read all tensors of a safetensors file.
+pub fn read_safetensors(content: &[u8]) -> TractResult<Vec<(String, Tensor)>> {
+    read_safetensors_maybe_shared(content, None)
+}
+
+/// Read all tensors of a safetensors file owned by `owner` (typically a memory map).
+///
+/// Tensors share their storage with `content` when the datum type and the alignment allow it,
+/// falling back to a copy otherwise.
+///
+/// # Safety
+///
+/// `content` must stay valid as long as `owner` is alive, and be writable (a private memory map
+/// for instance) in case the tensors get mutated.
+pub unsafe fn read_safetensors_shared(
+    content: &[u8],
+    owner: Arc<dyn std::any::Any + Send + Sync>,
+) -> TractResult<Vec<(String, Tensor)>> {
+    read_safetensors_maybe_shared(content, Some(owner))
+}
+
+fn read_safetensors_maybe_shared(
+    content: &[u8],
+    owner: Option<Arc<dyn std::any::Any + Send + Sync>>,
+) -> TractResult<Vec<(String, Tensor)>> {
+    ensure!(content.len() >= 8, "Truncated safetensors header");
+    let header_len = u64::from_le_bytes(content[0..8].try_into().unwrap()) as usize;
+    let header_end = header_len.checked_add(8).context("Invalid safetensors header size")?;
+    let header = content.get(8..header_end).context("Truncated safetensors header")?;
+    let data = &content[header_end..];
+    let header: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(header).context("Parsing safetensors header")?;
+    let mut tensors = vec![];
+    for (name, info) in header {
+        if name == "__metadata__" {
+            continue;
+        }
+        let tensor = read_one(&info, data, owner.as_ref())
+            .with_context(|| format!("Reading safetensors tensor {name:?}"))?;
+        tensors.push((name, tensor));
+    }
+    Ok(tensors)
+}
+
+fn read_one(
+    info: &serde_json::Value,
+    data: &[u8],
+    owner: Option<&Arc<dyn std::any::Any + Send + Sync>>,
+) -> TractResult<Tensor> {
+    let dtype = info["dtype"].as_str().context("Expected a string dtype")?;
+    let shape = info["shape"]
+        .as_array()
+        .context("Expected a shape array")?
+        .iter()
+        .map(|d| d.as_u64().map(|d| d as usize).context("Expected positive integer dimension"))
+        .collect::<TractResult<TVec<usize>>>()?;
+    let offsets = info["data_offsets"].as_array().context("Expected data offsets")?;
+    let (Some(start), Some(end), 2) = (
+        offsets.first().and_then(|o| o.as_u64()),
+        offsets.get(1).and_then(|o| o.as_u64()),
+        offsets.len(),
+    ) else {
+        bail!("Expected two data offsets, got {:?}", offsets)
+    };
+    let bytes = data.get(start as usize..end as usize).context("Data offsets out of bounds")?;
+    let dt = datum_type(dtype)?;
+    let volume = shape
+        .iter()
+        .try_fold(1usize, |acc, d| acc.checked_mul(*d))
+        .context("Invalid tensor shape")?;
+    let item_size = if dtype == "BF16" { 2 } else { dt.size_of() };
+    let len = volume.checked_mul(item_size).context("Invalid tensor size")?;
+    ensure!(bytes.len() == len, "Expected {} bytes, got {}", len, bytes.len());
+    if dtype == "BF16" {
+        let floats = bytes
+            .chunks_exact(2)
+            .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+            .collect::<Vec<f32>>();
+        return Ok(tract_ndarray::ArrayD::from_shape_vec(&*shape, floats)?.into_tensor());
+    }
+    if dt == DatumType::Bool {
+        // any non zero byte is true
+        return unsafe { Tensor::from_raw_dt(DatumType::U8, &shape, bytes)? }
+            .cast_to::<bool>()
+            .map(|t| t.into_owned());
+    }
+    if let Some(owner) = owner {
+        match unsafe { Tensor::from_external_bytes(dt, &shape, bytes, owner.clone()) } {
+            Ok(tensor) => return Ok(tensor),
+            Err(e) => debug!("Copying tensor: {e}"),
+        }
+    }
+    unsafe { Tensor::from_raw_dt(dt, &shape, bytes) }
+}
+
+/// Write tensors to a safetensors file.
+///
+/// Tensors are laid out by decreasing alignment, so that they all stay aligned when the file is
+/// memory-mapped.
+pub fn write_safetensors(w: &mut impl Write, tensors: &[(&str, &Tensor)]) -> TractResult<()> {
+    let mut tensors = tensors.to_vec();
+    tensors.sort_by_key(|(name, t)| (std::cmp::Reverse(t.datum_type().alignment()), *name));
+    let mut header = serde_json::Map::new();
+    let mut offset = 0;
+    for (name, tensor) in &tensors {
+        let dtype = datum_type_name(tensor.datum_type())
+            .with_context(|| format!("Can not store {tensor:?} in safetensors"))?;
+        let len = tensor.len() * tensor.datum_type().size_of();
+        header.insert(
+            name.to_string(),
+            serde_json::json!({
+                "dtype": dtype,
+                "shape": tensor.shape(),
+                "data_offsets": [offset, offset + len],
+            }),
+        );
+        offset += len;
+    }
+    let mut header = serde_json::to_vec(&header)?;
+    // pad header so that data starts 8-bytes aligned
+    header.resize(header.len().next_multiple_of(8), b' ');
+    w.write_all(&(header.len() as u64).to_le_bytes())?;
+    w.write_all(&header)?;
+    for (_, tensor) in tensors {
+        w.write_all(tensor.as_bytes())?;
+    }
+    Ok(())
+}
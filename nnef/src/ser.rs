@@ -4,6 +4,9 @@ use tract_core::ndarray::ArrayViewD;
 use tract_core::ndarray::Axis;
 use tract_itertools::Itertools;
 use tract_linalg::frame::block_quant::BlockQuantValue;
+use tract_linalg::mmm::{EagerPackedInput, MMMInputValue};
+
+use crate::optimized::{optimized_target, OPTIMIZED_TARGET_EXTENSION};
 
 pub fn rewrite_model(model: &mut TypedModel) -> TractResult<()> {
     model.prop_consts()?;
@@ -54,6 +57,44 @@ pub fn to_proto_model(framework: &Nnef, model: &TypedModel) -> TractResult<Proto
         .context("Translating AST to proto model")
 }
 
+/// A node the NNEF serializer can not translate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedNode {
+    pub name: String,
+    pub op: String,
+    pub reason: String,
+}
+
+/// Translate the model as `to_proto_model` does, but go on after a node fails and report all
+/// the failing nodes. Node names are the ones of the model after the NNEF rewriting rules.
+pub fn unsupported_nodes(
+    framework: &Nnef,
+    model: &TypedModel,
+) -> TractResult<Vec<UnsupportedNode>> {
+    let mut fixed_model = model.clone();
+    rewrite_model(&mut fixed_model)?;
+    let mut into_ast = IntoAst::new(framework, &fixed_model);
+    let mut unsupported = vec![];
+    for node in fixed_model.eval_order()? {
+        let node = fixed_model.node(node);
+        if let Err(e) = into_ast.node(node) {
+            unsupported.push(UnsupportedNode {
+                name: node.name.clone(),
+                op: node.op.name().to_string(),
+                reason: format!("{e:#}"),
+            });
+            // placeholders so the successors can be checked too
+            for ix in 0..node.outputs.len() {
+                let placeholder = into_ast.scoped_id(format!("{}_{ix}", node.name));
+                into_ast
+                    .mapping
+                    .insert((node.id, ix).into(), RValue::Identifier(placeholder).into());
+            }
+        }
+    }
+    Ok(unsupported)
+}
+
 pub fn to_fragment_def(
     parent: &IntoAst,
     model: &TypedModel,
@@ -230,6 +271,9 @@ impl<'a> IntoAst<'a> {
         let mut extension = vec![];
         self.registries.sort();
         for reg in self.registries {
+            if reg.0 == "tract_opt" {
+                extension.push((OPTIMIZED_TARGET_EXTENSION.into(), optimized_target()));
+            }
             if reg.0 != "tract_nnef" {
                 extension.push(("tract_registry".into(), reg.0));
             }
@@ -464,6 +508,12 @@ impl<'a> IntoAst<'a> {
                 .downcast_ref::<BlockQuantValue>()
             {
                 &bqv.fact.shape
+            } else if tensor
+                .to_scalar::<Opaque>()?
+                .downcast_ref::<Box<dyn MMMInputValue>>()
+                .is_some_and(|v| v.is::<EagerPackedInput>())
+            {
+                tensor.shape()
             } else {
                 bail!("Unexpected opaque tensor in serialization {tensor:?}");
             }
@@ -3,6 +3,10 @@ use std::io::{Read, Write};
 use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use tract_core::internal::*;
 use tract_linalg::frame::block_quant::{BlockQuant, BlockQuantFact, BlockQuantValue, Q4_0};
+use tract_linalg::frame::PackedFormat;
+use tract_linalg::mmm::{EagerPackedInput, MMMInputValue, PackedOpaqueFact};
+
+use crate::ops::opt::matmul::{packed_format_to_string, parse_packed_format};
 
 const TRACT_ITEM_TYPE_VENDOR: u16 = ((b'T' as u16) << 8u16) | b'R' as u16;
 
@@ -57,7 +61,30 @@ impl Header {
     }
 }
 
-pub fn read_tensor(mut reader: impl Read) -> TractResult<Tensor> {
+pub fn read_tensor(reader: impl Read) -> TractResult<Tensor> {
+    read_tensor_maybe_shared(reader, None)
+}
+
+/// Read a tensor from a whole .dat file content, owned by `owner` (typically a memory map).
+///
+/// The tensor shares its storage with `content` when the data type and the alignment allow it,
+/// falling back to a copy otherwise.
+///
+/// # Safety
+///
+/// `content` must stay valid as long as `owner` is alive, and be writable (a private memory map
+/// for instance) in case the tensor gets mutated.
+pub unsafe fn read_tensor_shared(
+    content: &[u8],
+    owner: Arc<dyn std::any::Any + Send + Sync>,
+) -> TractResult<Tensor> {
+    read_tensor_maybe_shared(content, Some((content, owner)))
+}
+
+fn read_tensor_maybe_shared(
+    mut reader: impl Read,
+    shared: Option<(&[u8], Arc<dyn std::any::Any + Send + Sync>)>,
+) -> TractResult<Tensor> {
     let header = Header::read(&mut reader)?;
     let shape: TVec<usize> = header.dims[0..header.rank as usize]
         .iter()
@@ -145,6 +172,9 @@ pub fn read_tensor(mut reader: impl Read) -> TractResult<Tensor> {
         (TRACT_ITEM_TYPE_VENDOR, 4, 64) => DatumType::ComplexI32,
         #[cfg(feature = "complex")]
         (TRACT_ITEM_TYPE_VENDOR, 4, 128) => DatumType::ComplexI64,
+        (TRACT_ITEM_TYPE_VENDOR, 0x4000, _) => {
+            return read_packed_input(&mut reader, &header);
+        }
         (TRACT_ITEM_TYPE_VENDOR, it, _) if (it & 0x2000) == 0x2000 => {
             return read_block_quant_value(&mut reader, &header);
         }
@@ -154,6 +184,15 @@ pub fn read_tensor(mut reader: impl Read) -> TractResult<Tensor> {
             header.bits_per_item
         ),
     };
+    if let Some((content, owner)) = shared {
+        if dt.is_copy() && header.bits_per_item as usize == dt.size_of() * 8 {
+            let data = &content[std::mem::size_of::<Header>()..];
+            match unsafe { Tensor::from_external_bytes(dt, &shape, data, owner) } {
+                Ok(tensor) => return Ok(tensor),
+                Err(e) => debug!("Copying tensor: {e}"),
+            }
+        }
+    }
     if dt.is_copy() {
         let mut tensor = unsafe { Tensor::uninitialized_dt(dt, &shape)? };
         if dt == DatumType::Bool && header.bits_per_item == 1 {
@@ -199,6 +238,13 @@ pub fn write_tensor(w: &mut impl Write, tensor: &Tensor) -> TractResult<()> {
         {
             return write_block_quant_value(w, bqv);
         }
+        if let Some(epi) = tensor
+            .to_scalar::<Opaque>()?
+            .downcast_ref::<Box<dyn MMMInputValue>>()
+            .and_then(|v| v.downcast_ref::<EagerPackedInput>())
+        {
+            return write_packed_input(w, epi);
+        }
     }
     let mut header = Header::default();
     if tensor.rank() > 8 {
@@ -263,7 +309,7 @@ fn read_block_quant_value(r: &mut impl Read, header: &Header) -> TractResult<Ten
     let shape: TVec<usize> = header.dims[0..2].iter().map(|x| (*x as usize)).collect();
     ensure!(shape.iter().product::<usize>() % format.block_len() == 0);
     let expected_len = shape.iter().product::<usize>() / format.block_len() * format.block_bytes();
-    ensure!(expected_len == header.data_size_bytes as _);
+    ensure!(expected_len == header.data_size_bytes as usize);
     let mut blob = unsafe { Blob::new_for_size_and_align(expected_len, 128) };
     r.read_exact(&mut blob)?;
     let fact = BlockQuantFact {
@@ -294,6 +340,52 @@ fn write_block_quant_value(w: &mut impl Write, value: &BlockQuantValue) -> Tract
     Ok(())
 }
 
+// Packed inputs payload: the packed format as a length-prefixed string, mn, k and panel_bytes as
+// u64, then the packed data.
+fn read_packed_input(r: &mut impl Read, header: &Header) -> TractResult<Tensor> {
+    ensure!(header.rank == 0);
+    let len = r.read_u32::<LE>()? as usize;
+    let mut format = vec![0u8; len];
+    r.read_exact(&mut format)?;
+    let format = parse_packed_format(std::str::from_utf8(&format)?)?;
+    let mn = r.read_u64::<LE>()? as usize;
+    let k = r.read_u64::<LE>()? as usize;
+    let panel_bytes = r.read_u64::<LE>()? as usize;
+    let data_len = (header.data_size_bytes as usize)
+        .checked_sub(4 + len + 24)
+        .context("Inconsistent packed input size")?;
+    let mut packed = unsafe { Blob::new_for_size_and_align(data_len, format.alignment_bytes) };
+    r.read_exact(&mut packed)?;
+    let fact = PackedOpaqueFact { format: Box::new(format), mn, k };
+    let epi = EagerPackedInput { fact, packed: Arc::new(packed), panel_bytes };
+    Ok(tensor0(Opaque(Arc::new(Box::new(epi) as Box<dyn MMMInputValue>))))
+}
+
+#[allow(clippy::field_reassign_with_default)]
+fn write_packed_input(w: &mut impl Write, value: &EagerPackedInput) -> TractResult<()> {
+    let format = value
+        .fact
+        .format
+        .downcast_ref::<PackedFormat>()
+        .with_context(|| format!("Unsupported packed input format {}", value.fact.format))?;
+    let format = packed_format_to_string(format);
+
+    let mut header = Header::default();
+    header.rank = 0;
+    header.bits_per_item = u32::MAX;
+    header.data_size_bytes = (4 + format.len() + 24 + value.packed.len()) as _;
+    header.item_type_vendor = TRACT_ITEM_TYPE_VENDOR;
+    header.item_type = 0x4000;
+    header.write(w)?;
+    w.write_u32::<LE>(format.len() as u32)?;
+    w.write_all(format.as_bytes())?;
+    w.write_u64::<LE>(value.fact.mn as u64)?;
+    w.write_u64::<LE>(value.fact.k as u64)?;
+    w.write_u64::<LE>(value.panel_bytes as u64)?;
+    w.write_all(value.packed.as_bytes())?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
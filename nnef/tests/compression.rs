@@ -0,0 +1,131 @@
+use tract_nnef::compression::{Compression, MemberCompression};
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::ops::konst::Const;
+use tract_nnef::tract_core::ops::math::add;
+use tract_nnef::tract_core::ops::submodel::SubmodelOp;
+
+// big enough not to be inlined in graph.nnef
+fn weights() -> Tensor {
+    tensor1(&(0..16).map(|i| i as f32).collect::<Vec<_>>())
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([16]))?;
+    let w = model.add_const("w", weights())?;
+    let y = model.wire_node("y", add(), &[x, w])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn check(loaded: TypedModel, shared: bool) -> TractResult<()> {
+    let w = &loaded.node_by_name("w").unwrap().op_as::<Const>().unwrap().0;
+    assert_eq!(w.has_external_storage(), shared);
+    assert_eq!(**w, weights());
+    let outputs = loaded.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = tensor1(&(0..16).map(|i| i as f32 + 1.0).collect::<Vec<_>>());
+    assert_eq!(*outputs[0], expected);
+    Ok(())
+}
+
+fn members(tar: &[u8]) -> TractResult<Vec<String>> {
+    tar::Archive::new(tar)
+        .entries()?
+        .map(|e| Ok(e?.path()?.to_string_lossy().into_owned()))
+        .collect()
+}
+
+#[test]
+fn gzip_level() -> TractResult<()> {
+    let nnef = tract_nnef::nnef();
+    let fast = nnef.write_to_compressed_tar(&model()?, vec![], Compression::Gzip { level: 1 })?;
+    let best = nnef.write_to_compressed_tar(&model()?, vec![], Compression::Gzip { level: 9 })?;
+    assert!(best.len() <= fast.len());
+    check(nnef.model_for_read(&mut &*best)?, false)
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tar.zst");
+    let nnef = tract_nnef::nnef();
+    let file = std::fs::File::create(&path)?;
+    nnef.write_to_compressed_tar(&model()?, file, Compression::zstd())?;
+    check(nnef.model_for_path(&path)?, false)?;
+    // no memory-mapping in compressed archives
+    check(nnef.with_mmap_weights(true).model_for_path(&path)?, false)
+}
+
+#[test]
+fn compressed_graph_in_tar() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tar");
+    let nnef = tract_nnef::nnef().with_member_compression(MemberCompression {
+        graph: Some(Compression::gzip()),
+        ..MemberCompression::default()
+    });
+    nnef.write(&model()?, std::fs::File::create(&path)?)?;
+    assert_eq!(members(&std::fs::read(&path)?)?, vec!("graph.nnef.gz", "w.dat"));
+    check(nnef.model_for_path(&path)?, false)?;
+    check(nnef.with_mmap_weights(true).model_for_path(&path)?, true)
+}
+
+#[test]
+fn compressed_small_tensors_in_tar() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_member_compression(MemberCompression {
+        tensors: Some(Compression::gzip()),
+        tensors_max_size: Some(1024),
+        ..MemberCompression::default()
+    });
+    let tar = nnef.write_to_tar(&model()?, vec![])?;
+    assert_eq!(members(&tar)?, vec!("graph.nnef", "w.dat.gz"));
+    check(nnef.model_for_read(&mut &*tar)?, false)?;
+
+    let nnef = nnef.with_member_compression(MemberCompression {
+        tensors: Some(Compression::gzip()),
+        tensors_max_size: Some(16),
+        ..MemberCompression::default()
+    });
+    let tar = nnef.write_to_tar(&model()?, vec![])?;
+    assert_eq!(members(&tar)?, vec!("graph.nnef", "w.dat"));
+    check(nnef.model_for_read(&mut &*tar)?, false)
+}
+
+fn model_with_submodel() -> TractResult<TypedModel> {
+    let mut outer = TypedModel::default();
+    let x = outer.add_source("x", f32::fact([16]))?;
+    let y = outer.wire_node("sub", SubmodelOp::new(Box::new(model()?), "sub")?, &[x])?;
+    outer.set_output_outlets(&y)?;
+    Ok(outer)
+}
+
+fn check_submodel(loaded: TypedModel) -> TractResult<()> {
+    let outputs = loaded.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = tensor1(&(0..16).map(|i| i as f32 + 1.0).collect::<Vec<_>>());
+    assert_eq!(*outputs[0], expected);
+    Ok(())
+}
+
+#[test]
+fn compressed_submodel() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_tract_core().with_member_compression(MemberCompression {
+        submodels: Some(Compression::Gzip { level: 1 }),
+        ..MemberCompression::default()
+    });
+    let tar = nnef.write_to_tar(&model_with_submodel()?, vec![])?;
+    assert_eq!(members(&tar)?, vec!("graph.nnef", "sub.nnef.tgz"));
+    check_submodel(nnef.model_for_read(&mut &*tar)?)
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_submodel() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_tract_core().with_member_compression(MemberCompression {
+        submodels: Some(Compression::zstd()),
+        ..MemberCompression::default()
+    });
+    let tar = nnef.write_to_tar(&model_with_submodel()?, vec![])?;
+    assert_eq!(members(&tar)?, vec!("graph.nnef", "sub.nnef.tar.zst"));
+    check_submodel(nnef.model_for_read(&mut &*tar)?)
+}
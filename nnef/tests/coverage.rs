@@ -0,0 +1,60 @@
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::ops::math::{add, exp};
+
+#[derive(Debug, Clone, Default, Hash)]
+struct Opaque;
+
+impl Op for Opaque {
+    fn name(&self) -> Cow<str> {
+        "Opaque".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Opaque {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        Ok(inputs)
+    }
+}
+
+impl TypedOp for Opaque {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(inputs[0].clone()))
+    }
+
+    as_op!();
+}
+
+fn model(opaque: bool) -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([2, 3]))?;
+    let a = if opaque { model.wire_node("a", Opaque, &[x])?[0] } else { x };
+    let b = model.wire_node("b", exp(), &[a])?[0];
+    let c = if opaque { model.wire_node("c", Opaque, &[b])?[0] } else { b };
+    let d = model.wire_node("d", add(), &[c, x])?;
+    model.set_output_outlets(&d)?;
+    Ok(model)
+}
+
+#[test]
+fn all_nodes_supported() -> TractResult<()> {
+    assert_eq!(tract_nnef::nnef().unsupported_nodes(&model(false)?)?, vec!());
+    Ok(())
+}
+
+#[test]
+fn unsupported_nodes_are_all_listed() -> TractResult<()> {
+    let model = model(true)?;
+    let nnef = tract_nnef::nnef();
+    assert!(nnef.write_to_tar(&model, vec![]).is_err());
+    let unsupported = nnef.unsupported_nodes(&model)?;
+    assert_eq!(unsupported.iter().map(|n| &*n.name).collect::<Vec<_>>(), vec!("a", "c"));
+    assert!(unsupported.iter().all(|n| n.op == "Opaque"));
+    assert!(unsupported[0].reason.contains("No serializer found"));
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use tract_nnef::ast::{Identifier, ProtoModel};
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::ops::math::{add, mul};
+
+// big enough not to be inlined in graph.nnef
+fn weights(offset: f32) -> Tensor {
+    tensor1(&(0..16).map(|i| i as f32 + offset).collect::<Vec<_>>())
+}
+
+// an "encoder" output, and a "decoder" output computed from it
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([16]))?;
+    let enc_w = model.add_const("enc_w", weights(0.0))?;
+    let encoded = model.wire_node("encoded", add(), &[x, enc_w])?[0];
+    let dec_w = model.add_const("dec_w", weights(1.0))?;
+    let decoded = model.wire_node("decoded", mul(), &[encoded, dec_w])?[0];
+    model.set_output_outlets(&[encoded, decoded])?;
+    Ok(model)
+}
+
+fn write_dir(nnef: &Nnef) -> TractResult<(temp_dir::TempDir, std::path::PathBuf)> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    nnef.write_to_dir(&model()?, &path)?;
+    Ok((dir, path))
+}
+
+fn check_encoder(nnef: &Nnef, proto: &ProtoModel) -> TractResult<()> {
+    let model = nnef.model_for_proto_model(proto)?;
+    assert_eq!(model.output_outlets()?.len(), 1);
+    let outputs = model.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    assert_eq!(*outputs[0], weights(1.0));
+    Ok(())
+}
+
+#[test]
+fn full_lazy_load() -> TractResult<()> {
+    let nnef = tract_nnef::nnef();
+    let (_dir, path) = write_dir(&nnef)?;
+    let lazy = nnef.lazy_proto_model_for_path(&path)?;
+    assert!(lazy.proto.tensors.is_empty());
+    assert_eq!(lazy.index.len(), 2);
+    assert_eq!(lazy.required_tensors().len(), 2);
+    let model = nnef.model_for_proto_model(&lazy.load(&nnef)?)?;
+    let outputs = model.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    assert_eq!(
+        *outputs[1],
+        tensor1(&(0..16).map(|i| (i + 1) as f32 * (i + 1) as f32).collect::<Vec<_>>())
+    );
+    Ok(())
+}
+
+#[test]
+fn pruned_dir_does_not_read_decoder_weights() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_manifest(true);
+    let (_dir, path) = write_dir(&nnef)?;
+    std::fs::write(path.join("dec_w.dat"), b"garbage")?;
+    assert!(nnef.model_for_path(&path).is_err());
+    let verifying = tract_nnef::nnef().with_verify_manifest(true).with_mmap_weights(true);
+    for nnef in [nnef, verifying] {
+        let mut lazy = nnef.lazy_proto_model_for_path(&path)?;
+        lazy.retain_outputs(&["encoded"])?;
+        assert_eq!(
+            lazy.required_tensors().into_iter().collect::<Vec<_>>(),
+            vec!(Identifier::from("enc_w"))
+        );
+        let proto = lazy.load(&nnef)?;
+        assert_eq!(proto.tensors.keys().collect::<Vec<_>>(), vec!(&Identifier::from("enc_w")));
+        check_encoder(&nnef, &proto)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn pruned_tar() -> TractResult<()> {
+    let nnef = tract_nnef::nnef();
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tar");
+    nnef.write_to_tar(&model()?, std::fs::File::create(&path)?)?;
+    for nnef in [nnef, tract_nnef::nnef().with_mmap_weights(true)] {
+        let mut lazy = nnef.lazy_proto_model_for_path(&path)?;
+        lazy.retain_outputs(&["encoded"])?;
+        check_encoder(&nnef, &lazy.load(&nnef)?)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn unknown_output() -> TractResult<()> {
+    let nnef = tract_nnef::nnef();
+    let (_dir, path) = write_dir(&nnef)?;
+    let mut lazy = nnef.lazy_proto_model_for_path(&path)?;
+    let err = lazy.retain_outputs(&["latent"]).unwrap_err();
+    assert!(err.to_string().contains("\"latent\" is not an output of the graph"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use tract_nnef::internal::*;
+use tract_nnef::manifest::{Manifest, MANIFEST_FILENAME};
+use tract_nnef::tract_core::ops::math::add;
+
+// big enough not to be inlined in graph.nnef
+fn weights() -> Tensor {
+    tensor1(&(0..16).map(|i| i as f32).collect::<Vec<_>>())
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([16]))?;
+    let w = model.add_const("w", weights())?;
+    let y = model.wire_node("y", add(), &[x, w])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn check(loaded: TypedModel) -> TractResult<()> {
+    let outputs = loaded.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = tensor1(&(0..16).map(|i| i as f32 + 1.0).collect::<Vec<_>>());
+    assert_eq!(*outputs[0], expected);
+    Ok(())
+}
+
+fn write_dir(nnef: &Nnef) -> TractResult<(temp_dir::TempDir, std::path::PathBuf)> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    nnef.write_to_dir(&model()?, &path)?;
+    Ok((dir, path))
+}
+
+#[test]
+fn manifest_in_tar() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_manifest(true).with_verify_manifest(true);
+    let tar = nnef.write_to_tar(&model()?, vec![])?;
+    let mut archive = tar::Archive::new(&*tar);
+    let mut manifest = archive
+        .entries()?
+        .map(|e| e.unwrap())
+        .find(|e| e.path().unwrap().to_str() == Some(MANIFEST_FILENAME))
+        .unwrap();
+    let mut content = String::new();
+    std::io::Read::read_to_string(&mut manifest, &mut content)?;
+    let manifest = Manifest::parse(&content)?;
+    assert_eq!(manifest.0.keys().collect::<Vec<_>>(), vec!("graph.nnef", "w.dat"));
+    check(nnef.model_for_read(&mut &*tar)?)
+}
+
+#[test]
+fn manifest_in_dir() -> TractResult<()> {
+    let (_dir, path) = write_dir(&tract_nnef::nnef().with_manifest(true))?;
+    let manifest = Manifest::parse(&std::fs::read_to_string(path.join(MANIFEST_FILENAME))?)?;
+    let mut data = vec![];
+    tract_nnef::tensors::write_tensor(&mut data, &weights())?;
+    assert_eq!(manifest.0["w.dat"], tract_nnef::manifest::sha256_hex(&data));
+    let nnef = tract_nnef::nnef().with_verify_manifest(true);
+    check(nnef.model_for_path(&path)?)?;
+    check(nnef.with_mmap_weights(true).model_for_path(&path)?)
+}
+
+#[test]
+fn corrupted_member() -> TractResult<()> {
+    let (_dir, path) = write_dir(&tract_nnef::nnef().with_manifest(true))?;
+    let mut data = std::fs::read(path.join("w.dat"))?;
+    *data.last_mut().unwrap() ^= 1;
+    std::fs::write(path.join("w.dat"), data)?;
+    // not verified: loads the tampered weights
+    assert!(tract_nnef::nnef().model_for_path(&path).is_ok());
+    let nnef = tract_nnef::nnef().with_verify_manifest(true);
+    for nnef in [nnef, tract_nnef::nnef().with_verify_manifest(true).with_mmap_weights(true)] {
+        let err = nnef.model_for_path(&path).unwrap_err();
+        assert!(format!("{err:#}").contains("Member \"w.dat\" is corrupted"), "{err:#}");
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_and_unexpected_members() -> TractResult<()> {
+    let (_dir, path) = write_dir(&tract_nnef::nnef().with_manifest(true))?;
+    let nnef = tract_nnef::nnef().with_verify_manifest(true);
+    std::fs::rename(path.join("w.dat"), path.join("v.dat"))?;
+    let err = nnef.model_for_path(&path).unwrap_err();
+    assert!(format!("{err:#}").contains("Member \"w.dat\" listed in the manifest is missing"));
+    std::fs::copy(path.join("v.dat"), path.join("w.dat"))?;
+    let err = nnef.model_for_path(&path).unwrap_err();
+    assert!(format!("{err:#}").contains("Member \"v.dat\" is not listed in the manifest"));
+    Ok(())
+}
+
+#[test]
+fn missing_manifest() -> TractResult<()> {
+    let (_dir, path) = write_dir(&tract_nnef::nnef())?;
+    assert!(!path.join(MANIFEST_FILENAME).exists());
+    let err = tract_nnef::nnef().with_verify_manifest(true).model_for_path(&path).unwrap_err();
+    assert!(format!("{err:#}").contains("No manifest.sha256 found"));
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::ops::konst::Const;
+use tract_nnef::tract_core::ops::math::add;
+
+// big enough not to be inlined in graph.nnef
+fn weights() -> Tensor {
+    tensor1(&(0..16).map(|i| i as f32).collect::<Vec<_>>())
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([16]))?;
+    let w = model.add_const("w", weights())?;
+    let y = model.wire_node("y", add(), &[x, w])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn konst<'a>(model: &'a TypedModel, name: &str) -> &'a Tensor {
+    &model.node_by_name(name).unwrap().op_as::<Const>().unwrap().0
+}
+
+fn check(nnef: &Nnef, path: &Path, shared: bool) -> TractResult<()> {
+    let loaded = nnef.model_for_path(path)?;
+    assert_eq!(konst(&loaded, "w").has_external_storage(), shared);
+    assert_eq!(konst(&loaded, "w"), &weights());
+    let outputs = loaded.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = tensor1(&(0..16).map(|i| i as f32 + 1.0).collect::<Vec<_>>());
+    assert_eq!(*outputs[0], expected);
+    Ok(())
+}
+
+#[test]
+fn mmap_directory() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    let nnef = tract_nnef::nnef();
+    nnef.write_to_dir(&model()?, &path)?;
+    check(&nnef, &path, false)?;
+    check(&nnef.with_mmap_weights(true), &path, true)
+}
+
+#[test]
+fn mmap_tar() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tar");
+    let nnef = tract_nnef::nnef();
+    nnef.write(&model()?, std::fs::File::create(&path)?)?;
+    check(&nnef, &path, false)?;
+    check(&nnef.with_mmap_weights(true), &path, true)
+}
+
+#[test]
+fn mmap_falls_back_on_compressed_tar() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tgz");
+    let nnef = tract_nnef::nnef().with_mmap_weights(true);
+    let gz = flate2::write::GzEncoder::new(
+        std::fs::File::create(&path)?,
+        flate2::Compression::default(),
+    );
+    nnef.write_to_tar(&model()?, gz)?.finish()?;
+    check(&nnef, &path, false)
+}
@@ -0,0 +1,84 @@
+use tract_nnef::internal::*;
+use tract_nnef::optimized::{optimized_target, StaleOptimizedModel};
+use tract_nnef::tract_core::ops::einsum::EinSum;
+use tract_nnef::tract_core::ops::math;
+
+fn values(shape: &[usize], scale: f32) -> TractResult<Tensor> {
+    let len = shape.iter().product::<usize>();
+    let data = (0..len).map(|i| ((i * 7) % 13) as f32 * scale - 1.0).collect::<Vec<_>>();
+    tensor1(&data).into_shape(shape)
+}
+
+// relu(x.w + b), with a symbolic batch size
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let n = model.symbols.sym("N");
+    let x = model.add_source("x", f32::fact(dims!(n, 32)))?;
+    let w = model.add_const("w", values(&[32, 64], 0.1)?)?;
+    let b = model.add_const("b", values(&[1, 64], 0.2)?)?;
+    let y = model.wire_node("mm", EinSum::new("mk,kn->mn".parse()?, f32::datum_type()), &[x, w])?;
+    let y = model.wire_node("add", math::add(), &[y[0], b])?;
+    let zero = model.add_const("zero", tensor2(&[[0f32]]))?;
+    let y = model.wire_node("relu", math::max(), &[y[0], zero])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn nnef() -> Nnef {
+    tract_nnef::nnef().with_tract_core().with_tract_opt()
+}
+
+fn run(model: TypedModel, n: usize) -> TractResult<Arc<Tensor>> {
+    let x = values(&[n, 32], 0.05)?;
+    let outputs = model.into_runnable()?.run(tvec!(x.into()))?;
+    Ok(outputs[0].clone().into_arc_tensor())
+}
+
+#[test]
+fn optimized_round_trip() -> TractResult<()> {
+    let optimized = model()?.into_optimized()?;
+    assert!(optimized.nodes().iter().any(|n| n.op().name() == "OptMatMul"));
+    let mut buffer = vec![];
+    nnef().write_to_tar(&optimized, &mut buffer)?;
+    let loaded = nnef().model_for_read(&mut &*buffer)?;
+    assert!(loaded.nodes().iter().any(|n| n.op().name() == "OptMatMul"));
+    for n in [1, 5] {
+        let expected = run(model()?, n)?;
+        let found = run(loaded.clone(), n)?;
+        found.close_enough(&expected, Approximation::Approximate)?;
+    }
+    Ok(())
+}
+
+// same length target, so that the tar headers stay valid
+fn tamper(buffer: &mut [u8]) {
+    let target = optimized_target();
+    let pos = buffer.windows(target.len()).position(|w| w == target.as_bytes()).unwrap();
+    buffer[pos + 1] = b'0';
+}
+
+#[test]
+fn stale_optimized_model() -> TractResult<()> {
+    let mut buffer = vec![];
+    nnef().write_to_tar(&model()?.into_optimized()?, &mut buffer)?;
+    tamper(&mut buffer);
+    let err = nnef().model_for_read(&mut &*buffer).unwrap_err();
+    assert!(err.downcast_ref::<StaleOptimizedModel>().is_some(), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn stale_optimized_model_is_reoptimized() -> TractResult<()> {
+    let mut buffer = vec![];
+    nnef().write_to_tar(&model()?.into_optimized()?, &mut buffer)?;
+    let mut reoptimized = false;
+    nnef().optimized_model_for_read_or_else(&mut &*buffer, || panic!())?;
+    tamper(&mut buffer);
+    let loaded = nnef().optimized_model_for_read_or_else(&mut &*buffer, || {
+        reoptimized = true;
+        model()?.into_optimized()
+    })?;
+    assert!(reoptimized);
+    run(loaded, 3)?.close_enough(&*run(model()?, 3)?, Approximation::Approximate)?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use tract_nnef::internal::*;
+use tract_nnef::tract_core::ops::cnn::{Conv, KernelFormat, PaddingSpec, PoolSpec};
+use tract_nnef::tract_core::ops::nn::DataFormat;
+
+fn x_dt() -> DatumType {
+    DatumType::QI8(QParams::ZpScale { zero_point: 0, scale: 0.1 })
+}
+
+fn y_dt() -> DatumType {
+    DatumType::QI8(QParams::ZpScale { zero_point: 0, scale: 0.5 })
+}
+
+fn k_scales() -> Tensor {
+    tensor1(&[0.01f32, 0.02, 0.03, 0.04, 0.05, 0.06, 0.07, 0.08])
+}
+
+// one scale per output channel, 8 output channels so the kernel is not inlined
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", x_dt().fact([1, 2, 3, 3]))?;
+    let kernel =
+        tensor1(&(0..16).map(|i| i as i8 - 8).collect::<Vec<_>>()).into_shape(&[8, 2, 1, 1])?;
+    let kernel = model.add_const("kernel", kernel)?;
+    let bias = model.add_const("bias", Tensor::zero::<i32>(&[8])?)?;
+    let x0 = model.add_const("x0", tensor0(0i32))?;
+    let x_scale = model.add_const("x_scale", tensor0(0.1f32))?;
+    let k0 = model.add_const("k0", tensor0(0i32))?;
+    let k_scale = model.add_const("k_scale", k_scales())?;
+    let y0 = model.add_const("y0", tensor0(0i32))?;
+    let y_scale = model.add_const("y_scale", tensor0(0.5f32))?;
+    let pool_spec = PoolSpec {
+        data_format: DataFormat::NCHW,
+        kernel_shape: tvec!(1, 1),
+        padding: PaddingSpec::Valid,
+        dilations: None,
+        strides: None,
+        input_channels: 2,
+        output_channels: 8,
+    };
+    let conv = Conv { pool_spec, kernel_fmt: KernelFormat::OIHW, group: 1, q_params: Some(y_dt()) };
+    let y =
+        model.wire_node("conv", conv, &[x, kernel, bias, x0, x_scale, k0, k_scale, y0, y_scale])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn run(model: TypedModel) -> TractResult<TValue> {
+    let mut x =
+        tensor1(&(0..18).map(|i| i as i8 * 3).collect::<Vec<_>>()).into_shape(&[1, 2, 3, 3])?;
+    unsafe { x.set_datum_type(x_dt()) };
+    Ok(model.into_runnable()?.run(tvec!(x.into()))?.remove(0))
+}
+
+#[test]
+fn conv_per_channel_kernel_round_trip() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_tract_core();
+    let model = model()?;
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    nnef.write_to_dir(&model, &path)?;
+    let quant = std::fs::read_to_string(path.join("graph.quant"))?;
+    assert!(quant.contains("zero_point_linear_quantize_per_axis(axis = 0"));
+
+    let reloaded = nnef.model_for_path(&path)?.into_decluttered()?;
+    let k_scale = reloaded.nodes().iter().find_map(|n| {
+        n.op_as::<tract_nnef::tract_core::ops::konst::Const>()
+            .filter(|k| *k.0 == k_scales())
+            .map(|_| ())
+    });
+    assert!(k_scale.is_some());
+    let expected = run(model)?;
+    let found = run(reloaded)?;
+    expected.close_enough(&found, Approximation::Exact)
+}
+
+#[test]
+fn conv_per_channel_kernel_unsupported_parameters() -> TractResult<()> {
+    let nnef = tract_nnef::nnef().with_tract_core();
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    nnef.write_to_dir(&model()?, &path)?;
+    let quant = std::fs::read_to_string(path.join("graph.quant"))?;
+    for (from, to) in [("zero_point = [0, ", "zero_point = [1, "), ("bits = 8", "bits = 4")] {
+        let tampered = quant
+            .lines()
+            .map(|l| if l.contains("per_axis") { l.replacen(from, to, 1) } else { l.to_string() })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_ne!(tampered.trim(), quant.trim());
+        std::fs::write(path.join("graph.quant"), tampered)?;
+        assert!(nnef.model_for_path(&path).is_err());
+    }
+    Ok(())
+}
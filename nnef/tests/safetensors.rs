@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use tract_nnef::framework::{WeightsFormat, SAFETENSORS_WEIGHTS_FILENAME};
+use tract_nnef::internal::*;
+use tract_nnef::safetensors::{read_safetensors, write_safetensors};
+use tract_nnef::tract_core::ops::konst::Const;
+use tract_nnef::tract_core::ops::math::add;
+
+// big enough not to be inlined in graph.nnef
+fn weights() -> Tensor {
+    tensor1(&(0..16).map(|i| i as f32).collect::<Vec<_>>())
+}
+
+fn model() -> TractResult<TypedModel> {
+    let mut model = TypedModel::default();
+    let x = model.add_source("x", f32::fact([16]))?;
+    let w = model.add_const("w", weights())?;
+    let y = model.wire_node("y", add(), &[x, w])?;
+    model.set_output_outlets(&y)?;
+    Ok(model)
+}
+
+fn konst<'a>(model: &'a TypedModel, name: &str) -> &'a Tensor {
+    &model.node_by_name(name).unwrap().op_as::<Const>().unwrap().0
+}
+
+fn check(nnef: &Nnef, path: &Path, shared: bool) -> TractResult<()> {
+    let loaded = nnef.model_for_path(path)?;
+    assert_eq!(konst(&loaded, "w").has_external_storage(), shared);
+    assert_eq!(konst(&loaded, "w"), &weights());
+    let outputs = loaded.into_runnable()?.run(tvec!(tensor1(&[1f32; 16]).into()))?;
+    let expected = tensor1(&(0..16).map(|i| i as f32 + 1.0).collect::<Vec<_>>());
+    assert_eq!(*outputs[0], expected);
+    Ok(())
+}
+
+#[test]
+fn round_trip() -> TractResult<()> {
+    let a = tensor2(&[[1f32, 2.], [3., 4.]]);
+    let b = tensor1(&[1u8, 2, 3]);
+    let c = tensor1(&[true, false]);
+    let d = tensor0(-12i64);
+    let mut data = vec![];
+    write_safetensors(&mut data, &[("a", &a), ("b", &b), ("c", &c), ("d", &d)])?;
+    let mut tensors = read_safetensors(&data)?;
+    tensors.sort_by(|x, y| x.0.cmp(&y.0));
+    assert_eq!(tensors, vec!(("a".into(), a), ("b".into(), b), ("c".into(), c), ("d".into(), d)));
+    Ok(())
+}
+
+#[test]
+fn read_bf16() -> TractResult<()> {
+    let header = br#"{"x":{"dtype":"BF16","shape":[2],"data_offsets":[0,4]},"__metadata__":{}}"#;
+    let mut data = (header.len() as u64).to_le_bytes().to_vec();
+    data.extend_from_slice(header);
+    // 1.0 and -2.5 in bfloat16
+    data.extend_from_slice(&[0x80, 0x3f, 0x20, 0xc0]);
+    let tensors = read_safetensors(&data)?;
+    assert_eq!(tensors, vec!(("x".into(), tensor1(&[1f32, -2.5]))));
+    Ok(())
+}
+
+#[test]
+fn reject_out_of_bounds() -> TractResult<()> {
+    let header = br#"{"x":{"dtype":"F32","shape":[2],"data_offsets":[0,8]}}"#;
+    let mut data = (header.len() as u64).to_le_bytes().to_vec();
+    data.extend_from_slice(header);
+    data.extend_from_slice(&[0; 4]);
+    assert!(read_safetensors(&data).is_err());
+    Ok(())
+}
+
+#[test]
+fn reject_overflowing_shape() -> TractResult<()> {
+    for shape in ["[4294967296,4294967296]", "[4611686018427387904]"] {
+        let header = format!(r#"{{"x":{{"dtype":"F32","shape":{shape},"data_offsets":[0,0]}}}}"#);
+        let mut data = (header.len() as u64).to_le_bytes().to_vec();
+        data.extend_from_slice(header.as_bytes());
+        assert!(read_safetensors(&data).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn tar() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model.nnef.tar");
+    let nnef = tract_nnef::nnef().with_weights_format(WeightsFormat::Safetensors);
+    nnef.write(&model()?, std::fs::File::create(&path)?)?;
+    let mut tar = tar::Archive::new(std::fs::File::open(&path)?);
+    let members = tar
+        .entries()?
+        .map(|e| Ok(e?.path()?.to_string_lossy().into_owned()))
+        .collect::<TractResult<Vec<_>>>()?;
+    assert_eq!(members, vec!("graph.nnef", SAFETENSORS_WEIGHTS_FILENAME));
+    check(&nnef, &path, false)?;
+    check(&nnef.with_mmap_weights(true), &path, true)
+}
+
+#[test]
+fn directory() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    let nnef = tract_nnef::nnef().with_weights_format(WeightsFormat::Safetensors);
+    nnef.write_to_dir(&model()?, &path)?;
+    assert!(path.join(SAFETENSORS_WEIGHTS_FILENAME).exists());
+    assert!(!path.join("w.dat").exists());
+    check(&nnef, &path, false)?;
+    check(&nnef.with_mmap_weights(true), &path, true)
+}
+
+#[test]
+fn variables_from_safetensors() -> TractResult<()> {
+    let dir = temp_dir::TempDir::new()?;
+    let path = dir.path().join("model");
+    std::fs::create_dir(&path)?;
+    std::fs::write(
+        path.join("graph.nnef"),
+        "version 1.0;
+
+graph net(x) -> (y)
+{
+  x = external<scalar>(shape = [16]);
+  w = variable<scalar>(label = \"model.layers.0.weight\", shape = [16]);
+  y = add(x, w);
+}
+",
+    )?;
+    let mut file = std::fs::File::create(path.join("model.safetensors"))?;
+    write_safetensors(&mut file, &[("model.layers.0.weight", &weights())])?;
+    check(&tract_nnef::nnef(), &path, false)
+}
@@ -3,7 +3,7 @@ use std::cmp::Ordering;
 use rustfft::num_traits::Float;
 use tract_nnef::{
     internal::*,
-    tract_ndarray::{s, ArrayView1},
+    tract_ndarray::{s, ArrayView1, ArrayView2},
 };
 
 pub fn register(registry: &mut Registry) {
@@ -114,6 +114,35 @@ impl BoxRepr {
     }
 }
 
+/// Greedy non-max suppression of the boxes of a single class.
+///
+/// `candidates` are (score, index) pairs, indexing the first axis of `boxes`. The selected
+/// candidates are returned by decreasing score, up to `max_selected` of them.
+pub fn select_boxes<T: Datum + Float>(
+    box_repr: BoxRepr,
+    boxes: ArrayView2<T>,
+    mut candidates: TVec<(T, usize)>,
+    max_selected: usize,
+    iou_threshold: T,
+) -> TVec<(T, usize)> {
+    candidates.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    let mut selected: TVec<(T, usize)> = tvec![];
+    for (score, index) in candidates {
+        if selected.len() >= max_selected {
+            break;
+        }
+        let box1 = boxes.slice(s![index, ..]);
+        let suppr = selected.iter().any(|(_, index)| {
+            let box2 = boxes.slice(s![*index, ..]);
+            box_repr.should_suppress_by_iou(box1, box2, iou_threshold)
+        });
+        if !suppr {
+            selected.push((score, index));
+        }
+    }
+    selected
+}
+
 #[derive(Debug, Clone, Hash)]
 pub struct NonMaxSuppression {
     pub center_point_box: BoxRepr,
@@ -155,36 +184,24 @@ impl NonMaxSuppression {
         for batch in 0..num_batches {
             for class in 0..num_classes {
                 // items: (score, index)
-                let mut candidates: TVec<(T, usize)> =
-                    if let Some(score_threshold) = score_threshold {
-                        (0..num_dim)
-                            .map(|i| (scores[[batch, class, i]], i))
-                            .filter(|(score, _)| *score > score_threshold)
-                            .collect()
-                    } else {
-                        (0..num_dim).map(|i| (scores[[batch, class, i]], i)).collect()
-                    };
-
-                candidates.sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
-
-                // items: (score, index)
-                let mut selected_in_class: TVec<(T, usize)> = tvec![];
-
-                for (score, index) in candidates {
-                    if selected_in_class.len() as i64 >= max_output_boxes_per_class {
-                        break;
-                    }
-
-                    let box1 = boxes.slice(s![batch, index, ..]);
-                    let suppr = selected_in_class.iter().any(|(_, index)| {
-                        let box2 = boxes.slice(s![batch, *index, ..]);
-                        self.center_point_box.should_suppress_by_iou(box1, box2, iou_threshold)
-                    });
-                    if !suppr {
-                        selected_in_class.push((score, index));
-                        selected_global.push((batch, class, index));
-                    }
-                }
+                let candidates: TVec<(T, usize)> = if let Some(score_threshold) = score_threshold {
+                    (0..num_dim)
+                        .map(|i| (scores[[batch, class, i]], i))
+                        .filter(|(score, _)| *score > score_threshold)
+                        .collect()
+                } else {
+                    (0..num_dim).map(|i| (scores[[batch, class, i]], i)).collect()
+                };
+
+                let selected_in_class = select_boxes(
+                    self.center_point_box,
+                    boxes.slice(s![batch, .., ..]),
+                    candidates,
+                    max_output_boxes_per_class.max(0) as usize,
+                    iou_threshold,
+                );
+                selected_global
+                    .extend(selected_in_class.into_iter().map(|(_, index)| (batch, class, index)));
             }
         }
 
@@ -290,3 +307,111 @@ fn load(builder: &mut ModelBuilder, invocation: &ResolvedInvocation) -> TractRes
         builder.wire(op, &[boxes, scores, max_output_boxes_per_class, iou_threshold])
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // cases of the ONNX backend tests test_nonmaxsuppression_*
+    fn boxes(center_point_box: bool) -> Tensor {
+        if center_point_box {
+            tensor3(&[[
+                [0.5f32, 0.5, 1.0, 1.0],
+                [0.5, 0.6, 1.0, 1.0],
+                [0.5, 0.4, 1.0, 1.0],
+                [0.5, 10.5, 1.0, 1.0],
+                [0.5, 10.6, 1.0, 1.0],
+                [0.5, 100.5, 1.0, 1.0],
+            ]])
+        } else {
+            tensor3(&[[
+                [0.0f32, 0.0, 1.0, 1.0],
+                [0.0, 0.1, 1.0, 1.1],
+                [0.0, -0.1, 1.0, 0.9],
+                [0.0, 10.0, 1.0, 11.0],
+                [0.0, 10.1, 1.0, 11.1],
+                [0.0, 100.0, 1.0, 101.0],
+            ]])
+        }
+    }
+
+    const SCORES: [f32; 6] = [0.9, 0.75, 0.6, 0.95, 0.5, 0.3];
+
+    fn nms(
+        center_point_box: bool,
+        boxes: Tensor,
+        scores: Tensor,
+        max_output_boxes_per_class: i64,
+        score_threshold: Option<f32>,
+    ) -> TractResult<Tensor> {
+        let op = NonMaxSuppression {
+            center_point_box: if center_point_box {
+                BoxRepr::CenterWidthHeight
+            } else {
+                BoxRepr::TwoPoints
+            },
+            num_selected_indices_symbol: SymbolScope::default().sym("n"),
+            has_score_threshold: score_threshold.is_some(),
+        };
+        let mut inputs = tvec!(
+            boxes.into_tvalue(),
+            scores.into_tvalue(),
+            tensor0(max_output_boxes_per_class).into_tvalue(),
+            tensor0(0.5f32).into_tvalue()
+        );
+        if let Some(threshold) = score_threshold {
+            inputs.push(tensor0(threshold).into_tvalue());
+        }
+        Ok(op.eval(inputs)?.remove(0).into_tensor())
+    }
+
+    #[test]
+    fn suppress_by_iou() -> TractResult<()> {
+        let found = nms(false, boxes(false), tensor3(&[[SCORES]]), 3, Some(0.0))?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0], [0, 0, 5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn center_point_box_format() -> TractResult<()> {
+        let found = nms(true, boxes(true), tensor3(&[[SCORES]]), 3, Some(0.0))?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0], [0, 0, 5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn limit_output_size() -> TractResult<()> {
+        let found = nms(false, boxes(false), tensor3(&[[SCORES]]), 2, Some(0.0))?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn suppress_by_iou_and_scores() -> TractResult<()> {
+        let found = nms(false, boxes(false), tensor3(&[[SCORES]]), 3, Some(0.4))?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn no_score_threshold_nor_limit() -> TractResult<()> {
+        let found = nms(false, boxes(false), tensor3(&[[SCORES]]), 0, None)?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0], [0, 0, 5]]));
+        Ok(())
+    }
+
+    #[test]
+    fn two_classes() -> TractResult<()> {
+        let found = nms(false, boxes(false), tensor3(&[[SCORES, SCORES]]), 2, None)?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0], [0, 1, 3], [0, 1, 0]]));
+        Ok(())
+    }
+
+    #[test]
+    fn two_batches() -> TractResult<()> {
+        let boxes = Tensor::stack_tensors(0, &[boxes(false), boxes(false)])?;
+        let found = nms(false, boxes, tensor3(&[[SCORES], [SCORES]]), 2, None)?;
+        assert_eq!(found, tensor2(&[[0i64, 0, 3], [0, 0, 0], [1, 0, 3], [1, 0, 0]]));
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use std::ops::Range;
 use tract_nnef::internal::*;
-use tract_nnef::tract_core::trivial_op_state_freeeze;
+use tract_nnef::tract_core::frozen::{read_usize, write_usize};
+use tract_nnef::tract_core::ops::OpStateFreeze;
 
 /// Concat with pulse along concat axis
 #[derive(Debug, Clone, Hash)]
@@ -46,7 +47,22 @@ impl TypedOp for PulsedSameAxisConcat {
 pub struct PulsedSameAxisConcatState {
     current_pos: usize,
 }
-trivial_op_state_freeeze!(PulsedSameAxisConcatState);
+
+impl FrozenOpState for PulsedSameAxisConcatState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(self.clone())
+    }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_usize(w, self.current_pos)
+    }
+}
+
+impl OpStateFreeze for PulsedSameAxisConcatState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(self.clone())
+    }
+}
 
 impl OpState for PulsedSameAxisConcatState {
     fn eval(
@@ -74,6 +90,11 @@ impl OpState for PulsedSameAxisConcatState {
 
         Ok(tvec!(data.into_tvalue()))
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.current_pos = read_usize(r)?;
+        Ok(())
+    }
 }
 
 pub fn overwrite_part_of_pulse(
@@ -2,6 +2,7 @@ use std::ops::AddAssign;
 
 use tract_ndarray::Axis;
 use tract_nnef::internal::*;
+use tract_nnef::tract_core::frozen::{read_opt_tensor, read_u64, write_opt_tensor, write_u64};
 use tract_nnef::tract_core::ops::OpStateFreeze;
 use tract_num_traits::Zero;
 
@@ -16,8 +17,6 @@ pub struct DeconvDelay {
     pub deconv_output_dim: TDim,
 }
 
-
-
 impl Op for DeconvDelay {
     fn name(&self) -> Cow<str> {
         "DeconvDelay".into()
@@ -69,9 +68,17 @@ impl OpState for DeconvDelayState {
         inputs: TVec<TValue>,
     ) -> TractResult<TVec<TValue>> {
         let op = op.downcast_ref::<DeconvDelay>().context("Wrong op")?;
-        if self.buffer.is_none() {
-            let mut buffer_size: TVec<usize> = inputs[0].shape().into();
-            buffer_size[op.axis] = op.overlap; //+ (op.stride - 1) * (op.pulse - 1);
+        let mut buffer_size: TVec<usize> = inputs[0].shape().into();
+        buffer_size[op.axis] = op.overlap; //+ (op.stride - 1) * (op.pulse - 1);
+        if let Some(buffer) = &self.buffer {
+            ensure!(
+                buffer.datum_type() == inputs[0].datum_type() && buffer.shape() == &*buffer_size,
+                "DeconvDelay buffer is a {:?}, expected {:?} {:?}",
+                buffer,
+                inputs[0].datum_type(),
+                buffer_size
+            );
+        } else {
             self.buffer = Some(Tensor::zero_dt(inputs[0].datum_type(), &buffer_size)?);
         }
         let mut input = inputs[0].clone().into_tensor();
@@ -79,6 +86,23 @@ impl OpState for DeconvDelayState {
         let output = input.slice(op.axis, 0, input.shape()[op.axis] - op.overlap)?;
         Ok(tvec!(output.into_tvalue()))
     }
+
+    fn restore(&mut self, op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        let op = op.downcast_ref::<DeconvDelay>().context("Wrong op")?;
+        self.valid_inputed = read_u64(r)? as i64 as isize;
+        let buffer = read_opt_tensor(r)?;
+        if let Some(buffer) = &buffer {
+            ensure!(
+                buffer.rank() > op.axis && buffer.shape()[op.axis] == op.overlap,
+                "DeconvDelay buffer is a {:?}, expected {} items on axis {}",
+                buffer,
+                op.overlap,
+                op.axis
+            );
+        }
+        self.buffer = buffer;
+        Ok(())
+    }
 }
 
 impl DeconvDelayState {
@@ -134,4 +158,9 @@ impl FrozenOpState for FrozenDeconvDelayState {
             buffer: self.buffer.as_ref().map(|t| t.clone().into_tensor()),
         })
     }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_u64(w, self.valid_inputed as i64 as u64)?;
+        write_opt_tensor(w, self.buffer.as_deref())
+    }
 }
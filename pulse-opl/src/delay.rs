@@ -1,4 +1,5 @@
 use tract_nnef::internal::*;
+use tract_nnef::tract_core::frozen::{read_opt_tensor, write_opt_tensor};
 use tract_nnef::tract_core::ops::OpStateFreeze;
 
 pub fn register(registry: &mut Registry) {
@@ -87,18 +88,48 @@ impl OpState for DelayState {
         let output_pulse = input_pulse + op.overlap;
         let mut output_shape: TVec<usize> = input.shape().into();
         output_shape[op.axis] = output_pulse;
+        let mut buffer_shape = input.shape().to_owned();
+        buffer_shape[op.axis] = buffered;
+        if let Some(buffer) = &self.buffer {
+            ensure!(
+                buffer.datum_type() == input.datum_type() && buffer.shape() == &*buffer_shape,
+                "Delay buffer is a {:?}, expected {:?} {:?}",
+                buffer,
+                input.datum_type(),
+                buffer_shape
+            );
+        }
         // build output
         unsafe {
             if self.buffer.is_none() {
-                let mut shape = input.shape().to_owned();
-                shape[op.axis] = buffered;
-                self.buffer = Some(Tensor::uninitialized_dt(input.datum_type(), &shape)?);
+                self.buffer = Some(Tensor::uninitialized_dt(input.datum_type(), &buffer_shape)?);
             };
             let mut output = Tensor::uninitialized_dt(input.datum_type(), &output_shape)?;
             self.apply_delay_unchecked(op, &input, &mut output);
             Ok(tvec!(output.into()))
         }
     }
+
+    fn restore(&mut self, op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        let op = op.downcast_ref::<Delay>().context("Wrong Op type")?;
+        let buffer = read_opt_tensor(r)?;
+        if let Some(buffer) = &buffer {
+            ensure!(
+                buffer.rank() == op.buffer_shape.len()
+                    && buffer.shape()[op.axis] == op.delay + op.overlap
+                    && buffer
+                        .shape()
+                        .iter()
+                        .zip(op.buffer_shape.iter())
+                        .all(|(b, d)| !matches!(d.to_usize(), Ok(d) if d != *b)),
+                "Delay buffer is a {:?}, expected a {:?} shape",
+                buffer,
+                op.buffer_shape
+            );
+        }
+        self.buffer = buffer;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -207,4 +238,8 @@ impl FrozenOpState for FrozenDelayState {
     fn unfreeze(&self) -> Box<dyn OpState> {
         Box::new(DelayState { buffer: self.buffer.as_ref().map(|t| t.clone().into_tensor()) })
     }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_opt_tensor(w, self.buffer.as_deref())
+    }
 }
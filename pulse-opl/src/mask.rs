@@ -1,6 +1,7 @@
 use tract_nnef::internal::*;
 use tract_nnef::ser::tdim;
-use tract_nnef::tract_core::trivial_op_state_freeeze;
+use tract_nnef::tract_core::frozen::{read_usize, write_usize};
+use tract_nnef::tract_core::ops::OpStateFreeze;
 
 pub fn register(registry: &mut Registry) {
     registry.register_primitive(
@@ -56,6 +57,11 @@ impl OpState for PulseMaskOpState {
         let tensor = self.pad(session, op, input)?;
         Ok(tvec!(tensor.into_tvalue()))
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.current_pos = read_usize(r)?;
+        Ok(())
+    }
 }
 
 impl PulseMaskOpState {
@@ -145,4 +151,18 @@ impl TypedOp for PulseMask {
     as_op!();
 }
 
-trivial_op_state_freeeze!(PulseMaskOpState);
+impl FrozenOpState for PulseMaskOpState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(self.clone())
+    }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_usize(w, self.current_pos)
+    }
+}
+
+impl OpStateFreeze for PulseMaskOpState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(self.clone())
+    }
+}
@@ -2,6 +2,7 @@ use tract_core::ndarray::*;
 use tract_core::ops::array::PadMode;
 use tract_nnef::internal::*;
 use tract_nnef::ser::tdim;
+use tract_nnef::tract_core::frozen::{read_opt_tensor, read_usize, write_opt_tensor, write_usize};
 use tract_nnef::tract_core::ops::OpStateFreeze;
 
 pub fn register(registry: &mut Registry) {
@@ -104,6 +105,12 @@ impl OpState for PulsePadOpState {
         let tensor = self.pad(session, op, input)?;
         Ok(tvec!(tensor.into_tvalue()))
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.current_pos = read_usize(r)?;
+        self.last_valid_frame = read_opt_tensor(r)?;
+        Ok(())
+    }
 }
 
 impl PulsePadOpState {
@@ -188,7 +195,18 @@ impl PulsePadOpState {
                     ))
                 },
                 PadMode::Edge => {
-                    let last_frame = self.last_valid_frame.as_ref().unwrap();
+                    let last_frame =
+                        self.last_valid_frame.as_ref().context("No valid frame to pad with")?;
+                    let mut frame_shape: TVec<usize> = input.shape().into();
+                    frame_shape.remove(op.axis);
+                    ensure!(
+                        last_frame.datum_type() == input.datum_type()
+                            && last_frame.shape() == &*frame_shape,
+                        "Pad frame is a {:?}, expected {:?} {:?}",
+                        last_frame,
+                        input.datum_type(),
+                        frame_shape
+                    );
                     unsafe {
                         dispatch_copy_by_size!(fill_slice_with_frame(input.datum_type())(
                             &mut input,
@@ -276,4 +294,9 @@ impl FrozenOpState for FrozenPulsePadOpState {
             last_valid_frame: self.last_valid_frame.as_ref().map(|t| t.clone().into_tensor()),
         })
     }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_usize(w, self.current_pos)?;
+        write_opt_tensor(w, self.last_valid_frame.as_deref())
+    }
 }
@@ -2,7 +2,8 @@ use crate::internal::*;
 use tract_core::ops::array::TypedConcat;
 use tract_pulse_opl::concat::overwrite_part_of_pulse;
 use tract_pulse_opl::ops::Delay;
-use tract_pulse_opl::tract_core::trivial_op_state_freeeze;
+use tract_pulse_opl::tract_core::frozen::{read_usize, write_usize};
+use tract_pulse_opl::tract_core::ops::OpStateFreeze;
 
 register_all!(TypedConcat: pulsify);
 
@@ -144,7 +145,22 @@ pub struct PulsedSameAxisConcatState {
     current_pos: usize,
     symbols_in_dim: Vec<Symbol>,
 }
-trivial_op_state_freeeze!(PulsedSameAxisConcatState);
+
+impl FrozenOpState for PulsedSameAxisConcatState {
+    fn unfreeze(&self) -> Box<dyn OpState> {
+        Box::new(self.clone())
+    }
+
+    fn save(&self, w: &mut dyn std::io::Write) -> TractResult<()> {
+        write_usize(w, self.current_pos)
+    }
+}
+
+impl OpStateFreeze for PulsedSameAxisConcatState {
+    fn freeze(&self) -> Box<dyn FrozenOpState> {
+        Box::new(self.clone())
+    }
+}
 
 impl OpState for PulsedSameAxisConcatState {
     fn eval(
@@ -173,4 +189,9 @@ impl OpState for PulsedSameAxisConcatState {
 
         Ok(tvec!(data.into_tvalue()))
     }
+
+    fn restore(&mut self, _op: &dyn Op, r: &mut dyn std::io::Read) -> TractResult<()> {
+        self.current_pos = read_usize(r)?;
+        Ok(())
+    }
 }
@@ -127,4 +127,61 @@ mod test {
             assert_eq!(&output[0].as_slice::<u8>().unwrap()[skip..], &expect[skip..]);
         }
     }
+
+    #[test]
+    fn save_and_restore() -> TractResult<()> {
+        let pulse = 4usize;
+        let mut model = PulsedModel::default();
+        let stream_dim = model.symbols.sym("S").to_dim();
+        let fact = PulsedFact {
+            datum_type: u8::datum_type(),
+            shape: (&[pulse]).into(),
+            stream: Some(StreamInfo { axis: 0, dim: stream_dim, delay: 0 }),
+        };
+        let source = model.add_source("source", fact.clone())?;
+        let delay =
+            model.wire_node("delay", Delay::new_typed(&(&fact).into(), 0, 6, 0), &[source])?;
+        model.set_output_outlets(&delay)?;
+        let plan = SimplePlan::new(model)?;
+        let input = |i: usize| tvec!(tensor1(&[i as u8; 4]).into());
+
+        let mut state = tract_core::plan::SimpleState::new(&plan)?;
+        state.run(input(1))?;
+        state.run(input(2))?;
+        let mut bytes = vec![];
+        state.freeze().save(&mut bytes)?;
+        let mut restored = tract_core::plan::SimpleState::new(&plan)?;
+        restored.restore(&mut &*bytes)?;
+        for i in 3..6 {
+            assert_eq!(restored.run(input(i))?, state.run(input(i))?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn restore_checks_buffer() -> TractResult<()> {
+        let plan = |delay: usize| -> TractResult<_> {
+            let mut model = PulsedModel::default();
+            let stream_dim = model.symbols.sym("S").to_dim();
+            let fact = PulsedFact {
+                datum_type: u8::datum_type(),
+                shape: (&[4]).into(),
+                stream: Some(StreamInfo { axis: 0, dim: stream_dim, delay: 0 }),
+            };
+            let source = model.add_source("source", fact.clone())?;
+            let op = Delay::new_typed(&(&fact).into(), 0, delay, 0);
+            let delay = model.wire_node("delay", op, &[source])?;
+            model.set_output_outlets(&delay)?;
+            SimplePlan::new(model)
+        };
+        let short = plan(3)?;
+        let mut state = tract_core::plan::SimpleState::new(&short)?;
+        state.run(tvec!(tensor1(&[1u8; 4]).into()))?;
+        let mut bytes = vec![];
+        state.freeze().save(&mut bytes)?;
+        let long = plan(6)?;
+        let mut restored = tract_core::plan::SimpleState::new(&long)?;
+        assert!(restored.restore(&mut &*bytes).is_err());
+        Ok(())
+    }
 }
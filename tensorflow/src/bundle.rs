@@ -0,0 +1,213 @@
+//! Reader for the TensorBundle format of TensorFlow checkpoints, as found in the `variables/`
+//! directory of a SavedModel.
+//!
+//! A bundle is made of an index (`{prefix}.index`), a LevelDB table mapping tensor names to
+//! `BundleEntryProto`, and of data shards (`{prefix}.data-{shard}-of-{shards}`) holding the
+//! raw tensor bytes.
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::bundle_header_proto::Endianness;
+use crate::tfpb::tensorflow::{
+    BundleEntryProto, BundleHeaderProto, DataType, TrackableObjectGraph,
+};
+
+const TABLE_MAGIC: u64 = 0xdb4775248b80fb57;
+const FOOTER_LEN: usize = 48;
+const OBJECT_GRAPH_KEY: &str = "_CHECKPOINTABLE_OBJECT_GRAPH";
+
+#[derive(Clone, Debug)]
+pub struct TensorBundle {
+    prefix: PathBuf,
+    header: BundleHeaderProto,
+    entries: HashMap<String, BundleEntryProto>,
+}
+
+impl TensorBundle {
+    /// Open the bundle with the given prefix (`some_saved_model/variables/variables`).
+    pub fn open(prefix: impl AsRef<Path>) -> TractResult<TensorBundle> {
+        let prefix = prefix.as_ref().to_path_buf();
+        let index_path = Self::path(&prefix, ".index");
+        let index = fs::read(&index_path).with_context(|| format!("Reading {index_path:?}"))?;
+        let mut header = None;
+        let mut entries = HashMap::new();
+        for (key, value) in read_table(&index).with_context(|| format!("Parsing {index_path:?}"))? {
+            if key.is_empty() {
+                header = Some(BundleHeaderProto::decode(&*value)?);
+            } else {
+                entries.insert(String::from_utf8(key)?, BundleEntryProto::decode(&*value)?);
+            }
+        }
+        let header = header.context("Tensor bundle index has no header")?;
+        ensure!(
+            header.endianness == Endianness::Little as i32,
+            "Only little endian tensor bundles are supported"
+        );
+        Ok(TensorBundle { prefix, header, entries })
+    }
+
+    fn path(prefix: &Path, suffix: &str) -> PathBuf {
+        let mut path = prefix.as_os_str().to_owned();
+        path.push(suffix);
+        path.into()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|k| &**k)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Read and check a tensor from the data shards.
+    pub fn tensor(&self, key: &str) -> TractResult<Tensor> {
+        let entry =
+            self.entries.get(key).with_context(|| format!("No {key:?} in tensor bundle"))?;
+        ensure!(entry.slices.is_empty(), "Partitioned tensor {key:?} is not supported");
+        let dt = DataType::from_i32(entry.dtype)
+            .with_context(|| format!("Invalid data type {} for {key:?}", entry.dtype))?;
+        let dt = DatumType::try_from(dt)?;
+        let shape: TVec<usize> =
+            entry.shape.as_ref().map(TVec::<usize>::try_from).transpose()?.unwrap_or_default();
+        let shard = Self::path(
+            &self.prefix,
+            &format!(".data-{:05}-of-{:05}", entry.shard_id, self.header.num_shards),
+        );
+        let mut data = vec![0u8; entry.size as usize];
+        let mut file = fs::File::open(&shard).with_context(|| format!("Opening {shard:?}"))?;
+        file.seek(SeekFrom::Start(entry.offset as u64))?;
+        file.read_exact(&mut data).with_context(|| format!("Reading {key:?} from {shard:?}"))?;
+        ensure!(unmask_crc(entry.crc32c) == crc32c(&data), "Checksum mismatch for {key:?}");
+        let len = shape.iter().product::<usize>();
+        if dt == DatumType::Blob {
+            let blobs = decode_strings(&data, len)?;
+            Ok(tract_ndarray::ArrayD::from_shape_vec(&*shape, blobs)?.into())
+        } else {
+            ensure!(
+                data.len() == len * dt.size_of(),
+                "{key:?} is {} bytes long, expected {dt:?}{shape:?}",
+                data.len()
+            );
+            unsafe { Tensor::from_raw_dt(dt, &shape, &data) }
+        }
+    }
+
+    /// The object graph of checkpoints written by the object-based TF2 API.
+    pub fn object_graph(&self) -> TractResult<Option<TrackableObjectGraph>> {
+        if !self.contains(OBJECT_GRAPH_KEY) {
+            return Ok(None);
+        }
+        let tensor = self.tensor(OBJECT_GRAPH_KEY)?;
+        let blob = tensor.to_scalar::<Blob>()?;
+        Ok(Some(TrackableObjectGraph::decode(blob.as_bytes())?))
+    }
+}
+
+// string tensors are the varint lengths, a checksum of the lengths, and the concatenated bytes
+fn decode_strings(data: &[u8], len: usize) -> TractResult<Vec<Blob>> {
+    let mut cursor = data;
+    let lengths = (0..len).map(|_| read_varint(&mut cursor)).collect::<TractResult<Vec<_>>>()?;
+    cursor = cursor.get(4..).context("Truncated string tensor")?;
+    lengths
+        .into_iter()
+        .map(|l| {
+            let bytes = cursor.get(..l as usize).context("Truncated string tensor")?;
+            cursor = &cursor[l as usize..];
+            Blob::try_from(bytes)
+        })
+        .collect()
+}
+
+fn read_varint(cursor: &mut &[u8]) -> TractResult<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = cursor.split_first().context("Truncated varint")?;
+        *cursor = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("Invalid varint")
+}
+
+fn read_block_handle(cursor: &mut &[u8]) -> TractResult<(usize, usize)> {
+    Ok((read_varint(cursor)? as usize, read_varint(cursor)? as usize))
+}
+
+// (key, value) pairs of a LevelDB table, as written by tensorflow/core/lib/io/table_builder.cc
+fn read_table(data: &[u8]) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    ensure!(data.len() >= FOOTER_LEN, "Truncated table");
+    let footer = &data[data.len() - FOOTER_LEN..];
+    let magic = u64::from_le_bytes(footer[FOOTER_LEN - 8..].try_into()?);
+    ensure!(magic == TABLE_MAGIC, "Bad magic number in table footer");
+    let mut cursor = footer;
+    let _metaindex = read_block_handle(&mut cursor)?;
+    let index = read_block_handle(&mut cursor)?;
+    let mut entries = vec![];
+    for (_, handle) in read_block(data, index)? {
+        let handle = read_block_handle(&mut &*handle)?;
+        entries.extend(read_block(data, handle)?);
+    }
+    Ok(entries)
+}
+
+fn read_block(data: &[u8], (offset, size): (usize, usize)) -> TractResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    // the block is followed by its compression type and checksum
+    let block = data.get(offset..offset + size).context("Block out of table bounds")?;
+    let compression = *data.get(offset + size).context("Block out of table bounds")?;
+    ensure!(compression == 0, "Compressed table blocks are not supported");
+    ensure!(block.len() >= 4, "Truncated block");
+    let restarts = u32::from_le_bytes(block[block.len() - 4..].try_into()?) as usize;
+    let end = block.len().checked_sub(4 * (restarts + 1)).context("Invalid block restarts")?;
+    let mut cursor = &block[..end];
+    let mut key = vec![];
+    let mut entries = vec![];
+    while !cursor.is_empty() {
+        let shared = read_varint(&mut cursor)? as usize;
+        let non_shared = read_varint(&mut cursor)? as usize;
+        let value_len = read_varint(&mut cursor)? as usize;
+        ensure!(
+            shared <= key.len() && non_shared + value_len <= cursor.len(),
+            "Invalid block entry"
+        );
+        key.truncate(shared);
+        key.extend_from_slice(&cursor[..non_shared]);
+        entries.push((key.clone(), cursor[non_shared..][..value_len].to_vec()));
+        cursor = &cursor[non_shared + value_len..];
+    }
+    Ok(entries)
+}
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f63b78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32c(data: &[u8]) -> u32 {
+    !data
+        .iter()
+        .fold(!0u32, |crc, b| CRC32C_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+fn unmask_crc(masked: u32) -> u32 {
+    let rot = masked.wrapping_sub(0xa282ead8);
+    rot.rotate_left(15)
+}
@@ -0,0 +1,211 @@
+//! Function library support for TF2 graphs.
+//!
+//! Function bodies use "node:output_arg:index" tensor names, which are translated to the
+//! "node:slot" convention of GraphDef. Calls (`PartitionedCall`, `StatefulPartitionedCall` and
+//! direct calls to library functions) are inlined, while `While` and `If` operators parse the
+//! functions they refer to as standalone graphs.
+
+use tract_hir::internal::*;
+
+use crate::tfpb::tensorflow::attr_value::Value;
+use crate::tfpb::tensorflow::{DataType, FunctionDef, GraphDef, NodeDef};
+
+pub type FunctionLibrary<'a> = HashMap<&'a str, &'a FunctionDef>;
+
+// guards against (unsupported) recursive functions
+const MAX_INLINING_DEPTH: usize = 64;
+
+pub fn library(graph: &GraphDef) -> FunctionLibrary {
+    graph
+        .library
+        .iter()
+        .flat_map(|lib| lib.function.iter())
+        .filter_map(|f| f.signature.as_ref().map(|s| (&*s.name, f)))
+        .collect()
+}
+
+pub fn get_function<'a>(library: &FunctionLibrary<'a>, name: &str) -> TractResult<&'a FunctionDef> {
+    library.get(name).copied().with_context(|| format!("Function {name} not found in library"))
+}
+
+// position of an output argument among the outputs of a (non-list) multiple output op
+fn output_arg_offset(library: &FunctionLibrary, op: &str, arg: &str) -> TractResult<usize> {
+    if let Some(f) = library.get(op) {
+        let outputs = &f.signature.as_ref().unwrap().output_arg;
+        return outputs
+            .iter()
+            .position(|o| o.name == arg)
+            .with_context(|| format!("Function {op} has no output named {arg}"));
+    }
+    let args: &[&str] = match op {
+        "FusedBatchNorm" | "FusedBatchNormV2" | "FusedBatchNormV3" => &[
+            "y",
+            "batch_mean",
+            "batch_variance",
+            "reserve_space_1",
+            "reserve_space_2",
+            "reserve_space_3",
+        ],
+        "Merge" => &["output", "value_index"],
+        "NonMaxSuppressionV4" => &["selected_indices", "valid_outputs"],
+        "NonMaxSuppressionV5" => &["selected_indices", "selected_scores", "valid_outputs"],
+        "Switch" => &["output_false", "output_true"],
+        "TopK" | "TopKV2" => &["values", "indices"],
+        "Unique" => &["y", "idx"],
+        "UniqueWithCounts" => &["y", "idx", "count"],
+        _ => return Ok(0),
+    };
+    args.iter().position(|a| *a == arg).with_context(|| format!("{op} has no output named {arg}"))
+}
+
+/// Nodes of a function body, with names prefixed by `prefix` and inputs translated to GraphDef
+/// conventions, and the names of the tensors it returns.
+///
+/// Function arguments are expected to be provided by nodes named `{prefix}{arg}`.
+fn instantiate(
+    library: &FunctionLibrary,
+    func: &FunctionDef,
+    prefix: &str,
+) -> TractResult<(Vec<NodeDef>, Vec<String>)> {
+    let signature = func.signature.as_ref().context("Function without signature")?;
+    let ops: HashMap<&str, &str> = func.node_def.iter().map(|n| (&*n.name, &*n.op)).collect();
+    let tensor = |name: &str| -> TractResult<String> {
+        if let Some(control) = name.strip_prefix('^') {
+            return Ok(format!("^{prefix}{control}"));
+        }
+        let parts: Vec<&str> = name.split(':').collect();
+        let slot = match (ops.get(parts[0]), &*parts) {
+            (_, [_]) => 0,
+            (None, [_, index]) => index.parse()?,
+            (Some(op), [_, arg]) => output_arg_offset(library, op, arg)?,
+            (Some(op), [_, arg, index]) => {
+                output_arg_offset(library, op, arg)? + index.parse::<usize>()?
+            }
+            _ => bail!("Unexpected tensor name {name} in function {}", signature.name),
+        };
+        Ok(if slot == 0 {
+            format!("{prefix}{}", parts[0])
+        } else {
+            format!("{prefix}{}:{slot}", parts[0])
+        })
+    };
+    let nodes = func
+        .node_def
+        .iter()
+        .map(|node| {
+            let mut node = node.clone();
+            node.name = format!("{prefix}{}", node.name);
+            node.input = node.input.iter().map(|i| tensor(i)).collect::<TractResult<_>>()?;
+            Ok(node)
+        })
+        .collect::<TractResult<_>>()?;
+    let outputs = signature
+        .output_arg
+        .iter()
+        .map(|arg| {
+            let ret = func.ret.get(&arg.name).with_context(|| {
+                format!("No value returned for {} in function {}", arg.name, signature.name)
+            })?;
+            tensor(ret)
+        })
+        .collect::<TractResult<_>>()?;
+    Ok((nodes, outputs))
+}
+
+/// Standalone GraphDef for a function body, its arguments becoming Placeholders.
+///
+/// Returns the graph, and the names of the input and output tensors.
+pub fn function_graph(
+    library: &FunctionLibrary,
+    func: &FunctionDef,
+) -> TractResult<(GraphDef, Vec<String>, Vec<String>)> {
+    let signature = func.signature.as_ref().context("Function without signature")?;
+    let mut node = vec![];
+    let mut inputs = vec![];
+    for arg in &signature.input_arg {
+        ensure!(
+            arg.number_attr.is_empty() && arg.type_list_attr.is_empty(),
+            "List argument {} of function {} is not supported",
+            arg.name,
+            signature.name
+        );
+        let mut placeholder = crate::tfpb::node().name(&arg.name).op("Placeholder");
+        // resources are only known once the variables are materialized as constants
+        if arg.r#type != DataType::DtResource as i32 {
+            let dt = DataType::from_i32(arg.r#type)
+                .filter(|dt| *dt != DataType::DtInvalid)
+                .with_context(|| {
+                    format!("Argument {} of function {} has no type", arg.name, signature.name)
+                })?;
+            placeholder = placeholder.attr("dtype", dt);
+        }
+        node.push(placeholder);
+        inputs.push(arg.name.clone());
+    }
+    let (body, outputs) = instantiate(library, func, "")?;
+    node.extend(inline_calls(library, body)?);
+    Ok((GraphDef { node, ..GraphDef::default() }, inputs, outputs))
+}
+
+pub(crate) fn called_function<'a>(
+    library: &FunctionLibrary<'a>,
+    node: &NodeDef,
+) -> Option<&'a str> {
+    if node.op == "PartitionedCall" || node.op == "StatefulPartitionedCall" {
+        match node.attr.get("f").and_then(|f| f.value.as_ref()) {
+            Some(Value::Func(f)) => {
+                library.get(&*f.name).map(|f| &*f.signature.as_ref().unwrap().name)
+            }
+            _ => None,
+        }
+    } else {
+        library.get(&*node.op).map(|f| &*f.signature.as_ref().unwrap().name)
+    }
+}
+
+/// Inline function calls.
+///
+/// The call node becomes an IdentityN over the function results, so references to its
+/// outputs stay valid.
+pub fn inline_calls(library: &FunctionLibrary, nodes: Vec<NodeDef>) -> TractResult<Vec<NodeDef>> {
+    let mut todo: Vec<(NodeDef, usize)> = nodes.into_iter().rev().map(|n| (n, 0)).collect();
+    let mut done = vec![];
+    while let Some((node, depth)) = todo.pop() {
+        let Some(name) = called_function(library, &node) else {
+            done.push(node);
+            continue;
+        };
+        ensure!(depth < MAX_INLINING_DEPTH, "Function calls nested too deep in {}", node.name);
+        let func = get_function(library, name)?;
+        let signature = func.signature.as_ref().unwrap();
+        let prefix = format!("{}/", node.name);
+        let (data_inputs, control_inputs): (Vec<&String>, Vec<&String>) =
+            node.input.iter().partition(|i| !i.starts_with('^'));
+        ensure!(
+            data_inputs.len() == signature.input_arg.len(),
+            "Call to {name} in {} has {} inputs, expected {}",
+            node.name,
+            data_inputs.len(),
+            signature.input_arg.len()
+        );
+        let (body, outputs) = instantiate(library, func, &prefix)?;
+        let mut identity_n = crate::tfpb::node().name(&node.name).op("IdentityN");
+        identity_n.input = outputs;
+        identity_n.input.extend(control_inputs.into_iter().cloned());
+        let mut inlined: Vec<NodeDef> = signature
+            .input_arg
+            .iter()
+            .zip(data_inputs)
+            .map(|(arg, input)| {
+                crate::tfpb::node()
+                    .name(format!("{prefix}{}", arg.name))
+                    .op("Identity")
+                    .input(input)
+            })
+            .collect();
+        inlined.extend(body);
+        done.push(identity_n);
+        todo.extend(inlined.into_iter().rev().map(|n| (n, depth + 1)));
+    }
+    Ok(done)
+}
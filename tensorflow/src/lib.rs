@@ -50,6 +50,8 @@ pub extern crate tract_hir;
 #[cfg(feature = "conform")]
 pub mod conform;
 
+pub mod bundle;
+pub mod functions;
 pub mod model;
 pub mod ops;
 pub mod tensor;
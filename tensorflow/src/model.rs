@@ -1,11 +1,42 @@
-use crate::tfpb::tensorflow::{GraphDef, NodeDef, SavedModel};
+use crate::bundle::TensorBundle;
+use crate::functions::{self, FunctionLibrary};
+use crate::tfpb::tensorflow::tensor_info::Encoding;
+use crate::tfpb::tensorflow::{GraphDef, NodeDef, SavedModel, SignatureDef, TensorInfo};
 use prost::Message;
 use std::{fs, path};
 use tract_hir::internal::*;
 
-#[derive(Default)]
-pub struct ParsingContext {
+pub struct ParsingContext<'a> {
     pub node_output_arities: HashMap<String, usize>,
+    pub framework: &'a Tensorflow,
+    pub library: &'a FunctionLibrary<'a>,
+}
+
+impl ParsingContext<'_> {
+    /// Parse a function of the library as a standalone model.
+    pub fn parse_function(&self, name: &str) -> TractResult<InferenceModel> {
+        let func = functions::get_function(self.library, name)?;
+        let (graph, inputs, outputs) = functions::function_graph(self.library, func)?;
+        let mut model = self
+            .framework
+            .parse_graph_in_library(&graph, InferenceModel::default(), self.library)
+            .with_context(|| format!("Parsing function {name}"))?
+            .0;
+        let inputs: TVec<OutletId> = inputs
+            .iter()
+            .map(|i| Ok(OutletId::new(model.node_id_by_name(i)?, 0)))
+            .collect::<TractResult<_>>()?;
+        let outputs: TVec<OutletId> = outputs
+            .iter()
+            .map(|o| {
+                let (node, slot) = Tensorflow::parse_input(o)?;
+                Ok(OutletId::new(model.node_id_by_name(node)?, slot))
+            })
+            .collect::<TractResult<_>>()?;
+        model.set_input_outlets(&inputs)?;
+        model.set_output_outlets(&outputs)?;
+        model.into_compact()
+    }
 }
 
 type OpBuilder = fn(&ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>>;
@@ -122,6 +153,121 @@ impl Tensorflow {
         Ok(saved.meta_graphs.remove(0).graph_def.unwrap())
     }
 
+    /// Load a SavedModel directory: `saved_model.pb` and the `variables/` checkpoint.
+    ///
+    /// Variables are restored from the checkpoint as constants. If a `signature` is given,
+    /// the model inputs and outputs are the ones of this SignatureDef, named after its keys.
+    pub fn saved_model_for_path(
+        &self,
+        dir: impl AsRef<path::Path>,
+        signature: Option<&str>,
+    ) -> TractResult<InferenceModel> {
+        let dir = dir.as_ref();
+        let mut saved = self.open_saved_model(&mut fs::File::open(dir.join("saved_model.pb"))?)?;
+        let meta_graph = if let Some(key) = signature {
+            let ix = saved
+                .meta_graphs
+                .iter()
+                .position(|mg| mg.signature_def.contains_key(key))
+                .with_context(|| {
+                    let mut keys: Vec<&String> =
+                        saved.meta_graphs.iter().flat_map(|mg| mg.signature_def.keys()).collect();
+                    keys.sort();
+                    format!("No signature named {key:?} in SavedModel (found: {keys:?})")
+                })?;
+            saved.meta_graphs.remove(ix)
+        } else {
+            saved.meta_graphs.into_iter().next().context("No meta graph in SavedModel")?
+        };
+        let graph = meta_graph.graph_def.context("No graph in SavedModel meta graph")?;
+        let mut model = self.parse_graph(&graph)?.0;
+        let variables = dir.join("variables").join("variables");
+        if dir.join("variables").join("variables.index").exists() {
+            let bundle = TensorBundle::open(&variables)?;
+            Self::restore_variables(&mut model, &graph, &bundle)?;
+        }
+        if let Some(key) = signature {
+            Self::apply_signature(&mut model, &meta_graph.signature_def[key])?;
+        }
+        Ok(model)
+    }
+
+    /// Replace variables with constants holding their values from the checkpoint.
+    ///
+    /// Variables are looked up by name and shared name, directly (name-based checkpoints)
+    /// or through the checkpoint object graph (object-based checkpoints).
+    pub fn restore_variables(
+        model: &mut InferenceModel,
+        graph: &GraphDef,
+        bundle: &TensorBundle,
+    ) -> TractResult<()> {
+        let mut object_keys = HashMap::new();
+        for object in bundle.object_graph()?.into_iter().flat_map(|g| g.nodes) {
+            for attr in object.attributes {
+                object_keys.insert(attr.full_name, attr.checkpoint_key);
+            }
+        }
+        for pbnode in &graph.node {
+            if pbnode.op != "VariableV2" && pbnode.op != "VarHandleOp" {
+                continue;
+            }
+            let mut names = vec![pbnode.name.clone()];
+            names.extend(pbnode.get_attr_opt_str("shared_name")?.filter(|s| !s.is_empty()));
+            let key = names
+                .iter()
+                .flat_map(|name| [Some(name), object_keys.get(name)])
+                .flatten()
+                .find(|key| bundle.contains(key));
+            if let Some(key) = key {
+                let value = bundle
+                    .tensor(key)
+                    .with_context(|| format!("Restoring variable {}", pbnode.name))?;
+                model.node_by_name_mut(&pbnode.name)?.op =
+                    Box::new(tract_hir::ops::konst::Const::new(value.into_arc_tensor()));
+            } else {
+                warn!("No value found in checkpoint for variable {}", pbnode.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Use the inputs and outputs of a SignatureDef as model interface, and prune the rest.
+    pub fn apply_signature(
+        model: &mut InferenceModel,
+        signature: &SignatureDef,
+    ) -> TractResult<()> {
+        fn sorted(map: &HashMap<String, TensorInfo>) -> Vec<(&String, &TensorInfo)> {
+            let mut items: Vec<_> = map.iter().collect();
+            items.sort_by_key(|(k, _)| *k);
+            items
+        }
+        let outlet = |model: &InferenceModel, info: &TensorInfo| -> TractResult<OutletId> {
+            let Some(Encoding::Name(name)) = &info.encoding else {
+                bail!("Only dense tensors are supported in signatures, got {info:?}")
+            };
+            let (node, slot) = Self::parse_input(name)?;
+            Ok(OutletId::new(model.node_id_by_name(node)?, slot))
+        };
+        let mut inputs = tvec!();
+        for (key, info) in sorted(&signature.inputs) {
+            let input = outlet(model, info)?;
+            if model.node_id_by_name(key).is_err() {
+                model.rename_node(input.node, key)?;
+            }
+            inputs.push(input);
+        }
+        let mut outputs = tvec!();
+        for (key, info) in sorted(&signature.outputs) {
+            let output = outlet(model, info)?;
+            model.set_outlet_label(output, key.to_string())?;
+            outputs.push(output);
+        }
+        model.set_input_outlets(&inputs)?;
+        model.set_output_outlets(&outputs)?;
+        *model = std::mem::take(model).into_compact()?;
+        Ok(())
+    }
+
     pub fn parse_graph(&self, graph: &GraphDef) -> TractResult<TfModelAndExtensions> {
         self.parse_graph_with_template(graph, Default::default())
     }
@@ -129,12 +275,33 @@ impl Tensorflow {
     pub fn parse_graph_with_template(
         &self,
         graph: &GraphDef,
-        mut model: InferenceModel
+        model: InferenceModel,
+    ) -> TractResult<TfModelAndExtensions> {
+        self.parse_graph_in_library(graph, model, &functions::library(graph))
+    }
+
+    fn parse_graph_in_library(
+        &self,
+        graph: &GraphDef,
+        mut model: InferenceModel,
+        library: &FunctionLibrary,
     ) -> TractResult<TfModelAndExtensions> {
         use crate::ops::control_flow as cf;
 
+        let inlined;
+        let graph = if graph.node.iter().any(|n| functions::called_function(library, n).is_some()) {
+            inlined = GraphDef {
+                node: functions::inline_calls(library, graph.node.clone())?,
+                ..GraphDef::default()
+            };
+            &inlined
+        } else {
+            graph
+        };
+
         let mut inputs = tvec!();
-        let mut context = ParsingContext::default();
+        let mut context =
+            ParsingContext { node_output_arities: HashMap::default(), framework: self, library };
         let mut control_inputs = vec![];
 
         // compute min output arity for all nodes
@@ -174,8 +341,12 @@ impl Tensorflow {
 
             let node_id = model.add_node(name.clone(), op, facts)?;
             if pbnode.op == "Placeholder" {
-                let dt = pbnode.get_attr_datum_type("dtype")?;
-                let mut fact = InferenceFact::dt(dt);
+                // function arguments holding resources have no dtype
+                let mut fact = if pbnode.attr.contains_key("dtype") {
+                    InferenceFact::dt(pbnode.get_attr_datum_type("dtype")?)
+                } else {
+                    InferenceFact::default()
+                };
                 if let Some(shape) = pbnode.get_attr_opt_shape("shape")? {
                     let shape_factoid = ShapeFactoid::closed(
                         shape
@@ -261,10 +432,10 @@ impl Framework<GraphDef, InferenceModel> for Tensorflow {
     }
 
     fn model_for_proto_model_with_model_template(
-            &self,
-            proto: &GraphDef,
-            template: InferenceModel,
-        ) -> TractResult<InferenceModel> {
+        &self,
+        proto: &GraphDef,
+        template: InferenceModel,
+    ) -> TractResult<InferenceModel> {
         Ok(self.parse_graph_with_template(proto, template)?.0)
     }
 }
@@ -0,0 +1,95 @@
+use tract_hir::internal::*;
+use tract_ndarray::Dimension;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn where_(_ctx: &ParsingContext, _pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(expand(Where))
+}
+
+/// Single input Where: coordinates of the true (or non-zero) elements, in row-major order.
+#[derive(Debug, Clone, Hash)]
+pub struct Where;
+
+impl Expansion for Where {
+    fn name(&self) -> Cow<str> {
+        "Where".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&outputs[0].datum_type, i64::datum_type())?;
+        s.equals(&outputs[0].rank, 2)?;
+        s.equals(&outputs[0].shape[1], inputs[0].rank.bex().to_dim())?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let count = target.symbols.new_with_prefix("where");
+        target.wire_node(prefix, NonZero(count), inputs)
+    }
+}
+
+/// Coordinates of the non-zero elements of the input, one row per element.
+#[derive(Debug, Clone, Hash)]
+pub struct NonZero(pub Symbol);
+
+impl NonZero {
+    unsafe fn eval_t<T: Datum + tract_num_traits::Zero>(input: &Tensor) -> TractResult<Tensor> {
+        let mut coords = vec![];
+        let mut count = 0;
+        for (coord, value) in input.to_array_view_unchecked::<T>().indexed_iter() {
+            if !value.is_zero() {
+                coords.extend(coord.slice().iter().map(|c| *c as i64));
+                count += 1;
+            }
+        }
+        Ok(tract_ndarray::Array2::from_shape_vec((count, input.rank()), coords)?.into_tensor())
+    }
+}
+
+impl Op for NonZero {
+    fn name(&self) -> Cow<str> {
+        "NonZero".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for NonZero {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let input = args_1!(inputs);
+        let output = unsafe {
+            if input.datum_type() == bool::datum_type() {
+                Self::eval_t::<u8>(&input)?
+            } else {
+                dispatch_numbers!(Self::eval_t(input.datum_type())(&input))?
+            }
+        };
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl TypedOp for NonZero {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        Ok(tvec!(i64::fact(dims![self.0.clone(), inputs[0].rank()])))
+    }
+
+    as_op!();
+}
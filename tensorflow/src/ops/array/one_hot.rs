@@ -0,0 +1,88 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::change_axes::wire_with_rank_broadcast;
+use tract_hir::tract_core::ops::logic::{Comp, Iff};
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn one_hot(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let axis = pb.get_attr_opt_int("axis")?.unwrap_or(-1);
+    Ok(expand(OneHot::new(axis)))
+}
+
+/// Inputs are indices, depth, on_value and off_value.
+#[derive(Debug, Clone, new, Hash)]
+pub struct OneHot {
+    axis: i64,
+}
+
+impl Expansion for OneHot {
+    fn name(&self) -> Cow<str> {
+        "OneHot".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 4)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[2].rank, 0)?;
+        s.equals(&inputs[3].rank, 0)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[3].datum_type, &outputs[0].datum_type)?;
+        s.equals(inputs[0].rank.bex() + 1, &outputs[0].rank)?;
+        s.given(&inputs[0].rank, move |s, irank| {
+            let axis = if self.axis < 0 { self.axis + irank + 1 } else { self.axis } as usize;
+            for ix in 0..axis {
+                s.equals(&inputs[0].shape[ix], &outputs[0].shape[ix])?;
+            }
+            for ix in axis + 1..irank as usize + 1 {
+                s.equals(&inputs[0].shape[ix - 1], &outputs[0].shape[ix])?;
+            }
+            s.given(&inputs[1].value, move |s, depth| {
+                let depth = depth.cast_to_scalar::<i64>()?;
+                s.equals(&outputs[0].shape[axis], depth.to_dim())
+            })
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let (Some(depth), Some(on), Some(off)) = (
+            target.outlet_fact(inputs[1])?.konst.clone(),
+            target.outlet_fact(inputs[2])?.konst.clone(),
+            target.outlet_fact(inputs[3])?.konst.clone(),
+        ) else {
+            bail!("Need depth, on_value and off_value to be const")
+        };
+        let depth = depth.cast_to_scalar::<i64>()?;
+        ensure!(depth >= 0, "Expected positive depth, got {depth}");
+        let rank = target.outlet_fact(inputs[0])?.rank();
+        let axis = if self.axis < 0 { self.axis + rank as i64 + 1 } else { self.axis } as usize;
+        // tensorflow fills with off_value for indices out of [0, depth), unlike the core OneHot
+        // which wraps negative indices around, so compare the indices to a range instead
+        let indices = target.wire_node(
+            format!("{prefix}.indices"),
+            tract_core::ops::cast::cast(i64::datum_type()),
+            &inputs[0..1],
+        )?;
+        let indices =
+            target.wire_node(format!("{prefix}.add_axis"), AxisOp::Add(axis), &indices)?;
+        let mut range_shape = tvec![1; rank + 1];
+        range_shape[axis] = depth as usize;
+        let range = tract_ndarray::ArrayD::from_shape_vec(&*range_shape, (0..depth).collect())?;
+        let range = target.add_const(format!("{prefix}.range"), range)?;
+        let hot = target.wire_node(format!("{prefix}.eq"), Comp::Eq, &[indices[0], range])?;
+        let on = target.add_const(format!("{prefix}.on"), on)?;
+        let off = target.add_const(format!("{prefix}.off"), off)?;
+        wire_with_rank_broadcast(prefix, target, Iff, &[hot[0], on, off])
+    }
+}
@@ -0,0 +1,166 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn split(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num_split = pb.get_attr_int("num_split")?;
+    Ok(expand(Split::new(num_split)))
+}
+
+pub fn split_v(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num_split = pb.get_attr_int("num_split")?;
+    Ok(expand(SplitV::new(num_split)))
+}
+
+fn axis(axis: &Tensor, rank: usize) -> TractResult<usize> {
+    let axis = axis.cast_to_scalar::<i64>()?;
+    let axis = if axis < 0 { axis + rank as i64 } else { axis };
+    ensure!(axis >= 0 && (axis as usize) < rank, "Invalid split axis {axis} for rank {rank}");
+    Ok(axis as usize)
+}
+
+/// Split in equal parts: axis, then value.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Split {
+    num_split: usize,
+}
+
+impl Expansion for Split {
+    fn name(&self) -> Cow<str> {
+        "Split".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, self.num_split)?;
+        s.equals(&inputs[0].rank, 0)?;
+        for output in outputs {
+            s.equals(&inputs[1].datum_type, &output.datum_type)?;
+            s.equals(&inputs[1].rank, &output.rank)?;
+        }
+        s.given_2(&inputs[1].shape, &inputs[0].value, move |s, shape, ax| {
+            let ax = axis(&ax, shape.len())?;
+            let mut shape = shape.clone();
+            shape[ax] = shape[ax].clone() / self.num_split as u64;
+            for output in outputs {
+                s.equals(&output.shape, shape.clone())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num_split)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let ax = target.outlet_fact(inputs[0])?.konst.clone().context("Need axis to be const")?;
+        let ax = axis(&ax, target.outlet_fact(inputs[1])?.rank())?;
+        tract_hir::ops::array::Split::new(ax as isize, self.num_split, None).wire(
+            prefix,
+            target,
+            &inputs[1..2],
+        )
+    }
+}
+
+/// Split in parts of given sizes: value, sizes (one of which may be -1), then axis.
+#[derive(Debug, Clone, new, Hash)]
+pub struct SplitV {
+    num_split: usize,
+}
+
+impl SplitV {
+    fn sizes(&self, dim: &TDim, sizes: &Tensor) -> TractResult<TVec<TDim>> {
+        let sizes = sizes.cast_to::<i64>()?;
+        let sizes = sizes.as_slice::<i64>()?;
+        ensure!(sizes.len() == self.num_split, "SplitV expects {} sizes", self.num_split);
+        let known: i64 = sizes.iter().filter(|s| **s >= 0).sum();
+        sizes
+            .iter()
+            .map(|&s| match s {
+                -1 => Ok(dim.clone() - known),
+                s if s >= 0 => Ok(s.to_dim()),
+                s => bail!("Invalid split size {s}"),
+            })
+            .collect()
+    }
+}
+
+impl Expansion for SplitV {
+    fn name(&self) -> Cow<str> {
+        "SplitV".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 3)?;
+        check_output_arity(outputs, self.num_split)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[2].rank, 0)?;
+        for output in outputs {
+            s.equals(&inputs[0].datum_type, &output.datum_type)?;
+            s.equals(&inputs[0].rank, &output.rank)?;
+        }
+        s.given_3(
+            &inputs[0].shape,
+            &inputs[1].value,
+            &inputs[2].value,
+            move |s, shape, sizes, ax| {
+                let ax = axis(&ax, shape.len())?;
+                let sizes = self.sizes(&shape[ax], &sizes)?;
+                for (output, size) in outputs.iter().zip(sizes) {
+                    let mut shape = shape.clone();
+                    shape[ax] = size;
+                    s.equals(&output.shape, shape)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num_split)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let (Some(sizes), Some(ax)) = (
+            target.outlet_fact(inputs[1])?.konst.clone(),
+            target.outlet_fact(inputs[2])?.konst.clone(),
+        ) else {
+            bail!("Need sizes and axis to be const")
+        };
+        let input = target.outlet_fact(inputs[0])?.clone();
+        let ax = axis(&ax, input.rank())?;
+        let sizes = self
+            .sizes(&input.shape[ax], &sizes)?
+            .iter()
+            .map(|d| d.to_usize())
+            .collect::<TractResult<Vec<usize>>>()?;
+        tract_hir::ops::array::Split::new(ax as isize, self.num_split, Some(sizes)).wire(
+            prefix,
+            target,
+            &inputs[0..1],
+        )
+    }
+}
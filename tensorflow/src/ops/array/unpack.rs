@@ -0,0 +1,79 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn unpack(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let num = pb.get_attr_int("num")?;
+    let axis = pb.get_attr_opt_int("axis")?.unwrap_or(0);
+    Ok(expand(Unpack::new(num, axis)))
+}
+
+#[derive(Debug, Clone, new, Hash)]
+pub struct Unpack {
+    num: usize,
+    axis: i64,
+}
+
+impl Unpack {
+    fn axis(&self, rank: usize) -> usize {
+        if self.axis < 0 {
+            (self.axis + rank as i64) as usize
+        } else {
+            self.axis as usize
+        }
+    }
+}
+
+impl Expansion for Unpack {
+    fn name(&self) -> Cow<str> {
+        "Unpack".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 1)?;
+        check_output_arity(outputs, self.num)?;
+        for output in outputs {
+            s.equals(&inputs[0].datum_type, &output.datum_type)?;
+            s.equals(inputs[0].rank.bex() - 1, &output.rank)?;
+        }
+        s.given(&inputs[0].shape, move |s, shape| {
+            let axis = self.axis(shape.len());
+            s.equals(&shape[axis], self.num.to_dim())?;
+            let mut shape = shape.clone();
+            shape.remove(axis);
+            for output in outputs {
+                s.equals(&output.shape, shape.clone())?;
+            }
+            Ok(())
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.num)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis = self.axis(target.outlet_fact(inputs[0])?.rank());
+        (0..self.num)
+            .map(|i| {
+                let wire = target.wire_node(
+                    format!("{prefix}.slice-{i}"),
+                    tract_core::ops::array::Slice::new(axis, i, i + 1),
+                    inputs,
+                )?;
+                Ok(target.wire_node(format!("{prefix}.rm-{i}"), AxisOp::Rm(axis), &wire)?[0])
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,313 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::logic::IfThenElse;
+use tract_hir::tract_core::ops::scan::wire_while;
+
+use crate::model::{ParsingContext, TfOpRegister};
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("IdentityN", identity_n);
+    reg.insert("If", if_);
+    reg.insert("StatelessIf", if_);
+    reg.insert("StatelessWhile", while_);
+    reg.insert("While", while_);
+}
+
+fn identity_n(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    Ok(Box::new(IdentityN(node.input.iter().filter(|i| !i.starts_with('^')).count())))
+}
+
+fn if_(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let then_body = ctx.parse_function(node.get_attr_func_name("then_branch")?)?;
+    let else_body = ctx.parse_function(node.get_attr_func_name("else_branch")?)?;
+    Ok(Box::new(If { then_body, else_body }))
+}
+
+fn while_(ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let cond = ctx.parse_function(node.get_attr_func_name("cond")?)?;
+    let body = ctx.parse_function(node.get_attr_func_name("body")?)?;
+    Ok(Box::new(While { cond, body }))
+}
+
+/// Typed version of a function body, given the facts of its inputs.
+fn typed_body(body: &InferenceModel, facts: &[TypedFact]) -> TractResult<TypedModel> {
+    let mut body = body.clone();
+    for (ix, fact) in facts.iter().enumerate() {
+        body.set_input_fact(ix, fact.without_value().into())?;
+    }
+    body.into_typed()
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct IdentityN(pub usize);
+
+impl Op for IdentityN {
+    fn name(&self) -> Cow<str> {
+        "IdentityN".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl EvalOp for IdentityN {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        Ok(inputs)
+    }
+}
+
+impl InferenceRulesOp for IdentityN {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, self.0)?;
+        check_output_arity(outputs, self.0)?;
+        for (input, output) in inputs.iter().zip(outputs.iter()) {
+            s.equals(&input.datum_type, &output.datum_type)?;
+            s.equals(&input.shape, &output.shape)?;
+        }
+        Ok(())
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.0)
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        _target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        Ok(node.inputs.iter().map(|i| mapping[i]).collect())
+    }
+
+    as_op!();
+}
+
+/// If and StatelessIf: the condition, then the inputs of both branches.
+#[derive(Debug, Clone)]
+pub struct If {
+    pub then_body: InferenceModel,
+    pub else_body: InferenceModel,
+}
+
+impl Op for If {
+    fn name(&self) -> Cow<str> {
+        "If".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl EvalOp for If {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let cond = inputs.remove(0);
+        let body = if cond_value(&cond)? { &self.then_body } else { &self.else_body };
+        body.clone().into_runnable()?.run(inputs)
+    }
+}
+
+// non scalar conditions are true if they are not empty
+fn cond_value(cond: &Tensor) -> TractResult<bool> {
+    if cond.rank() == 0 {
+        cond.cast_to_scalar::<bool>()
+    } else {
+        Ok(cond.len() > 0)
+    }
+}
+
+impl InferenceOp for If {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for body in [&mut self.then_body, &mut self.else_body] {
+                for (ix, input) in inputs[1..].iter_mut().enumerate() {
+                    changed |= body.input_fact_mut(ix)?.unify_with_mut(input)?;
+                }
+                for (ix, output) in outputs.iter_mut().enumerate() {
+                    let fact = body.output_fact_mut(ix)?;
+                    changed |= fact.datum_type.unify_with_mut(&mut output.datum_type)?;
+                    changed |= fact.shape.unify_with_mut(&mut output.shape)?;
+                }
+                changed |= body.analyse(false)?;
+            }
+            if !changed {
+                return Ok((inputs, outputs, observed.into_iter().cloned().collect()));
+            }
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        let then_outputs = self.then_body.outputs.len();
+        ensure!(
+            then_outputs == self.else_body.outputs.len(),
+            "If branches must produce the same number of outputs"
+        );
+        Ok(then_outputs)
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let mut inputs: TVec<OutletId> = node.inputs.iter().map(|o| mapping[o]).collect();
+        let facts = inputs[1..]
+            .iter()
+            .map(|i| target.outlet_fact(*i).cloned())
+            .collect::<TractResult<TVec<_>>>()?;
+        let cond_fact = target.outlet_fact(inputs[0])?.clone();
+        if cond_fact.rank() > 0 {
+            let not_empty = cond_fact.shape.volume().to_i64()? > 0;
+            inputs[0] = target.add_const(format!("{}.cond", node.name), tensor0(not_empty))?;
+        } else if cond_fact.datum_type != bool::datum_type() {
+            inputs[0] = target.wire_node(
+                format!("{}.cond", node.name),
+                tract_hir::ops::cast::cast(bool::datum_type()),
+                &inputs[0..1],
+            )?[0];
+        }
+        let op = IfThenElse {
+            then_body: typed_body(&self.then_body, &facts)?,
+            then_input_mapping: (1..inputs.len()).collect(),
+            else_body: typed_body(&self.else_body, &facts)?,
+            else_input_mapping: (1..inputs.len()).collect(),
+        };
+        target.wire_node(&node.name, op, &inputs)
+    }
+
+    as_op!();
+}
+
+/// While and StatelessWhile: loop variables are updated by the body while the condition holds.
+///
+/// Loops with a number of iterations known at load time are translated to a typed Scan, others
+/// to a While evaluating the condition at runtime.
+#[derive(Debug, Clone)]
+pub struct While {
+    pub cond: InferenceModel,
+    pub body: InferenceModel,
+}
+
+impl Op for While {
+    fn name(&self) -> Cow<str> {
+        "While".into()
+    }
+
+    not_a_typed_op!();
+}
+
+impl EvalOp for While {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, mut inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let cond = self.cond.clone().into_runnable()?;
+        let body = self.body.clone().into_runnable()?;
+        while cond_value(&cond.run(inputs.clone())?[0])? {
+            inputs = body.run(inputs)?;
+        }
+        Ok(inputs)
+    }
+}
+
+impl InferenceOp for While {
+    fn infer_facts(
+        &mut self,
+        inputs: TVec<&InferenceFact>,
+        outputs: TVec<&InferenceFact>,
+        observed: TVec<&InferenceFact>,
+    ) -> TractResult<(TVec<InferenceFact>, TVec<InferenceFact>, TVec<InferenceFact>)> {
+        let mut inputs: TVec<InferenceFact> = inputs.into_iter().cloned().collect();
+        let mut outputs: TVec<InferenceFact> = outputs.into_iter().cloned().collect();
+        loop {
+            let mut changed = false;
+            for (ix, (input, output)) in inputs.iter_mut().zip(outputs.iter_mut()).enumerate() {
+                // loop variables must keep the same type and shape across iterations
+                changed |= input.datum_type.unify_with_mut(&mut output.datum_type)?;
+                changed |= input.shape.unify_with_mut(&mut output.shape)?;
+                let mut fact = InferenceFact {
+                    datum_type: input.datum_type,
+                    shape: input.shape.clone(),
+                    ..InferenceFact::default()
+                };
+                changed |= self.cond.input_fact_mut(ix)?.unify_with_mut(&mut fact)?;
+                changed |= self.body.input_fact_mut(ix)?.unify_with_mut(&mut fact)?;
+                let body_output = self.body.output_fact_mut(ix)?;
+                changed |= body_output.datum_type.unify_with_mut(&mut fact.datum_type)?;
+                changed |= body_output.shape.unify_with_mut(&mut fact.shape)?;
+                changed |= input.datum_type.unify_with_mut(&mut fact.datum_type)?;
+                changed |= input.shape.unify_with_mut(&mut fact.shape)?;
+            }
+            changed |= self.cond.analyse(false)?;
+            changed |= self.body.analyse(false)?;
+            if !changed {
+                return Ok((inputs, outputs, observed.into_iter().cloned().collect()));
+            }
+        }
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(self.body.outputs.len())
+    }
+
+    fn to_typed(
+        &self,
+        _source: &InferenceModel,
+        node: &InferenceNode,
+        target: &mut TypedModel,
+        mapping: &HashMap<OutletId, OutletId>,
+    ) -> TractResult<TVec<OutletId>> {
+        let inputs: TVec<OutletId> = node.inputs.iter().map(|o| mapping[o]).collect();
+        let facts = inputs
+            .iter()
+            .map(|i| target.outlet_fact(*i).cloned())
+            .collect::<TractResult<TVec<_>>>()?;
+        let cond = bool_cond(typed_body(&self.cond, &facts)?)?;
+        let body = typed_body(&self.body, &facts)?;
+        wire_while(target, &node.name, cond, body, &inputs)
+    }
+
+    as_op!();
+}
+
+/// Loop condition computing a single boolean: non scalar conditions are true if they are not
+/// empty.
+fn bool_cond(mut cond: TypedModel) -> TractResult<TypedModel> {
+    let output = cond.outputs[0];
+    let fact = cond.outlet_fact(output)?.clone();
+    let wire = if fact.rank() > 0 {
+        let not_empty = fact.shape.volume().to_i64()? > 0;
+        let name = cond.unique_name("cond.not_empty");
+        cond.add_const(name, tensor0(not_empty))?
+    } else if fact.datum_type != bool::datum_type() {
+        let name = cond.unique_name("cond.as_bool");
+        cond.wire_node(name, tract_hir::ops::cast::cast(bool::datum_type()), &[output])?[0]
+    } else {
+        return Ok(cond);
+    };
+    cond.set_output_outlets(&[wire])?;
+    Ok(cond)
+}
@@ -0,0 +1,189 @@
+use tract_hir::internal::*;
+use tract_ndarray::{s, ArrayView4, ArrayViewMut4};
+
+use crate::model::ParsingContext;
+use crate::model::TfOpRegister;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn register_all_ops(reg: &mut TfOpRegister) {
+    reg.insert("ResizeBilinear", |ctx, pb| resize(ctx, pb, Interpolation::Bilinear));
+    reg.insert("ResizeNearestNeighbor", |ctx, pb| resize(ctx, pb, Interpolation::Nearest));
+}
+
+fn resize(
+    _ctx: &ParsingContext,
+    pb: &NodeDef,
+    interpolation: Interpolation,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let align_corners = pb.get_attr_opt_bool("align_corners")?.unwrap_or(false);
+    let half_pixel_centers = pb.get_attr_opt_bool("half_pixel_centers")?.unwrap_or(false);
+    Ok(Box::new(Resize { interpolation, align_corners, half_pixel_centers }))
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Interpolation {
+    Bilinear,
+    Nearest,
+}
+
+/// ResizeBilinear and ResizeNearestNeighbor: NHWC images, and the (const) output height and
+/// width.
+///
+/// Bilinear interpolation always produces f32.
+#[derive(Clone, Debug, Hash)]
+pub struct Resize {
+    pub interpolation: Interpolation,
+    pub align_corners: bool,
+    pub half_pixel_centers: bool,
+}
+
+impl Resize {
+    fn output_dt(&self, input: DatumType) -> DatumType {
+        match self.interpolation {
+            Interpolation::Bilinear => f32::datum_type(),
+            Interpolation::Nearest => input,
+        }
+    }
+
+    fn scale(&self, input: usize, output: usize) -> f32 {
+        if self.align_corners && output > 1 {
+            (input - 1) as f32 / (output - 1) as f32
+        } else {
+            input as f32 / output as f32
+        }
+    }
+
+    // input coordinates and interpolation weight of the upper one
+    fn bilinear_coords(&self, x: usize, scale: f32, len: usize) -> (usize, usize, f32) {
+        let x =
+            if self.half_pixel_centers { (x as f32 + 0.5) * scale - 0.5 } else { x as f32 * scale };
+        let lower = x.floor();
+        let upper = (x.ceil() as usize).min(len - 1);
+        (lower.max(0.0) as usize, upper, x - lower)
+    }
+
+    fn nearest_coord(&self, x: usize, scale: f32, len: usize) -> usize {
+        let x = if self.half_pixel_centers { (x as f32 + 0.5) * scale } else { x as f32 * scale };
+        let x = if self.align_corners { x.round() } else { x.floor() };
+        (x.max(0.0) as usize).min(len - 1)
+    }
+
+    fn eval_bilinear(&self, input: ArrayView4<f32>, mut output: ArrayViewMut4<f32>) {
+        let (_, ih, iw, _) = input.dim();
+        let (_, oh, ow, _) = output.dim();
+        let (scale_y, scale_x) = (self.scale(ih, oh), self.scale(iw, ow));
+        for y in 0..oh {
+            let (top, bottom, dy) = self.bilinear_coords(y, scale_y, ih);
+            for x in 0..ow {
+                let (left, right, dx) = self.bilinear_coords(x, scale_x, iw);
+                let mut pixel = output.slice_mut(s![.., y, x, ..]);
+                let top_left = input.slice(s![.., top, left, ..]);
+                let top_right = input.slice(s![.., top, right, ..]);
+                let bottom_left = input.slice(s![.., bottom, left, ..]);
+                let bottom_right = input.slice(s![.., bottom, right, ..]);
+                let top = &top_left + &((&top_right - &top_left) * dx);
+                let bottom = &bottom_left + &((&bottom_right - &bottom_left) * dx);
+                pixel.assign(&(&top + &((&bottom - &top) * dy)));
+            }
+        }
+    }
+
+    fn eval_nearest<T: Datum>(&self, input: &Tensor, output: &mut Tensor) -> TractResult<()> {
+        let input = input.to_array_view::<T>()?.into_dimensionality::<tract_ndarray::Ix4>()?;
+        let mut output =
+            output.to_array_view_mut::<T>()?.into_dimensionality::<tract_ndarray::Ix4>()?;
+        let (_, ih, iw, _) = input.dim();
+        let (_, oh, ow, _) = output.dim();
+        let (scale_y, scale_x) = (self.scale(ih, oh), self.scale(iw, ow));
+        for y in 0..oh {
+            let iy = self.nearest_coord(y, scale_y, ih);
+            for x in 0..ow {
+                let ix = self.nearest_coord(x, scale_x, iw);
+                output.slice_mut(s![.., y, x, ..]).assign(&input.slice(s![.., iy, ix, ..]));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Op for Resize {
+    fn name(&self) -> Cow<str> {
+        match self.interpolation {
+            Interpolation::Bilinear => "ResizeBilinear".into(),
+            Interpolation::Nearest => "ResizeNearestNeighbor".into(),
+        }
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for Resize {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let (input, size) = args_2!(inputs);
+        let size = size.cast_to::<i64>()?;
+        let size = size.as_slice::<i64>()?;
+        let shape = [input.shape()[0], size[0] as usize, size[1] as usize, input.shape()[3]];
+        let output = match self.interpolation {
+            Interpolation::Bilinear => {
+                let input = input.cast_to::<f32>()?;
+                let input = input.to_array_view::<f32>()?.into_dimensionality()?;
+                let mut output = tract_ndarray::Array4::<f32>::zeros(shape);
+                self.eval_bilinear(input, output.view_mut());
+                output.into_tensor()
+            }
+            Interpolation::Nearest => {
+                let mut output = unsafe { Tensor::uninitialized_dt(input.datum_type(), &shape)? };
+                dispatch_datum!(Self::eval_nearest(input.datum_type())(self, &input, &mut output))?;
+                output
+            }
+        };
+        Ok(tvec!(output.into_tvalue()))
+    }
+}
+
+impl InferenceRulesOp for Resize {
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 1)?;
+        s.given(&inputs[0].datum_type, move |s, dt| {
+            s.equals(&outputs[0].datum_type, self.output_dt(dt))
+        })?;
+        s.equals(&inputs[0].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&inputs[1].rank, 1)?;
+        s.equals(&inputs[1].shape[0], 2.to_dim())?;
+        s.equals(&inputs[0].shape[0], &outputs[0].shape[0])?;
+        s.equals(&inputs[0].shape[3], &outputs[0].shape[3])?;
+        s.given(&inputs[1].value, move |s, size| {
+            let size = size.cast_to::<TDim>()?;
+            let size = size.as_slice::<TDim>()?;
+            s.equals(&outputs[0].shape[1], size[0].clone())?;
+            s.equals(&outputs[0].shape[2], size[1].clone())
+        })
+    }
+
+    as_op!();
+    to_typed!();
+}
+
+impl TypedOp for Resize {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        let size = inputs[1].konst.as_ref().context("Need output size to be const")?;
+        let size = size.cast_to::<TDim>()?;
+        let size = size.as_slice::<TDim>()?;
+        let shape = &inputs[0].shape;
+        let dt = self.output_dt(inputs[0].datum_type);
+        Ok(tvec!(dt.fact([shape[0].clone(), size[0].clone(), size[1].clone(), shape[3].clone()])))
+    }
+
+    as_op!();
+}
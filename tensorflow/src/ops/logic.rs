@@ -16,9 +16,58 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("LogicalAnd", |_, _| Ok(ops::logic::And.into_hir()));
     reg.insert("LogicalOr", |_, _| Ok(ops::logic::Or.into_hir()));
     reg.insert("Merge", merge);
+    reg.insert("Select", |_, _| Ok(expand(Select)));
+    reg.insert("SelectV2", |_, _| Ok(expand(ops::logic::Iff)));
     reg.insert("Switch", |_, _| Ok(Box::new(Switch)));
 }
 
+/// Select (v1) semantics: a rank 1 condition picks rows of the higher rank branches.
+#[derive(Debug, Clone, Hash)]
+pub struct Select;
+
+impl Expansion for Select {
+    fn name(&self) -> Cow<str> {
+        "Select".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 3)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, bool::datum_type())?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[1].shape, &outputs[0].shape)?;
+        s.equals(&inputs[2].shape, &outputs[0].shape)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let cond_rank = target.outlet_fact(inputs[0])?.rank();
+        let rank = target.outlet_fact(inputs[1])?.rank();
+        let mut cond = inputs[0];
+        if cond_rank == 1 && rank > 1 {
+            for axis in 1..rank {
+                cond = target.wire_node(
+                    format!("{prefix}.cond-axis-{axis}"),
+                    AxisOp::Add(axis),
+                    &[cond],
+                )?[0];
+            }
+        }
+        ops::logic::Iff.wire(prefix, target, &[cond, inputs[1], inputs[2]])
+    }
+}
+
 #[derive(Debug, Clone, new, Hash)]
 pub struct Switch;
 
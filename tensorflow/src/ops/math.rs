@@ -5,6 +5,9 @@ use crate::model::ParsingContext;
 use crate::model::TfOpRegister;
 use crate::tfpb::tensorflow::NodeDef;
 
+mod arg_max;
+mod cumsum;
+mod einsum;
 mod reduce;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
@@ -12,9 +15,15 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Add", |_, _| Ok(ops::math::Add.into_hir()));
     reg.insert("AddN", add_n);
     reg.insert("AddV2", |_, _| Ok(ops::math::Add.into_hir()));
+    reg.insert("ArgMax", arg_max::arg_max);
+    reg.insert("BatchMatMul", batch_mat_mul);
+    reg.insert("BatchMatMulV2", batch_mat_mul);
     reg.insert("BiasAdd", |_, _| Ok(ops::math::Add.into_hir()));
     reg.insert("Ceil", |_, _| Ok(ops::math::ceil().into_hir()));
+    reg.insert("Cumsum", cumsum::cumsum);
     reg.insert("Div", |_, _| Ok(ops::math::Div.into_hir()));
+    reg.insert("Einsum", einsum::einsum);
+    reg.insert("Erf", |_, _| Ok(ops::math::erf().into_hir()));
     reg.insert("FloorMod", |_, _| Ok(ops::math::Rem.into_hir()));
     reg.insert("MatMul", mat_mul);
     reg.insert("Max", reduce::max);
@@ -43,3 +52,9 @@ pub fn mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn Infer
     let trans_b = pb.get_attr_bool("transpose_b")?;
     Ok(expand(ops::matmul::MatMulInference::default().with_a_trans(trans_a).with_b_trans(trans_b)))
 }
+
+pub fn batch_mat_mul(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let adj_x = pb.get_attr_opt_bool("adj_x")?.unwrap_or(false);
+    let adj_y = pb.get_attr_opt_bool("adj_y")?.unwrap_or(false);
+    Ok(expand(ops::matmul::MatMulInference::default().with_a_trans(adj_x).with_b_trans(adj_y)))
+}
@@ -0,0 +1,89 @@
+use tract_hir::internal::*;
+use tract_hir::tract_core::ops::scan::{self, ScanInfo};
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn cumsum(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let exclusive = pb.get_attr_opt_bool("exclusive")?.unwrap_or(false);
+    let reverse = pb.get_attr_opt_bool("reverse")?.unwrap_or(false);
+    Ok(expand(Cumsum { exclusive, reverse }))
+}
+
+/// Inputs are the data and the (scalar) axis. Translated to a Scan accumulating along the axis.
+#[derive(Debug, Clone, Hash)]
+pub struct Cumsum {
+    pub exclusive: bool,
+    pub reverse: bool,
+}
+
+impl Expansion for Cumsum {
+    fn name(&self) -> Cow<str> {
+        "Cumsum".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[0].shape, &outputs[0].shape)?;
+        s.equals(&inputs[1].rank, 0)?;
+        Ok(())
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis =
+            model.outlet_fact(inputs[1])?.konst.as_ref().context("Axis expected to be a const")?;
+        let axis = axis.cast_to_scalar::<i64>()?;
+        let data = model.outlet_fact(inputs[0])?.clone();
+        let axis = if axis < 0 { (axis + data.rank() as i64) as usize } else { axis as usize };
+        let mut var_shape = data.shape.clone();
+        var_shape.set(axis, 1.to_dim());
+        let zero = model.add_const(
+            format!("{prefix}.zero"),
+            Tensor::zero_dt(data.datum_type, &[])?.into_arc_tensor(),
+        )?;
+        let init = model.wire_node(
+            format!("{prefix}.init"),
+            tract_core::ops::array::MultiBroadcastTo::new(var_shape.clone()),
+            &[zero],
+        )?[0];
+        let chunk = if self.reverse { -1 } else { 1 };
+        let input_mapping =
+            vec![scan::InputMapping::Scan(ScanInfo { axis, chunk }), scan::InputMapping::State];
+        // the body outputs acc + x (inclusive sum, also next state) and acc (exclusive sum)
+        let output_mapping = vec![
+            scan::OutputMapping {
+                scan: Some((0, ScanInfo { axis, chunk })),
+                full_dim_hint: None,
+                last_value_slot: None,
+                state: true,
+            },
+            scan::OutputMapping {
+                scan: Some((1, ScanInfo { axis, chunk })),
+                full_dim_hint: None,
+                last_value_slot: None,
+                state: false,
+            },
+        ];
+        let mut body = TypedModel::default();
+        let var_fact = data.datum_type.fact(var_shape);
+        let x = body.add_source("scan_input", var_fact.clone())?;
+        let acc = body.add_source("acc_input", var_fact)?;
+        let sum = body.wire_node("add", tract_core::ops::math::add(), &[x, acc])?[0];
+        body.set_output_outlets(&[sum, acc])?;
+        let scan = scan::Scan::new(body, input_mapping, output_mapping, 0)?;
+        let wires = model.wire_node(prefix, scan, &[inputs[0], init])?;
+        Ok(tvec![wires[self.exclusive as usize]])
+    }
+}
@@ -0,0 +1,104 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn einsum(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let equation = pb.get_attr_str("equation")?;
+    let expr: AxesMapping = equation.replace("...", "*").parse()?;
+    Ok(expand(EinSum { expr }))
+}
+
+#[derive(Debug, Clone, Hash)]
+pub struct EinSum {
+    pub expr: AxesMapping,
+}
+
+impl Expansion for EinSum {
+    fn name(&self) -> Cow<str> {
+        "EinSum".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, self.expr.input_count())?;
+        check_output_arity(outputs, 1)?;
+        for input in inputs {
+            s.equals(&input.datum_type, &outputs[0].datum_type)?;
+        }
+        let ranks: Vec<_> = inputs.iter().map(|i| &i.rank).collect();
+        s.given_all(ranks, move |s, ranks| {
+            let ranks = ranks.iter().map(|r| *r as usize).collect::<TVec<_>>();
+            let expr = resolve_ellipsis(&self.expr, &ranks)?;
+            s.equals(&outputs[0].rank, expr.rank(InOut::Out(0)) as i64)?;
+            for axis in expr.iter_all_axes() {
+                let mut axes = vec![];
+                if let Some(result) = axis.outputs[0].first() {
+                    axes.push(outputs[0].shape[*result].bex())
+                }
+                for (input_id, input_axis_positions) in axis.inputs.iter().enumerate() {
+                    for position in input_axis_positions {
+                        axes.push(inputs[input_id].shape[*position].bex());
+                    }
+                }
+                s.equals_all(axes)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let ranks = inputs
+            .iter()
+            .map(|o| model.outlet_fact(*o).map(|f| f.rank()))
+            .collect::<TractResult<TVec<_>>>()?;
+        let expr = resolve_ellipsis(&self.expr, &ranks)?;
+        let operating_dt = model.outlet_fact(inputs[0])?.datum_type;
+        model.wire_node(
+            prefix,
+            tract_core::ops::einsum::EinSum { axes: expr, operating_dt, q_params: None },
+            inputs,
+        )
+    }
+}
+
+// ellipsis (as '*') are replaced by as many fresh axes as the input ranks require, aligned on
+// the right
+fn resolve_ellipsis(expr: &AxesMapping, ranks: &[usize]) -> TractResult<AxesMapping> {
+    if expr.axis('*').is_err() {
+        return Ok(expr.clone());
+    }
+    let elipsed_axes: TVec<usize> = ranks
+        .iter()
+        .enumerate()
+        .filter_map(|(ix, rank)| {
+            expr.axis_positions(InOut::In(ix), '*')
+                .ok()
+                .map(|_| rank + 1 - expr.rank(InOut::In(ix)))
+        })
+        .collect();
+    let max_axes = *elipsed_axes.iter().max().unwrap();
+    let axis_resolved: String = ('a'..)
+        .filter(|l| expr.iter_all_axes().all(|axis| *l != axis.repr))
+        .take(max_axes)
+        .collect();
+    let mut resolved = expr.to_string();
+    for axes in elipsed_axes {
+        resolved = resolved.replacen(
+            '*',
+            &axis_resolved.chars().skip(max_axes - axes).collect::<String>(),
+            1,
+        );
+    }
+    resolved = resolved.replacen('*', &axis_resolved, 1);
+    resolved.parse()
+}
@@ -0,0 +1,129 @@
+use tract_hir::internal::*;
+use tract_hir::ops::cnn::{PaddingSpec, PoolSpec};
+use tract_hir::ops::nn::DataFormat;
+use tract_hir::tract_core::ops::cnn::deconv::adjustments;
+use tract_hir::tract_core::ops::cnn::{Deconv, KernelFormat};
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn conv2d_backprop_input(
+    _ctx: &ParsingContext,
+    pb: &NodeDef,
+) -> TractResult<Box<dyn InferenceOp>> {
+    let data_format = super::data_format(pb)?;
+    let spatial = if data_format == DataFormat::NHWC { 1..3 } else { 2..4 };
+    let strides = super::strides(pb)?[spatial.clone()].into();
+    let dilations: TVec<usize> =
+        pb.get_attr_opt_list_int("dilations")?.unwrap_or(vec![1; 4]).into();
+    ensure!(dilations.len() == 4, "dilations must have 4 values, found {:?}", dilations);
+    let padding = super::padding(pb)?;
+    Ok(expand(Conv2DBackpropInput::new(data_format, padding, strides, dilations[spatial].into())))
+}
+
+/// Transposed convolution: inputs are the output shape, the (forward convolution, HWIO) filter
+/// and the data.
+#[derive(Debug, Clone, new, Hash)]
+pub struct Conv2DBackpropInput {
+    data_format: DataFormat,
+    padding: PaddingSpec,
+    strides: TVec<usize>,
+    dilations: TVec<usize>,
+}
+
+impl Expansion for Conv2DBackpropInput {
+    fn name(&self) -> Cow<str> {
+        "Conv2DBackpropInput".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 3)?;
+        check_output_arity(outputs, 1)?;
+        s.equals(&inputs[0].rank, 1)?;
+        s.equals(&inputs[0].shape[0], 4.to_dim())?;
+        s.equals(&inputs[1].rank, 4)?;
+        s.equals(&inputs[2].rank, 4)?;
+        s.equals(&outputs[0].rank, 4)?;
+        s.equals(&inputs[1].datum_type, &outputs[0].datum_type)?;
+        s.equals(&inputs[2].datum_type, &outputs[0].datum_type)?;
+        s.given(&inputs[0].value, move |s, shape| {
+            let shape = shape.cast_to::<TDim>()?;
+            s.equals(&outputs[0].shape, ShapeFactoid::from(shape.as_slice::<TDim>()?))
+        })
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        target: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let output_shape = target
+            .outlet_fact(inputs[0])?
+            .konst
+            .clone()
+            .context("Need output shape to be const")?;
+        let output_shape = output_shape.cast_to::<i64>()?;
+        let output_shape: TVec<usize> =
+            output_shape.as_slice::<i64>()?.iter().map(|d| *d as usize).collect();
+        let output_shape = self.data_format.shape(output_shape)?;
+        let input_shape = target.outlet_fact(inputs[2])?.shape.as_concrete().map(|s| s.to_vec());
+        let input_shape =
+            self.data_format.shape(input_shape.context("Need concrete input shape")?)?;
+        let filter_shape = target.outlet_fact(inputs[1])?.shape.as_concrete().map(|s| s.to_vec());
+        let filter_shape = filter_shape.context("Need concrete filter shape")?;
+
+        // the padding is the one of the forward convolution from the output to the input
+        let padding = match self.padding {
+            PaddingSpec::SameUpper => {
+                let pads = output_shape
+                    .hw_dims()
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, d)| {
+                        PaddingSpec::SameUpper.compute_one(
+                            ix,
+                            d,
+                            filter_shape[ix],
+                            self.dilations[ix],
+                            self.strides[ix],
+                        )
+                    })
+                    .collect::<TVec<_>>();
+                PaddingSpec::Explicit(
+                    pads.iter().map(|p| p.pad_before).collect(),
+                    pads.iter().map(|p| p.pad_after).collect(),
+                )
+            }
+            ref padding => padding.clone(),
+        };
+        let pool_spec = PoolSpec::new(
+            self.data_format,
+            filter_shape[0..2].into(),
+            padding,
+            Some(self.dilations.clone()),
+            Some(self.strides.clone()),
+            filter_shape[3],
+            filter_shape[2],
+        );
+        let adjustments = adjustments(&pool_spec, input_shape.hw_dims(), output_shape.hw_dims())?;
+
+        // forward convolution filters are HWIO, the deconvolution exchanges I and O
+        let kernel =
+            target.wire_node(format!("{prefix}.kernel"), AxisOp::Move(3, 2), &inputs[1..2])?;
+        let bias = target.add_const(
+            format!("{prefix}.bias"),
+            Tensor::zero_scalar_dt(target.outlet_fact(inputs[2])?.datum_type)?,
+        )?;
+        target.wire_node(
+            prefix,
+            Deconv::new(pool_spec, KernelFormat::HWIO, adjustments, 1),
+            &[inputs[2], kernel[0], bias],
+        )
+    }
+}
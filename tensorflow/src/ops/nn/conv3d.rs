@@ -0,0 +1,24 @@
+use tract_hir::internal::*;
+use tract_hir::ops::cnn;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn conv3d(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let channels_last = pb.get_attr_opt_raw_str("data_format")?.unwrap_or(b"NDHWC") == b"NDHWC";
+    let spatial = if channels_last { 1..4 } else { 2..5 };
+    let strides: TVec<usize> = pb.get_attr_list_int("strides")?.into();
+    ensure!(strides.len() == 5, "strides must have 5 values, found {:?}", strides);
+    let dilations: TVec<usize> =
+        pb.get_attr_opt_list_int("dilations")?.unwrap_or(vec![1; 5]).into();
+    ensure!(dilations.len() == 5, "dilations must have 5 values, found {:?}", dilations);
+    let mut op = cnn::Conv::default()
+        .hwio()
+        .padding(super::padding(pb)?)
+        .strides(strides[spatial.clone()].into())
+        .dilations(dilations[spatial].into());
+    if channels_last {
+        op = op.nhwc()
+    }
+    Ok(expand(op))
+}
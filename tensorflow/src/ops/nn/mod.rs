@@ -2,20 +2,26 @@ use tract_hir::internal::*;
 use tract_hir::ops::cnn::PaddingSpec;
 use tract_hir::ops::nn::{DataFormat, LayerSoftmax};
 
-use crate::model::TfOpRegister;
+use crate::model::{ParsingContext, TfOpRegister};
 use crate::tfpb::tensorflow::NodeDef;
 
 pub mod conv2d;
+pub mod conv2d_backprop_input;
+pub mod conv3d;
 pub mod dw_conv2d;
 pub mod fused_batch_norm;
 pub mod pools;
 pub mod s2b;
+pub mod top_k;
 
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("AvgPool", pools::avgpool);
     reg.insert("Conv2D", conv2d::conv2d);
+    reg.insert("Conv2DBackpropInput", conv2d_backprop_input::conv2d_backprop_input);
+    reg.insert("Conv3D", conv3d::conv3d);
     reg.insert("DepthwiseConv2dNative", dw_conv2d::depthwise_conv2d);
     reg.insert("FusedBatchNorm", fused_batch_norm::fused_batch_norm);
+    reg.insert("LeakyRelu", leaky_relu);
     reg.insert("MaxPool", pools::maxpool);
     reg.insert("Relu", |_, _| Ok(expand(tract_hir::ops::activations::Clip::new(Some(0.0), None))));
     reg.insert("Relu6", |_, _| {
@@ -23,8 +29,15 @@ pub fn register_all_ops(reg: &mut TfOpRegister) {
     });
     reg.insert("Sigmoid", |_, _| Ok(tract_hir::ops::nn::sigmoid().into_hir()));
     reg.insert("Softmax", |_, _| Ok(expand(LayerSoftmax::new(1, true))));
+    reg.insert("Softplus", |_, _| Ok(expand(tract_hir::ops::activations::Softplus)));
     reg.insert("SpaceToBatchND", s2b::space_to_batch_nd);
     reg.insert("BatchToSpaceND", s2b::batch_to_space_nd);
+    reg.insert("TopKV2", top_k::top_k_v2);
+}
+
+fn leaky_relu(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let alpha = pb.get_attr_opt_float("alpha")?.unwrap_or(0.2);
+    Ok(expand(tract_hir::ops::activations::LeakyRelu(alpha)))
 }
 
 pub fn strides(pb: &NodeDef) -> TractResult<Vec<usize>> {
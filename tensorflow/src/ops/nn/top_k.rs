@@ -0,0 +1,76 @@
+use tract_hir::internal::*;
+
+use crate::model::ParsingContext;
+use crate::tfpb::tensorflow::NodeDef;
+
+pub fn top_k_v2(_ctx: &ParsingContext, pb: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
+    let index_type = pb.get_attr_opt_datum_type("index_type")?.unwrap_or(DatumType::I32);
+    Ok(expand(TopKV2::new(index_type)))
+}
+
+/// Inputs are the data and the (scalar) k. Largest values along the last axis, in decreasing
+/// order.
+#[derive(Debug, Clone, new, Hash)]
+pub struct TopKV2 {
+    index_type: DatumType,
+}
+
+impl Expansion for TopKV2 {
+    fn name(&self) -> Cow<str> {
+        "TopKV2".into()
+    }
+
+    fn rules<'r, 'p: 'r, 's: 'r>(
+        &'s self,
+        s: &mut Solver<'r>,
+        inputs: &'p [TensorProxy],
+        outputs: &'p [TensorProxy],
+    ) -> InferenceResult {
+        check_input_arity(inputs, 2)?;
+        check_output_arity(outputs, 2)?;
+        s.equals(&inputs[0].datum_type, &outputs[0].datum_type)?;
+        s.equals(&outputs[1].datum_type, self.index_type)?;
+        s.equals(&inputs[1].rank, 0)?;
+        s.equals(&inputs[0].rank, &outputs[0].rank)?;
+        s.equals(&inputs[0].rank, &outputs[1].rank)?;
+        s.given(&inputs[0].rank, move |s, rank| {
+            let rank = rank as usize;
+            for ix in 0..rank - 1 {
+                s.equals(&inputs[0].shape[ix], &outputs[0].shape[ix])?;
+                s.equals(&inputs[0].shape[ix], &outputs[1].shape[ix])?;
+            }
+            s.given(&inputs[1].value, move |s, k| {
+                let k = k.cast_to_scalar::<i64>()?;
+                s.equals(&outputs[0].shape[rank - 1], k.to_dim())?;
+                s.equals(&outputs[1].shape[rank - 1], k.to_dim())
+            })
+        })
+    }
+
+    fn nboutputs(&self) -> TractResult<usize> {
+        Ok(2)
+    }
+
+    fn wire(
+        &self,
+        prefix: &str,
+        model: &mut TypedModel,
+        inputs: &[OutletId],
+    ) -> TractResult<TVec<OutletId>> {
+        let axis = model.outlet_fact(inputs[0])?.rank() - 1;
+        let fallback_k = model.symbols.new_with_prefix("k").into();
+        let mut wires = model.wire_node(
+            prefix,
+            tract_core::ops::array::Topk { axis, largest: true, fallback_k },
+            inputs,
+        )?;
+        if self.index_type != DatumType::I64 {
+            wires[1] = model.wire_node(
+                format!("{prefix}.indices"),
+                tract_core::ops::cast::cast(self.index_type),
+                &wires[1..2],
+            )?[0];
+        }
+        Ok(wires)
+    }
+}
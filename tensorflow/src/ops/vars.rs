@@ -7,6 +7,8 @@ use crate::tfpb::tensorflow::NodeDef;
 pub fn register_all_ops(reg: &mut TfOpRegister) {
     reg.insert("Assign", |_, _| Ok(Box::<Assign>::default()));
     reg.insert("VariableV2", variable_v2);
+    // resource variables are materialized as constants when restored from a checkpoint
+    reg.insert("ReadVariableOp", |_, _| Ok(Box::new(tract_hir::ops::identity::Identity)));
 }
 
 fn variable_v2(_ctx: &ParsingContext, node: &NodeDef) -> TractResult<Box<dyn InferenceOp>> {
@@ -204,6 +204,15 @@ impl NodeDef {
         Ok(None)
     }
 
+    pub fn get_attr_func_name(&self, name: &str) -> TractResult<&str> {
+        if let Some(a) = self.attr.get(name) {
+            if let Value::Func(f) = a.value.as_ref().unwrap() {
+                return Ok(&f.name);
+            }
+        };
+        bail!("Node {} ({}) expected function attribute '{}'", self.name, self.op, name)
+    }
+
     pub fn get_attr_list_int<T: tract_num_traits::FromPrimitive>(
         &self,
         name: &str,
@@ -0,0 +1,164 @@
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::attr_value::Value;
+use tract_tensorflow::tfpb::tensorflow::op_def::ArgDef;
+use tract_tensorflow::tfpb::tensorflow::*;
+
+fn func_attr(name: &str) -> AttrValue {
+    AttrValue {
+        value: Some(Value::Func(NameAttrList {
+            name: name.to_string(),
+            ..NameAttrList::default()
+        })),
+    }
+}
+
+fn konst(name: &str, t: Tensor) -> NodeDef {
+    let dt = DataType::try_from(t.datum_type()).unwrap();
+    tfpb::node()
+        .name(name)
+        .op("Const")
+        .attr("dtype", dt)
+        .attr("value", TensorProto::try_from(&t).unwrap())
+}
+
+fn function(
+    name: &str,
+    inputs: &[(&str, DataType)],
+    outputs: &[(&str, &str)],
+    nodes: Vec<NodeDef>,
+) -> FunctionDef {
+    let arg = |name: &str, dt: Option<DataType>| ArgDef {
+        name: name.to_string(),
+        r#type: dt.map(|dt| dt.into()).unwrap_or_default(),
+        ..ArgDef::default()
+    };
+    FunctionDef {
+        signature: Some(OpDef {
+            name: name.to_string(),
+            input_arg: inputs.iter().map(|(n, dt)| arg(n, Some(*dt))).collect(),
+            output_arg: outputs.iter().map(|(n, _)| arg(n, None)).collect(),
+            ..OpDef::default()
+        }),
+        node_def: nodes,
+        ret: outputs.iter().map(|(n, t)| (n.to_string(), t.to_string())).collect(),
+        ..FunctionDef::default()
+    }
+}
+
+fn placeholder(name: &str, dt: DataType, shape: &[i64]) -> NodeDef {
+    let dim =
+        shape.iter().map(|&size| tensor_shape_proto::Dim { size, name: String::new() }).collect();
+    tfpb::node()
+        .name(name)
+        .op("Placeholder")
+        .attr("dtype", dt)
+        .attr("shape", TensorShapeProto { dim, unknown_rank: false })
+}
+
+// y = 2 * neg(x) after 3 - i0 doubling iterations: calls are inlined (including in a loop
+// body), loops and conditionals refer to library functions. i0 is either 0, or an input.
+fn graph(i0_input: bool) -> GraphDef {
+    use DataType::*;
+    let library = vec![
+        function(
+            "double",
+            &[("x", DtFloat)],
+            &[("y", "add:z:0")],
+            vec![tfpb::node().name("add").op("AddV2").input("x").input("x")],
+        ),
+        function(
+            "cond",
+            &[("i", DtInt32), ("acc", DtFloat)],
+            &[("ok", "less:z:0")],
+            vec![
+                konst("limit", tensor0(3i32)),
+                tfpb::node().name("less").op("Less").input("i").input("limit:output:0"),
+            ],
+        ),
+        function(
+            "body",
+            &[("i", DtInt32), ("acc", DtFloat)],
+            &[("i_next", "inc:z:0"), ("acc_next", "call:output:0")],
+            vec![
+                konst("one", tensor0(1i32)),
+                tfpb::node().name("inc").op("AddV2").input("i").input("one:output:0"),
+                tfpb::node()
+                    .name("call")
+                    .op("PartitionedCall")
+                    .input("acc")
+                    .attr("f", func_attr("double")),
+            ],
+        ),
+        function(
+            "neg",
+            &[("x", DtFloat)],
+            &[("y", "neg:y:0")],
+            vec![tfpb::node().name("neg").op("Neg").input("x")],
+        ),
+        function("ident", &[("x", DtFloat)], &[("y", "x")], vec![]),
+    ];
+    let i0 = if i0_input { placeholder("i0", DtInt32, &[]) } else { konst("i0", tensor0(0i32)) };
+    let mut graph = tfpb::graph()
+        .node(placeholder("x", DtFloat, &[2]))
+        .node(i0)
+        .node(
+            tfpb::node()
+                .name("loop")
+                .op("StatelessWhile")
+                .input("i0")
+                .input("x")
+                .attr("cond", func_attr("cond"))
+                .attr("body", func_attr("body")),
+        )
+        .node(konst("flag", tensor0(1i32)))
+        .node(
+            tfpb::node()
+                .name("branch")
+                .op("StatelessIf")
+                .input("flag")
+                .input("loop:1")
+                .attr("then_branch", func_attr("neg"))
+                .attr("else_branch", func_attr("ident")),
+        )
+        .node(
+            tfpb::node()
+                .name("out")
+                .op("StatefulPartitionedCall")
+                .input("branch")
+                .attr("f", func_attr("double")),
+        );
+    graph.library = Some(FunctionDefLibrary { function: library, gradient: vec![] });
+    graph
+}
+
+#[test]
+fn functional_control_flow() -> TractResult<()> {
+    let mut model = tensorflow().parse_graph(&graph(false))?.0;
+    model.set_output_names(["out"])?;
+    let input = tensor1(&[1f32, 2f32]);
+    let expected = tensor1(&[-16f32, -32f32]);
+
+    let result = model.clone().into_runnable()?.run(tvec!(input.clone().into()))?;
+    assert_eq!(*result[0], expected);
+
+    let optimized = model.into_optimized()?;
+    assert!(optimized.nodes().iter().all(|n| n.op().name() != "While"));
+    let result = optimized.into_runnable()?.run(tvec!(input.into()))?;
+    assert_eq!(*result[0], expected);
+    Ok(())
+}
+
+#[test]
+fn while_with_trip_count_known_at_runtime() -> TractResult<()> {
+    let mut model = tensorflow().parse_graph(&graph(true))?.0;
+    model.set_output_names(["out"])?;
+    let optimized = model.into_optimized()?;
+    assert!(optimized.nodes().iter().any(|n| n.op().name() == "While"));
+    let plan = optimized.into_runnable()?;
+    for (i0, expected) in [(0, [-16f32, -32.]), (2, [-4., -8.]), (5, [-2., -4.])] {
+        let result = plan.run(tvec!(tensor1(&[1f32, 2f32]).into(), tensor0(i0).into()))?;
+        assert_eq!(*result[0], tensor1(&expected));
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+proptest! {
+    #[test]
+    fn one_hot(ref indices in vec(-1i32..5, 1..6), depth in 1i32..5, axis in -1i64..2) {
+        let indices = tensor1(indices);
+        let graph = tfpb::graph()
+            .node(placeholder_i32("indices"))
+            .node(const_i32("depth", &tensor0(depth)))
+            .node(const_f32("on", &tensor0(2f32)))
+            .node(const_f32("off", &tensor0(-1f32)))
+            .node(tfpb::node().name("op").op("OneHot")
+                .input("indices").input("depth").input("on").input("off")
+                .attr("T", DtFloat).attr("TI", DtInt32).attr("axis", axis));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("indices", indices)), "op")?;
+    }
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor_and_axis() -> BoxedStrategy<(Tensor, usize)> {
+    vec(1usize..4, 1..4)
+        .prop_flat_map(|shape| (0..shape.len(), Just(shape)))
+        .prop_map(|(axis, shape)| {
+            let len = shape.iter().product::<usize>();
+            let data = (0..len).map(|i| i as f32).collect::<Vec<_>>();
+            (tract_ndarray::ArrayD::from_shape_vec(shape, data).unwrap().into(), axis)
+        })
+        .boxed()
+}
+
+fn identity(input: &str) -> tfpb::tensorflow::NodeDef {
+    tfpb::node().name("output").op("Identity").input(input).attr("T", DtFloat)
+}
+
+proptest! {
+    #[test]
+    fn split((ref input, axis) in tensor_and_axis(), n in 1usize..3, output in 0usize..3) {
+        let n = n.min(input.shape()[axis]);
+        let mut input = input.clone();
+        let dim = input.shape()[axis] / n * n;
+        input = input.slice(axis, 0, dim).unwrap();
+        let graph = tfpb::graph()
+            .node(const_i32("axis", &tensor0(axis as i32)))
+            .node(placeholder_f32("input"))
+            .node(tfpb::node().name("op").op("Split").input("axis").input("input")
+                .attr("T", DtFloat).attr("num_split", n as i64))
+            .node(identity(&format!("op:{}", output.min(n - 1))));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input)), "output")?;
+    }
+
+    #[test]
+    fn split_v((ref input, axis) in tensor_and_axis(), first in 0usize..4, infer in proptest::bool::ANY) {
+        let dim = input.shape()[axis];
+        let first = first.min(dim);
+        let sizes = if infer { tensor1(&[first as i32, -1]) } else { tensor1(&[first as i32, (dim - first) as i32]) };
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(const_i32("sizes", &sizes))
+            .node(const_i32("axis", &tensor0(axis as i32 - input.rank() as i32)))
+            .node(tfpb::node().name("op").op("SplitV").input("input").input("sizes").input("axis")
+                .attr("T", DtFloat).attr("num_split", 2i64))
+            .node(identity("op:1"));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "output")?;
+    }
+
+    #[test]
+    fn unpack((ref input, axis) in tensor_and_axis(), output in 0usize..3) {
+        let n = input.shape()[axis];
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(tfpb::node().name("op").op("Unpack").input("input")
+                .attr("T", DtFloat).attr("num", n as i64).attr("axis", axis as i64))
+            .node(identity(&format!("op:{}", output.min(n - 1))));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "output")?;
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn resize_pb(
+    op: &str,
+    size: (usize, usize),
+    align_corners: bool,
+    half_pixel_centers: bool,
+) -> Vec<u8> {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("size", &tensor1(&[size.0 as i32, size.1 as i32])))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("input")
+                .input("size")
+                .attr("T", DtFloat)
+                .attr("align_corners", align_corners)
+                .attr("half_pixel_centers", half_pixel_centers),
+        );
+    graph.write_to_bytes().unwrap()
+}
+
+fn image() -> BoxedStrategy<Tensor> {
+    (1usize..3, 1usize..5, 1usize..5, 1usize..3)
+        .prop_flat_map(|(n, h, w, c)| tensor(vec![n, h, w, c]))
+        .boxed()
+}
+
+// align_corners and half_pixel_centers are mutually exclusive in tensorflow
+fn flags() -> BoxedStrategy<(bool, bool)> {
+    prop_oneof![Just((false, false)), Just((true, false)), Just((false, true))].boxed()
+}
+
+proptest! {
+    #[test]
+    fn resize_bilinear(ref input in image(), size in (1usize..8, 1usize..8), (ac, hpc) in flags()) {
+        let graph = resize_pb("ResizeBilinear", size, ac, hpc);
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+
+    #[test]
+    fn resize_nearest_neighbor(ref input in image(), size in (1usize..8, 1usize..8), (ac, hpc) in flags()) {
+        let graph = resize_pb("ResizeNearestNeighbor", size, ac, hpc);
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
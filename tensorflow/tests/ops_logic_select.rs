@@ -0,0 +1,100 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtBool, DtFloat};
+
+fn tensor<T: Datum + Arbitrary>(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(any::<T>(), len..len + 1)
+        .prop_map(move |data| {
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+// tf.compat.v1.where: the condition has the shape of the branches, or is a vector matching
+// their first dimension
+fn select_inputs() -> BoxedStrategy<(Tensor, Tensor, Tensor)> {
+    (vec(1usize..4, 1..4), proptest::bool::ANY)
+        .prop_flat_map(|(shape, rows)| {
+            let cond = if rows { vec![shape[0]] } else { shape.clone() };
+            (tensor::<bool>(cond), tensor::<i8>(shape.clone()), tensor::<i8>(shape))
+        })
+        .prop_map(|(c, t, e)| {
+            (c, t.cast_to::<f32>().unwrap().into_owned(), e.cast_to::<f32>().unwrap().into_owned())
+        })
+        .boxed()
+}
+
+// tf.where: the condition and the branches broadcast
+fn select_v2_inputs() -> BoxedStrategy<(Tensor, Tensor, Tensor)> {
+    (vec(1usize..4, 1..4), vec(proptest::bool::ANY, 3..4))
+        .prop_flat_map(|(shape, keep)| {
+            let broadcast = |ix: usize| {
+                shape
+                    .iter()
+                    .enumerate()
+                    .map(|(d, s)| if keep[ix] || d % 2 == 0 { *s } else { 1 })
+                    .collect::<Vec<_>>()
+            };
+            (tensor::<bool>(broadcast(0)), tensor::<i8>(broadcast(1)), tensor::<i8>(broadcast(2)))
+        })
+        .prop_map(|(c, t, e)| {
+            (c, t.cast_to::<f32>().unwrap().into_owned(), e.cast_to::<f32>().unwrap().into_owned())
+        })
+        .boxed()
+}
+
+fn select_pb(op: &str) -> Vec<u8> {
+    let graph = tfpb::graph()
+        .node(placeholder("cond", DtBool, None))
+        .node(placeholder_f32("then"))
+        .node(placeholder_f32("else"))
+        .node(
+            tfpb::node()
+                .name("op")
+                .op(op)
+                .input("cond")
+                .input("then")
+                .input("else")
+                .attr("T", DtFloat),
+        );
+    graph.write_to_bytes().unwrap()
+}
+
+proptest! {
+    #[test]
+    fn select((ref c, ref t, ref e) in select_inputs()) {
+        let inputs = vec!(("cond", c.clone()), ("then", t.clone()), ("else", e.clone()));
+        compare(&select_pb("Select"), inputs, "op")?;
+    }
+
+    #[test]
+    fn select_v2((ref c, ref t, ref e) in select_v2_inputs()) {
+        let inputs = vec!(("cond", c.clone()), ("then", t.clone()), ("else", e.clone()));
+        compare(&select_pb("SelectV2"), inputs, "op")?;
+    }
+
+    #[test]
+    fn where_(ref input in vec(1usize..4, 0..4).prop_flat_map(tensor::<bool>)) {
+        let graph = tfpb::graph()
+            .node(placeholder("input", DtBool, None))
+            .node(tfpb::node().name("op").op("Where").input("input").attr("T", DtBool));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
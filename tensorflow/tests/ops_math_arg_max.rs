@@ -0,0 +1,57 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{self, DtFloat, DtInt32};
+
+// distinct values, so that tensorflow and tract agree on the index
+fn input_and_axis() -> BoxedStrategy<(Tensor, usize)> {
+    vec(1usize..4, 1..4)
+        .prop_flat_map(|shape| {
+            let len = shape.iter().product::<usize>();
+            (0..shape.len(), Just(Vec::from_iter(0..len)).prop_shuffle(), Just(shape))
+        })
+        .prop_map(|(axis, data, shape)| {
+            let data = data.into_iter().map(|i| i as f32).collect::<Vec<_>>();
+            (tract_ndarray::ArrayD::from_shape_vec(shape, data).unwrap().into(), axis)
+        })
+        .boxed()
+}
+
+fn output_type() -> BoxedStrategy<DataType> {
+    prop_oneof![Just(DataType::DtInt64), Just(DtInt32)].boxed()
+}
+
+proptest! {
+    #[test]
+    fn arg_max((ref input, axis) in input_and_axis(), output_type in output_type()) {
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(const_i32("axis", &tensor0(axis as i32)))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("ArgMax")
+                    .input("input")
+                    .input("axis")
+                    .attr("T", DtFloat)
+                    .attr("Tidx", DtInt32)
+                    .attr("output_type", output_type),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn input_and_axis() -> BoxedStrategy<(Tensor, i32)> {
+    vec(1usize..4, 1..4)
+        .prop_flat_map(|shape| (-(shape.len() as i32)..shape.len() as i32, tensor(shape)))
+        .prop_map(|(axis, input)| (input, axis))
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn cumsum((ref input, axis) in input_and_axis(), exclusive: bool, reverse: bool) {
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(const_i32("axis", &tensor0(axis)))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("Cumsum")
+                    .input("input")
+                    .input("axis")
+                    .attr("T", DtFloat)
+                    .attr("Tidx", DtInt32)
+                    .attr("exclusive", exclusive)
+                    .attr("reverse", reverse),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
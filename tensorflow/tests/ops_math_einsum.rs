@@ -0,0 +1,80 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn matmul_inputs(adj_x: bool, adj_y: bool) -> BoxedStrategy<(Tensor, Tensor)> {
+    (vec(1usize..3, 0..3), 1usize..4, 1usize..4, 1usize..4)
+        .prop_flat_map(move |(batch, m, k, n)| {
+            let mut a = batch.clone();
+            a.extend(if adj_x { [k, m] } else { [m, k] });
+            let mut b = batch;
+            b.extend(if adj_y { [n, k] } else { [k, n] });
+            (tensor(a), tensor(b))
+        })
+        .boxed()
+}
+
+fn adjoints() -> BoxedStrategy<(bool, bool, Tensor, Tensor)> {
+    (proptest::bool::ANY, proptest::bool::ANY)
+        .prop_flat_map(|(x, y)| (Just(x), Just(y), matmul_inputs(x, y)))
+        .prop_map(|(x, y, (a, b))| (x, y, a, b))
+        .boxed()
+}
+
+fn binary_pb(op: tfpb::tensorflow::NodeDef) -> Vec<u8> {
+    let op = op.name("op").input("a").input("b").attr("T", DtFloat);
+    tfpb::graph()
+        .node(placeholder_f32("a"))
+        .node(placeholder_f32("b"))
+        .node(op)
+        .write_to_bytes()
+        .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn einsum_matmul((ref a, ref b) in matmul_inputs(false, false)) {
+        let graph = binary_pb(tfpb::node().op("Einsum").attr("N", 2i64).attr("equation", "...mk,...kn->...mn"));
+        compare(&graph, vec!(("a", a.clone()), ("b", b.clone())), "op")?;
+    }
+
+    #[test]
+    fn einsum_transposed((ref a, ref b) in matmul_inputs(true, true)) {
+        let rank = a.rank();
+        let batch = &"bcd"[0..rank - 2];
+        let equation = format!("{batch}km,{batch}nk->{batch}nm");
+        let graph = binary_pb(tfpb::node().op("Einsum").attr("N", 2i64).attr("equation", &*equation));
+        compare(&graph, vec!(("a", a.clone()), ("b", b.clone())), "op")?;
+    }
+
+    #[test]
+    fn batch_mat_mul_v2((adj_x, adj_y, ref a, ref b) in adjoints()) {
+        let graph = binary_pb(tfpb::node().op("BatchMatMulV2").attr("adj_x", adj_x).attr("adj_y", adj_y));
+        compare(&graph, vec!(("a", a.clone()), ("b", b.clone())), "op")?;
+    }
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn input() -> BoxedStrategy<Tensor> {
+    vec(1usize..4, 0..4).prop_flat_map(tensor).boxed()
+}
+
+fn unary(op: &str) -> tfpb::tensorflow::NodeDef {
+    tfpb::node().name("op").op(op).input("input").attr("T", DtFloat)
+}
+
+proptest! {
+    #[test]
+    fn leaky_relu(ref input in input(), alpha in 0f32..1f32) {
+        let graph = tfpb::graph().node(placeholder_f32("input")).node(unary("LeakyRelu").attr("alpha", alpha));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+
+    #[test]
+    fn softplus(ref input in input()) {
+        let graph = tfpb::graph().node(placeholder_f32("input")).node(unary("Softplus"));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+
+    #[test]
+    fn erf(ref input in input()) {
+        let graph = tfpb::graph().node(placeholder_f32("input")).node(unary("Erf"));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+
+    // gelu, as exported by keras without approximation: 0.5 * x * (1 + erf(x / sqrt(2)))
+    #[test]
+    fn gelu(ref input in input()) {
+        let binary = |name: &str, op: &str, a: &str, b: &str| {
+            tfpb::node().name(name).op(op).input(a).input(b).attr("T", DtFloat)
+        };
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(const_f32("half", &tensor0(0.5f32)))
+            .node(const_f32("one", &tensor0(1f32)))
+            .node(const_f32("sqrt2", &tensor0(2f32.sqrt())))
+            .node(binary("scaled", "RealDiv", "input", "sqrt2"))
+            .node(tfpb::node().name("erf").op("Erf").input("scaled").attr("T", DtFloat))
+            .node(binary("shifted", "AddV2", "erf", "one"))
+            .node(binary("halved", "Mul", "input", "half"))
+            .node(binary("op", "Mul", "halved", "shifted"));
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
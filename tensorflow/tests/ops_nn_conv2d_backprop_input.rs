@@ -0,0 +1,76 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+// output image size, kernel, strides and the matching forward convolution output
+fn problem() -> BoxedStrategy<(Tensor, Tensor, [usize; 2], [usize; 4], bool)> {
+    (1usize..3, 1usize..3, 1usize..4, 1usize..4, 1usize..3, 1usize..3, proptest::bool::ANY)
+        .prop_flat_map(|(ic, oc, kh, kw, sh, sw, valid)| {
+            (kh..kh + 6, kw..kw + 6, 1usize..3)
+                .prop_map(move |(ih, iw, n)| (n, ih, iw, ic, oc, kh, kw, sh, sw, valid))
+        })
+        .prop_flat_map(|(n, ih, iw, ic, oc, kh, kw, sh, sw, valid)| {
+            let (oh, ow) = if valid {
+                ((ih - kh) / sh + 1, (iw - kw) / sw + 1)
+            } else {
+                (ih.div_ceil(sh), iw.div_ceil(sw))
+            };
+            (
+                tensor(vec![n, oh, ow, oc]),
+                tensor(vec![kh, kw, ic, oc]),
+                Just([sh, sw]),
+                Just([n, ih, iw, ic]),
+                Just(valid),
+            )
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn conv2d_backprop_input((ref input, ref kernel, strides, sizes, valid) in problem()) {
+        let sizes = tensor1(&sizes.iter().map(|s| *s as i32).collect::<Vec<_>>());
+        let graph = tfpb::graph()
+            .node(const_i32("sizes", &sizes))
+            .node(const_f32("kernel", kernel))
+            .node(placeholder_f32("input"))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("Conv2DBackpropInput")
+                    .input("sizes")
+                    .input("kernel")
+                    .input("input")
+                    .attr("T", DtFloat)
+                    .attr("strides", vec![1, strides[0] as i64, strides[1] as i64, 1])
+                    .attr("padding", if valid { "VALID" } else { "SAME" }),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::DtFloat;
+
+fn tensor(shape: Vec<usize>) -> BoxedStrategy<Tensor> {
+    let len = shape.iter().product::<usize>();
+    vec(-10i32..10, len..len + 1)
+        .prop_map(move |data| {
+            let data = data.into_iter().map(|i| i as f32 / 2.0).collect::<Vec<_>>();
+            tract_ndarray::ArrayD::from_shape_vec(shape.clone(), data).unwrap().into()
+        })
+        .boxed()
+}
+
+fn img_and_ker() -> BoxedStrategy<(Tensor, Tensor, Vec<i64>)> {
+    (1usize..3, 1usize..3, 1usize..3, 1usize..3, 1usize..3)
+        .prop_flat_map(|(ic, oc, kd, kh, kw)| {
+            (kd..kd + 3, kh..kh + 3, kw..kw + 3, Just((ic, oc, kd, kh, kw)), vec(1i64..3, 3..4))
+        })
+        .prop_flat_map(|(d, h, w, (ic, oc, kd, kh, kw), strides)| {
+            (
+                tensor(vec![1, d, h, w, ic]),
+                tensor(vec![kd, kh, kw, ic, oc]),
+                Just(vec![1, strides[0], strides[1], strides[2], 1]),
+            )
+        })
+        .boxed()
+}
+
+proptest! {
+    #[test]
+    fn conv3d((ref input, ref kernel, ref strides) in img_and_ker(), valid in proptest::bool::ANY) {
+        let graph = tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(const_f32("kernel", kernel))
+            .node(
+                tfpb::node()
+                    .name("op")
+                    .op("Conv3D")
+                    .input("input")
+                    .input("kernel")
+                    .attr("T", DtFloat)
+                    .attr("strides", strides.clone())
+                    .attr("padding", if valid { "VALID" } else { "SAME" }),
+            );
+        let graph = graph.write_to_bytes().unwrap();
+        compare(&graph, vec!(("input", input.clone())), "op")?;
+    }
+}
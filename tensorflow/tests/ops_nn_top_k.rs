@@ -0,0 +1,60 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType::{DtFloat, DtInt32};
+
+// distinct values, so that tensorflow and tract agree on the indices
+fn input_and_k() -> BoxedStrategy<(Tensor, usize)> {
+    (vec(1usize..4, 0..3), 1usize..6)
+        .prop_flat_map(|(mut shape, last)| {
+            shape.push(last);
+            let len = shape.iter().product::<usize>();
+            (Just(shape), Just(Vec::from_iter(0..len)).prop_shuffle(), 1..last + 1)
+        })
+        .prop_map(|(shape, data, k)| {
+            let data = data.into_iter().map(|i| i as f32).collect::<Vec<_>>();
+            (tract_ndarray::ArrayD::from_shape_vec(shape, data).unwrap().into(), k)
+        })
+        .boxed()
+}
+
+fn top_k_pb(k: usize, output: usize) -> Vec<u8> {
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(const_i32("k", &tensor0(k as i32)))
+        .node(tfpb::node().name("op").op("TopKV2").input("input").input("k").attr("T", DtFloat))
+        .node(
+            tfpb::node()
+                .name("output")
+                .op("Identity")
+                .input(format!("op:{output}"))
+                .attr("T", if output == 0 { DtFloat } else { DtInt32 }),
+        );
+    graph.write_to_bytes().unwrap()
+}
+
+proptest! {
+    #[test]
+    fn top_k_values((ref input, k) in input_and_k()) {
+        compare(&top_k_pb(k, 0), vec!(("input", input.clone())), "output")?;
+    }
+
+    #[test]
+    fn top_k_indices((ref input, k) in input_and_k()) {
+        compare(&top_k_pb(k, 1), vec!(("input", input.clone())), "output")?;
+    }
+}
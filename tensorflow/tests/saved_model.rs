@@ -0,0 +1,176 @@
+use prost::Message;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::attr_value::Value;
+use tract_tensorflow::tfpb::tensorflow::tensor_shape_proto::Dim;
+use tract_tensorflow::tfpb::tensorflow::trackable_object_graph::trackable_object::SerializedTensor;
+use tract_tensorflow::tfpb::tensorflow::trackable_object_graph::TrackableObject;
+use tract_tensorflow::tfpb::tensorflow::*;
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f63b78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn masked_crc32c(data: &[u8]) -> u32 {
+    crc32c(data).rotate_right(15).wrapping_add(0xa282ead8)
+}
+
+fn varint(mut v: u64, buf: &mut Vec<u8>) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+fn shape(dims: &[i64]) -> TensorShapeProto {
+    TensorShapeProto {
+        dim: dims.iter().map(|&size| Dim { size, name: String::new() }).collect(),
+        unknown_rank: false,
+    }
+}
+
+fn string_attr(s: &str) -> AttrValue {
+    AttrValue { value: Some(Value::S(s.as_bytes().to_vec())) }
+}
+
+// a single block LevelDB table, without prefix compression
+fn write_table(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    fn block(entries: &[(&str, Vec<u8>)], table: &mut Vec<u8>) -> (usize, usize) {
+        let offset = table.len();
+        for (key, value) in entries {
+            varint(0, table);
+            varint(key.len() as u64, table);
+            varint(value.len() as u64, table);
+            table.extend_from_slice(key.as_bytes());
+            table.extend_from_slice(value);
+        }
+        table.extend_from_slice(&0u32.to_le_bytes());
+        table.extend_from_slice(&1u32.to_le_bytes());
+        let size = table.len() - offset;
+        table.extend_from_slice(&[0, 0, 0, 0, 0]);
+        (offset, size)
+    }
+    let mut table = vec![];
+    let (data_offset, data_size) = block(entries, &mut table);
+    let mut handle = vec![];
+    varint(data_offset as u64, &mut handle);
+    varint(data_size as u64, &mut handle);
+    let metaindex = block(&[], &mut table);
+    let index = block(&[(entries.last().unwrap().0, handle)], &mut table);
+    let mut footer = vec![];
+    for v in [metaindex.0, metaindex.1, index.0, index.1] {
+        varint(v as u64, &mut footer);
+    }
+    footer.resize(40, 0);
+    footer.extend_from_slice(&0xdb4775248b80fb57u64.to_le_bytes());
+    table.extend(footer);
+    table
+}
+
+fn write_saved_model(dir: &std::path::Path) -> TractResult<()> {
+    std::fs::create_dir_all(dir.join("variables"))?;
+
+    let weights: Vec<u8> = [10f32, 20f32].iter().flat_map(|f| f.to_le_bytes()).collect();
+    let object_graph = TrackableObjectGraph {
+        nodes: vec![TrackableObject {
+            attributes: vec![SerializedTensor {
+                name: "VARIABLE_VALUE".to_string(),
+                full_name: "w".to_string(),
+                checkpoint_key: "w/.ATTRIBUTES/VARIABLE_VALUE".to_string(),
+                optional_restore: false,
+            }],
+            ..TrackableObject::default()
+        }],
+    }
+    .encode_to_vec();
+    let mut object_graph_data = vec![];
+    varint(object_graph.len() as u64, &mut object_graph_data);
+    object_graph_data.extend(masked_crc32c(&object_graph_data).to_le_bytes());
+    object_graph_data.extend(object_graph);
+
+    let mut shard = vec![];
+    let mut entry = |dt: DataType, dims: &[i64], data: &[u8]| {
+        let entry = BundleEntryProto {
+            dtype: dt.into(),
+            shape: Some(shape(dims)),
+            offset: shard.len() as i64,
+            size: data.len() as i64,
+            crc32c: masked_crc32c(data),
+            ..BundleEntryProto::default()
+        };
+        shard.extend_from_slice(data);
+        entry.encode_to_vec()
+    };
+    let entries = [
+        ("", BundleHeaderProto { num_shards: 1, ..BundleHeaderProto::default() }.encode_to_vec()),
+        ("_CHECKPOINTABLE_OBJECT_GRAPH", entry(DataType::DtString, &[], &object_graph_data)),
+        ("w/.ATTRIBUTES/VARIABLE_VALUE", entry(DataType::DtFloat, &[2], &weights)),
+    ];
+    std::fs::write(dir.join("variables/variables.index"), write_table(&entries))?;
+    std::fs::write(dir.join("variables/variables.data-00000-of-00001"), shard)?;
+
+    let graph = tfpb::graph()
+        .node(
+            tfpb::node()
+                .name("serving_default_x")
+                .op("Placeholder")
+                .attr("dtype", DataType::DtFloat)
+                .attr("shape", shape(&[2])),
+        )
+        .node(
+            tfpb::node()
+                .name("w")
+                .op("VarHandleOp")
+                .attr("dtype", DataType::DtFloat)
+                .attr("shape", shape(&[2]))
+                .attr("shared_name", string_attr("w")),
+        )
+        .node(tfpb::node().name("w/read").op("ReadVariableOp").input("w"))
+        .node(tfpb::node().name("add").op("AddV2").input("serving_default_x").input("w/read"))
+        .node(
+            tfpb::node().name("saver_filename").op("Placeholder").attr("dtype", DataType::DtString),
+        )
+        .node(tfpb::node().name("save").op("SaveV2").input("saver_filename"));
+    let tensor_info = |name: &str| TensorInfo {
+        encoding: Some(tensor_info::Encoding::Name(name.to_string())),
+        ..TensorInfo::default()
+    };
+    let signature = SignatureDef {
+        inputs: [("x".to_string(), tensor_info("serving_default_x:0"))].into_iter().collect(),
+        outputs: [("y".to_string(), tensor_info("add:0"))].into_iter().collect(),
+        method_name: "tensorflow/serving/predict".to_string(),
+    };
+    let saved = SavedModel {
+        saved_model_schema_version: 1,
+        meta_graphs: vec![MetaGraphDef {
+            graph_def: Some(graph),
+            signature_def: [("serving_default".to_string(), signature)].into_iter().collect(),
+            ..MetaGraphDef::default()
+        }],
+    };
+    std::fs::write(dir.join("saved_model.pb"), saved.encode_to_vec())?;
+    Ok(())
+}
+
+#[test]
+fn saved_model_variables_and_signature() -> TractResult<()> {
+    let dir = std::env::temp_dir().join(format!("tract-saved-model-{}", std::process::id()));
+    write_saved_model(&dir)?;
+    let model = tensorflow().saved_model_for_path(&dir, Some("serving_default"))?;
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(model.input_outlets()?.len(), 1);
+    assert_eq!(model.node(model.input_outlets()?[0].node).name, "x");
+    assert_eq!(model.outlet_label(model.output_outlets()?[0]), Some("y"));
+    let model = model.into_optimized()?.into_runnable()?;
+    let result = model.run(tvec!(tensor1(&[1f32, 2f32]).into()))?;
+    assert_eq!(*result[0], tensor1(&[11f32, 22f32]));
+    Ok(())
+}
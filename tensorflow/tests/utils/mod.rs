@@ -30,7 +30,7 @@ pub fn run_tract<S: AsRef<str>>(
     inputs: &Vec<(S, Tensor)>,
     output: &str,
     mode: Mode,
-) -> TractResult<TVec<Arc<Tensor>>> {
+) -> TractResult<TVec<TValue>> {
     let mut model = tract_tensorflow::tensorflow().model_for_read(&mut &*graph)?;
     model.set_input_names(&inputs.iter().map(|pair| pair.0.as_ref()).collect::<Vec<&str>>())?;
     model.set_output_names(&[output])?;
@@ -38,7 +38,7 @@ pub fn run_tract<S: AsRef<str>>(
         model.set_input_fact(ix, tf.datum_type().fact(tf.shape()).into())?;
     }
     debug!("analysed");
-    let inputs = inputs.iter().map(|pair| pair.1.clone()).collect();
+    let inputs = inputs.iter().map(|pair| pair.1.clone().into()).collect();
     if mode == Mode::Infer {
         let plan = SimplePlan::new(&model)?;
         plan.run(inputs)
@@ -46,10 +46,11 @@ pub fn run_tract<S: AsRef<str>>(
         let mut model = model.into_typed()?;
         debug!("typed");
         if mode == Mode::Declutter {
-            model = model.declutter()?;
+            model.declutter()?;
             debug!("decluttered");
         } else if mode == Mode::Opt {
-            model = model.declutter()?.optimize()?;
+            model.declutter()?;
+            model.optimize()?;
             debug!("optimized");
         };
         trace!("{:#?}", model);
@@ -100,12 +101,12 @@ pub fn infer<S: AsRef<str>>(
         .unwrap();
     model.set_output_names(&[output_str]).unwrap();
     for (ix, (_, tf)) in inputs.iter().enumerate() {
-        model.set_input_fact(ix, tf.datum_type().fact(tf.shape()).into())?;
+        model.set_input_fact(ix, tf.datum_type().fact(tf.shape()).into()).unwrap();
     }
     let plan = SimplePlan::new(&model).unwrap();
     let mut state = SimpleState::new(&plan).unwrap();
     for (ix, (_, t)) in inputs.iter().enumerate() {
-        state.set_input(ix, t.clone()).unwrap();
+        state.set_input(ix, t.clone().into()).unwrap();
     }
     let output = model.node_by_name(output_str).unwrap();
     info!("Checking {} behaviour against tensorflow", output.name);
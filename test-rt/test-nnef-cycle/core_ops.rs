@@ -0,0 +1,332 @@
+use infra::{Test, TestResult, TestSuite};
+use itertools::Itertools;
+use tract_core::internal::*;
+use tract_core::ops::array::*;
+use tract_core::ops::binary::TypedBinOp;
+use tract_core::ops::cast::Cast;
+use tract_core::ops::cnn::{Conv, Deconv, KernelFormat, MaxPool, PaddingSpec, PoolSpec, SumPool};
+use tract_core::ops::einsum::{BasicMatMul, EinSum};
+use tract_core::ops::element_wise::ElementWiseOp;
+use tract_core::ops::logic::{Comp, Iff};
+use tract_core::ops::nn::{DataFormat, Reduce, Reducer, Softmax, SoftmaxExp};
+use tract_core::ops::scan::{InputMapping, OutputMapping, Scan, ScanInfo};
+use tract_core::ops::Downsample;
+
+// One test per unit element-wise and binary operator registered in the NNEF core registries,
+// and one per dumped primitive (see `primitives()` for the operators left out).
+pub fn suite() -> TestSuite {
+    let nnef = tract_nnef::nnef().with_tract_core();
+    let mut suite = TestSuite::default();
+    for registry in &nnef.registries {
+        let mut ops = TestSuite::default();
+        for (id, op) in &registry.unit_element_wise_ops {
+            let op = Box::new(ElementWiseOp(op.clone(), None));
+            ops.add(&id.0, CoreOpCycle { op, arity: 1 });
+        }
+        let mut dumped = vec![];
+        for (id, op) in &registry.binary_ops {
+            // only the first identifier registered for an operator is used by the dumper
+            if dumped.contains(&op.as_ref().type_id()) {
+                continue;
+            }
+            dumped.push(op.as_ref().type_id());
+            let op = Box::new(TypedBinOp(op.clone(), None));
+            ops.add(&id.0, CoreOpCycle { op, arity: 2 });
+        }
+        suite.add(&registry.id.0, ops);
+    }
+    let mut primitives = TestSuite::default();
+    for (name, case) in self::primitives().unwrap() {
+        primitives.add(name, case);
+    }
+    suite.add("primitives", primitives);
+    suite
+}
+
+#[derive(Clone, Debug)]
+pub struct CoreOpCycle {
+    op: Box<dyn TypedOp>,
+    arity: usize,
+}
+
+fn input(dt: DatumType, ix: usize) -> TractResult<Tensor> {
+    let tensor = match dt {
+        DatumType::F32 => {
+            tensor1(&(0..6).map(|i| 0.1 * (i + 1) as f32 + 0.05 * ix as f32).collect_vec())
+        }
+        DatumType::I32 => tensor1(&(0..6).map(|i| (i + ix + 1) as i32).collect_vec()),
+        DatumType::Bool => tensor1(&(0..6).map(|i| (i + ix) % 2 == 1).collect_vec()),
+        _ => bail!("No test input for {dt:?}"),
+    };
+    tensor.into_shape(&[2, 3])
+}
+
+impl CoreOpCycle {
+    fn model(&self, dt: DatumType) -> TractResult<TypedModel> {
+        let mut model = TypedModel::default();
+        let inputs = (0..self.arity)
+            .map(|ix| model.add_source(format!("input_{ix}"), dt.fact([2, 3])))
+            .collect::<TractResult<TVec<_>>>()?;
+        let output = model.wire_node("op", self.op.clone(), &inputs)?;
+        model.set_output_outlets(&output)?;
+        Ok(model)
+    }
+}
+
+impl Test for CoreOpCycle {
+    fn run_with_approx(
+        &self,
+        _suite: &str,
+        id: &str,
+        runtime: &dyn Runtime,
+        approx: Approximation,
+    ) -> TestResult {
+        // the first datum type the operator can evaluate
+        for dt in [DatumType::F32, DatumType::I32, DatumType::Bool] {
+            let inputs = (0..self.arity)
+                .map(|ix| Ok(input(dt, ix)?.into_tvalue()))
+                .collect::<TractResult<TVec<_>>>()?;
+            let Ok(mut model) = self.model(dt) else { continue };
+            let Ok(reference) = model.clone().into_runnable().and_then(|p| p.run(inputs.clone()))
+            else {
+                continue;
+            };
+            model.properties.insert("tract-rt-test.id".to_string(), rctensor0(id.to_string()));
+            let found = runtime.prepare(model)?.run(inputs)?;
+            return found[0].close_enough(&reference[0], approx);
+        }
+        bail!("Could not evaluate {:?} on any test datum type", self.op)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Arg {
+    Input(Tensor),
+    Const(Tensor),
+}
+
+#[derive(Clone, Debug)]
+pub struct PrimitiveCycle {
+    op: Box<dyn TypedOp>,
+    args: Vec<Arg>,
+}
+
+fn case(op: impl TypedOp, args: impl IntoIterator<Item = Arg>) -> PrimitiveCycle {
+    PrimitiveCycle { op: Box::new(op), args: args.into_iter().collect() }
+}
+
+fn f32s(shape: &[usize]) -> TractResult<Tensor> {
+    let len = shape.iter().product::<usize>();
+    tensor1(&(0..len).map(|i| 0.1 * (i + 1) as f32 - 0.3).collect_vec()).into_shape(shape)
+}
+
+fn pool_spec(kernel: usize, input_channels: usize, output_channels: usize) -> PoolSpec {
+    PoolSpec::new(
+        DataFormat::NCHW,
+        tvec!(kernel),
+        PaddingSpec::Valid,
+        None,
+        None,
+        input_channels,
+        output_channels,
+    )
+}
+
+fn cumulative_sum() -> TractResult<Scan> {
+    let mut body = TypedModel::default();
+    let x = body.add_source("x", f32::fact([1, 3]))?;
+    let acc = body.add_source("acc", f32::fact([1, 3]))?;
+    let sum = body.wire_node("sum", tract_core::ops::math::add(), &[x, acc])?;
+    body.set_output_outlets(&sum)?;
+    let scan = ScanInfo { axis: 0, chunk: 1 };
+    Scan::new(
+        body,
+        vec![InputMapping::Scan(scan), InputMapping::State],
+        vec![OutputMapping {
+            scan: Some((0, scan)),
+            full_dim_hint: None,
+            last_value_slot: None,
+            state: true,
+        }],
+        0,
+    )
+}
+
+/// One case per operator with a dumper in the core registries.
+///
+/// Out of scope:
+/// * sources and constants, present in every case;
+/// * DynSlice and DynTile, which need symbolic shapes;
+/// * Fft, Stft and the complex conversions, which need complex inputs;
+/// * ForceEval, Load, Store and SubmodelOp, which carry state or resources;
+/// * the optimized matmul and binary operators, which only appear in codegen'd models;
+/// * quantized Conv, covered by the conv_q unit suite.
+fn primitives() -> TractResult<Vec<(&'static str, PrimitiveCycle)>> {
+    use Arg::*;
+    let x = || f32s(&[2, 3]);
+    let i64s = |v: &[i64], shape: &[usize]| tensor1(v).into_shape(shape);
+    Ok(vec![
+        ("basic_matmul", case(BasicMatMul::default(), [Input(x()?), Const(f32s(&[3, 4])?)])),
+        (
+            "einsum",
+            case(
+                EinSum::new("mk,kn->mn".parse()?, f32::datum_type()),
+                [Input(x()?), Const(f32s(&[3, 4])?)],
+            ),
+        ),
+        (
+            "conv",
+            case(
+                Conv::new(pool_spec(2, 2, 3), KernelFormat::OIHW, 1, None),
+                [Input(f32s(&[1, 2, 5])?), Const(f32s(&[3, 2, 2])?), Const(f32s(&[3])?)],
+            ),
+        ),
+        (
+            "deconv",
+            case(
+                Deconv::new(pool_spec(2, 2, 3), KernelFormat::OIHW, tvec!(0), 1),
+                [Input(f32s(&[1, 2, 5])?), Const(f32s(&[3, 2, 2])?), Const(f32s(&[3])?)],
+            ),
+        ),
+        ("max_pool", case(MaxPool::new(pool_spec(2, 2, 2), None), [Input(f32s(&[1, 2, 5])?)])),
+        (
+            "sum_pool",
+            case(SumPool::new(pool_spec(2, 2, 2), false, true), [Input(f32s(&[1, 2, 5])?)]),
+        ),
+        ("reduce", case(Reduce::new(tvec!(1), Reducer::Sum), [Input(x()?)])),
+        ("softmax", case(Softmax::new(tvec!(1), None, SoftmaxExp::Libc), [Input(x()?)])),
+        ("concat", case(TypedConcat::new(0), [Input(x()?), Input(x()?)])),
+        ("slice", case(Slice::new(1, 1, 3), [Input(x()?)])),
+        ("axis_op", case(AxisOp::Move(0, 1), [Input(x()?)])),
+        ("tile", case(Tile::new(tvec!(2.to_dim(), 1.to_dim())), [Input(x()?)])),
+        (
+            "pad",
+            case(Pad::new(vec![(1, 0), (0, 2)], PadMode::Constant(rctensor0(0f32))), [Input(x()?)]),
+        ),
+        ("comp", case(Comp::LT, [Input(x()?), Const(tensor2(&[[0.2f32], [0.4]]))])),
+        (
+            "select",
+            case(
+                Iff,
+                [
+                    Const(tensor2(&[[true, false, true], [false, true, false]])),
+                    Input(x()?),
+                    Input(x()?),
+                ],
+            ),
+        ),
+        ("gather", case(Gather::new(0), [Input(x()?), Const(tensor1(&[1i64, 0]))])),
+        (
+            "gather_elements",
+            case(GatherElements::new(1), [Input(x()?), Const(i64s(&[2, 0, 1, 1, 0, 2], &[2, 3])?)]),
+        ),
+        ("gather_nd", case(GatherNd::new(0), [Input(x()?), Const(i64s(&[1, 0], &[2, 1])?)])),
+        (
+            "scatter_elements",
+            case(
+                ScatterElements::new(1),
+                [Input(x()?), Const(i64s(&[2, 0], &[2, 1])?), Const(tensor2(&[[5f32], [6.]]))],
+            ),
+        ),
+        (
+            "scatter_nd",
+            case(
+                ScatterNd,
+                [Input(x()?), Const(i64s(&[1], &[1, 1])?), Const(tensor2(&[[5f32, 6., 7.]]))],
+            ),
+        ),
+        (
+            "one_hot",
+            case(
+                OneHot { axis: 1, dim: 3, off: rctensor0(0f32), on: rctensor0(1f32) },
+                [Input(tensor1(&[0i64, 2]))],
+            ),
+        ),
+        ("topk", case(Topk::new(1, true, 2.to_dim()), [Input(x()?), Const(tensor0(2i64))])),
+        ("trilu", case(Trilu { upper: true }, [Input(f32s(&[3, 3])?), Const(tensor0(0i64))])),
+        ("cast", case(Cast::new(i32::datum_type()), [Input(tensor2(&[[1f32, 2.], [3., 4.]]))])),
+        ("downsample", case(Downsample::new(1, 2, 0), [Input(x()?)])),
+        (
+            "broadcast",
+            case(MultiBroadcastTo::new(ShapeFact::from(&[2, 3])), [Input(f32s(&[1, 3])?)]),
+        ),
+        (
+            "range",
+            case(
+                Range::new(6.to_dim()),
+                [Const(tensor0(0i64)), Const(tensor0(6i64)), Const(tensor0(1i64))],
+            ),
+        ),
+        ("scan", case(cumulative_sum()?, [Input(x()?), Const(tensor2(&[[0f32; 3]]))])),
+    ])
+}
+
+impl PrimitiveCycle {
+    fn model(&self) -> TractResult<(TypedModel, TVec<TValue>)> {
+        let mut model = TypedModel::default();
+        let mut wires = tvec!();
+        let mut inputs = tvec!();
+        for (ix, arg) in self.args.iter().enumerate() {
+            let wire = match arg {
+                Arg::Input(t) => {
+                    inputs.push(t.clone().into_tvalue());
+                    model.add_source(format!("input_{ix}"), TypedFact::shape_and_dt_of(t))?
+                }
+                Arg::Const(t) => model.add_const(format!("const_{ix}"), t.clone())?,
+            };
+            wires.push(wire);
+        }
+        let outputs = model.wire_node("op", self.op.clone(), &wires)?;
+        model.set_output_outlets(&outputs)?;
+        Ok((model, inputs))
+    }
+}
+
+impl Test for PrimitiveCycle {
+    fn run_with_approx(
+        &self,
+        _suite: &str,
+        id: &str,
+        runtime: &dyn Runtime,
+        approx: Approximation,
+    ) -> TestResult {
+        let (mut model, inputs) = self.model()?;
+        let reference = model.clone().into_runnable()?.run(inputs.clone())?;
+        model.properties.insert("tract-rt-test.id".to_string(), rctensor0(id.to_string()));
+        let found = runtime.prepare(model)?.run(inputs)?;
+        ensure!(found.len() == reference.len());
+        for (found, reference) in found.iter().zip(reference.iter()) {
+            found.close_enough(reference, approx)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn every_dumper_is_covered() -> TractResult<()> {
+    use std::any::TypeId;
+    let mut covered: Vec<TypeId> =
+        primitives()?.iter().map(|(_, c)| c.op.as_any().type_id()).collect();
+    covered.extend([
+        TypeId::of::<tract_core::ops::source::TypedSource>(),
+        TypeId::of::<tract_core::ops::konst::Const>(),
+        TypeId::of::<tract_core::ops::identity::PinConst>(),
+        TypeId::of::<DynSlice>(),
+        TypeId::of::<DynTile>(),
+        TypeId::of::<tract_core::ops::fft::Fft>(),
+        TypeId::of::<tract_core::ops::fft::Stft>(),
+        TypeId::of::<tract_core::ops::memory::force_eval::ForceEval>(),
+        TypeId::of::<tract_core::ops::memory::load::Load>(),
+        TypeId::of::<tract_core::ops::memory::store::Store>(),
+        TypeId::of::<tract_core::ops::submodel::SubmodelOp>(),
+        TypeId::of::<tract_core::ops::matmul::pack::OptMatMulPack>(),
+        TypeId::of::<tract_core::ops::matmul::optimized::OptMatMul>(),
+        TypeId::of::<tract_core::ops::binary::OptBinByScalar>(),
+        TypeId::of::<tract_core::ops::binary::OptBinUnicast>(),
+    ]);
+    for registry in &tract_nnef::nnef().with_tract_core().registries {
+        let missing = registry.from_tract.keys().filter(|k| !covered.contains(k)).count();
+        ensure!(missing == 0, "{missing} operators dumped by {} have no cycle test", registry.id.0);
+    }
+    Ok(())
+}
@@ -59,6 +59,8 @@ mod nnef_cycle {
         }
 
         fn prepare(&self, model: TypedModel) -> TractResult<Box<dyn Runnable>> {
+            let unsupported = self.0.unsupported_nodes(&model)?;
+            ensure!(unsupported.is_empty(), "Nodes can not be serialized to NNEF: {unsupported:?}");
             info!("Store to NNEF");
             let mut buffer = vec![];
             eprintln!("{model}");
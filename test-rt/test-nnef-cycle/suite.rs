@@ -1,6 +1,8 @@
 use infra::Test;
 use suite_unit::conv_q::{QConvProblem, QConvProblemParams};
 
+mod core_ops;
+
 pub fn suite() -> &'static infra::TestSuite {
     lazy_static::lazy_static! {
         static ref SUITE: infra::TestSuite  = mk_suite();
@@ -21,7 +23,10 @@ fn mk_suite() -> infra::TestSuite {
         compatible_conv_q,
     );
 
-    infra::TestSuite::default().with("onnx", onnx).with("unit", unit)
+    infra::TestSuite::default()
+        .with("onnx", onnx)
+        .with("unit", unit)
+        .with("core_ops", core_ops::suite())
 }
 
 fn ignore_onnx(t: &[String]) -> bool {
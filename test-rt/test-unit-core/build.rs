@@ -18,4 +18,16 @@ fn main() {
         "optimized()",
         "Approximation::Approximate",
     );
+    suite.test_runtime(
+        "parallel",
+        "suite_unit::suite().unwrap()",
+        "parallel()",
+        "Approximation::Approximate",
+    );
+    suite.test_runtime(
+        "memory_arena",
+        "suite_unit::suite().unwrap()",
+        "memory_arena()",
+        "Approximation::Approximate",
+    );
 }
@@ -53,3 +53,49 @@ mod optimized {
     }
     include!(concat!(env!("OUT_DIR"), "/tests/optimized.rs"));
 }
+
+mod parallel {
+    use super::*;
+
+    pub fn parallel() -> &'static ParallelRuntime {
+        &ParallelRuntime
+    }
+
+    #[derive(Debug)]
+    pub struct ParallelRuntime;
+
+    impl Runtime for ParallelRuntime {
+        fn name(&self) -> Cow<str> {
+            Cow::Borrowed("parallel")
+        }
+        fn prepare(&self, model: TypedModel) -> TractResult<Box<dyn Runnable>> {
+            let options = PlanOptions { parallel: true, ..PlanOptions::default() };
+            Ok(Box::new(Arc::new(model.into_optimized()?.into_runnable_with_options(&options)?)))
+        }
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/tests/parallel.rs"));
+}
+
+mod memory_arena {
+    use super::*;
+
+    pub fn memory_arena() -> &'static MemoryArenaRuntime {
+        &MemoryArenaRuntime
+    }
+
+    #[derive(Debug)]
+    pub struct MemoryArenaRuntime;
+
+    impl Runtime for MemoryArenaRuntime {
+        fn name(&self) -> Cow<str> {
+            Cow::Borrowed("memory_arena")
+        }
+        fn prepare(&self, model: TypedModel) -> TractResult<Box<dyn Runnable>> {
+            let options = PlanOptions { memory_arena: true, ..PlanOptions::default() };
+            Ok(Box::new(Arc::new(model.into_optimized()?.into_runnable_with_options(&options)?)))
+        }
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/tests/memory_arena.rs"));
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+
+use flexbuffers::{FlexBufferType, Reader};
+use tract_core::internal::*;
+
+/// A value decoded from the flexbuffer custom options of a CUSTOM operator.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum FlexValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    Blob(Vec<u8>),
+    Vector(Vec<FlexValue>),
+    Map(BTreeMap<String, FlexValue>),
+}
+
+impl FlexValue {
+    pub fn decode(bytes: &[u8]) -> TractResult<FlexValue> {
+        if bytes.is_empty() {
+            return Ok(FlexValue::Null);
+        }
+        let reader = Reader::get_root(bytes).map_err(|e| format_err!("{e:?}"))?;
+        Self::from_reader(&reader)
+    }
+
+    fn from_reader(reader: &Reader<&[u8]>) -> TractResult<FlexValue> {
+        use FlexBufferType::*;
+        let e = |e: flexbuffers::ReaderError| format_err!("Decoding flexbuffer: {e:?}");
+        Ok(match reader.flexbuffer_type() {
+            Null => FlexValue::Null,
+            Bool => FlexValue::Bool(reader.get_bool().map_err(e)?),
+            Int | IndirectInt => FlexValue::Int(reader.get_i64().map_err(e)?),
+            UInt | IndirectUInt => FlexValue::UInt(reader.get_u64().map_err(e)?),
+            Float | IndirectFloat => FlexValue::Float(reader.get_f64().map_err(e)?),
+            Key | String => FlexValue::String(reader.as_str().to_string()),
+            Blob => FlexValue::Blob(reader.get_blob().map_err(e)?.0.to_vec()),
+            Map => {
+                let map = reader.get_map().map_err(e)?;
+                let values = map
+                    .iter_keys()
+                    .zip(map.iter_values())
+                    .map(|(k, v)| Ok((k.to_string(), Self::from_reader(&v)?)))
+                    .collect::<TractResult<_>>()?;
+                FlexValue::Map(values)
+            }
+            t if t.is_vector() => {
+                let vector = reader.get_vector().map_err(e)?;
+                FlexValue::Vector(
+                    vector.iter().map(|v| Self::from_reader(&v)).collect::<TractResult<_>>()?,
+                )
+            }
+            t => bail!("Unsupported flexbuffer type {t:?}"),
+        })
+    }
+
+    /// Lookup a key, if this is a Map.
+    pub fn get(&self, key: &str) -> Option<&FlexValue> {
+        if let FlexValue::Map(map) = self {
+            map.get(key)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_i64(&self) -> TractResult<i64> {
+        match self {
+            FlexValue::Int(i) => Ok(*i),
+            FlexValue::UInt(u) => Ok(*u as i64),
+            FlexValue::Bool(b) => Ok(*b as i64),
+            _ => bail!("Expected an integer, got {self:?}"),
+        }
+    }
+
+    pub fn as_f64(&self) -> TractResult<f64> {
+        match self {
+            FlexValue::Float(f) => Ok(*f),
+            FlexValue::Int(i) => Ok(*i as f64),
+            FlexValue::UInt(u) => Ok(*u as f64),
+            _ => bail!("Expected a number, got {self:?}"),
+        }
+    }
+
+    pub fn as_bool(&self) -> TractResult<bool> {
+        match self {
+            FlexValue::Bool(b) => Ok(*b),
+            FlexValue::Int(i) => Ok(*i != 0),
+            FlexValue::UInt(u) => Ok(*u != 0),
+            _ => bail!("Expected a boolean, got {self:?}"),
+        }
+    }
+
+    /// Integer value for a key of a Map, or the default if the key is absent.
+    pub fn get_i64_or(&self, key: &str, default: i64) -> TractResult<i64> {
+        self.get(key).map(|v| v.as_i64()).transpose().map(|v| v.unwrap_or(default))
+    }
+
+    /// Required integer value for a key of a Map.
+    pub fn get_i64(&self, key: &str) -> TractResult<i64> {
+        self.get(key).with_context(|| format!("Missing {key} option"))?.as_i64()
+    }
+
+    /// Required number value for a key of a Map.
+    pub fn get_f64(&self, key: &str) -> TractResult<f64> {
+        self.get(key).with_context(|| format!("Missing {key} option"))?.as_f64()
+    }
+
+    /// Boolean value for a key of a Map, or the default if the key is absent.
+    pub fn get_bool_or(&self, key: &str, default: bool) -> TractResult<bool> {
+        self.get(key).map(|v| v.as_bool()).transpose().map(|v| v.unwrap_or(default))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flexbuffers::Builder;
+
+    fn options() -> Vec<u8> {
+        let mut builder = Builder::default();
+        let mut map = builder.start_map();
+        map.push("int", -3i64);
+        map.push("uint", 7u64);
+        map.push("float", 0.5f64);
+        map.push("bool", true);
+        map.push("string", "foo");
+        let mut vector = map.start_vector("vector");
+        vector.push(1i64);
+        vector.push(2.5f64);
+        vector.end_vector();
+        map.end_map();
+        builder.view().to_vec()
+    }
+
+    #[test]
+    fn decode_empty() -> TractResult<()> {
+        assert_eq!(FlexValue::decode(&[])?, FlexValue::Null);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_scalars() -> TractResult<()> {
+        assert_eq!(FlexValue::decode(&flexbuffers::singleton(12i64))?, FlexValue::Int(12));
+        assert_eq!(FlexValue::decode(&flexbuffers::singleton(1.5f64))?, FlexValue::Float(1.5));
+        assert_eq!(FlexValue::decode(&flexbuffers::singleton(false))?, FlexValue::Bool(false));
+        assert_eq!(
+            FlexValue::decode(&flexbuffers::singleton("bar"))?,
+            FlexValue::String("bar".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decode_map() -> TractResult<()> {
+        let value = FlexValue::decode(&options())?;
+        assert_eq!(value.get("int"), Some(&FlexValue::Int(-3)));
+        assert_eq!(value.get("uint"), Some(&FlexValue::UInt(7)));
+        assert_eq!(value.get("float"), Some(&FlexValue::Float(0.5)));
+        assert_eq!(value.get("bool"), Some(&FlexValue::Bool(true)));
+        assert_eq!(value.get("string"), Some(&FlexValue::String("foo".into())));
+        assert_eq!(
+            value.get("vector"),
+            Some(&FlexValue::Vector(vec![FlexValue::Int(1), FlexValue::Float(2.5)]))
+        );
+        assert_eq!(value.get("missing"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn accessors() -> TractResult<()> {
+        let value = FlexValue::decode(&options())?;
+        assert_eq!(value.get_i64("int")?, -3);
+        assert_eq!(value.get_i64("uint")?, 7);
+        assert_eq!(value.get_f64("int")?, -3.);
+        assert_eq!(value.get_f64("float")?, 0.5);
+        assert_eq!(value.get_i64_or("missing", 100)?, 100);
+        assert_eq!(value.get_i64_or("int", 100)?, -3);
+        assert!(value.get_bool_or("bool", false)?);
+        assert!(!value.get_bool_or("missing", false)?);
+        assert!(value.get_i64("missing").is_err());
+        assert!(value.get_i64("string").is_err());
+        assert!(value.get_f64("vector").is_err());
+        Ok(())
+    }
+}
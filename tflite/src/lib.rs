@@ -2,9 +2,10 @@
 #[macro_use]
 extern crate derive_new;
 
+mod flex;
 mod model;
 mod ops;
-mod registry;
+pub mod registry;
 pub mod rewriter;
 mod ser;
 mod tensors;
@@ -20,6 +21,7 @@ mod tensors;
 mod tflite_generated;
 pub use tflite_generated::tflite;
 
+pub use flex::FlexValue;
 pub use model::Tflite;
 
 pub mod prelude {
@@ -28,6 +30,8 @@ pub mod prelude {
 
 pub mod internal {
     pub use crate::model::TfliteProtoModel;
+    pub use crate::registry::{DeserOp, Registry};
+    pub use crate::FlexValue;
     pub use tract_core::internal::*;
     pub use tract_core;
 }
@@ -1,15 +1,18 @@
-use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 
 use flatbuffers::FlatBufferBuilder;
 use tract_core::internal::*;
 
-use crate::registry::Registry;
+use crate::flex::FlexValue;
+use crate::registry::{subgraph, DeserOp, Registry, Variables};
 use crate::tensors::{flat_tensor_to_tract_fact, flat_tensor_uses_per_axis_q};
 use crate::tflite;
 use crate::tflite::{Buffer, BufferArgs};
 
-pub struct Tflite(Registry);
+pub struct Tflite {
+    registry: Registry,
+    signature: Option<String>,
+}
 
 impl Debug for Tflite {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -21,7 +24,7 @@ impl Default for Tflite {
     fn default() -> Self {
         let mut registry = Registry::default();
         crate::ops::register_all(&mut registry);
-        Tflite(registry)
+        Tflite { registry, signature: None }
     }
 }
 
@@ -37,6 +40,16 @@ impl TfliteProtoModel {
     pub fn root(&self) -> tflite::Model {
         unsafe { tflite::root_as_model_unchecked(&self.0) }
     }
+
+    /// Keys of the SignatureDefs declared in the model.
+    pub fn signature_keys(&self) -> Vec<String> {
+        self.root()
+            .signature_defs()
+            .into_iter()
+            .flatten()
+            .filter_map(|sig| sig.signature_key().map(|s| s.to_string()))
+            .collect()
+    }
 }
 
 fn write_model<'fb>(
@@ -60,13 +73,37 @@ fn write_model<'fb>(
 }
 
 impl Tflite {
+    /// Use the SignatureDef named `key` as the model entry point instead of the first subgraph.
+    ///
+    /// Inputs and outputs are then named after the signature.
+    pub fn with_signature(mut self, key: impl Into<String>) -> Self {
+        self.signature = Some(key.into());
+        self
+    }
+
+    /// Register a translator for CUSTOM operators with the given custom code.
+    pub fn with_custom_op<T>(mut self, name: impl Into<String>, to: T) -> Self
+    where
+        T: Fn(&mut DeserOp, &FlexValue) -> TractResult<TVec<OutletId>> + Send + Sync + 'static,
+    {
+        self.registry.reg_custom_to_tract(name, to);
+        self
+    }
+
     pub fn write(&self, model: &TypedModel, mut w: impl std::io::Write) -> TractResult<()> {
-        let builder = write_model(&self.0, model)?;
+        let builder = write_model(&self.registry, model)?;
         w.write_all(builder.finished_data())?;
         Ok(())
     }
 }
 
+// (tensor index, signature name) pairs
+fn signature_io<'a>(
+    maps: Option<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<tflite::TensorMap<'a>>>>,
+) -> Vec<(i32, Option<&'a str>)> {
+    maps.into_iter().flatten().map(|map| (map.tensor_index() as i32, map.name())).collect()
+}
+
 impl Framework<TfliteProtoModel, TypedModel> for Tflite {
     fn proto_model_for_read(
         &self,
@@ -83,39 +120,54 @@ impl Framework<TfliteProtoModel, TypedModel> for Tflite {
         mut target: TypedModel,
     ) -> TractResult<TypedModel> {
         let root = proto.root();
-        let main = &root
-            .subgraphs()
-            .context("No subgraphs in Tflite model")?
-            .get(0);
+        let (main, inputs, outputs) = if let Some(key) = &self.signature {
+            let sig = root
+                .signature_defs()
+                .into_iter()
+                .flatten()
+                .find(|sig| sig.signature_key() == Some(key.as_str()))
+                .with_context(|| {
+                    format!(
+                        "No signature named {key:?} in Tflite model (found: {:?})",
+                        proto.signature_keys()
+                    )
+                })?;
+            (
+                subgraph(&root, sig.subgraph_index() as usize)
+                    .with_context(|| format!("Invalid subgraph for signature {key:?}"))?,
+                signature_io(sig.inputs()),
+                signature_io(sig.outputs()),
+            )
+        } else {
+            let main = subgraph(&root, 0)?;
+            let inputs = main.inputs().context("No inputs in Tflite model")?;
+            let outputs = main.outputs().context("No outputs in Tflite model")?;
+            (
+                main,
+                inputs.iter().map(|i| (i, None)).collect(),
+                outputs.iter().map(|o| (o, None)).collect(),
+            )
+        };
         let mut mapping = HashMap::new();
-        for input in main.inputs().context("No inputs in Tflite model")? {
-            if !flat_tensor_uses_per_axis_q(main, input) {
-                let (fact, name) = flat_tensor_to_tract_fact(&root, main, input)?;
-                let it = target.add_source(name, fact)?;
+        let mut variables = Variables::default();
+        for (input, sig_name) in inputs {
+            if !flat_tensor_uses_per_axis_q(&main, input) {
+                let (fact, name) = flat_tensor_to_tract_fact(&root, &main, &target.symbols, input)?;
+                let it = target.add_source(sig_name.unwrap_or(name), fact)?;
                 mapping.insert(input, it);
             }
         }
-        for op in main.operators().context("No operators in Tflite model")? {
-            for input in op.inputs().context("No input in Tflite  operator")? {
-                if let Entry::Vacant(slot) = mapping.entry(input) {
-                    let (fact, name) = flat_tensor_to_tract_fact(&root, main, input)?;
-                    let value = fact.konst.with_context(|| format!("Error in TF file for operator {:?}. No prior computation nor constant for input {}", op, input))?;
-                    let konst = target.add_const(name, value)?;
-                    slot.insert(konst);
+        self.registry.deser_subgraph(&root, &main, &mut target, &mut mapping, &mut variables)?;
+        let outputs: TVec<_> = outputs
+            .into_iter()
+            .map(|(o, sig_name)| {
+                let outlet = mapping[&o];
+                if let Some(name) = sig_name {
+                    target.set_outlet_label(outlet, name.to_string())?;
                 }
-            }
-            self.0
-                .deser_op(&root, main, &op, &mut target, &mut mapping)
-                .with_context(|| {
-                    format!("Translating proto-op from Tflite into tract op: {op:#?}")
-                })?;
-        }
-        let outputs: TVec<_> = main
-            .outputs()
-            .context("No outputs in Tflite model")?
-            .iter()
-            .map(|o| mapping[&o])
-            .collect();
+                Ok(outlet)
+            })
+            .collect::<TractResult<_>>()?;
         target.set_output_outlets(&outputs)?;
         Ok(target)
     }
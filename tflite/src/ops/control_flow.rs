@@ -0,0 +1,140 @@
+use tract_core::internal::*;
+use tract_core::ops::logic::IfThenElse;
+use tract_core::ops::scan::wire_while;
+
+use crate::registry::{subgraph, DeserOp, Registry};
+use crate::tflite::BuiltinOperator;
+
+/// Control flow operators.
+///
+/// WHILE loops are translated to a Scan when their trip count can be computed at load time, and
+/// evaluate their condition at runtime otherwise. Resource variables are frozen at load time, see
+/// [crate::registry::Variables].
+pub fn register_all(reg: &mut Registry) {
+    reg.reg_to_tract(BuiltinOperator::CALL_ONCE, de_call_once);
+    reg.reg_to_tract(BuiltinOperator::IF, de_if);
+    reg.reg_to_tract(BuiltinOperator::WHILE, de_while);
+
+    reg.reg_to_tract(BuiltinOperator::ASSIGN_VARIABLE, de_assign_variable);
+    reg.reg_to_tract(BuiltinOperator::READ_VARIABLE, de_read_variable);
+    reg.reg_to_tract(BuiltinOperator::VAR_HANDLE, de_var_handle);
+}
+
+fn de_call_once(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_call_once_options);
+    let init = subgraph(op.ctx.model, options.init_subgraph_index() as usize)?;
+    // the init subgraph has no inputs nor outputs: it is inlined for its side effects on
+    // variables
+    let mut mapping = HashMap::new();
+    let initializing = std::mem::replace(&mut op.ctx.variables.initializing, true);
+    let result = op.ctx.registry.deser_subgraph(
+        op.ctx.model,
+        &init,
+        op.ctx.target,
+        &mut mapping,
+        op.ctx.variables,
+    );
+    op.ctx.variables.initializing = initializing;
+    result?;
+    Ok(tvec!())
+}
+
+fn de_if(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_if_options);
+    let then_body = op.ctx.registry.subgraph_to_model(
+        op.ctx.model,
+        options.then_subgraph_index() as usize,
+        &op.ctx.target.symbols,
+        op.ctx.variables,
+    )?;
+    let else_body = op.ctx.registry.subgraph_to_model(
+        op.ctx.model,
+        options.else_subgraph_index() as usize,
+        &op.ctx.target.symbols,
+        op.ctx.variables,
+    )?;
+    ensure!(then_body.inputs.len() + 1 == op.inputs.len());
+    ensure!(else_body.inputs.len() + 1 == op.inputs.len());
+    let mut inputs: TVec<OutletId> = op.inputs.into();
+    if op.ctx.target.outlet_fact(inputs[0])?.rank() > 0 {
+        inputs[0] = op.ctx.target.wire_node(
+            format!("{}.cond", op.prefix),
+            AxisOp::Reshape(0, op.ctx.target.outlet_fact(inputs[0])?.shape.to_tvec(), tvec!()),
+            &inputs[0..1],
+        )?[0];
+    }
+    let ite = IfThenElse {
+        then_input_mapping: (1..op.inputs.len()).collect(),
+        then_body,
+        else_input_mapping: (1..op.inputs.len()).collect(),
+        else_body,
+    };
+    op.ctx.target.wire_node(op.prefix, ite, &inputs)
+}
+
+fn de_while(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_while_options);
+    let cond = op.ctx.registry.subgraph_to_model(
+        op.ctx.model,
+        options.cond_subgraph_index() as usize,
+        &op.ctx.target.symbols,
+        op.ctx.variables,
+    )?;
+    let body = op.ctx.registry.subgraph_to_model(
+        op.ctx.model,
+        options.body_subgraph_index() as usize,
+        &op.ctx.target.symbols,
+        op.ctx.variables,
+    )?;
+    ensure!(cond.inputs.len() == op.inputs.len());
+    ensure!(body.inputs.len() == op.inputs.len());
+    ensure!(body.outputs.len() == op.inputs.len());
+    wire_while(op.ctx.target, op.prefix, cond, body, op.inputs)
+}
+
+fn variable_key(op: &DeserOp, handle: OutletId) -> TractResult<String> {
+    let handle = op
+        .ctx
+        .target
+        .outlet_fact(handle)?
+        .konst
+        .clone()
+        .context("Variable handle must come from a VAR_HANDLE")?;
+    Ok(handle.to_scalar::<String>()?.clone())
+}
+
+fn de_var_handle(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let options = builtin!(op, builtin_options_as_var_handle_options);
+    let key =
+        format!("{}/{}", options.container().unwrap_or(""), options.shared_name().unwrap_or(""));
+    Ok(tvec!(op.ctx.target.add_const(op.prefix, tensor0(key))?))
+}
+
+fn de_assign_variable(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let key = variable_key(op, op.inputs[0])?;
+    ensure!(
+        op.ctx.variables.initializing,
+        "Unsupported ASSIGN_VARIABLE of {key} outside of a CALL_ONCE initialization subgraph: \
+         variables are frozen at load time"
+    );
+    let Some(value) = op.ctx.target.outlet_fact(op.inputs[1])?.konst.clone() else {
+        bail!(
+            "Unsupported ASSIGN_VARIABLE of a non-constant value to {key}: variables are frozen \
+             at load time"
+        )
+    };
+    op.ctx.variables.values.insert(key, value);
+    Ok(tvec!())
+}
+
+fn de_read_variable(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
+    let key = variable_key(op, op.inputs[0])?;
+    let value = op
+        .ctx
+        .variables
+        .values
+        .get(&key)
+        .cloned()
+        .with_context(|| format!("Variable {key} read before being assigned"))?;
+    Ok(tvec!(op.ctx.target.add_const(op.prefix, value)?))
+}
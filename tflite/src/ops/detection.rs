@@ -0,0 +1,222 @@
+use std::cmp::Ordering;
+
+use tract_core::internal::*;
+use tract_core::ndarray::{s, Array2, ArrayView2, Axis, Ix2, Ix3};
+use tract_core::ops::cast::wire_cast;
+use tract_onnx_opl::non_max_suppression::{select_boxes, BoxRepr};
+
+use crate::flex::FlexValue;
+use crate::registry::{DeserOp, Registry};
+
+// https://github.com/tensorflow/tensorflow/blob/master/tensorflow/lite/kernels/detection_postprocess.cc
+
+pub fn register_all(reg: &mut Registry) {
+    reg.reg_custom_to_tract("TFLite_Detection_PostProcess", de_detection_postprocess);
+}
+
+fn de_detection_postprocess(op: &mut DeserOp, options: &FlexValue) -> TractResult<TVec<OutletId>> {
+    ensure!(op.inputs.len() == 3, "TFLite_Detection_PostProcess expects 3 inputs");
+    let post = DetectionPostProcess {
+        max_detections: options.get_i64("max_detections")? as usize,
+        max_classes_per_detection: options.get_i64("max_classes_per_detection")? as usize,
+        detections_per_class: options.get_i64_or("detections_per_class", 100)? as usize,
+        use_regular_nms: options.get_bool_or("use_regular_nms", false)?,
+        nms_score_threshold: options.get_f64("nms_score_threshold")? as f32,
+        nms_iou_threshold: options.get_f64("nms_iou_threshold")? as f32,
+        num_classes: options.get_i64("num_classes")? as usize,
+        y_scale: options.get_f64("y_scale")? as f32,
+        x_scale: options.get_f64("x_scale")? as f32,
+        h_scale: options.get_f64("h_scale")? as f32,
+        w_scale: options.get_f64("w_scale")? as f32,
+    };
+    let inputs = wire_cast(op.prefix, op.ctx.target, op.inputs, f32::datum_type())?;
+    op.ctx.target.wire_node(op.prefix, post, &inputs)
+}
+
+/// Box decoding against anchors followed by non-max suppression, as performed at the end of
+/// SSD-like object detection models.
+///
+/// Inputs are box encodings [batch, anchors, 4], class scores [batch, anchors, classes] (with
+/// an optional leading background class) and anchors [anchors, 4] in (y, x, h, w) format.
+/// Outputs are boxes [batch, detections, 4] in (ymin, xmin, ymax, xmax) format, classes and
+/// scores [batch, detections] and the number of valid detections [batch].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectionPostProcess {
+    pub max_detections: usize,
+    pub max_classes_per_detection: usize,
+    pub detections_per_class: usize,
+    pub use_regular_nms: bool,
+    pub nms_score_threshold: f32,
+    pub nms_iou_threshold: f32,
+    pub num_classes: usize,
+    pub y_scale: f32,
+    pub x_scale: f32,
+    pub h_scale: f32,
+    pub w_scale: f32,
+}
+
+impl DetectionPostProcess {
+    fn output_len(&self) -> usize {
+        if self.use_regular_nms {
+            self.max_detections
+        } else {
+            self.max_detections * self.max_classes_per_detection
+        }
+    }
+
+    fn decode_boxes(&self, encodings: ArrayView2<f32>, anchors: ArrayView2<f32>) -> Array2<f32> {
+        let mut boxes = Array2::<f32>::zeros((encodings.nrows(), 4));
+        for ((enc, anchor), mut bx) in
+            encodings.outer_iter().zip(anchors.outer_iter()).zip(boxes.outer_iter_mut())
+        {
+            let yc = enc[0] / self.y_scale * anchor[2] + anchor[0];
+            let xc = enc[1] / self.x_scale * anchor[3] + anchor[1];
+            let half_h = 0.5 * (enc[2] / self.h_scale).exp() * anchor[2];
+            let half_w = 0.5 * (enc[3] / self.w_scale).exp() * anchor[3];
+            bx[0] = yc - half_h;
+            bx[1] = xc - half_w;
+            bx[2] = yc + half_h;
+            bx[3] = xc + half_w;
+        }
+        boxes
+    }
+
+    // (score, anchor, class) triplets, by decreasing score
+    fn fast_nms(
+        &self,
+        boxes: ArrayView2<f32>,
+        scores: ArrayView2<f32>,
+    ) -> Vec<(f32, usize, usize)> {
+        let per_anchor = self.max_classes_per_detection.min(self.num_classes);
+        let top_classes: Vec<Vec<(f32, usize)>> = scores
+            .outer_iter()
+            .map(|row| {
+                let mut classes: Vec<(f32, usize)> =
+                    row.iter().copied().enumerate().map(|(c, s)| (s, c)).collect();
+                classes.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+                classes.truncate(per_anchor);
+                classes
+            })
+            .collect();
+        let candidates = top_classes
+            .iter()
+            .enumerate()
+            .filter(|(_, classes)| {
+                classes.first().is_some_and(|(s, _)| *s >= self.nms_score_threshold)
+            })
+            .map(|(anchor, classes)| (classes[0].0, anchor))
+            .collect();
+        let selected = select_boxes(
+            BoxRepr::TwoPoints,
+            boxes,
+            candidates,
+            self.max_detections,
+            self.nms_iou_threshold,
+        );
+        selected
+            .into_iter()
+            .flat_map(|(_, anchor)| top_classes[anchor].iter().map(move |&(s, c)| (s, anchor, c)))
+            .collect()
+    }
+
+    fn regular_nms(
+        &self,
+        boxes: ArrayView2<f32>,
+        scores: ArrayView2<f32>,
+    ) -> Vec<(f32, usize, usize)> {
+        let mut detections = vec![];
+        for class in 0..self.num_classes {
+            let candidates = scores
+                .column(class)
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|(_, s)| *s >= self.nms_score_threshold)
+                .map(|(anchor, s)| (s, anchor))
+                .collect();
+            let selected = select_boxes(
+                BoxRepr::TwoPoints,
+                boxes,
+                candidates,
+                self.detections_per_class,
+                self.nms_iou_threshold,
+            );
+            detections.extend(selected.into_iter().map(|(s, anchor)| (s, anchor, class)));
+        }
+        detections.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        detections.truncate(self.max_detections);
+        detections
+    }
+}
+
+impl Op for DetectionPostProcess {
+    fn name(&self) -> Cow<str> {
+        "DetectionPostProcess".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for DetectionPostProcess {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        let (encodings, scores, anchors) = args_3!(inputs);
+        let encodings = encodings.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let scores = scores.to_array_view::<f32>()?.into_dimensionality::<Ix3>()?;
+        let anchors = anchors.to_array_view::<f32>()?.into_dimensionality::<Ix2>()?;
+        let batch = encodings.shape()[0];
+        let label_offset = scores.shape()[2]
+            .checked_sub(self.num_classes)
+            .context("Not enough columns in class scores")?;
+        let len = self.output_len();
+        let mut out_boxes = Tensor::zero::<f32>(&[batch, len, 4])?;
+        let mut out_classes = Tensor::zero::<f32>(&[batch, len])?;
+        let mut out_scores = Tensor::zero::<f32>(&[batch, len])?;
+        let mut out_count = Tensor::zero::<f32>(&[batch])?;
+        let mut out_boxes_view = out_boxes.to_array_view_mut::<f32>()?;
+        let mut out_classes_view = out_classes.to_array_view_mut::<f32>()?;
+        let mut out_scores_view = out_scores.to_array_view_mut::<f32>()?;
+        let mut out_count_view = out_count.to_array_view_mut::<f32>()?;
+        for b in 0..batch {
+            let boxes = self.decode_boxes(encodings.index_axis(Axis(0), b), anchors.view());
+            let scores = scores.slice(s![b, .., label_offset..]);
+            let detections = if self.use_regular_nms {
+                self.regular_nms(boxes.view(), scores)
+            } else {
+                self.fast_nms(boxes.view(), scores)
+            };
+            for (ix, (score, anchor, class)) in detections.iter().take(len).enumerate() {
+                out_boxes_view.slice_mut(s![b, ix, ..]).assign(&boxes.row(*anchor));
+                out_classes_view[[b, ix]] = *class as f32;
+                out_scores_view[[b, ix]] = *score;
+            }
+            out_count_view[[b]] = detections.len().min(len) as f32;
+        }
+        Ok(tvec!(
+            out_boxes.into_tvalue(),
+            out_classes.into_tvalue(),
+            out_scores.into_tvalue(),
+            out_count.into_tvalue()
+        ))
+    }
+}
+
+impl TypedOp for DetectionPostProcess {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        ensure!(inputs.len() == 3);
+        ensure!(inputs[0].rank() == 3 && inputs[1].rank() == 3 && inputs[2].rank() == 2);
+        let batch = inputs[0].shape[0].clone();
+        let len = self.output_len().to_dim();
+        Ok(tvec!(
+            f32::fact([batch.clone(), len.clone(), 4.to_dim()]),
+            f32::fact([batch.clone(), len.clone()]),
+            f32::fact([batch.clone(), len]),
+            f32::fact([batch]),
+        ))
+    }
+
+    as_op!();
+}
@@ -23,13 +23,19 @@ macro_rules! builtin {
 
 mod array;
 mod cnn;
+mod control_flow;
+mod detection;
 mod element_wise;
 mod math;
 mod nn;
 
+pub(crate) use nn::FullyConnected;
+
 pub fn register_all(reg: &mut Registry) {
     array::register_all(reg);
     cnn::register_all(reg);
+    control_flow::register_all(reg);
+    detection::register_all(reg);
     element_wise::register_all(reg);
     math::register_all(reg);
     nn::register_all(reg);
@@ -45,7 +51,13 @@ fn wire_fused_activation(
 ) -> TractResult<TVec<OutletId>> {
     let prefix = format!("{}.fused", op.prefix);
     let mut op = DeserOp {
-        ctx: DeserContext { model: op.ctx.model, subgraph: op.ctx.subgraph, target: op.ctx.target },
+        ctx: DeserContext {
+            registry: op.ctx.registry,
+            model: op.ctx.model,
+            subgraph: op.ctx.subgraph,
+            target: op.ctx.target,
+            variables: op.ctx.variables,
+        },
         prefix: &prefix,
         flat: op.flat,
         inputs: wires,
@@ -77,11 +89,7 @@ fn linearops_quantization_suport(
             rctensor0(k_qp.scale().unwrap().get(0))
         };
         let k_zp = k_qp.zero_point().unwrap().iter().map(|i| i as i32).collect_vec();
-        let k_zp = if k_zp.iter().all_equal() {
-            tensor0(k_zp[0])
-        } else {
-            tensor1(&k_zp)
-        };
+        let k_zp = if k_zp.iter().all_equal() { tensor0(k_zp[0]) } else { tensor1(&k_zp) };
         inputs.push(op.ctx.target.add_const(format!("{p}.i0"), rctensor0(iqp.zp_scale().0))?);
         inputs.push(op.ctx.target.add_const(format!("{p}.iscale"), rctensor0(iqp.zp_scale().1))?);
         inputs.push(op.ctx.target.add_const(format!("{p}.k0"), k_zp.into_arc_tensor())?);
@@ -1,8 +1,8 @@
 use tract_core::internal::*;
 use tract_core::ops as core;
 use tract_core::ops::cast::wire_cast;
-use tract_core::ops::change_axes::wire_with_rank_broadcast;
 use tract_core::ops::cast::Cast;
+use tract_core::ops::change_axes::wire_with_rank_broadcast;
 use tract_core::ops::einsum::BasicMatMul;
 use tract_core::ops::einsum::EinSum;
 use tract_core::ops::math::add;
@@ -20,15 +20,18 @@ use crate::tflite::BatchMatMulOptionsArgs;
 use crate::tflite::BuiltinOptions;
 use crate::tflite::ExpandDimsOptions;
 use crate::tflite::ExpandDimsOptionsArgs;
+use crate::tflite::FullyConnectedOptions;
+use crate::tflite::FullyConnectedOptionsArgs;
 use crate::tflite::ReducerOptions;
 use crate::tflite::ReducerOptionsArgs;
 use crate::tflite::SoftmaxOptions;
 use crate::tflite::SoftmaxOptionsArgs;
 use crate::tflite::TensorType;
-use crate::tflite::{BuiltinOperator, FullyConnectedOptionsWeightsFormat};
+use crate::tflite::{ActivationFunctionType, BuiltinOperator, FullyConnectedOptionsWeightsFormat};
 
 pub fn register_all(reg: &mut Registry) {
     reg.reg_to_tflite(ser_matmul);
+    reg.reg_to_tflite(ser_fully_connected);
     reg.reg_to_tract(BuiltinOperator::BATCH_MATMUL, de_batch_matmul);
 
     reg.reg_to_tract(BuiltinOperator::FULLY_CONNECTED, de_fully_connected);
@@ -49,23 +52,37 @@ pub fn register_all(reg: &mut Registry) {
 fn de_batch_matmul(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
     let (a, b) = args_2!(op.facts()?);
     let options = builtin!(op, builtin_options_as_batch_mat_mul_options);
-    ensure!(a.datum_type.is_float());
     ensure!(!options.asymmetric_quantize_inputs());
     ensure!(a.rank() == b.rank());
     let rank = a.rank();
+    // quantized variant gets a bias and the 6 quantization parameters as extra inputs
+    let input_count = if a.datum_type.is_float() { 2 } else { 9 };
     let mut axes = tvec!(
-        Axis::new('M', 2, 1).input(0, rank - 2 + options.adj_x() as usize).output(0, rank - 2),
-        Axis::new('N', 2, 1).input(1, rank - 1 - options.adj_y() as usize).output(0, rank - 1),
-        Axis::new('K', 2, 1)
+        Axis::new('M', input_count, 1)
+            .input(0, rank - 2 + options.adj_x() as usize)
+            .output(0, rank - 2),
+        Axis::new('N', input_count, 1)
+            .input(1, rank - 1 - options.adj_y() as usize)
+            .output(0, rank - 1),
+        Axis::new('K', input_count, 1)
             .input(0, rank - 1 - options.adj_x() as usize)
             .input(1, rank - 2 + options.adj_y() as usize)
     );
     for (ix, repr) in ('a'..).take(rank - 2).enumerate() {
-        axes.push(Axis::new(repr, 2, 1).input(0, ix).input(1, ix).output(0, ix));
+        axes.push(Axis::new(repr, input_count, 1).input(0, ix).input(1, ix).output(0, ix));
+    }
+    let axes: AxesMapping = AxesMapping::new(input_count, 1, axes)?;
+    if a.datum_type.is_float() {
+        let einsum = EinSum { axes, q_params: None, operating_dt: a.datum_type };
+        op.ctx.target.wire_node(op.prefix, einsum, op.inputs)
+    } else {
+        let mut inputs: TVec<OutletId> = op.inputs.into();
+        inputs.push(op.ctx.target.add_const(format!("{}.bias", op.prefix), rctensor0(0i32))?);
+        let qp = super::linearops_quantization_suport(op, &a, &mut inputs)?;
+        ensure!(qp.is_some(), "Integer BATCH_MATMUL must have a quantized output");
+        let einsum = EinSum { axes, q_params: qp, operating_dt: i32::datum_type() };
+        op.ctx.target.wire_node(op.prefix, einsum, &inputs)
     }
-    let axes: AxesMapping = AxesMapping::new(2, 1, axes)?;
-    let einsum = EinSum { axes, q_params: None, operating_dt: a.datum_type };
-    op.ctx.target.wire_node(op.prefix, einsum, op.inputs)
 }
 
 fn de_fully_connected(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
@@ -95,6 +112,13 @@ fn de_fully_connected(op: &mut DeserOp) -> TractResult<TVec<OutletId>> {
         }
         wires
     } else {
+        if bias.datum_type.is_quantized() {
+            inputs[2] = op.ctx.target.wire_node(
+                format!("{}.cast_bias", op.prefix),
+                Cast { to: bias.datum_type.unquantized() },
+                &[inputs[2]],
+            )?[0];
+        }
         let qp = super::linearops_quantization_suport(op, &input, &mut inputs)?;
         let axes = "BI,OI,O,,,,,,->BO".parse()?;
         let einsum = EinSum { axes, q_params: qp, operating_dt: i32::datum_type() };
@@ -216,15 +240,130 @@ fn ser_matmul(
         builder.fb(),
         &BatchMatMulOptionsArgs { adj_x, adj_y, asymmetric_quantize_inputs: false },
     );
+    let version = match model.outlet_fact(node.inputs[0])?.datum_type.unquantized() {
+        DatumType::I8 => 2,
+        DatumType::I16 => 3,
+        _ => 1,
+    };
     builder.write_op_with_options(
         &inputs,
         &output,
-        BuiltinOp::new(126, 1, BuiltinOperator::BATCH_MATMUL, BuiltinOptions::BatchMatMulOptions),
+        BuiltinOp::new(
+            126,
+            version,
+            BuiltinOperator::BATCH_MATMUL,
+            BuiltinOptions::BatchMatMulOptions,
+        ),
         options.as_union_value(),
     )?;
     Ok(())
 }
 
+/// Quantized EinSum with a fully connected layout (BI,OI,O->BO).
+///
+/// The tflite rewriter substitutes it to the EinSum before the translation to matrix
+/// multiplications, as BATCH_MATMUL can not carry the bias.
+#[derive(Clone, Debug, Hash)]
+pub struct FullyConnected(pub EinSum);
+
+impl Op for FullyConnected {
+    fn name(&self) -> Cow<str> {
+        "FullyConnected".into()
+    }
+
+    op_as_typed_op!();
+}
+
+impl EvalOp for FullyConnected {
+    fn is_stateless(&self) -> bool {
+        true
+    }
+
+    fn eval(&self, inputs: TVec<TValue>) -> TractResult<TVec<TValue>> {
+        self.0.eval(inputs)
+    }
+}
+
+impl TypedOp for FullyConnected {
+    fn output_facts(&self, inputs: &[&TypedFact]) -> TractResult<TVec<TypedFact>> {
+        self.0.output_facts(inputs)
+    }
+
+    as_op!();
+}
+
+fn ser_fully_connected(
+    builder: &mut SubgraphBuilder,
+    model: &TypedModel,
+    node: &TypedNode,
+    op: &FullyConnected,
+) -> TractResult<()> {
+    let facts = model.node_input_facts(node.id)?;
+    // 0 1 2 3  4  5  6  7  8
+    // x w b x0 xs k0 ks y0 ys
+    let konst = |ix: usize| {
+        facts[ix]
+            .konst
+            .as_ref()
+            .with_context(|| format!("FullyConnected input {ix} must be constant"))
+    };
+    let iscale = konst(4)?.cast_to_scalar::<f32>()?;
+    let co = facts[1].shape[0].to_usize()?;
+    // per-layer or per-channel
+    let k0 = konst(5)?.cast_to::<i32>()?.as_slice::<i32>()?.iter().map(|k| *k as i64).collect_vec();
+    let kscale = konst(6)?.cast_to::<f32>()?.as_slice::<f32>()?.to_vec();
+    ensure!(
+        facts[1].datum_type.unquantized() != i8::datum_type() || k0.iter().all(|k| *k == 0),
+        "int8 fully connected weights must have a zero zero point, got {k0:?}"
+    );
+    let node_name = &node.name;
+    let mut inputs = tvec!(builder.map_outlet(model, node.inputs[0])?);
+    inputs.push(builder.write_fact_with_per_axis_q(
+        format!("{node_name}.weights"),
+        konst(1)?,
+        &k0,
+        &kscale,
+        0,
+    )?);
+    let bias = konst(2)?.cast_to::<i32>()?.into_owned();
+    let bias = if bias.rank() == 0 {
+        bias.broadcast_scalar_to_shape(&[co])?
+    } else {
+        bias.into_shape(&[co])?
+    };
+    let bscale = kscale.iter().map(|k| k * iscale).collect_vec();
+    inputs.push(builder.write_fact_with_per_axis_q(
+        format!("{node_name}.bias"),
+        bias,
+        &vec![0i64; bscale.len()],
+        &bscale,
+        0,
+    )?);
+    let output = builder.outlets_to_tensors[&node.id.into()];
+    let options = FullyConnectedOptions::create(
+        builder.fb(),
+        &FullyConnectedOptionsArgs {
+            fused_activation_function: ActivationFunctionType::NONE,
+            weights_format: FullyConnectedOptionsWeightsFormat::DEFAULT,
+            keep_num_dims: false,
+            asymmetric_quantize_inputs: false,
+        },
+    );
+    let version =
+        if op.0.q_params.is_some_and(|qp| qp.unquantized() == i8::datum_type()) { 4 } else { 1 };
+    builder.write_op_with_options(
+        &inputs,
+        &[output],
+        BuiltinOp::new(
+            9,
+            version,
+            BuiltinOperator::FULLY_CONNECTED,
+            BuiltinOptions::FullyConnectedOptions,
+        ),
+        options.as_union_value(),
+    )
+}
+
 fn ser_reduce(
     builder: &mut SubgraphBuilder,
     model: &TypedModel,
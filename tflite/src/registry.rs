@@ -1,11 +1,16 @@
 use std::any::TypeId;
+use std::collections::hash_map::Entry;
 
 use tract_core::internal::*;
 
+use crate::flex::FlexValue;
 use crate::ser::SubgraphBuilder;
+use crate::tensors::flat_tensor_to_tract_fact;
 use crate::tflite::{BuiltinOperator, Model, Operator, SubGraph};
 
 pub type ToTract = Box<dyn Fn(&mut DeserOp) -> TractResult<TVec<OutletId>> + Send + Sync + 'static>;
+pub type ToTractCustom =
+    Box<dyn Fn(&mut DeserOp, &FlexValue) -> TractResult<TVec<OutletId>> + Send + Sync + 'static>;
 pub type ToTflite<T> = fn(&mut SubgraphBuilder, &TypedModel, &TypedNode, &T) -> TractResult<()>;
 pub type ToTfliteRaw = Box<
     dyn Fn(&mut SubgraphBuilder, &TypedModel, &TypedNode) -> TractResult<()>
@@ -17,13 +22,28 @@ pub type ToTfliteRaw = Box<
 #[derive(Default)]
 pub struct Registry {
     pub to_tract: HashMap<i32, ToTract>,
+    pub to_tract_custom: HashMap<String, ToTractCustom>,
     pub to_tflite: HashMap<TypeId, ToTfliteRaw>,
 }
 
+/// Resource variables.
+///
+/// Variables are frozen at load time: they can only be assigned constant values, by the
+/// initialization subgraphs of CALL_ONCE operators, and READ_VARIABLE is translated to the
+/// constant value.
+#[derive(Clone, Debug, Default)]
+pub struct Variables {
+    pub values: HashMap<String, Arc<Tensor>>,
+    /// Set while translating a CALL_ONCE initialization subgraph.
+    pub initializing: bool,
+}
+
 pub struct DeserContext<'ctx> {
+    pub registry: &'ctx Registry,
     pub model: &'ctx Model<'ctx>,
     pub subgraph: &'ctx SubGraph<'ctx>,
     pub target: &'ctx mut TypedModel,
+    pub variables: &'ctx mut Variables,
 }
 
 pub struct DeserOp<'op> {
@@ -43,6 +63,13 @@ impl DeserOp<'_> {
     }
 }
 
+/// The subgraph at `index`, failing on invalid indices.
+pub fn subgraph<'m>(model: &Model<'m>, index: usize) -> TractResult<SubGraph<'m>> {
+    let subgraphs = model.subgraphs().context("No subgraphs in Tflite model")?;
+    ensure!(index < subgraphs.len(), "Invalid subgraph index {index}");
+    Ok(subgraphs.get(index))
+}
+
 impl Registry {
     pub fn reg_to_tflite<T: Op>(&mut self, tflite: ToTflite<T>) {
         self.to_tflite.insert(
@@ -58,6 +85,70 @@ impl Registry {
         self.to_tract.insert(op.0, Box::new(to));
     }
 
+    /// Register a translator for CUSTOM operators with the given custom code.
+    ///
+    /// The translator is given the decoded flexbuffer custom options.
+    pub fn reg_custom_to_tract<T>(&mut self, name: impl Into<String>, to: T)
+    where
+        T: Fn(&mut DeserOp, &FlexValue) -> TractResult<TVec<OutletId>> + Send + Sync + 'static,
+    {
+        self.to_tract_custom.insert(name.into(), Box::new(to));
+    }
+
+    /// Translate a subgraph to a standalone TypedModel, with a source for each subgraph input.
+    pub fn subgraph_to_model(
+        &self,
+        model: &Model,
+        subgraph_index: usize,
+        symbols: &SymbolScope,
+        variables: &mut Variables,
+    ) -> TractResult<TypedModel> {
+        let subgraph = subgraph(model, subgraph_index)?;
+        let mut target = TypedModel { symbols: symbols.clone(), ..TypedModel::default() };
+        let mut mapping = HashMap::new();
+        for input in subgraph.inputs().context("No inputs in Tflite subgraph")? {
+            let (fact, name) = flat_tensor_to_tract_fact(model, &subgraph, symbols, input)?;
+            let it = target.add_source(name, fact.without_value())?;
+            mapping.insert(input, it);
+        }
+        self.deser_subgraph(model, &subgraph, &mut target, &mut mapping, variables)?;
+        let outputs: TVec<_> = subgraph
+            .outputs()
+            .context("No outputs in Tflite subgraph")?
+            .iter()
+            .map(|o| mapping[&o])
+            .collect();
+        target.set_output_outlets(&outputs)?;
+        Ok(target)
+    }
+
+    /// Translate all operators of a subgraph into target, using and updating the mapping from
+    /// tflite tensor ids to outlets.
+    pub fn deser_subgraph(
+        &self,
+        model: &Model,
+        subgraph: &SubGraph,
+        target: &mut TypedModel,
+        mapping: &mut HashMap<i32, OutletId>,
+        variables: &mut Variables,
+    ) -> TractResult<()> {
+        for op in subgraph.operators().context("No operators in Tflite subgraph")? {
+            for input in op.inputs().context("No input in Tflite  operator")? {
+                if let Entry::Vacant(slot) = mapping.entry(input) {
+                    let (fact, name) =
+                        flat_tensor_to_tract_fact(model, subgraph, &target.symbols, input)?;
+                    let value = fact.konst.with_context(|| format!("Error in TF file for operator {:?}. No prior computation nor constant for input {}", op, input))?;
+                    let konst = target.add_const(name, value)?;
+                    slot.insert(konst);
+                }
+            }
+            self.deser_op(model, subgraph, &op, target, mapping, variables).with_context(|| {
+                format!("Translating proto-op from Tflite into tract op: {op:#?}")
+            })?;
+        }
+        Ok(())
+    }
+
     pub fn deser_op(
         &self,
         model: &Model,
@@ -65,11 +156,11 @@ impl Registry {
         flat_op: &Operator,
         target: &mut TypedModel,
         mapping: &mut HashMap<i32, OutletId>,
+        variables: &mut Variables,
     ) -> TractResult<()> {
         let inputs: TVec<OutletId> =
             flat_op.inputs().unwrap().iter().map(|o| mapping[&o]).collect();
         let tensors = subgraph.tensors().unwrap();
-        let prefix = tensors.get(flat_op.outputs().unwrap().get(0) as usize).name().unwrap();
         let opcode_index = flat_op.opcode_index();
         let operator_code = model.operator_codes().unwrap().get(opcode_index as _);
         let opcode = if operator_code.deprecated_builtin_code() as i32
@@ -79,20 +170,51 @@ impl Registry {
         } else {
             operator_code.deprecated_builtin_code() as i32
         };
-        let ctx = DeserContext { model, subgraph, target };
-        let results = if let Some(op) = self.to_tract.get(&opcode) {
-            let output_facts = flat_op
+        // operators with no output (like ASSIGN_VARIABLE) are named after their opcode
+        let prefix = if let Some(output) = flat_op.outputs().unwrap().iter().next() {
+            tensors.get(output as usize).name().unwrap().to_string()
+        } else {
+            let name = BuiltinOperator(opcode).variant_name().unwrap_or("UNKNOWN");
+            target.unique_name(name.to_lowercase()).to_string()
+        };
+        let symbols = target.symbols.clone();
+        let output_facts = || {
+            flat_op
                 .outputs()
                 .unwrap()
                 .iter()
-                .map(|t| Ok(crate::tensors::flat_tensor_to_tract_fact(model, subgraph, t)?.0))
-                .collect::<TractResult<TVec<TypedFact>>>()?;
+                .map(|t| Ok(flat_tensor_to_tract_fact(model, subgraph, &symbols, t)?.0))
+                .collect::<TractResult<TVec<TypedFact>>>()
+        };
+        let custom = if opcode == BuiltinOperator::CUSTOM.0 {
+            operator_code.custom_code().and_then(|code| self.to_tract_custom.get(code))
+        } else {
+            None
+        };
+        let results = if let Some(op) = custom {
+            let options =
+                FlexValue::decode(flat_op.custom_options().map(|o| o.bytes()).unwrap_or_default())
+                    .context("Decoding custom options")?;
+            let ctx = DeserContext { registry: self, model, subgraph, target, variables };
+            (op)(
+                &mut DeserOp {
+                    ctx,
+                    prefix: &prefix,
+                    flat: flat_op,
+                    inputs: &inputs,
+                    output_facts: &output_facts()?,
+                },
+                &options,
+            )
+            .with_context(|| format!("Custom operator is {operator_code:#?}"))?
+        } else if let Some(op) = self.to_tract.get(&opcode) {
+            let ctx = DeserContext { registry: self, model, subgraph, target, variables };
             (op)(&mut DeserOp {
                 ctx,
-                prefix,
+                prefix: &prefix,
                 flat: flat_op,
                 inputs: &inputs,
-                output_facts: &output_facts,
+                output_facts: &output_facts()?,
             })
             .with_context(|| format!("Opcode is {operator_code:#?}"))?
         } else {
@@ -2,13 +2,16 @@ use tract_core::internal::*;
 use tract_core::ops::array::{Pad, PadMode};
 use tract_core::ops::cnn::{rewrite_conv_with_n_axis, KernelFormat, MaxPool, PoolSpec, SumPool};
 use tract_core::ops::cnn::{Conv, PaddingSpec};
-use tract_core::ops::einsum::BasicMatMul;
+use tract_core::ops::einsum::{BasicMatMul, EinSum};
 use tract_core::ops::element_wise::ElementWiseOp;
 use tract_core::ops::math::Recip;
 use tract_core::ops::nn::{expand_mean_of_squares, DataFormat, Softmax};
 use tract_core::tract_data::itertools::Itertools;
 
+use crate::ops::FullyConnected;
+
 pub fn rewrite_for_tflite(model: &mut TypedModel) -> TractResult<()> {
+    Rewriter::default().with_rule_for("quantized_einsum", quantized_einsum).rewrite(&(), model)?;
     tract_core::ops::einsum::rewrite_einsums_as_matmul(model)?;
     Rewriter::default()
         .with_rule_for("trivial_axes_around_matmul", trivial_axes_around_matmul)
@@ -28,6 +31,71 @@ pub fn rewrite_for_tflite(model: &mut TypedModel) -> TractResult<()> {
     tract_core::optim::Optimizer::prop_consts().optimize(model)
 }
 
+fn quantized_einsum(
+    _ctx: &(),
+    model: &TypedModel,
+    node: &TypedNode,
+    _name: &str,
+    op: &EinSum,
+) -> TractResult<Option<TypedModelPatch>> {
+    let Some(qp) = op.q_params else { return Ok(None) };
+    ensure!(node.inputs.len() == 9, "Expected 9 inputs for quantized EinSum");
+    let facts = model.node_input_facts(node.id)?;
+    let qparams: Vec<&Tensor> = facts[3..9]
+        .iter()
+        .map(|f| f.konst.as_deref().context("tflite requires constant quantization parameters"))
+        .try_collect()?;
+    // tflite tensors carry their quantization parameters
+    let check = |dt: DatumType, zp: &Tensor, scale: &Tensor, what: &str| -> TractResult<()> {
+        let (zp, scale) = (zp.cast_to_scalar::<i32>()?, scale.cast_to_scalar::<f32>()?);
+        ensure!(
+            dt.qparams() == Some(QParams::ZpScale { zero_point: zp, scale }),
+            "EinSum {what} type {dt:?} does not match quantization parameters (zp: {zp}, scale: {scale})"
+        );
+        Ok(())
+    };
+    check(facts[0].datum_type, qparams[0], qparams[1], "input")?;
+    check(qp, qparams[4], qparams[5], "output")?;
+    let axes = |io: InOut| op.axes.axes(io).map(|a| a.repr).collect_vec();
+    let (a, b, bias, c) =
+        (axes(InOut::In(0)), axes(InOut::In(1)), axes(InOut::In(2)), axes(InOut::Out(0)));
+    let fully_connected = a.len() == 2
+        && b.len() == 2
+        && a[1] == b[1]
+        && c == [a[0], b[0]]
+        && (bias.is_empty() || bias == [b[0]])
+        && facts[1].konst.is_some()
+        && facts[2].konst.is_some();
+    if fully_connected {
+        // tflite int8 kernels assume symmetric weights
+        let k0 = qparams[2].cast_to::<i32>()?;
+        let k0 = k0.as_slice::<i32>()?;
+        ensure!(
+            facts[1].datum_type.unquantized() != i8::datum_type() || k0.iter().all(|k| *k == 0),
+            "int8 fully connected weights must have a zero zero point, got {k0:?}"
+        );
+        return Ok(Some(TypedModelPatch::replace_single_op(
+            model,
+            node,
+            &node.inputs,
+            FullyConnected(op.clone()),
+        )?));
+    }
+    // other quantized EinSums are translated to BATCH_MATMUL, which has no bias
+    let bias_is_zero = facts[2]
+        .konst
+        .as_ref()
+        .map(|b| b.cast_to::<i32>().map(|b| b.as_slice::<i32>().unwrap().iter().all(|x| *x == 0)))
+        .transpose()?
+        .unwrap_or(false);
+    ensure!(
+        bias_is_zero,
+        "Quantized EinSum with a bias are only supported as fully connected layers"
+    );
+    check(facts[1].datum_type, qparams[2], qparams[3], "second input")?;
+    Ok(None)
+}
+
 fn trivial_axes_around_matmul(
     _ctx: &(),
     model: &TypedModel,
@@ -43,7 +111,7 @@ fn trivial_axes_around_matmul(
     let trivial_axes = (0..rank - 2)
         .filter(|axis| facts[0].shape[*axis].is_one() && facts[1].shape[*axis].is_one())
         .collect_vec();
-    
+
     ensure!(!trivial_axes.is_empty(), "Found Einsum with 4 > axes and no trivial axes");
     let mut patch = TypedModelPatch::default();
     let mut wire = patch.taps(model, &node.inputs)?;
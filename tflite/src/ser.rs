@@ -213,7 +213,16 @@ impl<'f, 'b, 'mb> SubgraphBuilder<'f, 'b, 'mb> {
         } else {
             0
         };
-        let shape = fact.shape.as_concrete().unwrap().iter().map(|d| *d as i32).collect_vec();
+        // symbolic dimensions are written as -1 in the shape signature, and 1 in the shape
+        let shape_signature = if fact.shape.is_concrete() {
+            None
+        } else {
+            let signature =
+                fact.shape.iter().map(|d| d.to_i64().map(|d| d as i32).unwrap_or(-1)).collect_vec();
+            Some(self.fb().create_vector(&signature))
+        };
+        let shape =
+            fact.shape.iter().map(|d| d.to_i64().map(|d| d as i32).unwrap_or(1)).collect_vec();
         let shape = self.fb().create_vector(&shape);
         let name = self.fb().create_string(name.as_ref());
         let tensor = Tensor::create(
@@ -226,7 +235,7 @@ impl<'f, 'b, 'mb> SubgraphBuilder<'f, 'b, 'mb> {
                 shape: Some(shape),
                 type_: fact.datum_type.try_into()?,
                 sparsity: None,
-                shape_signature: None,
+                shape_signature,
                 has_rank: true,
                 variant_tensors: None,
             },
@@ -100,9 +100,22 @@ pub fn per_axis_q_params<'m>(
     Ok((zp.iter().map(|i| i as i32).collect_vec(), scale.iter().collect_vec()))
 }
 
+/// Dimension of a tensor shape signature.
+///
+/// Dynamic dimensions (-1) are mapped to symbols named after their axis, so inputs and outputs
+/// sharing a dynamic axis (typically the batch) share the same symbol.
+fn signature_dim(symbols: &SymbolScope, axis: usize, dim: i32) -> TDim {
+    if dim < 0 {
+        symbols.sym(&format!("d{axis}")).to_dim()
+    } else {
+        dim.to_dim()
+    }
+}
+
 pub fn flat_tensor_to_tract_fact<'m>(
     &model: &'m Model<'m>,
     graph: &'m SubGraph<'m>,
+    symbols: &SymbolScope,
     id: i32,
 ) -> TractResult<(TypedFact, &'m str)> {
     let flat = graph.tensors().unwrap().get(id as _);
@@ -112,7 +125,21 @@ pub fn flat_tensor_to_tract_fact<'m>(
             dt = dt.quantize(QParams::ZpScale { zero_point: zp.get(0) as _, scale: scale.get(0) })
         }
     }
-    let mut fact = dt.fact(flat.shape().unwrap().iter().map(|d| d as usize).collect_vec());
+    // only the subgraph inputs and outputs get symbols: operators compute the other shapes
+    let interface = graph.inputs().is_some_and(|i| i.iter().any(|i| i == id))
+        || graph.outputs().is_some_and(|o| o.iter().any(|o| o == id));
+    let signature = flat.shape_signature().filter(|s| interface && s.iter().any(|d| d < 0));
+    let mut fact = if let Some(signature) = signature {
+        dt.fact(
+            signature
+                .iter()
+                .enumerate()
+                .map(|(axis, d)| signature_dim(symbols, axis, d))
+                .collect_vec(),
+        )
+    } else {
+        dt.fact(flat.shape().unwrap().iter().map(|d| d as usize).collect_vec())
+    };
     let buffer_ix = flat.buffer() as usize;
     if buffer_ix != 0 {
         let buffer = model.buffers().unwrap().get(flat.buffer() as usize);
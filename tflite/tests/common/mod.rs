@@ -0,0 +1,333 @@
+//! Minimal hand-written Tflite models, for the features the tract writer does not produce.
+#![allow(dead_code)]
+use flatbuffers::{FlatBufferBuilder, UnionWIPOffset, WIPOffset};
+use tract_tflite::internal::*;
+use tract_tflite::tflite;
+use tract_tflite::tflite::{BuiltinOperator, BuiltinOptions, TensorType};
+
+pub struct TensorDef {
+    pub name: String,
+    pub type_: TensorType,
+    pub shape: Vec<i32>,
+    pub shape_signature: Option<Vec<i32>>,
+    pub data: Option<Vec<u8>>,
+    /// zero points and scales
+    pub quantization: Option<(Vec<i64>, Vec<f32>)>,
+}
+
+pub fn tensor(name: &str, type_: TensorType, shape: &[i32]) -> TensorDef {
+    TensorDef {
+        name: name.into(),
+        type_,
+        shape: shape.into(),
+        shape_signature: None,
+        data: None,
+        quantization: None,
+    }
+}
+
+pub fn konst<T: Copy>(name: &str, type_: TensorType, shape: &[i32], data: &[T]) -> TensorDef {
+    let bytes = unsafe {
+        std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+    };
+    TensorDef { data: Some(bytes.to_vec()), ..tensor(name, type_, shape) }
+}
+
+pub enum Options {
+    None,
+    Add,
+    Mul,
+    FullyConnected,
+    BatchMatMul,
+    If { then: i32, else_: i32 },
+    While { cond: i32, body: i32 },
+    CallOnce { init: i32 },
+    VarHandle { name: String },
+    Custom(Vec<u8>),
+}
+
+pub struct OpDef {
+    pub code: BuiltinOperator,
+    pub custom_code: Option<String>,
+    pub inputs: Vec<i32>,
+    pub outputs: Vec<i32>,
+    pub options: Options,
+}
+
+pub fn op(code: BuiltinOperator, inputs: &[i32], outputs: &[i32], options: Options) -> OpDef {
+    OpDef { code, custom_code: None, inputs: inputs.into(), outputs: outputs.into(), options }
+}
+
+#[derive(Default)]
+pub struct GraphDef {
+    pub tensors: Vec<TensorDef>,
+    pub inputs: Vec<i32>,
+    pub outputs: Vec<i32>,
+    pub ops: Vec<OpDef>,
+}
+
+pub struct SignatureDef {
+    pub key: String,
+    pub subgraph: u32,
+    pub inputs: Vec<(String, u32)>,
+    pub outputs: Vec<(String, u32)>,
+}
+
+#[derive(Default)]
+pub struct ModelDef {
+    pub graphs: Vec<GraphDef>,
+    pub signatures: Vec<SignatureDef>,
+}
+
+impl ModelDef {
+    pub fn build(&self) -> Vec<u8> {
+        let mut fb = FlatBufferBuilder::new();
+        let mut buffers = vec![tflite::Buffer::create(&mut fb, &tflite::BufferArgs { data: None })];
+        let mut codes: Vec<(BuiltinOperator, Option<String>)> = vec![];
+        let mut graphs = vec![];
+        for graph in &self.graphs {
+            let mut tensors = vec![];
+            for t in &graph.tensors {
+                let buffer = if let Some(data) = &t.data {
+                    let data = fb.create_vector(data);
+                    buffers.push(tflite::Buffer::create(
+                        &mut fb,
+                        &tflite::BufferArgs { data: Some(data) },
+                    ));
+                    buffers.len() as u32 - 1
+                } else {
+                    0
+                };
+                let name = fb.create_string(&t.name);
+                let shape = fb.create_vector(&t.shape);
+                let shape_signature = t.shape_signature.as_ref().map(|s| fb.create_vector(s));
+                let quantization = t.quantization.as_ref().map(|(zero_point, scale)| {
+                    let zero_point = fb.create_vector(zero_point);
+                    let scale = fb.create_vector(scale);
+                    tflite::QuantizationParameters::create(
+                        &mut fb,
+                        &tflite::QuantizationParametersArgs {
+                            zero_point: Some(zero_point),
+                            scale: Some(scale),
+                            ..tflite::QuantizationParametersArgs::default()
+                        },
+                    )
+                });
+                tensors.push(tflite::Tensor::create(
+                    &mut fb,
+                    &tflite::TensorArgs {
+                        shape: Some(shape),
+                        type_: t.type_,
+                        buffer,
+                        name: Some(name),
+                        shape_signature,
+                        quantization,
+                        ..tflite::TensorArgs::default()
+                    },
+                ));
+            }
+            let mut ops = vec![];
+            for op in &graph.ops {
+                let code = (op.code, op.custom_code.clone());
+                let opcode_index = if let Some(ix) = codes.iter().position(|c| *c == code) {
+                    ix
+                } else {
+                    codes.push(code);
+                    codes.len() - 1
+                } as u32;
+                let (options_type, options) = options(&mut fb, &op.options);
+                let custom_options = if let Options::Custom(bytes) = &op.options {
+                    Some(fb.create_vector(bytes))
+                } else {
+                    None
+                };
+                let inputs = fb.create_vector(&op.inputs);
+                let outputs = fb.create_vector(&op.outputs);
+                ops.push(tflite::Operator::create(
+                    &mut fb,
+                    &tflite::OperatorArgs {
+                        opcode_index,
+                        inputs: Some(inputs),
+                        outputs: Some(outputs),
+                        builtin_options_type: options_type,
+                        builtin_options: options,
+                        custom_options,
+                        custom_options_format: tflite::CustomOptionsFormat::FLEXBUFFERS,
+                        ..tflite::OperatorArgs::default()
+                    },
+                ));
+            }
+            let tensors = fb.create_vector(&tensors);
+            let inputs = fb.create_vector(&graph.inputs);
+            let outputs = fb.create_vector(&graph.outputs);
+            let operators = fb.create_vector(&ops);
+            graphs.push(tflite::SubGraph::create(
+                &mut fb,
+                &tflite::SubGraphArgs {
+                    tensors: Some(tensors),
+                    inputs: Some(inputs),
+                    outputs: Some(outputs),
+                    operators: Some(operators),
+                    name: None,
+                },
+            ));
+        }
+        let codes = codes
+            .iter()
+            .map(|(code, custom)| {
+                let custom_code = custom.as_ref().map(|c| fb.create_string(c));
+                tflite::OperatorCode::create(
+                    &mut fb,
+                    &tflite::OperatorCodeArgs {
+                        deprecated_builtin_code: code.0.min(127) as i8,
+                        custom_code,
+                        version: 1,
+                        builtin_code: *code,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut signatures = vec![];
+        for sig in &self.signatures {
+            let mut maps = |io: &[(String, u32)]| {
+                let maps = io
+                    .iter()
+                    .map(|(name, tensor_index)| {
+                        let name = fb.create_string(name);
+                        tflite::TensorMap::create(
+                            &mut fb,
+                            &tflite::TensorMapArgs {
+                                name: Some(name),
+                                tensor_index: *tensor_index,
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                fb.create_vector(&maps)
+            };
+            let inputs = maps(&sig.inputs);
+            let outputs = maps(&sig.outputs);
+            let signature_key = fb.create_string(&sig.key);
+            signatures.push(tflite::SignatureDef::create(
+                &mut fb,
+                &tflite::SignatureDefArgs {
+                    inputs: Some(inputs),
+                    outputs: Some(outputs),
+                    signature_key: Some(signature_key),
+                    subgraph_index: sig.subgraph,
+                },
+            ));
+        }
+        let operator_codes = fb.create_vector(&codes);
+        let subgraphs = fb.create_vector(&graphs);
+        let buffers = fb.create_vector(&buffers);
+        let signature_defs = fb.create_vector(&signatures);
+        let model = tflite::Model::create(
+            &mut fb,
+            &tflite::ModelArgs {
+                version: 3,
+                operator_codes: Some(operator_codes),
+                subgraphs: Some(subgraphs),
+                buffers: Some(buffers),
+                signature_defs: Some(signature_defs),
+                ..tflite::ModelArgs::default()
+            },
+        );
+        fb.finish(model, Some("TFL3"));
+        fb.finished_data().to_vec()
+    }
+
+    pub fn load(&self) -> TractResult<TypedModel> {
+        tract_tflite::tflite().model_for_read(&mut &*self.build())
+    }
+}
+
+fn options(
+    fb: &mut FlatBufferBuilder,
+    options: &Options,
+) -> (BuiltinOptions, Option<WIPOffset<UnionWIPOffset>>) {
+    match options {
+        Options::None | Options::Custom(_) => (BuiltinOptions::NONE, None),
+        Options::Add => (
+            BuiltinOptions::AddOptions,
+            Some(
+                tflite::AddOptions::create(fb, &tflite::AddOptionsArgs::default()).as_union_value(),
+            ),
+        ),
+        Options::Mul => (
+            BuiltinOptions::MulOptions,
+            Some(
+                tflite::MulOptions::create(fb, &tflite::MulOptionsArgs::default()).as_union_value(),
+            ),
+        ),
+        Options::FullyConnected => (
+            BuiltinOptions::FullyConnectedOptions,
+            Some(
+                tflite::FullyConnectedOptions::create(
+                    fb,
+                    &tflite::FullyConnectedOptionsArgs::default(),
+                )
+                .as_union_value(),
+            ),
+        ),
+        Options::BatchMatMul => (
+            BuiltinOptions::BatchMatMulOptions,
+            Some(
+                tflite::BatchMatMulOptions::create(fb, &tflite::BatchMatMulOptionsArgs::default())
+                    .as_union_value(),
+            ),
+        ),
+        Options::If { then, else_ } => (
+            BuiltinOptions::IfOptions,
+            Some(
+                tflite::IfOptions::create(
+                    fb,
+                    &tflite::IfOptionsArgs {
+                        then_subgraph_index: *then,
+                        else_subgraph_index: *else_,
+                    },
+                )
+                .as_union_value(),
+            ),
+        ),
+        Options::While { cond, body } => (
+            BuiltinOptions::WhileOptions,
+            Some(
+                tflite::WhileOptions::create(
+                    fb,
+                    &tflite::WhileOptionsArgs {
+                        cond_subgraph_index: *cond,
+                        body_subgraph_index: *body,
+                    },
+                )
+                .as_union_value(),
+            ),
+        ),
+        Options::CallOnce { init } => (
+            BuiltinOptions::CallOnceOptions,
+            Some(
+                tflite::CallOnceOptions::create(
+                    fb,
+                    &tflite::CallOnceOptionsArgs { init_subgraph_index: *init },
+                )
+                .as_union_value(),
+            ),
+        ),
+        Options::VarHandle { name } => {
+            let shared_name = fb.create_string(name);
+            (
+                BuiltinOptions::VarHandleOptions,
+                Some(
+                    tflite::VarHandleOptions::create(
+                        fb,
+                        &tflite::VarHandleOptionsArgs {
+                            container: None,
+                            shared_name: Some(shared_name),
+                        },
+                    )
+                    .as_union_value(),
+                ),
+            )
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use tract_core::ops::scan::{Scan, While};
+use tract_tflite::internal::*;
+use tract_tflite::tflite::{BuiltinOperator as Bo, TensorType};
+
+mod common;
+use common::*;
+
+// while i < n { i += 1; x += x }, over loop variables (i, x, n)
+fn loop_graphs() -> Vec<GraphDef> {
+    let vars = || {
+        vec![
+            tensor("i", TensorType::INT32, &[]),
+            tensor("x", TensorType::FLOAT32, &[2]),
+            tensor("n", TensorType::INT32, &[]),
+        ]
+    };
+    let mut cond = GraphDef { tensors: vars(), inputs: vec![0, 1, 2], ..GraphDef::default() };
+    cond.tensors.push(tensor("lt", TensorType::BOOL, &[]));
+    cond.ops.push(op(Bo::LESS, &[0, 2], &[3], Options::None));
+    cond.outputs = vec![3];
+    let mut body = GraphDef { tensors: vars(), inputs: vec![0, 1, 2], ..GraphDef::default() };
+    body.tensors.push(konst("one", TensorType::INT32, &[], &[1i32]));
+    body.tensors.push(tensor("i+1", TensorType::INT32, &[]));
+    body.tensors.push(tensor("x+x", TensorType::FLOAT32, &[2]));
+    body.ops.push(op(Bo::ADD, &[0, 3], &[4], Options::Add));
+    body.ops.push(op(Bo::ADD, &[1, 1], &[5], Options::Add));
+    body.outputs = vec![4, 5, 2];
+    vec![cond, body]
+}
+
+fn while_model(n: Option<i32>) -> ModelDef {
+    let mut main = GraphDef::default();
+    main.tensors.push(tensor("x", TensorType::FLOAT32, &[2]));
+    main.tensors.push(konst("zero", TensorType::INT32, &[], &[0i32]));
+    if let Some(n) = n {
+        main.tensors.push(konst("n", TensorType::INT32, &[], &[n]));
+        main.inputs = vec![0];
+    } else {
+        main.tensors.push(tensor("n", TensorType::INT32, &[]));
+        main.inputs = vec![0, 2];
+    }
+    main.tensors.push(tensor("i.final", TensorType::INT32, &[]));
+    main.tensors.push(tensor("x.final", TensorType::FLOAT32, &[2]));
+    main.tensors.push(tensor("n.final", TensorType::INT32, &[]));
+    main.ops.push(op(Bo::WHILE, &[1, 0, 2], &[3, 4, 5], Options::While { cond: 1, body: 2 }));
+    main.outputs = vec![4];
+    let mut graphs = vec![main];
+    graphs.extend(loop_graphs());
+    ModelDef { graphs, ..ModelDef::default() }
+}
+
+#[test]
+fn while_with_trip_count_known_at_load_time() -> TractResult<()> {
+    let model = while_model(Some(3)).load()?;
+    assert!(model.nodes().iter().any(|n| n.op_is::<Scan>()));
+    let y = model.into_runnable()?.run(tvec!(tensor1(&[1f32, -1.]).into()))?;
+    assert_eq!(*y[0], tensor1(&[8f32, -8.]));
+    Ok(())
+}
+
+#[test]
+fn while_with_trip_count_known_at_runtime() -> TractResult<()> {
+    let model = while_model(None).load()?;
+    assert!(model.nodes().iter().any(|n| n.op_is::<While>()));
+    let plan = model.into_optimized()?.into_runnable()?;
+    for n in [0, 2] {
+        let y = plan.run(tvec!(tensor1(&[1f32, -1.]).into(), tensor0(n).into()))?;
+        let scale = (1 << n) as f32;
+        assert_eq!(*y[0], tensor1(&[scale, -scale]));
+    }
+    Ok(())
+}
+
+// if c { x * x } else { x }
+#[test]
+fn if_() -> TractResult<()> {
+    let mut main = GraphDef::default();
+    main.tensors.push(tensor("c", TensorType::BOOL, &[]));
+    main.tensors.push(tensor("x", TensorType::FLOAT32, &[2]));
+    main.tensors.push(tensor("y", TensorType::FLOAT32, &[2]));
+    main.ops.push(op(Bo::IF, &[0, 1], &[2], Options::If { then: 1, else_: 2 }));
+    main.inputs = vec![0, 1];
+    main.outputs = vec![2];
+    let mut then = GraphDef::default();
+    then.tensors.push(tensor("x", TensorType::FLOAT32, &[2]));
+    then.tensors.push(tensor("x*x", TensorType::FLOAT32, &[2]));
+    then.ops.push(op(Bo::MUL, &[0, 0], &[1], Options::Mul));
+    then.inputs = vec![0];
+    then.outputs = vec![1];
+    let else_ = GraphDef {
+        tensors: vec![tensor("x", TensorType::FLOAT32, &[2])],
+        inputs: vec![0],
+        outputs: vec![0],
+        ..GraphDef::default()
+    };
+    let model = ModelDef { graphs: vec![main, then, else_], ..ModelDef::default() }.load()?;
+    let plan = model.into_runnable()?;
+    for (c, expected) in [(true, [9f32, 4.]), (false, [3f32, -2.])] {
+        let y = plan.run(tvec!(tensor0(c).into(), tensor1(&[3f32, -2.]).into()))?;
+        assert_eq!(*y[0], tensor1(&expected));
+    }
+    Ok(())
+}
+
+// the main graph reads a variable, assigned by the CALL_ONCE init subgraph (or the main graph)
+fn variable_model(assign_in_main: bool) -> ModelDef {
+    let var = || Options::VarHandle { name: "v".into() };
+    let mut main = GraphDef::default();
+    main.tensors.push(tensor("x", TensorType::FLOAT32, &[2]));
+    main.tensors.push(tensor("handle", TensorType::RESOURCE, &[]));
+    main.tensors.push(tensor("v", TensorType::FLOAT32, &[2]));
+    main.tensors.push(tensor("y", TensorType::FLOAT32, &[2]));
+    main.tensors.push(konst("value", TensorType::FLOAT32, &[2], &[10f32, 20.]));
+    main.ops.push(op(Bo::CALL_ONCE, &[], &[], Options::CallOnce { init: 1 }));
+    main.ops.push(op(Bo::VAR_HANDLE, &[], &[1], var()));
+    if assign_in_main {
+        main.ops.push(op(Bo::ASSIGN_VARIABLE, &[1, 4], &[], Options::None));
+    }
+    main.ops.push(op(Bo::READ_VARIABLE, &[1], &[2], Options::None));
+    main.ops.push(op(Bo::ADD, &[0, 2], &[3], Options::Add));
+    main.inputs = vec![0];
+    main.outputs = vec![3];
+    let mut init = GraphDef::default();
+    init.tensors.push(tensor("handle", TensorType::RESOURCE, &[]));
+    init.tensors.push(konst("init_value", TensorType::FLOAT32, &[2], &[1f32, 2.]));
+    init.ops.push(op(Bo::VAR_HANDLE, &[], &[0], var()));
+    init.ops.push(op(Bo::ASSIGN_VARIABLE, &[0, 1], &[], Options::None));
+    ModelDef { graphs: vec![main, init], ..ModelDef::default() }
+}
+
+#[test]
+fn call_once_initializes_variables() -> TractResult<()> {
+    let model = variable_model(false).load()?;
+    let y = model.into_runnable()?.run(tvec!(tensor1(&[3f32, -2.]).into()))?;
+    assert_eq!(*y[0], tensor1(&[4f32, 0.]));
+    Ok(())
+}
+
+#[test]
+fn assign_variable_outside_call_once_is_rejected() {
+    let err = variable_model(true).load().unwrap_err();
+    assert!(format!("{err:?}").contains("frozen at load time"), "{err:?}");
+}
+
+// two entry points: "plus" computes a + b, "times" computes a * b
+fn signature_model(times_subgraph: u32) -> ModelDef {
+    let graph = |code, options| {
+        let mut graph = GraphDef::default();
+        graph.tensors.push(tensor("a", TensorType::FLOAT32, &[2]));
+        graph.tensors.push(tensor("b", TensorType::FLOAT32, &[2]));
+        graph.tensors.push(tensor("c", TensorType::FLOAT32, &[2]));
+        graph.ops.push(op(code, &[0, 1], &[2], options));
+        graph.inputs = vec![0, 1];
+        graph.outputs = vec![2];
+        graph
+    };
+    let signature = |key: &str, subgraph| SignatureDef {
+        key: key.into(),
+        subgraph,
+        // inputs in another order than the subgraph ones
+        inputs: vec![("rhs".into(), 1), ("lhs".into(), 0)],
+        outputs: vec![("result".into(), 2)],
+    };
+    ModelDef {
+        graphs: vec![graph(Bo::ADD, Options::Add), graph(Bo::MUL, Options::Mul)],
+        signatures: vec![signature("plus", 0), signature("times", times_subgraph)],
+    }
+}
+
+#[test]
+fn signature_selection() -> TractResult<()> {
+    let buffer = signature_model(1).build();
+    let proto = tract_tflite::tflite().proto_model_for_read(&mut &*buffer)?;
+    assert_eq!(proto.signature_keys(), vec!["plus".to_string(), "times".to_string()]);
+    let model = tract_tflite::tflite().with_signature("times").model_for_read(&mut &*buffer)?;
+    let inputs =
+        model.input_outlets()?.iter().map(|o| &*model.node(o.node).name).collect::<Vec<_>>();
+    assert_eq!(inputs, vec!["rhs", "lhs"]);
+    assert_eq!(model.outlet_label(model.output_outlets()?[0]), Some("result"));
+    let y = model
+        .into_runnable()?
+        .run(tvec!(tensor1(&[2f32, 3.]).into(), tensor1(&[4f32, 5.]).into()))?;
+    assert_eq!(*y[0], tensor1(&[8f32, 15.]));
+    Ok(())
+}
+
+#[test]
+fn unknown_or_invalid_signature() {
+    let buffer = signature_model(1).build();
+    let err = tract_tflite::tflite().with_signature("minus").model_for_read(&mut &*buffer);
+    assert!(format!("{:?}", err.unwrap_err()).contains("No signature named \"minus\""));
+    let buffer = signature_model(7).build();
+    let err = tract_tflite::tflite().with_signature("times").model_for_read(&mut &*buffer);
+    assert!(format!("{:?}", err.unwrap_err()).contains("Invalid subgraph index 7"));
+}
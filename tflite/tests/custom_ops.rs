@@ -0,0 +1,134 @@
+use flexbuffers::Builder;
+use tract_core::ops::math;
+use tract_tflite::internal::*;
+use tract_tflite::tflite::{BuiltinOperator as Bo, TensorType};
+
+mod common;
+use common::*;
+
+fn flex_map(entries: &[(&str, f64)], ints: &[(&str, i64)], bools: &[(&str, bool)]) -> Vec<u8> {
+    let mut builder = Builder::default();
+    let mut map = builder.start_map();
+    for (k, v) in entries {
+        map.push(k, *v);
+    }
+    for (k, v) in ints {
+        map.push(k, *v);
+    }
+    for (k, v) in bools {
+        map.push(k, *v);
+    }
+    map.end_map();
+    builder.view().to_vec()
+}
+
+fn custom_op(code: &str, inputs: &[i32], outputs: &[i32], options: Vec<u8>) -> OpDef {
+    OpDef {
+        custom_code: Some(code.into()),
+        ..op(Bo::CUSTOM, inputs, outputs, Options::Custom(options))
+    }
+}
+
+// y = x * factor, factor coming from the custom options
+fn scale_model() -> ModelDef {
+    let mut main = GraphDef::default();
+    main.tensors.push(tensor("x", TensorType::FLOAT32, &[2]));
+    main.tensors.push(tensor("y", TensorType::FLOAT32, &[2]));
+    main.ops.push(custom_op("Scale", &[0], &[1], flex_map(&[("factor", 3.)], &[], &[])));
+    main.inputs = vec![0];
+    main.outputs = vec![1];
+    ModelDef { graphs: vec![main], ..ModelDef::default() }
+}
+
+#[test]
+fn custom_op_dispatch() -> TractResult<()> {
+    let buffer = scale_model().build();
+    let model = tract_tflite::tflite()
+        .with_custom_op("Scale", |op, options| {
+            let factor = options.get_f64("factor")? as f32;
+            let factor =
+                op.ctx.target.add_const(format!("{}.factor", op.prefix), tensor1(&[factor]))?;
+            op.ctx.target.wire_node(op.prefix, math::mul(), &[op.inputs[0], factor])
+        })
+        .model_for_read(&mut &*buffer)?;
+    let y = model.into_runnable()?.run(tvec!(tensor1(&[1f32, -2.]).into()))?;
+    assert_eq!(*y[0], tensor1(&[3f32, -6.]));
+    Ok(())
+}
+
+#[test]
+fn unregistered_custom_op() {
+    let err = scale_model().load().unwrap_err();
+    assert!(format!("{err:?}").contains("Unsupported"), "{err:?}");
+}
+
+// Anchors (yc, xc, h, w) decode to, with zero encodings:
+//  A0: [0, 0, .5, .5]
+//  A1: [.0625, .0625, .5625, .5625], overlapping A0 (IoU .62)
+//  A2: [.5, .5, 1, 1]
+// Class scores (background, c0, c1):
+//  A0: [0, .9, .1], A1: [0, .8, .4], A2: [0, .1, .7]
+fn detection_model(use_regular_nms: bool) -> ModelDef {
+    let mut main = GraphDef::default();
+    main.tensors.push(tensor("encodings", TensorType::FLOAT32, &[1, 3, 4]));
+    main.tensors.push(tensor("scores", TensorType::FLOAT32, &[1, 3, 3]));
+    main.tensors.push(konst(
+        "anchors",
+        TensorType::FLOAT32,
+        &[3, 4],
+        &[0.25f32, 0.25, 0.5, 0.5, 0.3125, 0.3125, 0.5, 0.5, 0.75, 0.75, 0.5, 0.5],
+    ));
+    main.tensors.push(tensor("boxes", TensorType::FLOAT32, &[1, 3, 4]));
+    main.tensors.push(tensor("classes", TensorType::FLOAT32, &[1, 3]));
+    main.tensors.push(tensor("detection_scores", TensorType::FLOAT32, &[1, 3]));
+    main.tensors.push(tensor("count", TensorType::FLOAT32, &[1]));
+    let options = flex_map(
+        &[
+            ("nms_score_threshold", 0.3),
+            ("nms_iou_threshold", 0.5),
+            ("y_scale", 10.),
+            ("x_scale", 10.),
+            ("h_scale", 5.),
+            ("w_scale", 5.),
+        ],
+        &[("max_detections", 3), ("max_classes_per_detection", 1), ("num_classes", 2)],
+        &[("use_regular_nms", use_regular_nms)],
+    );
+    main.ops.push(custom_op("TFLite_Detection_PostProcess", &[0, 1, 2], &[3, 4, 5, 6], options));
+    main.inputs = vec![0, 1];
+    main.outputs = vec![3, 4, 5, 6];
+    ModelDef { graphs: vec![main], ..ModelDef::default() }
+}
+
+fn run_detection(use_regular_nms: bool) -> TractResult<TVec<TValue>> {
+    let model = detection_model(use_regular_nms).load()?;
+    let encodings = Tensor::zero::<f32>(&[1, 3, 4])?;
+    let scores = tensor3(&[[[0f32, 0.9, 0.1], [0., 0.8, 0.4], [0., 0.1, 0.7]]]);
+    model.into_runnable()?.run(tvec!(encodings.into(), scores.into()))
+}
+
+const A0: [f32; 4] = [0., 0., 0.5, 0.5];
+const A1: [f32; 4] = [0.0625, 0.0625, 0.5625, 0.5625];
+const A2: [f32; 4] = [0.5, 0.5, 1., 1.];
+
+#[test]
+fn detection_postprocess_fast_nms() -> TractResult<()> {
+    let outputs = run_detection(false)?;
+    // A1 best class is c0, suppressed by A0
+    assert_eq!(*outputs[0], tensor3(&[[A0, A2, [0.; 4]]]));
+    assert_eq!(*outputs[1], tensor2(&[[0f32, 1., 0.]]));
+    assert_eq!(*outputs[2], tensor2(&[[0.9f32, 0.7, 0.]]));
+    assert_eq!(*outputs[3], tensor1(&[2f32]));
+    Ok(())
+}
+
+#[test]
+fn detection_postprocess_regular_nms() -> TractResult<()> {
+    let outputs = run_detection(true)?;
+    // per class suppression: A1 survives as a c1 detection
+    assert_eq!(*outputs[0], tensor3(&[[A0, A2, A1]]));
+    assert_eq!(*outputs[1], tensor2(&[[0f32, 1., 1.]]));
+    assert_eq!(*outputs[2], tensor2(&[[0.9f32, 0.7, 0.4]]));
+    assert_eq!(*outputs[3], tensor1(&[3f32]));
+    Ok(())
+}
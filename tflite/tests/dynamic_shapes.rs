@@ -0,0 +1,63 @@
+use tract_tflite::internal::*;
+use tract_tflite::tflite::{BuiltinOperator as Bo, TensorType};
+
+mod common;
+use common::*;
+
+// z = (x + y) + y, with a dynamic leading axis
+fn add_model() -> ModelDef {
+    let dynamic = |name| TensorDef {
+        shape_signature: Some(vec![-1, 2]),
+        ..tensor(name, TensorType::FLOAT32, &[1, 2])
+    };
+    let mut main = GraphDef::default();
+    main.tensors.push(dynamic("x"));
+    main.tensors.push(dynamic("y:0"));
+    main.tensors.push(dynamic("t"));
+    main.tensors.push(dynamic("z"));
+    main.ops.push(op(Bo::ADD, &[0, 1], &[2], Options::Add));
+    main.ops.push(op(Bo::ADD, &[2, 1], &[3], Options::Add));
+    main.inputs = vec![0, 1];
+    main.outputs = vec![3];
+    ModelDef { graphs: vec![main], ..ModelDef::default() }
+}
+
+fn check_runs(model: TypedModel) -> TractResult<()> {
+    let plan = model.into_optimized()?.into_runnable()?;
+    for n in [1, 3] {
+        let x = tensor1(&(0..2 * n).map(|i| i as f32).collect::<Vec<_>>()).into_shape(&[n, 2])?;
+        let y = tensor1(&vec![10f32; 2 * n]).into_shape(&[n, 2])?;
+        let z =
+            tensor1(&(0..2 * n).map(|i| i as f32 + 20.).collect::<Vec<_>>()).into_shape(&[n, 2])?;
+        let outputs = plan.run(tvec!(x.into(), y.into()))?;
+        assert_eq!(*outputs[0], z);
+    }
+    Ok(())
+}
+
+#[test]
+fn dynamic_dims_are_symbols() -> TractResult<()> {
+    let model = add_model().load()?;
+    // one symbol, shared by the inputs, none for the intermediate tensor
+    let d0 = model.symbols.get("d0").context("No d0 symbol")?;
+    assert_eq!(model.symbols.all_symbols(), vec![d0.clone()]);
+    let shape = [d0.to_dim(), 2.to_dim()];
+    assert_eq!(*model.input_fact(0)?.shape, shape);
+    assert_eq!(*model.input_fact(1)?.shape, shape);
+    assert_eq!(*model.output_fact(0)?.shape, shape);
+    check_runs(model)
+}
+
+#[test]
+fn dynamic_dims_roundtrip() -> TractResult<()> {
+    let model = add_model().load()?;
+    let mut buffer = vec![];
+    tract_tflite::tflite().write(&model, &mut buffer)?;
+    let reloaded = tract_tflite::tflite().model_for_read(&mut &*buffer)?;
+    for input in 0..2 {
+        let shape = &reloaded.input_fact(input)?.shape;
+        assert!(shape[0].to_i64().is_err());
+        assert_eq!(shape[1], 2.to_dim());
+    }
+    check_runs(reloaded)
+}
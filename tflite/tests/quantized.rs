@@ -0,0 +1,77 @@
+use tract_tflite::internal::*;
+use tract_tflite::tflite::{BuiltinOperator as Bo, TensorType};
+
+mod common;
+use common::*;
+
+fn q(def: TensorDef, zero_point: i64, scale: f32) -> TensorDef {
+    TensorDef { quantization: Some((vec![zero_point], vec![scale])), ..def }
+}
+
+fn qi8(data: Tensor, zero_point: i32, scale: f32) -> Tensor {
+    let mut data = data;
+    unsafe {
+        data.set_datum_type(i8::datum_type().quantize(QParams::ZpScale { zero_point, scale }))
+    };
+    data
+}
+
+// writes the model tract loaded, reloads it, and checks both versions agree
+fn roundtrip(def: ModelDef, input: Tensor, op: Bo, version: i32) -> TractResult<()> {
+    let model = def.load()?;
+    let expected = model.clone().into_runnable()?.run(tvec!(input.clone().into()))?;
+    let mut buffer = vec![];
+    tract_tflite::tflite().write(&model, &mut buffer)?;
+    let proto = tract_tflite::tflite().proto_model_for_read(&mut &*buffer)?;
+    let codes = proto.root().operator_codes().unwrap();
+    assert!(codes.iter().any(|c| c.builtin_code() == op && c.version() == version));
+    let reloaded = tract_tflite::tflite().model_for_read(&mut &*buffer)?;
+    let found = reloaded.into_runnable()?.run(tvec!(input.into()))?;
+    assert_eq!(found[0].datum_type(), expected[0].datum_type());
+    found[0].close_enough(&expected[0], Approximation::Exact)?;
+    assert!(expected[0].as_slice::<i8>()?.iter().any(|x| *x != 0));
+    Ok(())
+}
+
+fn fully_connected_model(weights_zero_point: i64) -> ModelDef {
+    let mut main = GraphDef::default();
+    main.tensors.push(q(tensor("x", TensorType::INT8, &[2, 3]), 1, 0.5));
+    let weights = [1i8, -2, 3, 4, 0, -1, 2, 2, 2, -3, 1, 0];
+    main.tensors
+        .push(q(konst("w", TensorType::INT8, &[4, 3], &weights), weights_zero_point, 0.25));
+    main.tensors.push(q(konst("b", TensorType::INT32, &[4], &[8i32, -8, 0, 4]), 0, 0.125));
+    main.tensors.push(q(tensor("y", TensorType::INT8, &[2, 4]), -2, 0.5));
+    main.ops.push(op(Bo::FULLY_CONNECTED, &[0, 1, 2], &[3], Options::FullyConnected));
+    main.inputs = vec![0];
+    main.outputs = vec![3];
+    ModelDef { graphs: vec![main], ..ModelDef::default() }
+}
+
+#[test]
+fn fully_connected() -> TractResult<()> {
+    let x = qi8(tensor2(&[[3i8, -1, 5], [0, 7, -4]]), 1, 0.5);
+    roundtrip(fully_connected_model(0), x, Bo::FULLY_CONNECTED, 4)
+}
+
+// tflite int8 kernels assume symmetric weights
+#[test]
+fn fully_connected_asymmetric_weights() -> TractResult<()> {
+    let model = fully_connected_model(1).load()?;
+    assert!(tract_tflite::tflite().write(&model, &mut vec![]).is_err());
+    Ok(())
+}
+
+#[test]
+fn batch_matmul() -> TractResult<()> {
+    let mut main = GraphDef::default();
+    main.tensors.push(q(tensor("a", TensorType::INT8, &[1, 2, 3]), 0, 0.5));
+    let b = [1i8, -2, 3, 4, 0, -1, 2, 2, 2, -3, 1, 0];
+    main.tensors.push(q(konst("b", TensorType::INT8, &[1, 3, 4], &b), 0, 0.25));
+    main.tensors.push(q(tensor("c", TensorType::INT8, &[1, 2, 4]), 0, 0.25));
+    main.ops.push(op(Bo::BATCH_MATMUL, &[0, 1], &[2], Options::BatchMatMul));
+    main.inputs = vec![0];
+    main.outputs = vec![2];
+    let model = ModelDef { graphs: vec![main], ..ModelDef::default() };
+    let a = qi8(tensor3(&[[[3i8, -1, 5], [0, 7, -4]]]), 0, 0.5);
+    roundtrip(model, a, Bo::BATCH_MATMUL, 2)
+}